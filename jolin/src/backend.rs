@@ -0,0 +1,248 @@
+/*
+ * backend.rs
+ * Optional routines provided by an external BLAS/LAPACK.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::error::JolinError;
+
+/// Routines that may be provided by an external library for an element type.
+///
+/// Every routine returns `false` or `None` if it isn't provided, or the
+/// library can't handle the input (e.g. a dimension above `i32::MAX`) and
+/// left it unchanged, and the caller should fall back to the pure Rust
+/// implementation. All matrices are stored in the column-major order.
+///
+/// With the `blas` feature, `f64` and `f32` implement the routines with
+/// BLAS/LAPACK's `?gemm`, `?getrf`, `?geqrf`/`?orgqr` and `?syev`. jolin
+/// doesn't choose a BLAS implementation: the final binary has to link a
+/// library providing the CBLAS and LAPACK symbols, e.g. OpenBLAS with
+/// `RUSTFLAGS="-l openblas"` or `cargo:rustc-link-lib=openblas` in a build
+/// script.
+pub trait Backend: Sized {
+    /// Compute `C = A B`, where `A` is `m * k`, `B` is `k * n` and `C` is
+    /// `m * n`.
+    fn gemm(_m: usize, _n: usize, _k: usize, _a: &[Self], _b: &[Self], _c: &mut [Self]) -> bool {
+        false
+    }
+
+    /// LU decomposition with partial pivoting of the `n * n` matrix `a` in
+    /// place. `L` (with the unit diagonal omitted) and `U` are packed in `a`.
+    /// The answer is the permutation `p`, i.e. row `i` of `LU` is row `p[i]`
+    /// of the input, or a singular matrix error.
+    fn getrf(_n: usize, _a: &mut [Self]) -> Option<Result<Vec<usize>, JolinError>> {
+        None
+    }
+
+    /// QR decomposition of the `m * n` matrix `a` with `m >= n`. `a` is
+    /// replaced by `R` and the answer is the `m * m` matrix `Q`.
+    fn geqrf(_m: usize, _n: usize, _a: &mut [Self]) -> Option<Vec<Self>> {
+        None
+    }
+
+    /// Eigen decomposition of the `n * n` symmetric matrix `a`. `a` is
+    /// replaced by the eigenvectors and the answer is the eigenvalues in
+    /// ascending order.
+    fn syev(_n: usize, _a: &mut [Self]) -> Option<Vec<Self>> {
+        None
+    }
+}
+
+#[cfg(feature = "blas")]
+mod ffi {
+    pub const CBLAS_COL_MAJOR: i32 = 102;
+    pub const CBLAS_NO_TRANS: i32 = 111;
+
+    extern "C" {
+        pub fn cblas_dgemm(layout: i32, transa: i32, transb: i32, m: i32, n: i32, k: i32,
+            alpha: f64, a: *const f64, lda: i32, b: *const f64, ldb: i32,
+            beta: f64, c: *mut f64, ldc: i32);
+        pub fn cblas_sgemm(layout: i32, transa: i32, transb: i32, m: i32, n: i32, k: i32,
+            alpha: f32, a: *const f32, lda: i32, b: *const f32, ldb: i32,
+            beta: f32, c: *mut f32, ldc: i32);
+
+        pub fn dgetrf_(m: *const i32, n: *const i32, a: *mut f64, lda: *const i32,
+            ipiv: *mut i32, info: *mut i32);
+        pub fn sgetrf_(m: *const i32, n: *const i32, a: *mut f32, lda: *const i32,
+            ipiv: *mut i32, info: *mut i32);
+
+        pub fn dgeqrf_(m: *const i32, n: *const i32, a: *mut f64, lda: *const i32,
+            tau: *mut f64, work: *mut f64, lwork: *const i32, info: *mut i32);
+        pub fn sgeqrf_(m: *const i32, n: *const i32, a: *mut f32, lda: *const i32,
+            tau: *mut f32, work: *mut f32, lwork: *const i32, info: *mut i32);
+
+        pub fn dorgqr_(m: *const i32, n: *const i32, k: *const i32, a: *mut f64, lda: *const i32,
+            tau: *const f64, work: *mut f64, lwork: *const i32, info: *mut i32);
+        pub fn sorgqr_(m: *const i32, n: *const i32, k: *const i32, a: *mut f32, lda: *const i32,
+            tau: *const f32, work: *mut f32, lwork: *const i32, info: *mut i32);
+
+        pub fn dsyev_(jobz: *const u8, uplo: *const u8, n: *const i32, a: *mut f64, lda: *const i32,
+            w: *mut f64, work: *mut f64, lwork: *const i32, info: *mut i32);
+        pub fn ssyev_(jobz: *const u8, uplo: *const u8, n: *const i32, a: *mut f32, lda: *const i32,
+            w: *mut f32, work: *mut f32, lwork: *const i32, info: *mut i32);
+    }
+}
+
+/// Convert a dimension or a workspace size to LAPACK's integer, or `None` if
+/// it doesn't fit, so the caller falls back to the pure Rust implementation.
+#[cfg(feature = "blas")]
+fn lapack_int(x: usize) -> Option<i32> {
+    i32::try_from(x).ok()
+}
+
+#[cfg(feature = "blas")]
+macro_rules! impl_lapack_backend {
+    ($t: ty, $gemm: ident, $getrf: ident, $geqrf: ident, $orgqr: ident, $syev: ident) => {
+        impl Backend for $t {
+            fn gemm(m: usize, n: usize, k: usize, a: &[$t], b: &[$t], c: &mut [$t]) -> bool {
+                if m == 0 || n == 0 || k == 0 {
+                    return false
+                }
+                let (Some(m_i), Some(n_i), Some(k_i)) = (lapack_int(m), lapack_int(n), lapack_int(k)) else {
+                    return false
+                };
+                unsafe {
+                    ffi::$gemm(ffi::CBLAS_COL_MAJOR, ffi::CBLAS_NO_TRANS, ffi::CBLAS_NO_TRANS,
+                        m_i, n_i, k_i, 1.0, a.as_ptr(), m_i,
+                        b.as_ptr(), k_i, 0.0, c.as_mut_ptr(), m_i);
+                }
+                true
+            }
+
+            fn getrf(n: usize, a: &mut [$t]) -> Option<Result<Vec<usize>, JolinError>> {
+                if n == 0 {
+                    return None
+                }
+                let n_i = lapack_int(n)?;
+                let mut ipiv = vec![0i32; n];
+                let mut info = 0i32;
+                unsafe {
+                    ffi::$getrf(&n_i, &n_i, a.as_mut_ptr(), &n_i, ipiv.as_mut_ptr(), &mut info);
+                }
+                if info < 0 {
+                    // an illegal argument is reported before `a` is touched
+                    return None
+                }
+                if info > 0 {
+                    // info is the 1-based column of the zero pivot
+                    return Some(Err(JolinError::singular_matrix_at((info - 1) as usize)))
+                }
+                // ipiv records the row interchanges in order (1-based)
+                let mut p: Vec<usize> = (0..n).collect();
+                for (i, v) in ipiv.iter().enumerate() {
+                    p.swap(i, (*v - 1) as usize);
+                }
+                Some(Ok(p))
+            }
+
+            fn geqrf(m: usize, n: usize, a: &mut [$t]) -> Option<Vec<$t>> {
+                if n == 0 || m < n {
+                    return None
+                }
+                let (m_i, n_i) = (lapack_int(m)?, lapack_int(n)?);
+                lapack_int(m.checked_mul(m)?)?;
+                // restored if LAPACK fails after overwriting `a`
+                let original = a.to_vec();
+                let mut tau: Vec<$t> = vec![0.0; n];
+                let mut info = 0i32;
+                let mut query: $t = 0.0;
+                unsafe {
+                    ffi::$geqrf(&m_i, &n_i, a.as_mut_ptr(), &m_i, tau.as_mut_ptr(), &mut query, &-1, &mut info);
+                }
+                if info != 0 {
+                    return None
+                }
+                let lwork = lapack_int(query as usize)?.max(1);
+                let mut work: Vec<$t> = vec![0.0; lwork as usize];
+                unsafe {
+                    ffi::$geqrf(&m_i, &n_i, a.as_mut_ptr(), &m_i, tau.as_mut_ptr(), work.as_mut_ptr(), &lwork, &mut info);
+                }
+                if info != 0 {
+                    a.copy_from_slice(&original);
+                    return None
+                }
+
+                // The reflectors below the diagonal are expanded into the full Q
+                let mut q: Vec<$t> = vec![0.0; m * m];
+                q[..(m * n)].copy_from_slice(a);
+                unsafe {
+                    ffi::$orgqr(&m_i, &m_i, &n_i, q.as_mut_ptr(), &m_i, tau.as_ptr(), &mut query, &-1, &mut info);
+                }
+                let lwork = match lapack_int(query as usize) {
+                    Some(lwork) if info == 0 => lwork.max(1),
+                    _ => {
+                        a.copy_from_slice(&original);
+                        return None
+                    }
+                };
+                let mut work: Vec<$t> = vec![0.0; lwork as usize];
+                unsafe {
+                    ffi::$orgqr(&m_i, &m_i, &n_i, q.as_mut_ptr(), &m_i, tau.as_ptr(), work.as_mut_ptr(), &lwork, &mut info);
+                }
+                if info != 0 {
+                    a.copy_from_slice(&original);
+                    return None
+                }
+                for c in 0..n {
+                    for r in (c + 1)..m {
+                        a[r + c * m] = 0.0;
+                    }
+                }
+                Some(q)
+            }
+
+            fn syev(n: usize, a: &mut [$t]) -> Option<Vec<$t>> {
+                if n == 0 {
+                    return None
+                }
+                let n_i = lapack_int(n)?;
+                // restored if LAPACK fails after overwriting `a`
+                let original = a.to_vec();
+                let mut w: Vec<$t> = vec![0.0; n];
+                let mut info = 0i32;
+                let mut query: $t = 0.0;
+                unsafe {
+                    ffi::$syev(&b'V', &b'U', &n_i, a.as_mut_ptr(), &n_i, w.as_mut_ptr(), &mut query, &-1, &mut info);
+                }
+                if info != 0 {
+                    return None
+                }
+                let lwork = lapack_int(query as usize)?.max(1);
+                let mut work: Vec<$t> = vec![0.0; lwork as usize];
+                unsafe {
+                    ffi::$syev(&b'V', &b'U', &n_i, a.as_mut_ptr(), &n_i, w.as_mut_ptr(), work.as_mut_ptr(), &lwork, &mut info);
+                }
+                if info != 0 {
+                    a.copy_from_slice(&original);
+                    return None
+                }
+                Some(w)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "blas")]
+impl_lapack_backend!(f64, cblas_dgemm, dgetrf_, dgeqrf_, dorgqr_, dsyev_);
+#[cfg(feature = "blas")]
+impl_lapack_backend!(f32, cblas_sgemm, sgetrf_, sgeqrf_, sorgqr_, ssyev_);
+
+#[cfg(not(feature = "blas"))]
+impl Backend for f64 {}
+#[cfg(not(feature = "blas"))]
+impl Backend for f32 {}
+
+#[cfg(test)]
+mod test {
+    #[cfg(not(feature = "blas"))]
+    #[test]
+    fn test_pure_rust_fallback() {
+        use crate::backend::Backend;
+        let mut a = vec![1.0f64, 2.0, 3.0, 4.0];
+        assert!(!f64::gemm(2, 2, 2, &[1.0; 4], &[1.0; 4], &mut a));
+        assert!(f64::getrf(2, &mut a).is_none());
+        assert!(f32::syev(1, &mut [1.0f32]).is_none());
+    }
+}
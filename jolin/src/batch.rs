@@ -0,0 +1,341 @@
+/*
+ * batch.rs
+ * Stacks of matrices of the same shape.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber};
+use crate::decomp::lu::lu_inplace;
+use crate::error::JolinError;
+
+/// A stack of matrices of the same shape, e.g. one 3x3 system per pixel.
+///
+/// The matrices are stored one after another in a single data vector, each
+/// in column-major order, so batched operations work on contiguous memory
+/// without a matrix object per item.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::batch::Batch;
+/// let a = Batch::from_matrices(&[mat64![2.0, 0.0; 0.0, 4.0], Mat64::identity(2)]).unwrap();
+/// let b = Batch::from_matrices(&[mat64![2.0; 4.0], mat64![1.0; 2.0]]).unwrap();
+/// let x = a.solve(&b).unwrap();
+/// assert_eq!(x.get(0), mat64![1.0; 1.0]);
+/// assert_eq!(x.get(1), mat64![1.0; 2.0]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch<T: Matrix> {
+    len: usize,
+    row: usize,
+    column: usize,
+    data: Vec<T::Elem>,
+}
+
+/// Packed LU decompositions of a batch of square matrices, see `Batch::lu`
+#[derive(Clone)]
+pub struct BatchLU<T: Matrix> {
+    /// `L` below the diagonal and `U` on and above it, as `lu_inplace`
+    packed: Batch<T>,
+    /// Permutation indices of all matrices one after another
+    p: Vec<usize>,
+}
+
+impl<T: Matrix> Batch<T> {
+    /// Create a batch of `len` zero matrices of `row * column`
+    pub fn zero(len: usize, row: usize, column: usize) -> Batch<T> {
+        Batch { len, row, column, data: vec![T::Elem::zero(); len * row * column] }
+    }
+
+    /// Create a batch from the data vector holding the matrices one after
+    /// another, each in column-major order.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the length of the data isn't
+    ///    `len * row * column`.
+    pub fn from_vec(len: usize, row: usize, column: usize, data: Vec<T::Elem>) -> Result<Batch<T>, JolinError> {
+        if len.checked_mul(row).and_then(|x| x.checked_mul(column)) != Some(data.len()) {
+            return Err(JolinError::shape_mismatching())
+        }
+        Ok(Batch { len, row, column, data })
+    }
+
+    /// Create a batch by copying the matrices.
+    ///
+    /// Potential errors:
+    /// 1. Not enough input - if there is no matrix, as the shape is unknown.
+    /// 2. Shape mismatching - if the matrices have different shapes.
+    pub fn from_matrices(mats: &[T]) -> Result<Batch<T>, JolinError> {
+        let first = mats.first().ok_or_else(JolinError::not_enough_input)?;
+        let (row, column) = (first.row(), first.column());
+        let mut data = Vec::with_capacity(mats.len() * row * column);
+        for m in mats {
+            if (m.row(), m.column()) != (row, column) {
+                return Err(JolinError::shape_mismatching_with((row, column), (m.row(), m.column())))
+            }
+            data.extend_from_slice(m.data());
+        }
+        Ok(Batch { len: mats.len(), row, column, data })
+    }
+
+    /// Count of matrices
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the batch has no matrix
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Row count of each matrix
+    pub fn row(&self) -> usize {
+        self.row
+    }
+
+    /// Column count of each matrix
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Data vector of all matrices
+    pub fn data(&self) -> &[T::Elem] {
+        &self.data
+    }
+
+    /// Data of the matrix at `i` in column-major order. Panics if `i` is out
+    /// of range.
+    pub fn matrix_data(&self, i: usize) -> &[T::Elem] {
+        let size = self.row * self.column;
+        &self.data[(i * size)..((i + 1) * size)]
+    }
+
+    /// Mutable data of the matrix at `i` in column-major order. Panics if
+    /// `i` is out of range.
+    pub fn matrix_data_mut(&mut self, i: usize) -> &mut [T::Elem] {
+        let size = self.row * self.column;
+        &mut self.data[(i * size)..((i + 1) * size)]
+    }
+
+    /// Copy the matrix at `i`. Panics if `i` is out of range.
+    pub fn get(&self, i: usize) -> T {
+        T::new(self.row, self.column, self.matrix_data(i))
+    }
+
+    /// Overwrite the matrix at `i`. Panics if `i` is out of range.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the shape of the matrix differs from the batch.
+    pub fn set(&mut self, i: usize, mat: &T) -> Result<(), JolinError> {
+        if (mat.row(), mat.column()) != (self.row, self.column) {
+            return Err(JolinError::shape_mismatching_with((self.row, self.column), (mat.row(), mat.column())))
+        }
+        self.matrix_data_mut(i).copy_from_slice(mat.data());
+        Ok(())
+    }
+
+    /// Copy all matrices out of the batch
+    pub fn to_matrices(&self) -> Vec<T> {
+        (0..self.len).map(|i| self.get(i)).collect()
+    }
+
+    /// Sum of the matrices at the same positions of two batches
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the lengths or the matrix shapes differ.
+    pub fn add(&self, other: &Batch<T>) -> Result<Batch<T>, JolinError> {
+        if self.len != other.len {
+            return Err(JolinError::length_mismatching(self.len, other.len))
+        }
+        if (self.row, self.column) != (other.row, other.column) {
+            return Err(JolinError::shape_mismatching_with((self.row, self.column), (other.row, other.column)))
+        }
+        let data = self.data.iter().zip(other.data.iter()).map(|(x, y)| *x + *y).collect();
+        Ok(Batch { data, ..*self })
+    }
+
+    /// Products of the matrices at the same positions of two batches
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the lengths differ, or the matrices of
+    ///    `self` have a column count other than the row count of `other`.
+    pub fn mul(&self, other: &Batch<T>) -> Result<Batch<T>, JolinError> {
+        if self.len != other.len {
+            return Err(JolinError::length_mismatching(self.len, other.len))
+        }
+        if self.column != other.row {
+            return Err(JolinError::shape_mismatching_with((self.column, other.column), (other.row, other.column)))
+        }
+        let (m, k, n) = (self.row, self.column, other.column);
+        let mut ans = Batch::zero(self.len, m, n);
+        for i in 0..self.len {
+            let a = self.matrix_data(i);
+            let b = other.matrix_data(i);
+            let c = ans.matrix_data_mut(i);
+            for col in 0..n {
+                for j in 0..k {
+                    let b_jc = b[j + col * k];
+                    for r in 0..m {
+                        c[r + col * m] = c[r + col * m] + a[r + j * m] * b_jc;
+                    }
+                }
+            }
+        }
+        Ok(ans)
+    }
+
+    /// Transposes of all matrices
+    pub fn tr(&self) -> Batch<T> {
+        let (m, n) = (self.row, self.column);
+        let mut ans = Batch::zero(self.len, n, m);
+        for i in 0..self.len {
+            let a = self.matrix_data(i);
+            let t = ans.matrix_data_mut(i);
+            for c in 0..n {
+                for r in 0..m {
+                    t[c + r * n] = a[r + c * m];
+                }
+            }
+        }
+        ans
+    }
+
+    /// LU decompositions of all matrices with the same pivoting as
+    /// `lu_inplace`.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the matrices are not square.
+    /// 2. Singular matrix - if any matrix is singular.
+    pub fn lu(&self) -> Result<BatchLU<T>, JolinError> {
+        if self.row != self.column {
+            return Err(JolinError::shape_mismatching_with((self.row, self.row), (self.row, self.column)))
+        }
+        let mut packed = self.clone();
+        let mut p = Vec::with_capacity(self.len * self.row);
+        for i in 0..self.len {
+            let mut mat = self.get(i);
+            p.extend(lu_inplace(&mut mat)?);
+            packed.matrix_data_mut(i).copy_from_slice(mat.data());
+        }
+        Ok(BatchLU { packed, p })
+    }
+
+    /// Solve `A_i X_i = B_i` for all matrices of the batches, see `lu` and
+    /// `BatchLU::solve`.
+    pub fn solve(&self, b: &Batch<T>) -> Result<Batch<T>, JolinError> {
+        self.lu()?.solve(b)
+    }
+}
+
+impl<T: Matrix> BatchLU<T> {
+    /// Solve `A_i X_i = B_i` with the factors of each `A_i`.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the lengths differ, or the row count of the
+    ///    matrices of `b` isn't the size of `A_i`.
+    pub fn solve(&self, b: &Batch<T>) -> Result<Batch<T>, JolinError> {
+        let n = self.packed.row;
+        if b.len != self.packed.len {
+            return Err(JolinError::length_mismatching(self.packed.len, b.len))
+        }
+        if b.row != n {
+            return Err(JolinError::shape_mismatching_with((n, b.column), (b.row, b.column)))
+        }
+        let mut ans = b.clone();
+        let mut y = vec![T::Elem::zero(); n];
+        for i in 0..b.len {
+            let lu = self.packed.matrix_data(i);
+            let p = &self.p[(i * n)..((i + 1) * n)];
+            let rhs = b.matrix_data(i);
+            let x = ans.matrix_data_mut(i);
+            for c in 0..b.column {
+                // L y = P b
+                for r in 0..n {
+                    let mut v = rhs[p[r] + c * n];
+                    for j in 0..r {
+                        v = v - lu[r + j * n] * y[j];
+                    }
+                    y[r] = v;
+                }
+                // U x = y
+                for r in (0..n).rev() {
+                    let mut v = y[r];
+                    for j in (r + 1)..n {
+                        v = v - lu[r + j * n] * x[j + c * n];
+                    }
+                    x[r + c * n] = v / lu[r + r * n];
+                }
+            }
+        }
+        Ok(ans)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::batch::{*};
+    use crate::error::{JolinError, JolinErrorKind};
+
+    #[test]
+    #[allow(clippy::needless_range_loop)]
+    fn test_batch_basics() {
+        let mats = [mat64![1.0, 2.0, 3.0; 4.0, 5.0, 6.0], mat64![-1.0, 0.0, 1.0; 2.0, 2.0, 2.0]];
+        let a = Batch::from_matrices(&mats).unwrap();
+        assert_eq!((a.len(), a.row(), a.column()), (2, 2, 3));
+        assert_eq!(a.to_matrices(), mats.to_vec());
+        assert_eq!(a.tr().get(1), tr(&mats[1]));
+        assert_eq!(a.add(&a).unwrap().get(0), add(&mats[0], &mats[0]).unwrap());
+
+        let prod = a.mul(&a.tr()).unwrap();
+        for i in 0..2 {
+            assert_eq!(prod.get(i), mul(&mats[i], &tr(&mats[i])).unwrap());
+        }
+        let err = a.mul(&a).unwrap_err();
+        assert_eq!(err.context(), JolinError::shape_mismatching_with((3, 3), (2, 3)).context());
+        let err = a.add(&a.tr()).unwrap_err();
+        assert_eq!(err.context(), JolinError::shape_mismatching_with((2, 3), (3, 2)).context());
+        let one = Batch::from_matrices(&mats[..1]).unwrap();
+        let err = a.mul(&one.tr()).unwrap_err();
+        assert_eq!(err.context(), JolinError::length_mismatching(2, 1).context());
+        assert_eq!(err.to_string(), "shape mismatching: expected 2 items, got 1");
+        assert_eq!(a.add(&one).unwrap_err().context(), JolinError::length_mismatching(2, 1).context());
+
+        let mut z: Batch<Mat64> = Batch::zero(2, 2, 3);
+        z.set(1, &mats[1]).unwrap();
+        assert_eq!(z.get(1), mats[1]);
+        assert!(z.set(0, &Mat64::zero(3, 2)).is_err());
+        assert_eq!(Batch::<Mat64>::from_matrices(&[]).unwrap_err().kind(), JolinErrorKind::NotEnoughInput);
+        assert!(Batch::from_matrices(&[Mat64::zero(1, 2), Mat64::zero(2, 1)]).is_err());
+        assert!(Batch::<Mat64>::from_vec(2, 2, 2, vec![0.0; 7]).is_err());
+        assert!(Batch::<Mat64>::zero(0, 3, 3).is_empty());
+    }
+
+    #[test]
+    fn test_batch_solve() {
+        let mats: Vec<Mat32> = (0..50).map(|i| {
+            let t = i as f32;
+            Mat32::new(3, 3, &[1.0, t, 2.0, 0.5, 3.0, -t, t, 1.0, 4.0])
+        }).collect();
+        let rhs: Vec<Mat32> = (0..50).map(|i| Mat32::new(3, 2, &[1.0, 2.0, i as f32, 0.0, -1.0, 1.0])).collect();
+        let a = Batch::from_matrices(&mats).unwrap();
+        let b = Batch::from_matrices(&rhs).unwrap();
+        let lud = a.lu().unwrap();
+        let x = lud.solve(&b).unwrap();
+        for i in 0..50 {
+            let residual = sub(&mul(&mats[i], &x.get(i)).unwrap(), &rhs[i]).unwrap();
+            assert!(residual.data().iter().all(|v| v.abs() < 1e-3));
+        }
+        assert_eq!(x, a.solve(&b).unwrap());
+        let err = lud.solve(&Batch::zero(50, 2, 1)).unwrap_err();
+        assert_eq!(err.context(), JolinError::shape_mismatching_with((3, 1), (2, 1)).context());
+        let err = lud.solve(&Batch::zero(49, 3, 1)).unwrap_err();
+        assert_eq!(err.context(), JolinError::length_mismatching(50, 49).context());
+
+        let singular = Batch::from_matrices(&[Mat64::identity(2), Mat64::zero(2, 2)]).unwrap();
+        assert_eq!(singular.lu().err().unwrap().kind(), JolinErrorKind::SingularMatrix);
+        assert!(Batch::<Mat64>::zero(1, 2, 3).lu().is_err());
+    }
+}
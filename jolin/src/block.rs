@@ -0,0 +1,425 @@
+/*
+ * block.rs
+ * Partitioned matrices with named blocks.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::cell::OnceCell;
+use crate::matrix::{Matrix, add, mul, sub, select};
+use crate::decomp::lu::{lu, lu_solve, LUDecomposition};
+use crate::error::JolinError;
+
+/// A matrix partitioned into a grid of blocks, e.g. the saddle point matrix
+/// `[A, B^T; B, 0]`. Each block may be named, and blocks never set are zero.
+///
+/// The dense form is assembled at the first call of `dense` and kept until a
+/// block is modified.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::block::BlockMatrix;
+/// let a = Mat64::identity(2);
+/// let b = mat64![1.0, 2.0];
+/// let mut kkt = BlockMatrix::new(&[2, 1], &[2, 1]);
+/// kkt.set("A", 0, 0, a).unwrap();
+/// kkt.set("B", 1, 0, b.clone()).unwrap();
+/// kkt.set("Bt", 0, 1, tr(&b)).unwrap();
+/// assert_eq!(kkt.dense(), &mat64![1.0, 0.0, 1.0; 0.0, 1.0, 2.0; 1.0, 2.0, 0.0]);
+/// assert_eq!(kkt.get("B"), Some(&b));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BlockMatrix<T: Matrix> {
+    row_sizes: Vec<usize>,
+    column_sizes: Vec<usize>,
+    /// Blocks stored row by row over the grid
+    blocks: Vec<Option<T>>,
+    names: Vec<(String, (usize, usize))>,
+    dense: OnceCell<T>,
+}
+
+impl<T: Matrix> BlockMatrix<T> {
+    /// Create a block matrix of zero blocks, where the block at `(i, j)` has
+    /// `row_sizes[i]` rows and `column_sizes[j]` columns.
+    pub fn new(row_sizes: &[usize], column_sizes: &[usize]) -> BlockMatrix<T> {
+        BlockMatrix {
+            row_sizes: row_sizes.to_vec(),
+            column_sizes: column_sizes.to_vec(),
+            blocks: vec![None; row_sizes.len() * column_sizes.len()],
+            names: Vec::new(),
+            dense: OnceCell::new(),
+        }
+    }
+
+    /// Create a block matrix from a grid of named blocks given row by row.
+    ///
+    /// Potential errors:
+    /// 1. Not enough input - if the grid is empty.
+    /// 2. Shape mismatching - if the rows of the grid have different lengths,
+    ///    or the blocks don't line up.
+    pub fn from_grid(grid: &[&[(&str, &T)]]) -> Result<BlockMatrix<T>, JolinError> {
+        if grid.is_empty() || grid[0].is_empty() {
+            return Err(JolinError::not_enough_input())
+        }
+        let row_sizes: Vec<usize> = grid.iter().map(|r| r[0].1.row()).collect();
+        let column_sizes: Vec<usize> = grid[0].iter().map(|b| b.1.column()).collect();
+        let mut ans = BlockMatrix::new(&row_sizes, &column_sizes);
+        for (i, r) in grid.iter().enumerate() {
+            if r.len() != column_sizes.len() {
+                return Err(JolinError::shape_mismatching())
+            }
+            for (j, (name, block)) in r.iter().enumerate() {
+                ans.set(name, i, j, (*block).clone())?;
+            }
+        }
+        Ok(ans)
+    }
+
+    /// Count of block rows
+    pub fn block_rows(&self) -> usize {
+        self.row_sizes.len()
+    }
+
+    /// Count of block columns
+    pub fn block_columns(&self) -> usize {
+        self.column_sizes.len()
+    }
+
+    /// Row count of the dense form
+    pub fn row(&self) -> usize {
+        self.row_sizes.iter().sum()
+    }
+
+    /// Column count of the dense form
+    pub fn column(&self) -> usize {
+        self.column_sizes.iter().sum()
+    }
+
+    /// Shape `(rows, columns)` of the block at `(i, j)`
+    pub fn block_shape(&self, i: usize, j: usize) -> (usize, usize) {
+        (self.row_sizes[i], self.column_sizes[j])
+    }
+
+    /// Set the block at `(i, j)` and name it. A name already used by another
+    /// block is moved to this block.
+    ///
+    /// Potential errors:
+    /// 1. Invalid value - if `(i, j)` is out of the grid.
+    /// 2. Shape mismatching - if the shape of the block doesn't fit the partition.
+    pub fn set(&mut self, name: &str, i: usize, j: usize, block: T) -> Result<(), JolinError> {
+        if i >= self.block_rows() || j >= self.block_columns() {
+            return Err(JolinError::invalid_value())
+        }
+        if (block.row(), block.column()) != self.block_shape(i, j) {
+            return Err(JolinError::shape_mismatching())
+        }
+        self.names.retain(|(n, pos)| n != name && *pos != (i, j));
+        self.names.push((name.to_string(), (i, j)));
+        let k = self.slot(i, j);
+        self.blocks[k] = Some(block);
+        self.dense.take();
+        Ok(())
+    }
+
+    /// Get a block by its name
+    pub fn get(&self, name: &str) -> Option<&T> {
+        let (i, j) = self.position(name)?;
+        self.at(i, j)
+    }
+
+    /// Get a mutable block by its name. The assembled dense form is dropped.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut T> {
+        let (i, j) = self.position(name)?;
+        self.dense.take();
+        let k = self.slot(i, j);
+        self.blocks[k].as_mut()
+    }
+
+    /// Get the block at `(i, j)`, or `None` for a zero block
+    pub fn at(&self, i: usize, j: usize) -> Option<&T> {
+        self.blocks[self.slot(i, j)].as_ref()
+    }
+
+    /// Get the position `(i, j)` of a named block
+    pub fn position(&self, name: &str) -> Option<(usize, usize)> {
+        self.names.iter().find(|(n, _)| n == name).map(|(_, pos)| *pos)
+    }
+
+    /// Get the dense form of the matrix
+    pub fn dense(&self) -> &T {
+        self.dense.get_or_init(|| {
+            let mut ans = T::zero(self.row(), self.column());
+            let mut r0 = 0;
+            for i in 0..self.block_rows() {
+                let mut c0 = 0;
+                for j in 0..self.block_columns() {
+                    if let Some(block) = self.at(i, j) {
+                        for c in 0..block.column() {
+                            for r in 0..block.row() {
+                                *ans.elem_mut(r0 + r, c0 + c) = block.elem(r, c);
+                            }
+                        }
+                    }
+                    c0 += self.column_sizes[j];
+                }
+                r0 += self.row_sizes[i];
+            }
+            ans
+        })
+    }
+
+    fn slot(&self, i: usize, j: usize) -> usize {
+        i * self.block_columns() + j
+    }
+}
+
+/// Add two block matrices block by block. The answer keeps the names of `a`.
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the matrices are partitioned differently.
+pub fn block_add<T: Matrix>(a: &BlockMatrix<T>, b: &BlockMatrix<T>) -> Result<BlockMatrix<T>, JolinError> {
+    if a.row_sizes != b.row_sizes || a.column_sizes != b.column_sizes {
+        return Err(JolinError::shape_mismatching())
+    }
+    let mut ans = BlockMatrix::new(&a.row_sizes, &a.column_sizes);
+    for (k, (x, y)) in a.blocks.iter().zip(b.blocks.iter()).enumerate() {
+        ans.blocks[k] = match (x, y) {
+            (Some(x), Some(y)) => Some(add(x, y)?),
+            (Some(x), None) => Some(x.clone()),
+            (None, y) => y.clone(),
+        };
+    }
+    ans.names = a.names.clone();
+    Ok(ans)
+}
+
+/// Multiply two block matrices block by block, skipping the zero blocks. The
+/// answer has no names.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::block::{BlockMatrix, block_mul};
+/// let a = BlockMatrix::from_grid(&[&[("A", &mat64![2.0]), ("B", &mat64![1.0, 1.0])]]).unwrap();
+/// let b = BlockMatrix::from_grid(&[&[("C", &mat64![1.0])], &[("D", &mat64![3.0; 4.0])]]).unwrap();
+/// assert_eq!(block_mul(&a, &b).unwrap().dense(), &mat64![9.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the column partition of `a` differs from the row partition of `b`.
+pub fn block_mul<T: Matrix>(a: &BlockMatrix<T>, b: &BlockMatrix<T>) -> Result<BlockMatrix<T>, JolinError> {
+    if a.column_sizes != b.row_sizes {
+        return Err(JolinError::shape_mismatching())
+    }
+    let mut ans = BlockMatrix::new(&a.row_sizes, &b.column_sizes);
+    for i in 0..a.block_rows() {
+        for j in 0..b.block_columns() {
+            let mut sum: Option<T> = None;
+            for k in 0..a.block_columns() {
+                if let (Some(x), Some(y)) = (a.at(i, k), b.at(k, j)) {
+                    let p = mul(x, y)?;
+                    sum = Some(match sum {
+                        Some(s) => add(&s, &p)?,
+                        None => p,
+                    });
+                }
+            }
+            let slot = ans.slot(i, j);
+            ans.blocks[slot] = sum;
+        }
+    }
+    Ok(ans)
+}
+
+/// Compute the Schur complement of the named diagonal block of a 2-by-2 block
+/// matrix. For `M = [A, B; C, D]`, it is `D - C A^-1 B` with the pivot `A`,
+/// and `A - B D^-1 C` with the pivot `D`. The answer keeps the factorization
+/// of the pivot, see `SchurComplement`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::block::{BlockMatrix, schur_complement};
+/// let m = BlockMatrix::from_grid(&[
+///     &[("A", &mat64![2.0]), ("B", &mat64![1.0])],
+///     &[("C", &mat64![4.0]), ("D", &mat64![3.0])],
+/// ]).unwrap();
+/// let s = schur_complement(&m, "A").unwrap();
+/// assert_eq!(s.complement, mat64![1.0]);
+/// assert_eq!(s.pivot.solve(&mat64![4.0]).unwrap(), mat64![2.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the matrix is not partitioned into 2-by-2 blocks,
+///    or the pivot block is not square.
+/// 2. Invalid value - if no diagonal block has the name.
+/// 3. Singular matrix - if the pivot block is zero or singular.
+pub fn schur_complement<T: Matrix>(m: &BlockMatrix<T>, pivot: &str) -> Result<SchurComplement<T>, JolinError> {
+    if m.block_rows() != 2 || m.block_columns() != 2 {
+        return Err(JolinError::shape_mismatching())
+    }
+    let p = match m.position(pivot) {
+        Some((i, j)) if i == j => i,
+        _ => return Err(JolinError::invalid_value()),
+    };
+    let q = 1 - p;
+    let pivot_block = match m.at(p, p) {
+        Some(block) => block,
+        None => return Err(JolinError::singular_matrix()),
+    };
+    let block = |i: usize, j: usize| match m.at(i, j) {
+        Some(block) => block.clone(),
+        None => T::zero(m.row_sizes[i], m.column_sizes[j]),
+    };
+    SchurComplement::from_blocks(pivot_block, &block(p, q), &block(q, p), &block(q, q))
+}
+
+/// Schur complement `S = D - C A^-1 B` of the pivot block `A` in
+/// `M = [A, B; C, D]`, together with the factorization of `A`, which can be
+/// reused for block elimination, e.g. to solve with `A` again. With the pivot
+/// `D`, swap the roles of `A` and `D`, and of `B` and `C`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::block::SchurComplement;
+/// let m = mat64![2.0, 1.0, 0.0; 4.0, 3.0, 1.0; 0.0, 1.0, 5.0];
+/// let s = SchurComplement::from_dense(&m, 1).unwrap();
+/// assert_eq!(s.complement, mat64![1.0, 1.0; 1.0, 5.0]);
+/// assert_eq!(s.pivot.solve(&mat64![4.0]).unwrap(), mat64![2.0]);
+/// ```
+pub struct SchurComplement<T: Matrix> {
+    /// LU decomposition of the pivot block `A`
+    pub pivot: LUDecomposition<T>,
+    /// The Schur complement `D - C A^-1 B`
+    pub complement: T,
+}
+
+impl<T: Matrix> SchurComplement<T> {
+    /// Compute the Schur complement from the blocks of `M = [A, B; C, D]`.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if `A` or `D` is not square, or the blocks
+    ///    don't line up.
+    /// 2. Singular matrix - if `A` is singular.
+    pub fn from_blocks(a: &T, b: &T, c: &T, d: &T) -> Result<SchurComplement<T>, JolinError> {
+        let (k, n) = (a.row(), d.row());
+        let lines_up = a.column() == k && d.column() == n
+            && (b.row(), b.column()) == (k, n) && (c.row(), c.column()) == (n, k);
+        if !lines_up {
+            return Err(JolinError::shape_mismatching())
+        }
+        let pivot = lu(a)?;
+        let complement = sub(d, &mul(c, &lu_solve(&pivot, b))?)?;
+        Ok(SchurComplement { pivot, complement })
+    }
+
+    /// Compute the Schur complement of the leading `block_size * block_size`
+    /// block of a square matrix.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the matrix is not square.
+    /// 2. Invalid value - if the block size is zero or larger than the matrix.
+    /// 3. Singular matrix - if the leading block is singular.
+    pub fn from_dense(m: &T, block_size: usize) -> Result<SchurComplement<T>, JolinError> {
+        if m.row() != m.column() {
+            return Err(JolinError::shape_mismatching())
+        }
+        if block_size == 0 || block_size > m.row() {
+            return Err(JolinError::invalid_value())
+        }
+        let head: Vec<usize> = (0..block_size).collect();
+        let tail: Vec<usize> = (block_size..m.row()).collect();
+        Self::from_blocks(
+            &select(m, &head, &head)?, &select(m, &head, &tail)?,
+            &select(m, &tail, &head)?, &select(m, &tail, &tail)?,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+    use crate::block::{*};
+
+    #[test]
+    fn test_block_matrix() {
+        let mut m: BlockMatrix<Mat64> = BlockMatrix::new(&[1, 2], &[2, 1]);
+        assert_eq!(m.dense(), &Mat64::zero(3, 3));
+        m.set("A", 0, 0, mat64![1.0, 2.0]).unwrap();
+        m.set("D", 1, 1, mat64![3.0; 4.0]).unwrap();
+        assert_eq!(m.dense(), &mat64![1.0, 2.0, 0.0; 0.0, 0.0, 3.0; 0.0, 0.0, 4.0]);
+        *m.get_mut("D").unwrap().elem_mut(0, 0) = 5.0;
+        assert_eq!(m.dense().elem(1, 2), 5.0);
+        // moving a name
+        m.set("A", 1, 0, Mat64::identity(2)).unwrap();
+        assert_eq!(m.position("A"), Some((1, 0)));
+        assert_eq!(m.at(0, 0), Some(&mat64![1.0, 2.0]));
+        assert_eq!(m.set("X", 0, 0, Mat64::zero(2, 2)).unwrap_err().kind(), JolinErrorKind::ShapeMismatching);
+        assert_eq!(m.set("X", 2, 0, Mat64::zero(1, 2)).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        assert!(m.get("X").is_none());
+    }
+
+    #[test]
+    fn test_block_arithmetic() {
+        let a = mat64![1.0, 2.0; 3.0, 4.0];
+        let b = mat64![1.0; 1.0];
+        let c = mat64![2.0, 0.0];
+        let d = mat64![5.0];
+        let m = BlockMatrix::from_grid(&[&[("A", &a), ("B", &b)], &[("C", &c), ("D", &d)]]).unwrap();
+        let sum = block_add(&m, &m).unwrap();
+        assert_eq!(sum.dense(), &add(m.dense(), m.dense()).unwrap());
+        assert!(sum.get("C").is_some());
+        let prod = block_mul(&m, &m).unwrap();
+        assert_eq!(prod.dense(), &mul(m.dense(), m.dense()).unwrap());
+
+        let s = schur_complement(&m, "A").unwrap();
+        let expected = sub(&d, &mul(&c, &mul(&inv2(&a), &b).unwrap()).unwrap()).unwrap();
+        assert!(eq_with_error(&s.complement, &expected, 1e-12));
+        assert_eq!(s.complement, SchurComplement::from_blocks(&a, &b, &c, &d).unwrap().complement);
+        let s = schur_complement(&m, "D").unwrap();
+        let expected = sub(&a, &elemwise(&mul(&b, &c).unwrap(), |x| x / 5.0)).unwrap();
+        assert!(eq_with_error(&s.complement, &expected, 1e-12));
+        assert_eq!(s.pivot.solve(&mat64![10.0]).unwrap(), mat64![2.0]);
+        // a missing block is zero
+        let mut upper: BlockMatrix<Mat64> = BlockMatrix::new(&[2, 1], &[2, 1]);
+        upper.set("A", 0, 0, a.clone()).unwrap();
+        upper.set("B", 0, 1, b.clone()).unwrap();
+        upper.set("D", 1, 1, d.clone()).unwrap();
+        assert_eq!(schur_complement(&upper, "A").unwrap().complement, d);
+        assert_eq!(schur_complement(&m, "B").err().unwrap().kind(), JolinErrorKind::InvalidValue);
+
+        let wide = BlockMatrix::from_grid(&[&[("A", &a), ("B", &b)]]).unwrap();
+        assert!(block_add(&m, &wide).is_err());
+        assert!(block_mul(&wide, &wide).is_err());
+        assert!(schur_complement(&wide, "A").is_err());
+    }
+
+    #[test]
+    fn test_schur_complement_of_dense() {
+        let m = mat64![4.0, 1.0, 2.0, 0.5; 1.0, 3.0, 0.0, 1.0; 2.0, 0.0, 5.0, 1.0; 0.5, 1.0, 1.0, 6.0];
+        let (a, b) = (mat64![4.0, 1.0; 1.0, 3.0], mat64![2.0, 0.5; 0.0, 1.0]);
+        let (c, d) = (tr(&b), mat64![5.0, 1.0; 1.0, 6.0]);
+        let s = SchurComplement::from_dense(&m, 2).unwrap();
+        let expected = sub(&d, &mul(&c, &mul(&inv2(&a), &b).unwrap()).unwrap()).unwrap();
+        assert!(eq_with_error(&s.complement, &expected, 1e-12));
+        let from_blocks = SchurComplement::from_blocks(&a, &b, &c, &d).unwrap();
+        assert_eq!(from_blocks.complement, s.complement);
+        // det(M) = det(A) det(S)
+        let det_s = s.complement.elem(0, 0) * s.complement.elem(1, 1) - s.complement.elem(0, 1) * s.complement.elem(1, 0);
+        assert!((s.pivot.det() * det_s - crate::det::det(&m).unwrap()).abs() < 1e-9);
+
+        let full = SchurComplement::from_dense(&m, 4).unwrap();
+        assert_eq!((full.complement.row(), full.complement.column()), (0, 0));
+        assert_eq!(SchurComplement::from_dense(&m, 0).err().unwrap().kind(), JolinErrorKind::InvalidValue);
+        assert!(SchurComplement::from_dense(&Mat64::zero(2, 3), 1).is_err());
+        assert_eq!(SchurComplement::from_dense(&Mat64::zero(2, 2), 1).err().unwrap().kind(), JolinErrorKind::SingularMatrix);
+        assert!(SchurComplement::from_blocks(&a, &c, &b, &d).is_ok());
+        assert!(SchurComplement::from_blocks(&a, &mat64![1.0; 1.0], &c, &d).is_err());
+    }
+
+    fn inv2(a: &Mat64) -> Mat64 {
+        let det = a.elem(0, 0) * a.elem(1, 1) - a.elem(0, 1) * a.elem(1, 0);
+        mat64![a.elem(1, 1) / det, -a.elem(0, 1) / det; -a.elem(1, 0) / det, a.elem(0, 0) / det]
+    }
+}
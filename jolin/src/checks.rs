@@ -0,0 +1,174 @@
+/*
+ * checks.rs
+ * Predicates of matrix structures and values.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, MatrixView, LikeNumber, trmul, sub, find};
+use crate::decomp::cholesky::cholesky;
+
+/// Triangle of a matrix
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Triangle {
+    /// Entries on and above the diagonal
+    Upper,
+    /// Entries on and below the diagonal
+    Lower,
+}
+
+/// Check whether the matrix is square and `|A[i, j] - A[j, i]| <= tol` for
+/// all entries. A matrix containing NaN off the diagonal is not symmetric.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::checks::is_symmetric;
+/// assert!(is_symmetric(&mat64![1.0, 2.0; 2.0 + 1e-12, 3.0], 1e-10));
+/// assert!(!is_symmetric(&mat64![1.0, 2.0; 2.1, 3.0], 1e-10));
+/// ```
+pub fn is_symmetric<T: MatrixView>(mat: &T, tol: T::Elem) -> bool {
+    mat.row() == mat.column() && (0..mat.row())
+        .all(|r| (0..r).all(|c| (mat.elem(r, c) - mat.elem(c, r)).abs() <= tol))
+}
+
+/// Check whether the matrix is square and `|A^T A - I|` is at most `tol`
+/// element-wisely.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::checks::is_orthogonal;
+/// let s = 0.5f64.sqrt();
+/// assert!(is_orthogonal(&mat64![s, -s; s, s], 1e-12));
+/// assert!(!is_orthogonal(&mat64![1.0, 1.0; 0.0, 1.0], 1e-12));
+/// ```
+pub fn is_orthogonal<T: Matrix>(mat: &T, tol: T::Elem) -> bool {
+    if mat.row() != mat.column() {
+        return false
+    }
+    let gram = trmul(mat, mat).unwrap();
+    let diff = sub(&gram, &T::identity(mat.row())).unwrap();
+    diff.data().iter().all(|x| x.abs() <= tol)
+}
+
+/// Check whether the matrix is symmetric within `tol` and positive definite.
+/// The definiteness is decided by attempting the Cholesky decomposition.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::checks::is_positive_definite;
+/// assert!(is_positive_definite(&mat64![2.0, 1.0; 1.0, 2.0], 1e-12));
+/// assert!(!is_positive_definite(&mat64![1.0, 2.0; 2.0, 1.0], 1e-12));
+/// ```
+pub fn is_positive_definite<T: Matrix>(mat: &T, tol: T::Elem) -> bool {
+    is_symmetric(mat, tol) && cholesky(mat).is_ok()
+}
+
+/// Check whether the entries outside the given triangle are at most `tol` in
+/// absolute value. Rectangular matrices are allowed.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::checks::{is_triangular, Triangle};
+/// let a = mat64![1.0, 2.0, 3.0; 0.0, 4.0, 5.0];
+/// assert!(is_triangular(&a, Triangle::Upper, 0.0));
+/// assert!(!is_triangular(&a, Triangle::Lower, 0.0));
+/// ```
+pub fn is_triangular<T: MatrixView>(mat: &T, triangle: Triangle, tol: T::Elem) -> bool {
+    (0..mat.column()).all(|c| {
+        let outside = match triangle {
+            Triangle::Upper => usize::min(c + 1, mat.row())..mat.row(),
+            Triangle::Lower => 0..usize::min(c, mat.row()),
+        };
+        outside.into_iter().all(|r| mat.elem(r, c).abs() <= tol)
+    })
+}
+
+/// Check whether any element of the matrix is NaN.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::checks::has_nan;
+/// assert!(has_nan(&mat64![1.0, f64::NAN]));
+/// assert!(!has_nan(&mat64![1.0, f64::INFINITY]));
+/// ```
+pub fn has_nan<T: MatrixView>(mat: &T) -> bool {
+    mat.data().iter().any(|x| x.to_f64().is_nan())
+}
+
+/// Check whether any element of the matrix is positive or negative infinity.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::checks::has_inf;
+/// assert!(has_inf(&mat64![1.0, f64::NEG_INFINITY]));
+/// assert!(!has_inf(&mat64![1.0, f64::NAN]));
+/// ```
+pub fn has_inf<T: MatrixView>(mat: &T) -> bool {
+    mat.data().iter().any(|x| x.to_f64().is_infinite())
+}
+
+/// Check whether all elements of the matrix are neither NaN nor infinity.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::checks::is_finite;
+/// assert!(is_finite(&mat64![1.0, 2.0]));
+/// assert!(!is_finite(&mat64![1.0, f64::NAN]));
+/// ```
+pub fn is_finite<T: MatrixView>(mat: &T) -> bool {
+    mat.data().iter().all(|x| x.to_f64().is_finite())
+}
+
+/// Find the `(row, column)` indices of the NaN and infinite elements in
+/// column-major order.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::checks::find_non_finite;
+/// let a = mat64![1.0, f64::INFINITY; f64::NAN, 4.0];
+/// assert_eq!(find_non_finite(&a), vec![(1, 0), (0, 1)]);
+/// ```
+pub fn find_non_finite<T: MatrixView>(mat: &T) -> Vec<(usize, usize)> {
+    find(mat, |x| !x.to_f64().is_finite())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::checks::{*};
+
+    #[test]
+    fn test_checks() {
+        let a = mat64![4.0, 1.0, 0.0; 1.0, 3.0, 1.0; 0.0, 1.0, 2.0];
+        assert!(is_symmetric(&a, 0.0));
+        assert!(is_positive_definite(&a, 0.0));
+        assert!(!is_symmetric(&mat64![1.0, 2.0], 1.0));
+        assert!(!is_symmetric(&mat64![1.0, f64::NAN; 1.0, 1.0], 1.0));
+        assert!(!is_positive_definite(&mat64![1.0, 0.0; 0.0, -1.0], 0.0));
+        assert!(is_orthogonal(&mat64![0.0, 1.0; 1.0, 0.0], 0.0));
+        assert!(!is_orthogonal(&mat64![1.0; 0.0], 1.0));
+
+        let l = mat64![1.0, 0.0; 2.0, 3.0; 4.0, 5.0];
+        assert!(is_triangular(&l, Triangle::Lower, 0.0));
+        assert!(!is_triangular(&l, Triangle::Upper, 1.0));
+        assert!(is_triangular(&l, Triangle::Upper, 5.0));
+        assert!(is_triangular(&Mat64::identity(3), Triangle::Upper, 0.0));
+        assert!(is_triangular(&Mat64::identity(3), Triangle::Lower, 0.0));
+    }
+
+    #[test]
+    fn test_non_finite() {
+        let a = Mat32::new(2, 2, &[1.0, f32::NAN, f32::INFINITY, 2.0]);
+        assert!(has_nan(&a));
+        assert!(has_inf(&a));
+        assert!(!is_finite(&a));
+        assert_eq!(find_non_finite(&a), vec![(1, 0), (0, 1)]);
+        let b = Mat64::identity(3);
+        assert!(!has_nan(&b) && !has_inf(&b) && is_finite(&b));
+        assert!(find_non_finite(&b).is_empty());
+        assert!(is_finite(&Mat64::zero(0, 2)));
+        assert!(find_non_finite(&Mat64::zero(0, 2)).is_empty());
+    }
+}
@@ -0,0 +1,169 @@
+/*
+ * circulant.rs
+ * Circulant matrices with fast multiplication.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber};
+use crate::error::JolinError;
+use crate::fft::{Complex, fft, ifft};
+
+/// Circulant matrix of shape `n * n`, where each column is the previous one
+/// rotated down by one. Only the first column is stored.
+///
+/// All circulant matrices are diagonalized by the discrete Fourier
+/// transform, so multiplication and solving take `O(n log n)` with FFT,
+/// and the eigenvalues are the transform of the first column.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::circulant::Circulant;
+/// let c: Circulant<Mat64> = Circulant::new(vec![1.0, 2.0, 3.0]);
+/// assert_eq!(c.dense(), mat64![1.0, 3.0, 2.0; 2.0, 1.0, 3.0; 3.0, 2.0, 1.0]);
+/// let x = mat64![1.0; 0.0; 1.0];
+/// assert!(eq_with_error(&c.mul(&x).unwrap(), &mat64![3.0; 5.0; 4.0], 1e-12));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Circulant<T: Matrix> {
+    column: Vec<T::Elem>,
+}
+
+impl<T: Matrix> Circulant<T> {
+    /// Create a circulant matrix from its first column
+    pub fn new(first_column: Vec<T::Elem>) -> Circulant<T> {
+        Circulant { column: first_column }
+    }
+
+    /// Row and column count of the matrix
+    pub fn size(&self) -> usize {
+        self.column.len()
+    }
+
+    /// The first column
+    pub fn first_column(&self) -> &[T::Elem] {
+        &self.column
+    }
+
+    /// The dense form of the matrix, where the element at `(i, j)` is
+    /// `c[(i - j) mod n]`
+    pub fn dense(&self) -> T {
+        let n = self.size();
+        let mut ans = T::zero(n, n);
+        for c in 0..n {
+            for r in 0..n {
+                *ans.elem_mut(r, c) = self.column[(r + n - c) % n];
+            }
+        }
+        ans
+    }
+
+    /// Eigenvalues of the matrix, i.e. the discrete Fourier transform of the
+    /// first column, as pairs of the real and imaginary parts
+    pub fn eigenvalues(&self) -> Vec<(f64, f64)> {
+        self.spectrum().iter().map(|v| (v.re, v.im)).collect()
+    }
+
+    /// Multiply the matrix with `b` in `O(n log n)` per column of `b`
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the row count of `b` isn't the size.
+    pub fn mul(&self, b: &T) -> Result<T, JolinError> {
+        self.apply_spectrum(b, &self.spectrum())
+    }
+
+    /// Solve `C X = B` in `O(n log n)` per column of `B`
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the row count of `b` isn't the size.
+    /// 2. Singular matrix - if an eigenvalue is zero up to rounding errors.
+    pub fn solve(&self, b: &T) -> Result<T, JolinError> {
+        if b.row() != self.size() {
+            return Err(JolinError::shape_mismatching())
+        }
+        self.apply_spectrum(b, &self.inverse_spectrum()?)
+    }
+
+    /// Determinant, the product of the eigenvalues
+    pub fn det(&self) -> T::Elem {
+        let product = self.spectrum().iter().fold(Complex::new(1.0, 0.0), |acc, v| acc * *v);
+        // the imaginary part is a rounding error, as the eigenvalues of a
+        // real matrix are in conjugate pairs
+        T::Elem::one().times_real(product.re)
+    }
+
+    /// Inverse matrix, which is also circulant
+    ///
+    /// Potential errors:
+    /// 1. Singular matrix - if an eigenvalue is zero up to rounding errors.
+    pub fn inverse(&self) -> Result<Circulant<T>, JolinError> {
+        let column = ifft(&self.inverse_spectrum()?);
+        Ok(Circulant::new(column.iter().map(|v| T::Elem::one().times_real(v.re)).collect()))
+    }
+
+    fn spectrum(&self) -> Vec<Complex> {
+        let c: Vec<Complex> = self.column.iter().map(|v| Complex::new(v.to_f64(), 0.0)).collect();
+        fft(&c)
+    }
+
+    /// Reciprocals of the eigenvalues
+    fn inverse_spectrum(&self) -> Result<Vec<Complex>, JolinError> {
+        let spectrum = self.spectrum();
+        let max = spectrum.iter().map(|v| v.abs()).fold(0.0, f64::max);
+        let tol = max * self.size() as f64 * T::Elem::epsilon().to_f64();
+        if spectrum.iter().any(|v| v.abs() <= tol) {
+            return Err(JolinError::singular_matrix())
+        }
+        let one = Complex::new(1.0, 0.0);
+        Ok(spectrum.iter().map(|v| one / *v).collect())
+    }
+
+    /// Multiply each column of `b` by the circulant matrix with the
+    /// eigenvalues `spectrum`, in the frequency domain
+    fn apply_spectrum(&self, b: &T, spectrum: &[Complex]) -> Result<T, JolinError> {
+        if b.row() != self.size() {
+            return Err(JolinError::shape_mismatching())
+        }
+        let mut ans = T::zero(b.row(), b.column());
+        for c in 0..b.column() {
+            let x: Vec<Complex> = b.data_column(c).iter().map(|v| Complex::new(v.to_f64(), 0.0)).collect();
+            let y: Vec<Complex> = fft(&x).iter().zip(spectrum.iter()).map(|(u, v)| *u * *v).collect();
+            for (r, v) in ifft(&y).iter().enumerate() {
+                *ans.elem_mut(r, c) = T::Elem::one().times_real(v.re);
+            }
+        }
+        Ok(ans)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+    use crate::circulant::{*};
+    use crate::det::det;
+
+    #[test]
+    fn test_circulant() {
+        let c: Circulant<Mat64> = Circulant::new(vec![4.0, -1.0, 0.5, 2.0, 1.0]);
+        let dense = c.dense();
+        let b = mat64![1.0, 0.0; 2.0, 1.0; -1.0, 0.0; 0.5, 3.0; 0.0, 1.0];
+        assert!(eq_with_error(&c.mul(&b).unwrap(), &mul(&dense, &b).unwrap(), 1e-12));
+        let x = c.solve(&b).unwrap();
+        assert!(eq_with_error(&mul(&dense, &x).unwrap(), &b, 1e-12));
+        assert!(eq_with_error(&mul(&c.inverse().unwrap().dense(), &dense).unwrap(), &Mat64::identity(5), 1e-12));
+        assert!((c.det() - det(&dense).unwrap()).abs() < 1e-9);
+        let (re, im) = c.eigenvalues()[0];
+        assert!((re - 6.5).abs() < 1e-12 && im.abs() < 1e-12);
+
+        let err = c.mul(&Mat64::zero(4, 1)).unwrap_err();
+        assert_eq!(err.kind(), JolinErrorKind::ShapeMismatching);
+        // every row sums to zero
+        let singular: Circulant<Mat64> = Circulant::new(vec![1.0, -1.0, 0.0, 0.0]);
+        assert_eq!(singular.inverse().unwrap_err().kind(), JolinErrorKind::SingularMatrix);
+        assert!(singular.solve(&Mat64::zero(4, 1)).is_err());
+        assert!(singular.det().abs() < 1e-12);
+    }
+}
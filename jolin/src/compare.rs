@@ -0,0 +1,215 @@
+/*
+ * compare.rs
+ * Fuzzy comparison of matrices with diagnostics, mainly for tests.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::fmt;
+use crate::matrix::{Matrix, LikeNumber};
+use crate::error::JolinError;
+
+/// Report of the element-wise difference between two matrices of the same
+/// shape
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixDiff<T: Matrix> {
+    /// Maximal absolute difference `|a - b|`
+    pub max_abs_diff: T::Elem,
+    /// Maximal relative difference `|a - b| / max(|a|, |b|)`. Equal elements
+    /// have zero relative difference, including the case that both are zero.
+    pub max_rel_diff: T::Elem,
+    /// Location `(row, column)` of the element with the maximal absolute
+    /// difference, or `None` if the matrices are empty
+    pub worst: Option<(usize, usize)>,
+    /// Count of elements where either matrix is NaN
+    pub nan_count: usize,
+}
+
+/// Compute the element-wise difference report of two matrices.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::compare::mat_diff;
+/// let d = mat_diff(&mat64![1.0, 2.0; 3.0, 4.0], &mat64![1.0, 2.5; 3.0, 4.0]).unwrap();
+/// assert_eq!(d.max_abs_diff, 0.5);
+/// assert_eq!(d.max_rel_diff, 0.2);
+/// assert_eq!(d.worst, Some((0, 1)));
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the shapes of the matrices don't match.
+pub fn mat_diff<T: Matrix>(a: &T, b: &T) -> Result<MatrixDiff<T>, JolinError> {
+    if a.row() != b.row() || a.column() != b.column() {
+        return Err(JolinError::shape_mismatching())
+    }
+    let zero = T::Elem::zero();
+    let mut diff = MatrixDiff { max_abs_diff: zero, max_rel_diff: zero, worst: None, nan_count: 0 };
+    for c in 0..a.column() {
+        for r in 0..a.row() {
+            let (x, y) = (a.elem(r, c), b.elem(r, c));
+            if x.partial_cmp(&x).is_none() || y.partial_cmp(&y).is_none() {
+                diff.nan_count += 1;
+                continue;
+            }
+            if x == y {
+                // also covers equal infinities
+                if diff.worst.is_none() {
+                    diff.worst = Some((r, c));
+                }
+                continue;
+            }
+            let d = (x - y).abs();
+            if diff.worst.is_none() || d > diff.max_abs_diff {
+                diff.max_abs_diff = d;
+                diff.worst = Some((r, c));
+            }
+            let scale = if x.abs() > y.abs() { x.abs() } else { y.abs() };
+            let rel = d / scale;
+            if rel > diff.max_rel_diff {
+                diff.max_rel_diff = rel;
+            }
+        }
+    }
+    Ok(diff)
+}
+
+/// Check whether every pair of elements satisfies
+/// `|a - b| <= abs + rel * max(|a|, |b|)`. NaN is never equal to anything.
+///
+/// The error message contains the difference report, and it is used by
+/// `assert_mat_eq!`.
+pub fn check_mat_eq<T: Matrix>(a: &T, b: &T, abs: T::Elem, rel: T::Elem) -> Result<(), String>
+    where T::Elem: fmt::Display {
+    let diff = match mat_diff(a, b) {
+        Ok(diff) => diff,
+        Err(_) => return Err(format!(
+            "matrices have different shapes: {}x{} and {}x{}", a.row(), a.column(), b.row(), b.column()
+        )),
+    };
+    let mut first = None;
+    for c in 0..a.column() {
+        for r in 0..a.row() {
+            let (x, y) = (a.elem(r, c), b.elem(r, c));
+            if x == y {
+                continue;
+            }
+            // NaN fails the comparison below as well
+            let scale = if x.abs() > y.abs() { x.abs() } else { y.abs() };
+            if (x - y).abs() <= abs + rel * scale {
+                continue;
+            }
+            if first.is_none() {
+                first = Some((r, c));
+            }
+        }
+    }
+    if let Some((r, c)) = first {
+        let worst = match diff.worst {
+            Some((r, c)) => format!(" at ({}, {}): {} vs {}", r, c, a.elem(r, c), b.elem(r, c)),
+            None => String::new(),
+        };
+        return Err(format!(
+            "matrices are not equal with abs = {}, rel = {}\n{}\nfirst differing element at ({}, {}): {} vs {}\nworst element{}",
+            abs, rel, diff, r, c, a.elem(r, c), b.elem(r, c), worst
+        ))
+    }
+    Ok(())
+}
+
+impl<T: Matrix> fmt::Display for MatrixDiff<T> where T::Elem: fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "max abs diff: {}, max rel diff: {}, NaN count: {}",
+            self.max_abs_diff, self.max_rel_diff, self.nan_count)
+    }
+}
+
+/// Assert two matrices are equal up to the absolute and/or relative
+/// tolerance, i.e. `|a - b| <= abs + rel * max(|a|, |b|)` for all elements.
+/// Both tolerances default to zero, and a single tolerance without a name is
+/// the absolute one. On failure, the panic message reports the maximal
+/// differences, the first differing element in column-major order and the
+/// worst element.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::{mat64, assert_mat_eq};
+/// let a = mat64![1.0, 2.0; 3.0, 4.0];
+/// let b = mat64![1.0, 2.0 + 1e-12; 3.0, 4.0];
+/// assert_mat_eq!(a, b, 1e-9);
+/// assert_mat_eq!(a, b, abs = 1e-9);
+/// assert_mat_eq!(a, b, rel = 1e-9);
+/// assert_mat_eq!(a, b, abs = 0.0, rel = 1e-9);
+/// ```
+#[macro_export]
+macro_rules! assert_mat_eq {
+    ($a: expr, $b: expr) => {
+        $crate::assert_mat_eq!($a, $b, abs = 0.0, rel = 0.0)
+    };
+    ($a: expr, $b: expr, abs = $abs: expr) => {
+        $crate::assert_mat_eq!($a, $b, abs = $abs, rel = 0.0)
+    };
+    ($a: expr, $b: expr, rel = $rel: expr) => {
+        $crate::assert_mat_eq!($a, $b, abs = 0.0, rel = $rel)
+    };
+    ($a: expr, $b: expr, abs = $abs: expr, rel = $rel: expr) => {
+        if let Err(msg) = $crate::compare::check_mat_eq(&$a, &$b, $abs, $rel) {
+            panic!("assertion `{} == {}` failed: {}", stringify!($a), stringify!($b), msg);
+        }
+    };
+    ($a: expr, $b: expr, $abs: expr) => {
+        $crate::assert_mat_eq!($a, $b, abs = $abs, rel = 0.0)
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::compare::{*};
+
+    #[test]
+    fn test_mat_diff() {
+        let a = mat64![1.0, -2.0; 0.0, 100.0];
+        let b = mat64![1.0, -2.2; 0.0, 100.5];
+        let d = mat_diff(&a, &b).unwrap();
+        assert_eq!(d.worst, Some((1, 1)));
+        assert!((d.max_abs_diff - 0.5).abs() < 1e-12);
+        assert!((d.max_rel_diff - 0.2 / 2.2).abs() < 1e-12);
+        assert_eq!(d.nan_count, 0);
+
+        let d = mat_diff(&a, &a).unwrap();
+        assert_eq!((d.max_abs_diff, d.max_rel_diff, d.worst), (0.0, 0.0, Some((0, 0))));
+        let d = mat_diff(&mat64![f64::NAN, 1.0], &mat64![f64::NAN, 1.0]).unwrap();
+        assert_eq!(d.nan_count, 1);
+        assert!(mat_diff(&a, &Mat64::zero(2, 1)).is_err());
+    }
+
+    #[test]
+    fn test_check_mat_eq() {
+        let a = mat64![1.0, 1000.0];
+        let b = mat64![1.001, 1000.5];
+        assert!(check_mat_eq(&a, &b, 0.6, 0.0).is_ok());
+        assert!(check_mat_eq(&a, &b, 0.0, 1e-3).is_ok());
+        assert!(check_mat_eq(&a, &b, 1e-2, 1e-4).is_err());
+        let msg = check_mat_eq(&a, &b, 0.0, 0.0).unwrap_err();
+        assert!(msg.contains("worst element at (0, 1): 1000 vs 1000.5"));
+        assert!(msg.contains("first differing element at (0, 0): 1 vs 1.001"));
+        assert!(check_mat_eq(&mat64![f64::NAN], &mat64![f64::NAN], 1.0, 1.0).is_err());
+        assert!(check_mat_eq(&mat64![f64::INFINITY], &mat64![f64::INFINITY], 0.0, 0.0).is_ok());
+        assert!(check_mat_eq(&a, &Mat64::zero(1, 1), 1.0, 1.0).unwrap_err().contains("different shapes"));
+    }
+
+    #[test]
+    fn test_assert_mat_eq() {
+        let a = Mat32::new(1, 2, &[1.0, 2.0]);
+        assert_mat_eq!(a, a.clone());
+        assert_mat_eq!(a, Mat32::new(1, 2, &[1.0, 2.001]), abs = 1e-2);
+        assert_mat_eq!(a, Mat32::new(1, 2, &[1.0, 2.001]), 1e-2);
+    }
+
+    #[test]
+    #[should_panic(expected = "max abs diff: 1")]
+    fn test_assert_mat_eq_panic() {
+        assert_mat_eq!(mat64![1.0, 2.0], mat64![1.0, 3.0], rel = 1e-6);
+    }
+}
@@ -0,0 +1,235 @@
+/*
+ * decomp/cholesky.rs
+ * Cholesky decomposition of symmetric positive definite matrices.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::cmp::Ordering;
+use crate::matrix::{Matrix, LikeNumber};
+use crate::error::JolinError;
+
+/// The answer of Cholesky decomposition
+#[derive(Debug, Clone)]
+pub struct CholeskyDecomposition<T: Matrix> {
+    /// Lower triangular matrix with positive diagonal
+    pub l: T,
+}
+
+/// Cholesky decomposition `A = L L^T` of a symmetric positive definite
+/// matrix. The answer is a `CholeskyDecomposition` struct holding the lower
+/// triangular `L` with positive diagonal.
+///
+/// Only the lower triangle of the input is read.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::decomp::cholesky::cholesky;
+/// let a = mat64![4.0, 2.0; 2.0, 5.0];
+/// assert_eq!(cholesky(&a).unwrap().l, mat64![2.0, 0.0; 1.0, 2.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the matrix is not square.
+/// 2. Invalid value - if the matrix is not positive definite.
+pub fn cholesky<T: Matrix>(mat: &T) -> Result<CholeskyDecomposition<T>, JolinError> {
+    if mat.row() != mat.column() {
+        return Err(JolinError::shape_mismatching())
+    }
+    let n = mat.row();
+    let mut l = T::zero(n, n);
+    for j in 0..n {
+        let mut d = mat.elem(j, j);
+        for k in 0..j {
+            d = d - l.elem(j, k) * l.elem(j, k);
+        }
+        // NaN is rejected as well
+        if !is_positive(d) {
+            return Err(JolinError::invalid_value())
+        }
+        let d = d.sqrt();
+        *l.elem_mut(j, j) = d;
+        for i in (j + 1)..n {
+            let mut v = mat.elem(i, j);
+            for k in 0..j {
+                v = v - l.elem(i, k) * l.elem(j, k);
+            }
+            *l.elem_mut(i, j) = v / d;
+        }
+    }
+    Ok(CholeskyDecomposition { l })
+}
+
+impl<T: Matrix> CholeskyDecomposition<T> {
+    /// Solve `A X = B` by forward and back substitution
+    /// ```
+    /// # use jolin::matrix::{*};
+    /// # use jolin::mat64;
+    /// # use jolin::decomp::cholesky::cholesky;
+    /// let ch = cholesky(&mat64![4.0, 2.0; 2.0, 5.0]).unwrap();
+    /// assert!(eq_with_error(&ch.solve(&mat64![6.0; 7.0]).unwrap(), &mat64![1.0; 1.0], 1e-12));
+    /// assert!((ch.det() - 16.0).abs() < 1e-12);
+    /// ```
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the row count of `b` isn't the size of `A`.
+    pub fn solve(&self, b: &T) -> Result<T, JolinError> {
+        if b.row() != self.l.row() {
+            return Err(JolinError::shape_mismatching_with((self.l.row(), b.column()), (b.row(), b.column())))
+        }
+        Ok(solve_lower_transposed(&self.l, &solve_lower(&self.l, b)))
+    }
+
+    /// Inverse of `A`
+    pub fn inverse(&self) -> T {
+        let n = self.l.row();
+        solve_lower_transposed(&self.l, &solve_lower(&self.l, &T::identity(n)))
+    }
+
+    /// Determinant of `A`, the squared product of the diagonal of `L`
+    pub fn det(&self) -> T::Elem {
+        let d = (0..self.l.row()).fold(T::Elem::one(), |acc, i| acc * self.l.elem(i, i));
+        d * d
+    }
+
+    /// Update the decomposition to the one of `A + v v^T` in `O(n^2)`.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the length of `v` isn't the size of `A`.
+    pub fn update(&mut self, v: &[T::Elem]) -> Result<(), JolinError> {
+        self.rank_one(v, false)
+    }
+
+    /// Update the decomposition to the one of `A - v v^T` in `O(n^2)`. The
+    /// decomposition is unchanged if an error is returned.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the length of `v` isn't the size of `A`.
+    /// 2. Invalid value - if `A - v v^T` is not positive definite.
+    pub fn downdate(&mut self, v: &[T::Elem]) -> Result<(), JolinError> {
+        self.rank_one(v, true)
+    }
+
+    /// Rank-1 modification by a sequence of rotations on the columns of `L`
+    #[allow(clippy::needless_range_loop)]
+    fn rank_one(&mut self, v: &[T::Elem], downdate: bool) -> Result<(), JolinError> {
+        let n = self.l.row();
+        if v.len() != n {
+            return Err(JolinError::shape_mismatching_with((n, 1), (v.len(), 1)))
+        }
+        let mut l = self.l.clone();
+        let mut x = v.to_vec();
+        for k in 0..n {
+            let lkk = l.elem(k, k);
+            let r2 = if downdate { lkk * lkk - x[k] * x[k] } else { lkk * lkk + x[k] * x[k] };
+            if !is_positive(r2) {
+                return Err(JolinError::invalid_value())
+            }
+            let r = r2.sqrt();
+            let c = r / lkk;
+            let s = x[k] / lkk;
+            *l.elem_mut(k, k) = r;
+            for i in (k + 1)..n {
+                let lik = if downdate {
+                    (l.elem(i, k) - s * x[i]) / c
+                } else {
+                    (l.elem(i, k) + s * x[i]) / c
+                };
+                *l.elem_mut(i, k) = lik;
+                x[i] = c * x[i] - s * lik;
+            }
+        }
+        self.l = l;
+        Ok(())
+    }
+}
+
+/// Whether `v` is greater than zero, which is false for NaN
+fn is_positive<T: LikeNumber>(v: T) -> bool {
+    v.partial_cmp(&T::zero()) == Some(Ordering::Greater)
+}
+
+/// Solve `L X = B` for a lower triangular `L` by forward substitution
+pub(crate) fn solve_lower<T: Matrix>(l: &T, b: &T) -> T {
+    let n = l.row();
+    let mut x = b.clone();
+    for c in 0..b.column() {
+        for i in 0..n {
+            let mut t = x.elem(i, c);
+            for j in 0..i {
+                t = t - l.elem(i, j) * x.elem(j, c);
+            }
+            *x.elem_mut(i, c) = t / l.elem(i, i);
+        }
+    }
+    x
+}
+
+/// Solve `L^T X = B` for a lower triangular `L` by back substitution
+pub(crate) fn solve_lower_transposed<T: Matrix>(l: &T, b: &T) -> T {
+    let n = l.row();
+    let mut x = b.clone();
+    for c in 0..b.column() {
+        for i in (0..n).rev() {
+            let mut t = x.elem(i, c);
+            for j in (i + 1)..n {
+                t = t - l.elem(j, i) * x.elem(j, c);
+            }
+            *x.elem_mut(i, c) = t / l.elem(i, i);
+        }
+    }
+    x
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+    use crate::decomp::cholesky::{*};
+
+    #[test]
+    fn test_cholesky() {
+        let a = mat64![4.0, 12.0, -16.0; 12.0, 37.0, -43.0; -16.0, -43.0, 98.0];
+        let l = cholesky(&a).unwrap().l;
+        assert_eq!(l, mat64![2.0, 0.0, 0.0; 6.0, 1.0, 0.0; -8.0, 5.0, 3.0]);
+        assert_eq!(mul(&l, &tr(&l)).unwrap(), a);
+
+        let b = mat64![1.0; 2.0; 3.0];
+        let y = solve_lower(&l, &b);
+        assert!(eq_with_error(&mul(&l, &y).unwrap(), &b, 1e-12));
+        let x = solve_lower_transposed(&l, &b);
+        assert!(eq_with_error(&mul(&tr(&l), &x).unwrap(), &b, 1e-12));
+
+        let err = cholesky(&mat64![1.0, 2.0; 2.0, 1.0]).unwrap_err();
+        assert_eq!(err.kind(), JolinErrorKind::InvalidValue);
+        assert!(cholesky(&mat64![f64::NAN]).is_err());
+        assert!(cholesky(&Mat64::zero(2, 3)).is_err());
+    }
+
+    #[test]
+    fn test_cholesky_methods() {
+        let a = mat64![4.0, 12.0, -16.0; 12.0, 37.0, -43.0; -16.0, -43.0, 98.0];
+        let mut ch = cholesky(&a).unwrap();
+        let b = mat64![1.0, 0.0; 2.0, 1.0; 3.0, 0.0];
+        assert!(eq_with_error(&mul(&a, &ch.solve(&b).unwrap()).unwrap(), &b, 1e-10));
+        assert!(eq_with_error(&mul(&a, &ch.inverse()).unwrap(), &Mat64::identity(3), 1e-10));
+        assert!((ch.det() - 36.0).abs() < 1e-10);
+        assert!(ch.solve(&Mat64::zero(2, 1)).is_err());
+
+        let v = [1.0, -2.0, 0.5];
+        let vvt = mat64![1.0, -2.0, 0.5; -2.0, 4.0, -1.0; 0.5, -1.0, 0.25];
+        ch.update(&v).unwrap();
+        let expected = cholesky(&add(&a, &vvt).unwrap()).unwrap().l;
+        assert!(eq_with_error(&ch.l, &expected, 1e-10));
+        ch.downdate(&v).unwrap();
+        assert!(eq_with_error(&ch.l, &cholesky(&a).unwrap().l, 1e-10));
+
+        // A - v v^T is indefinite
+        let before = ch.l.clone();
+        assert_eq!(ch.downdate(&[3.0, 0.0, 0.0]).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        assert_eq!(ch.l, before);
+        assert_eq!(ch.update(&[1.0]).unwrap_err().kind(), JolinErrorKind::ShapeMismatching);
+    }
+}
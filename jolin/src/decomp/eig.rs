@@ -0,0 +1,221 @@
+/*
+ * decomp/eig.rs
+ * Eigen decomposition of symmetric matrices.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber, tr};
+use crate::error::JolinError;
+use crate::backend::Backend;
+use crate::decomp::cholesky::{cholesky, solve_lower, solve_lower_transposed};
+
+/// The answer of eigen decomposition `A = V * diag(values) * V^T`
+#[derive(Debug, Clone)]
+pub struct EigenDecomposition<T: Matrix> {
+    /// Eigenvalues in ascending order
+    pub values: Vec<T::Elem>,
+    /// Eigenvectors stored as columns, in the order of the eigenvalues
+    pub vectors: T,
+}
+
+/// Maximal count of Jacobi sweeps before giving up on convergence
+const MAX_JACOBI_SWEEPS: usize = 100;
+
+/// Eigen decomposition of a real symmetric matrix with the cyclic
+/// [Jacobi eigenvalue algorithm](https://en.wikipedia.org/wiki/Jacobi_eigenvalue_algorithm).
+///
+/// The eigenvectors are orthonormal. Only the symmetric part of the input is
+/// considered, i.e. the upper and lower triangles should agree.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::decomp::eig::eig_symmetric;
+/// let a = mat64![2.0, 1.0; 1.0, 2.0];
+/// let ans = eig_symmetric(&a).unwrap();
+/// assert!((ans.values[0] - 1.0).abs() < 1e-12);
+/// assert!((ans.values[1] - 3.0).abs() < 1e-12);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the matrix is not square.
+pub fn eig_symmetric<T: Matrix>(mat: &T) -> Result<EigenDecomposition<T>, JolinError> {
+    if mat.row() != mat.column() {
+        return Err(JolinError::shape_mismatching())
+    }
+    let n = mat.row();
+    let mut a = mat.clone();
+    if cfg!(feature = "blas") {
+        if let Some(values) = T::Elem::syev(n, a.data_mut()) {
+            return Ok(EigenDecomposition { values, vectors: a })
+        }
+    }
+    let mut v = T::identity(n);
+    let norm2: T::Elem = a.data().iter().map(|x| (*x) * (*x)).sum();
+    let eps = T::Elem::epsilon();
+    let threshold = eps * eps * norm2;
+
+    for _sweep in 0..MAX_JACOBI_SWEEPS {
+        let mut off = T::Elem::zero();
+        for q in 0..n {
+            for p in 0..q {
+                off = off + a.elem(p, q) * a.elem(p, q);
+            }
+        }
+        if off <= threshold {
+            break;
+        }
+        for p in 0..n {
+            for q in (p + 1)..n {
+                let apq = a.elem(p, q);
+                if apq == T::Elem::zero() {
+                    continue;
+                }
+                // rotation angle to annihilate a[p, q]
+                let theta = (a.elem(q, q) - a.elem(p, p)) / apq.times_real(2.0);
+                let t = theta.sign() / (theta.abs() + (theta * theta + T::Elem::one()).sqrt());
+                let c = T::Elem::one() / (t * t + T::Elem::one()).sqrt();
+                let s = t * c;
+
+                for k in 0..n {
+                    let akp = a.elem(k, p);
+                    let akq = a.elem(k, q);
+                    *a.elem_mut(k, p) = c * akp - s * akq;
+                    *a.elem_mut(k, q) = s * akp + c * akq;
+                }
+                for k in 0..n {
+                    let apk = a.elem(p, k);
+                    let aqk = a.elem(q, k);
+                    *a.elem_mut(p, k) = c * apk - s * aqk;
+                    *a.elem_mut(q, k) = s * apk + c * aqk;
+                }
+                *a.elem_mut(p, q) = T::Elem::zero();
+                *a.elem_mut(q, p) = T::Elem::zero();
+
+                for k in 0..n {
+                    let vkp = v.elem(k, p);
+                    let vkq = v.elem(k, q);
+                    *v.elem_mut(k, p) = c * vkp - s * vkq;
+                    *v.elem_mut(k, q) = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    // sort the eigenpairs by eigenvalues in ascending order
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|i, j| a.elem(*i, *i).partial_cmp(&a.elem(*j, *j)).unwrap_or(std::cmp::Ordering::Equal));
+    let values = order.iter().map(|i| a.elem(*i, *i)).collect();
+    let mut vectors = T::zero(n, n);
+    for (c, src) in order.iter().enumerate() {
+        for r in 0..n {
+            *vectors.elem_mut(r, c) = v.elem(r, *src);
+        }
+    }
+    Ok(EigenDecomposition { values, vectors })
+}
+
+/// Solve the symmetric generalized eigenproblem `A x = lambda B x` where `B`
+/// is symmetric positive definite, e.g. the stiffness and mass matrices in
+/// modal analysis.
+///
+/// With the Cholesky decomposition `B = L L^T`, it is reduced to the standard
+/// symmetric problem of `L^-1 A L^-T`, whose eigenvectors `y` are transformed
+/// back by `x = L^-T y`. The eigenvalues are in ascending order and the
+/// eigenvectors are `B`-orthonormal, i.e. `X^T B X = I`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::decomp::eig::eig_symmetric_generalized;
+/// let a = mat64![2.0, 0.0; 0.0, 8.0];
+/// let b = mat64![1.0, 0.0; 0.0, 2.0];
+/// let ans = eig_symmetric_generalized(&a, &b).unwrap();
+/// assert!((ans.values[0] - 2.0).abs() < 1e-12);
+/// assert!((ans.values[1] - 4.0).abs() < 1e-12);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the matrices are not square or of different sizes.
+/// 2. Invalid value - if `B` is not positive definite.
+pub fn eig_symmetric_generalized<T: Matrix>(a: &T, b: &T) -> Result<EigenDecomposition<T>, JolinError> {
+    if a.row() != a.column() || b.row() != b.column() || a.row() != b.row() {
+        return Err(JolinError::shape_mismatching())
+    }
+    let l = cholesky(b)?.l;
+    // L^-1 A L^-T = L^-1 (L^-1 A)^T since A is symmetric
+    let w = solve_lower(&l, a);
+    let mut c = solve_lower(&l, &tr(&w));
+    let n = a.row();
+    for j in 0..n {
+        for i in (j + 1)..n {
+            let v = (c.elem(i, j) + c.elem(j, i)).times_real(0.5);
+            *c.elem_mut(i, j) = v;
+            *c.elem_mut(j, i) = v;
+        }
+    }
+    let eig = eig_symmetric(&c)?;
+    Ok(EigenDecomposition { values: eig.values, vectors: solve_lower_transposed(&l, &eig.vectors) })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::decomp::eig::{eig_symmetric, eig_symmetric_generalized};
+
+    #[allow(clippy::needless_range_loop)]
+    fn rebuild(values: &[f64], vectors: &Mat64) -> Mat64 {
+        let n = values.len();
+        let mut d = Mat64::zero(n, n);
+        for i in 0..n {
+            *d.elem_mut(i, i) = values[i];
+        }
+        mul(&mul(vectors, &d).unwrap(), &tr(vectors)).unwrap()
+    }
+
+    #[test]
+    fn test_eig_symmetric_3x3() {
+        let a = mat64![4.0, 1.0, -2.0; 1.0, 2.0, 0.0; -2.0, 0.0, 3.0];
+        let ans = eig_symmetric(&a).unwrap();
+        let vtv = mul(&tr(&ans.vectors), &ans.vectors).unwrap();
+        assert!(eq_with_error(&vtv, &Mat64::identity(3), 1e-12));
+        assert!(eq_with_error(&rebuild(&ans.values, &ans.vectors), &a, 1e-12));
+        assert!(ans.values[0] <= ans.values[1] && ans.values[1] <= ans.values[2]);
+        // trace is the sum of eigenvalues
+        assert!((ans.values.iter().sum::<f64>() - 9.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_eig_symmetric_diagonal() {
+        let a = mat64![3.0, 0.0, 0.0; 0.0, -1.0, 0.0; 0.0, 0.0, 2.0];
+        let ans = eig_symmetric(&a).unwrap();
+        assert_eq!(ans.values, vec![-1.0, 2.0, 3.0]);
+        assert!(eig_symmetric(&mat64![1.0, 2.0]).is_err());
+    }
+
+    #[test]
+    fn test_eig_symmetric_generalized() {
+        let a = mat64![6.0, 2.0, 1.0; 2.0, 5.0, 2.0; 1.0, 2.0, 4.0];
+        let b = mat64![2.0, 1.0, 0.0; 1.0, 3.0, 1.0; 0.0, 1.0, 2.0];
+        let ans = eig_symmetric_generalized(&a, &b).unwrap();
+        let x = &ans.vectors;
+        let xtbx = mul(&tr(x), &mul(&b, x).unwrap()).unwrap();
+        assert!(eq_with_error(&xtbx, &Mat64::identity(3), 1e-12));
+        for k in 0..3 {
+            let v = Mat64::new(3, 1, x.data_column(k));
+            let av = mul(&a, &v).unwrap();
+            let bv = elemwise(&mul(&b, &v).unwrap(), |e| e * ans.values[k]);
+            assert!(eq_with_error(&av, &bv, 1e-10));
+        }
+        assert!(ans.values[0] <= ans.values[1] && ans.values[1] <= ans.values[2]);
+
+        let identity = eig_symmetric_generalized(&a, &Mat64::identity(3)).unwrap();
+        let standard = eig_symmetric(&a).unwrap();
+        for k in 0..3 {
+            assert!((identity.values[k] - standard.values[k]).abs() < 1e-12);
+        }
+        assert!(eig_symmetric_generalized(&a, &mat64![1.0, 0.0, 0.0; 0.0, -1.0, 0.0; 0.0, 0.0, 1.0]).is_err());
+        assert!(eig_symmetric_generalized(&a, &Mat64::identity(2)).is_err());
+    }
+}
@@ -6,11 +6,15 @@
  * See LICENSE file in the root of the repo.
  */
 
-use crate::matrix::{Matrix, LikeNumber};
+use crate::matrix::{Matrix, MatrixView, LikeNumber, mul, trmul, gemm};
 use crate::error::JolinError;
+use crate::backend::Backend;
 use crate::Mat64;
+use crate::settings;
+use crate::det::permutation_order;
 
 /// The answer of LU decomposition
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LUDecomposition<T: Matrix> {
     /// Lower triangular matrix
     pub l: T,
@@ -20,6 +24,87 @@ pub struct LUDecomposition<T: Matrix> {
     pub p: Vec<usize>
 }
 
+impl<T: Matrix> LUDecomposition<T> {
+    /// Solve `A X = B` with the factors of `A`, which can be reused for many
+    /// right-hand sides.
+    /// ```
+    /// # use jolin::matrix::{*};
+    /// # use jolin::mat64;
+    /// # use jolin::decomp::lu::lu;
+    /// let lud = lu(&mat64![2.0, 1.0; 1.0, 3.0]).unwrap();
+    /// assert!(eq_with_error(&lud.solve(&mat64![3.0; 4.0]).unwrap(), &mat64![1.0; 1.0], 1e-12));
+    /// assert_eq!(lud.det(), 5.0);
+    /// ```
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the row count of `b` isn't the size of `A`.
+    pub fn solve(&self, b: &T) -> Result<T, JolinError> {
+        if b.row() != self.u.row() {
+            return Err(JolinError::shape_mismatching_with((self.u.row(), b.column()), (b.row(), b.column())))
+        }
+        Ok(lu_solve(self, b))
+    }
+
+    /// Inverse of `A`
+    pub fn inverse(&self) -> T {
+        lu_solve(self, &T::identity(self.u.row()))
+    }
+
+    /// Update the factors to the ones of `A + u v^T` in `O(n^2)` with
+    /// Bennett's algorithm, where `u` and `v` are column vectors. The rows
+    /// are not pivoted again, so the factors may lose accuracy after many
+    /// updates. The factors are unchanged if an error is returned.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if `u` or `v` is not a column vector of the
+    ///    size of `A`.
+    /// 2. Singular matrix - if a pivot of the new factors is zero.
+    pub fn update(&mut self, u: &T, v: &T) -> Result<(), JolinError> {
+        let n = self.u.row();
+        for x in [u, v] {
+            if (x.row(), x.column()) != (n, 1) {
+                return Err(JolinError::shape_mismatching_with((n, 1), (x.row(), x.column())))
+            }
+        }
+        // P (A + u v^T) = L U + (P u) v^T
+        let mut x: Vec<T::Elem> = self.p.iter().map(|i| u.data()[*i]).collect();
+        let mut y = v.data().to_vec();
+        let mut l = self.l.clone();
+        let mut up = self.u.clone();
+        for i in 0..n {
+            let d = up.elem(i, i) + x[i] * y[i];
+            if d == T::Elem::zero() {
+                return Err(JolinError::singular_matrix_at(i))
+            }
+            *up.elem_mut(i, i) = d;
+            y[i] = y[i] / d;
+            for j in (i + 1)..n {
+                let u_ij = up.elem(i, j) + x[i] * y[j];
+                *up.elem_mut(i, j) = u_ij;
+                x[j] = x[j] - x[i] * l.elem(j, i);
+                *l.elem_mut(j, i) = l.elem(j, i) + y[i] * x[j];
+                y[j] = y[j] - y[i] * u_ij;
+            }
+        }
+        self.l = l;
+        self.u = up;
+        Ok(())
+    }
+
+    /// Determinant of `A`
+    pub fn det(&self) -> T::Elem {
+        let mut ans = T::Elem::one();
+        for i in 0..self.u.row() {
+            ans = ans * self.l.elem(i, i) * self.u.elem(i, i);
+        }
+        if permutation_order(&self.p).is_multiple_of(2) {
+            ans
+        } else {
+            -ans
+        }
+    }
+}
+
 /// General LU decomposition. The answer will be a `LUDecomposition` struct.
 /// 
 /// Row-max pivoting is adopted. The row with maximal absolute value on the 
@@ -29,70 +114,142 @@ pub struct LUDecomposition<T: Matrix> {
 /// 1. Shape mismatching - if the matrix is not square.
 /// 2. Singular matrix - if the matrix is singular
 pub fn lu<T: Matrix>(mat: &T) -> Result<LUDecomposition<T>, JolinError> {
+    let mut packed = mat.clone();
+    let p = lu_inplace(&mut packed)?;
+    Ok(unpack_lu(&packed, p))
+}
+
+/// LU decomposition in place. The matrix is overwritten by `L` (below the
+/// diagonal, the unit diagonal of `L` is implicit) and `U` (on and above the
+/// diagonal), and the answer is the permutation index `p` as in
+/// `LUDecomposition`, i.e. row `i` of `LU` is row `p[i]` of the input.
+///
+/// Unlike `lu`, no other matrix is allocated. The pivoting is the same as
+/// `lu`. If an error is returned, the content of the matrix is unspecified.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::decomp::lu::lu_inplace;
+/// let mut a = mat64![1.0, 2.0; 3.0, 4.0];
+/// let p = lu_inplace(&mut a).unwrap();
+/// assert_eq!(p, vec![1, 0]);
+/// assert_eq!(a, mat64![3.0, 4.0; 1.0 / 3.0, 2.0 - 4.0 / 3.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the matrix is not square.
+/// 2. Singular matrix - if the matrix is singular
+pub fn lu_inplace<T: Matrix>(mat: &mut T) -> Result<Vec<usize>, JolinError> {
     if mat.row() != mat.column() {
         // Square matrix is required
         return Err(JolinError::shape_mismatching())
     }
-    
-    // We will operate on the cloned matrix
-    let mut a = mat.clone();
-    let n = a.row();
-    let mut p : Vec<usize> = (0..n).collect();
-    let mut inv_p: Vec<usize> = p.clone();
-    let mut l: T = T::identity(n);
-    let mut u: T = T::zero(n, n);
-
-    // eliminate column i
-    for i in 0..n {
-        // find the row with maximal element at column i
-        let pivot_row_in_a = argmaxabs(a.data_column(i));
-        if a.elem(pivot_row_in_a, i) == T::Elem::zero() {
-            return Err(JolinError::singular_matrix())
-        } 
-        {
-            let pivot_row_in_pa = inv_p[pivot_row_in_a];
-            let idx1 = p[i];
-            let idx2 = p[pivot_row_in_pa];
-            p[i] = idx2;
-            p[pivot_row_in_pa] = idx1;
-            inv_p[idx2] = i;
-            inv_p[idx1] = pivot_row_in_pa;
-            
-            // swap row of pivot_row_in_pa with row i in matrix L
-            for c in 0..i {
-                let idx1 = l.idx(i, c);
-                let idx2 = l.idx(pivot_row_in_pa, c);
-                let v1 = l.data()[idx1];
-                let v2 = l.data()[idx2];
-                l.data_mut()[idx1] = v2;
-                l.data_mut()[idx2] = v1;
-            }
+    let n = mat.row();
+    if cfg!(feature = "blas") {
+        if let Some(ans) = T::Elem::getrf(n, mat.data_mut()) {
+            return ans
         }
+    }
+    lu_blocked(mat, settings::get().lu_block_size)
+}
 
-        // move row of pivot from A to U
-        {
-            for c in 0..n {
-                let idx_u = u.idx(i, c);
-                let idx_a = a.idx(pivot_row_in_a, c);
-                u.data_mut()[idx_u] = a.data()[idx_a];
-                a.data_mut()[idx_a] = T::Elem::zero();
+/// Right-looking blocked LU decomposition in place with panels of `nb`
+/// columns. Each panel is factorized with the pivoting of `lu_inplace`, the
+/// rows of `U` right to it are solved, and the trailing matrix is updated by
+/// one matrix multiplication, which runs in the cache-blocked (and parallel)
+/// kernels.
+fn lu_blocked<T: Matrix>(mat: &mut T, nb: usize) -> Result<Vec<usize>, JolinError> {
+    let n = mat.row();
+    let nb = if nb == 0 || nb >= n { n } else { nb };
+    let mut p: Vec<usize> = (0..n).collect();
+    for k0 in (0..n).step_by(usize::max(nb, 1)) {
+        let k1 = usize::min(k0 + nb, n);
+        // eliminate column i within the panel
+        for i in k0..k1 {
+            // find the row with maximal element at column i, ties are broken by
+            // the original row index
+            let mut pivot = i;
+            for r in (i + 1)..n {
+                let v = mat.elem(r, i).abs();
+                let best = mat.elem(pivot, i).abs();
+                if v > best || (v == best && p[r] < p[pivot]) {
+                    pivot = r;
+                }
+            }
+            if mat.elem(pivot, i) == T::Elem::zero() {
+                return Err(JolinError::singular_matrix_at(i))
+            }
+            if pivot != i {
+                p.swap(i, pivot);
+                for c in 0..n {
+                    let idx1 = mat.idx(i, c);
+                    let idx2 = mat.idx(pivot, c);
+                    mat.data_mut().swap(idx1, idx2);
+                }
+            }
+
+            let u_ii = mat.elem(i, i);
+            for r in (i + 1)..n {
+                // eliminate row r with row i of U
+                if mat.elem(r, i) != T::Elem::zero() {
+                    let ratio = mat.elem(r, i) / u_ii;
+                    for c in (i + 1)..k1 {
+                        let original_value = mat.elem(r, c);
+                        *mat.elem_mut(r, c) = original_value - ratio * mat.elem(i, c);
+                    }
+                    *mat.elem_mut(r, i) = ratio;
+                }
             }
         }
-        for r in 0..n {
-            // eliminate row r in A with ith row of U[i]
-            if a.elem(r, i) != T::Elem::zero() {
-                let ratio = a.elem(r, i) / u.elem(i, i);
-                for c in i..n {
-                    let original_value = a.elem(r, c);
-                    *a.elem_mut(r, c) = original_value - ratio * u.elem(i, c);
+        if k1 == n {
+            break
+        }
+        // U12 = L11^-1 A12
+        for c in k1..n {
+            for i in k0..k1 {
+                let u_ic = mat.elem(i, c);
+                for r in (i + 1)..k1 {
+                    *mat.elem_mut(r, c) = mat.elem(r, c) - mat.elem(r, i) * u_ic;
                 }
-                *l.elem_mut(inv_p[r], i) = ratio;
+            }
+        }
+        // A22 = A22 - L21 U12
+        let (m2, kb) = (n - k1, k1 - k0);
+        let mut l21 = T::zero(m2, kb);
+        for c in 0..kb {
+            l21.data_mut()[(c * m2)..((c + 1) * m2)].copy_from_slice(&mat.data_column(k0 + c)[k1..]);
+        }
+        let mut u12 = T::zero(kb, m2);
+        let mut a22 = T::zero(m2, m2);
+        for c in 0..m2 {
+            let col = mat.data_column(k1 + c);
+            u12.data_mut()[(c * kb)..((c + 1) * kb)].copy_from_slice(&col[k0..k1]);
+            a22.data_mut()[(c * m2)..((c + 1) * m2)].copy_from_slice(&col[k1..]);
+        }
+        gemm(-T::Elem::one(), &l21, false, &u12, false, T::Elem::one(), &mut a22)?;
+        for c in 0..m2 {
+            let start = mat.idx(k1, k1 + c);
+            mat.data_mut()[start..(start + m2)].copy_from_slice(a22.data_column(c));
+        }
+    }
+    Ok(p)
+}
+
+/// Split the packed LU decomposition into `L` and `U`
+fn unpack_lu<T: Matrix>(packed: &T, p: Vec<usize>) -> LUDecomposition<T> {
+    let n = packed.row();
+    let mut l = T::identity(n);
+    let mut u = T::zero(n, n);
+    for c in 0..n {
+        for r in 0..n {
+            if r > c {
+                *l.elem_mut(r, c) = packed.elem(r, c);
+            } else {
+                *u.elem_mut(r, c) = packed.elem(r, c);
             }
         }
     }
-    Ok(LUDecomposition {
-        l, u , p
-   })
+    LUDecomposition { l, u, p }
 }
 
 /// Trait to provide type-specific LU decomposition, which comes with better
@@ -111,14 +268,21 @@ pub trait LUDecomposable: Matrix{
 }
 
 impl LUDecomposable for Mat64 {
+    #[allow(clippy::needless_range_loop)]
     fn lu_decomp(mat: &Mat64) -> Result<LUDecomposition<Mat64>, JolinError> {
-        // Magic number: 1e-16 is the significant figure of float 64
-        // We use it for evaluate whether the number is close to zero enough
+        // Pivots below the tolerance (1e-16 by default, the significant
+        // figure of float 64) are considered zero
+        let tol = settings::get().pivot_tolerance;
 
         if mat.row() != mat.column() {
         // Square matrix is required
             return Err(JolinError::shape_mismatching())
         }
+        if cfg!(feature = "blas") {
+            if let Some(ans) = lu_from_backend(mat) {
+                return ans
+            }
+        }
     
         // We will operate on the cloned matrix
         let mut a = mat.clone();
@@ -132,8 +296,8 @@ impl LUDecomposable for Mat64 {
         for i in 0..n {
             // find the row with maximal element at column i
             let pivot_row_in_a = argmaxabs(a.data_column(i));
-            if f64::abs(a.elem(pivot_row_in_a, i)) < 1e-16 {
-                return Err(JolinError::singular_matrix())
+            if f64::abs(a.elem(pivot_row_in_a, i)) < tol {
+                return Err(JolinError::singular_matrix_at(i))
             } 
             {
                 let pivot_row_in_pa = inv_p[pivot_row_in_a];
@@ -166,7 +330,7 @@ impl LUDecomposable for Mat64 {
             }
             for r in 0..n {
                 // eliminate row r in A with ith row of U[i]
-                if f64::abs(a.elem(r, i)) >= 1e-16 {
+                if f64::abs(a.elem(r, i)) >= tol {
                     let ratio = a.elem(r, i) / u.elem(i, i);
                     for c in i..n {
                         let original_value = a.elem(r, c);
@@ -182,9 +346,173 @@ impl LUDecomposable for Mat64 {
     }
 }
 
+/// LU decomposition of a square matrix by the backend of the element type.
+/// Returns `None` if the backend doesn't provide it.
+fn lu_from_backend<T: Matrix>(mat: &T) -> Option<Result<LUDecomposition<T>, JolinError>> {
+    let mut packed = mat.clone();
+    match T::Elem::getrf(mat.row(), packed.data_mut())? {
+        Ok(p) => Some(Ok(unpack_lu(&packed, p))),
+        Err(err) => Some(Err(err)),
+    }
+}
+
+/// Solve `A X = B` with the LU decomposition of `A`. Shapes are not checked.
+#[allow(clippy::needless_range_loop)]
+pub(crate) fn lu_solve<T: Matrix>(lud: &LUDecomposition<T>, b: &T) -> T {
+    let n = lud.u.row();
+    let mut x = T::zero(n, b.column());
+    let mut y = vec![T::Elem::zero(); n];
+    for c in 0..b.column() {
+        // L y = P b
+        for i in 0..n {
+            let mut t = b.elem(lud.p[i], c);
+            for j in 0..i {
+                t = t - lud.l.elem(i, j) * y[j];
+            }
+            y[i] = t / lud.l.elem(i, i);
+        }
+        // U x = y
+        for i in (0..n).rev() {
+            let mut t = y[i];
+            for j in (i + 1)..n {
+                t = t - lud.u.elem(i, j) * x.elem(j, c);
+            }
+            *x.elem_mut(i, c) = t / lud.u.elem(i, i);
+        }
+    }
+    x
+}
+
+/// Estimate the reciprocal condition number `1 / (|A|_1 |A^-1|_1)` in the
+/// 1-norm from the LU decomposition of `A` in `O(n^2)`, without forming `A`
+/// or its inverse. Both norms are estimated by the Hager-Higham method, which
+/// only needs products of `A`, `A^-1` and their transposes with vectors.
+///
+/// The estimated norms are lower bounds and usually exact, so the answer is
+/// an upper bound of the true reciprocal condition number, and it's rarely
+/// off by more than a small factor. A value near the machine epsilon means
+/// that solutions with the factors are unreliable.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::decomp::lu::{lu, rcond_estimate};
+/// let lud = lu(&mat64![1.0, 0.0; 0.0, 1e-8]).unwrap();
+/// assert!((rcond_estimate(&lud) - 1e-8).abs() < 1e-20);
+/// ```
+#[allow(clippy::needless_range_loop)]
+pub fn rcond_estimate<T: Matrix>(lud: &LUDecomposition<T>) -> T::Elem {
+    let n = lud.u.row();
+    if n == 0 {
+        return T::Elem::one()
+    }
+    let column = |x: &[T::Elem]| T::new(n, 1, x);
+    // A = P^T L U, where row i of L U is row p[i] of A
+    let apply = |x: &[T::Elem]| {
+        let lu_x = mul(&lud.l, &mul(&lud.u, &column(x)).unwrap()).unwrap();
+        let mut y = vec![T::Elem::zero(); n];
+        for (i, v) in lu_x.data().iter().enumerate() {
+            y[lud.p[i]] = *v;
+        }
+        y
+    };
+    let apply_t = |y: &[T::Elem]| {
+        let py: Vec<T::Elem> = lud.p.iter().map(|i| y[*i]).collect();
+        trmul(&lud.u, &trmul(&lud.l, &column(&py)).unwrap()).unwrap().data().to_vec()
+    };
+    let solve = |x: &[T::Elem]| lu_solve(lud, &column(x)).data().to_vec();
+    let solve_t = |y: &[T::Elem]| {
+        // U^T w = y, then L^T z = w, and A^-T y = P^T z
+        let mut w = vec![T::Elem::zero(); n];
+        for i in 0..n {
+            let mut t = y[i];
+            for j in 0..i {
+                t = t - lud.u.elem(j, i) * w[j];
+            }
+            w[i] = t / lud.u.elem(i, i);
+        }
+        let mut z = vec![T::Elem::zero(); n];
+        for i in (0..n).rev() {
+            let mut t = w[i];
+            for j in (i + 1)..n {
+                t = t - lud.l.elem(j, i) * z[j];
+            }
+            z[i] = t / lud.l.elem(i, i);
+        }
+        let mut ans = vec![T::Elem::zero(); n];
+        for (i, v) in z.iter().enumerate() {
+            ans[lud.p[i]] = *v;
+        }
+        ans
+    };
+    let norm = norm1_estimate(n, apply, apply_t);
+    let inv_norm = norm1_estimate(n, solve, solve_t);
+    T::Elem::one() / (norm * inv_norm)
+}
+
+/// Hager's estimate of the 1-norm of the `n * n` operator `B` given the
+/// products with `B` and `B^T`, improved by Higham's alternating vector
+fn norm1_estimate<E: LikeNumber, F, G>(n: usize, apply: F, apply_t: G) -> E
+    where F: Fn(&[E]) -> Vec<E>, G: Fn(&[E]) -> Vec<E> {
+    let norm1 = |v: &[E]| v.iter().map(|x| x.abs()).sum::<E>();
+    let mut x = vec![E::one().times_real(1.0 / n as f64); n];
+    let mut est = E::zero();
+    let mut last = None;
+    for _iter in 0..5 {
+        let y = apply(&x);
+        est = norm1(&y);
+        let signs: Vec<E> = y.iter().map(|v| v.sign()).collect();
+        let z = apply_t(&signs);
+        let (j, z_max) = z.iter().enumerate()
+            .fold((0, E::zero()), |acc, (i, v)| if v.abs() > acc.1 { (i, v.abs()) } else { acc });
+        let ztx = z.iter().zip(x.iter()).map(|(a, b)| *a * *b).sum::<E>();
+        if z_max <= ztx || last == Some(j) {
+            break
+        }
+        last = Some(j);
+        x = vec![E::zero(); n];
+        x[j] = E::one();
+    }
+    let alternating: Vec<E> = (0..n).map(|i| {
+        let v = 1.0 + if n > 1 { i as f64 / (n - 1) as f64 } else { 0.0 };
+        E::one().times_real(if i.is_multiple_of(2) { v } else { -v })
+    }).collect();
+    let alt = norm1(&apply(&alternating)).times_real(2.0 / (3 * n) as f64);
+    if alt > est { alt } else { est }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct LUDecompositionData<T> {
+    l: T,
+    u: T,
+    p: Vec<usize>,
+}
+
+/// The factors are checked to be square of the same size, and `p` to be a
+/// permutation.
+#[cfg(feature = "serde")]
+impl<'de, T: Matrix + serde::Deserialize<'de>> serde::Deserialize<'de> for LUDecomposition<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let raw = LUDecompositionData::<T>::deserialize(deserializer)?;
+        let n = raw.p.len();
+        if (raw.l.row(), raw.l.column(), raw.u.row(), raw.u.column()) != (n, n, n, n) {
+            return Err(D::Error::custom("the shapes of L, U and P don't match"))
+        }
+        let mut seen = vec![false; n];
+        for &i in raw.p.iter() {
+            if i >= n || seen[i] {
+                return Err(D::Error::custom("P is not a permutation"))
+            }
+            seen[i] = true;
+        }
+        Ok(LUDecomposition { l: raw.l, u: raw.u, p: raw.p })
+    }
+}
+
 // Get the index of the element of maximal absolute value
 fn argmaxabs<T: LikeNumber>(elems: &[T]) -> usize {
-    if elems.len() == 0 {
+    if elems.is_empty() {
         return 0
     }
     let mut ans = 0usize;
@@ -211,7 +539,8 @@ mod test {
     #[test]
     fn test_lu_singular() {
         let ans = lu(&mat64![1.0, 1.0; 2.0, 2.0]);
-        assert!(ans.is_err())
+        assert!(ans.is_err());
+        assert_eq!(ans.err().unwrap().context(), JolinError::singular_matrix_at(1).context());
     }
 
     #[test]
@@ -234,6 +563,30 @@ mod test {
         assert_eq!(ans.u, mat64![4.0, 7.0, 5.0; 0.0, 3.75, 1.25; 0.0, 0.0, 5.0/3.0]);
     }
 
+    #[test]
+    fn test_lu_inplace() {
+        use crate::decomp::lu::LUDecomposable;
+        let mat = mat64![
+            2.0, 0.0, 4.0, 3.0; 
+            -4.0, 5.0, -7.0, 10.0;
+            1.0, 15.0, 2.0, -4.5;
+            -2.0, 0.0, 2.0, -13.0
+        ];
+        let mut packed = mat.clone();
+        let p = lu_inplace(&mut packed).unwrap();
+        // compare with the independent implementation of Mat64
+        let ans = Mat64::lu_decomp(&mat).unwrap();
+        assert_eq!(p, ans.p);
+        for c in 0..4 {
+            for r in 0..4 {
+                let expected = if r > c { ans.l.elem(r, c) } else { ans.u.elem(r, c) };
+                assert_eq!(packed.elem(r, c), expected);
+            }
+        }
+        assert!(lu_inplace(&mut mat64![1.0, 2.0; 2.0, 4.0]).is_err());
+        assert!(lu_inplace(&mut Mat64::zero(2, 3)).is_err());
+    }
+
     #[test]
     fn test_lu_4x4() {
         let mat = mat64![
@@ -270,4 +623,105 @@ mod test {
             }
         }
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_lu_serde() {
+        let ans = lu(&mat64![1.0, 2.0; 3.0, 4.0]).unwrap();
+        let json = serde_json::to_string(&ans).unwrap();
+        let back: LUDecomposition<Mat64> = serde_json::from_str(&json).unwrap();
+        assert_eq!((back.l, back.u, back.p), (ans.l, ans.u, ans.p));
+
+        let bad_p = json.replace(r#""p":[1,0]"#, r#""p":[1,1]"#);
+        assert!(serde_json::from_str::<LUDecomposition<Mat64>>(&bad_p).is_err());
+        let short_p = json.replace(r#""p":[1,0]"#, r#""p":[0]"#);
+        assert!(serde_json::from_str::<LUDecomposition<Mat64>>(&short_p).is_err());
+    }
+
+    #[test]
+    fn test_lu_methods() {
+        let mat = mat64![
+            2.0, 3.0, 4.0;
+            4.0, 7.0, 5.0;
+            3.0, 9.0, 5.0];
+        for lud in [lu(&mat).unwrap(), Mat64::lu_decomp(&mat).unwrap()] {
+            let b = mat64![1.0, 0.0; 2.0, 1.0; 3.0, -1.0];
+            let x = lud.solve(&b).unwrap();
+            assert!(crate::matrix::eq_with_error(&mul(&mat, &x).unwrap(), &b, 1e-12));
+            let inv = lud.inverse();
+            assert!(crate::matrix::eq_with_error(&mul(&inv, &mat).unwrap(), &Mat64::identity(3), 1e-12));
+            assert!((lud.det() - 25.0).abs() < 1e-12);
+            assert_eq!(lud.solve(&Mat64::zero(2, 1)).unwrap_err().kind(), crate::error::JolinErrorKind::ShapeMismatching);
+        }
+    }
+
+    #[test]
+    fn test_lu_update() {
+        let mat = mat64![
+            2.0, 3.0, 4.0;
+            4.0, 7.0, 5.0;
+            3.0, 9.0, 5.0];
+        let u = mat64![1.0; -2.0; 0.5];
+        let v = mat64![0.5; 1.0; -1.0];
+        let updated = crate::matrix::add(&mat, &mul(&u, &crate::matrix::tr(&v)).unwrap()).unwrap();
+        for mut lud in [lu(&mat).unwrap(), Mat64::lu_decomp(&mat).unwrap()] {
+            lud.update(&u, &v).unwrap();
+            let rebuild = mul(&lud.l, &lud.u).unwrap();
+            for c in 0..3 {
+                for r in 0..3 {
+                    assert!((rebuild.elem(r, c) - updated.elem(lud.p[r], c)).abs() < 1e-12);
+                }
+            }
+            assert!((lud.det() - crate::det::det(&updated).unwrap()).abs() < 1e-10);
+        }
+        // A - A[:, 0] e_0^T has a zero column
+        let mut lud = lu(&mat).unwrap();
+        let before = (lud.l.clone(), lud.u.clone());
+        let col = mat64![-2.0; -4.0; -3.0];
+        assert!(lud.update(&col, &mat64![1.0; 0.0; 0.0]).is_err());
+        assert_eq!((lud.l, lud.u), before);
+    }
+
+    #[test]
+    fn test_rcond_estimate() {
+        use crate::matrix::{Matrix, MatrixView, Mat64};
+        use crate::special::hilbert;
+        let norm1 = |a: &Mat64| (0..a.column())
+            .map(|c| a.data_column(c).iter().map(|x| x.abs()).sum::<f64>())
+            .fold(0.0, f64::max);
+        let cases: Vec<Mat64> = vec![
+            mat64![4.0, 1.0, 0.0; 1.0, 3.0, 1.0; 0.0, 1.0, 2.0],
+            mat64![1.0, 2.0, 3.0; 4.0, 5.0, 6.0; 7.0, 8.0, 10.0],
+            mat64![0.0, 2.0, -1.0, 5.0; 3.0, 0.0, 1.0, 1.0; -2.0, 4.0, 0.0, 1.0; 1.0, 1.0, 7.0, 0.0],
+            hilbert(6),
+        ];
+        for a in cases {
+            let lud = lu(&a).unwrap();
+            let exact = 1.0 / (norm1(&a) * norm1(&lud.inverse()));
+            let est = rcond_estimate(&lud);
+            assert!(est >= exact * (1.0 - 1e-9), "{} < {}", est, exact);
+            assert!(est <= exact * 3.0, "{} > 3 * {}", est, exact);
+        }
+        let lud = lu(&mat64![2.0]).unwrap();
+        assert_eq!(rcond_estimate(&lud), 1.0);
+    }
+
+    #[test]
+    fn test_lu_blocked() {
+        use crate::matrix::{Matrix, MatrixView, Mat64};
+        let n = 37;
+        let data: Vec<f64> = (0..(n * n)).map(|i| ((i * i) as f64 * 0.37).sin()).collect();
+        let mat = Mat64::new(n, n, &data);
+        let mut unblocked = mat.clone();
+        let p = lu_blocked(&mut unblocked, n).unwrap();
+        for nb in [1, 5, 16] {
+            let mut blocked = mat.clone();
+            assert_eq!(lu_blocked(&mut blocked, nb).unwrap(), p);
+            for (x, y) in blocked.data().iter().zip(unblocked.data().iter()) {
+                assert!((x - y).abs() < 1e-10, "{} vs {}", x, y);
+            }
+        }
+        let mut singular = mat64![1.0, 2.0, 3.0; 2.0, 4.0, 6.0; 1.0, 0.0, 1.0];
+        assert_eq!(lu_blocked(&mut singular, 1).unwrap_err().kind(), crate::error::JolinErrorKind::SingularMatrix);
+    }
 }
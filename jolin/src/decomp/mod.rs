@@ -10,4 +10,10 @@
 pub mod lu;
 
 /// QR decomposition algorithms
-pub mod qr;
\ No newline at end of file
+pub mod qr;
+
+/// Eigen decomposition algorithms
+pub mod eig;
+
+/// Cholesky decomposition algorithm
+pub mod cholesky;
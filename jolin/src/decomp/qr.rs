@@ -8,22 +8,185 @@
 
 use std::iter::zip;
 
-use crate::matrix::{Matrix, LikeNumber, mul, tr};
+use crate::matrix::{Matrix, LikeNumber, tr};
 use crate::error::JolinError;
+use crate::backend::Backend;
+use crate::settings::{self, Settings, KernelChoice};
+use crate::orthogonal::{givens, rotate_rows, rotate_columns, householder_vector};
 
 /// The answer of QR decomposition
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct QRDecomposition<T: Matrix> {
     pub q: T,
     pub r: T,
 }
 
+impl<T: Matrix> QRDecomposition<T> {
+    /// Solve the least squares problem `min ||A x - b||` with the factors of
+    /// `A`, by the back substitution `R x = Q^T b`. Each column of `b` is
+    /// solved separately.
+    /// ```
+    /// # use jolin::matrix::{*};
+    /// # use jolin::mat64;
+    /// # use jolin::decomp::qr::qr_househoulder;
+    /// let qr = qr_househoulder(&mat64![1.0, 0.0; 1.0, 1.0; 1.0, 2.0]).unwrap();
+    /// let x = qr.solve(&mat64![1.0; 3.0; 5.0]).unwrap();
+    /// assert!(eq_with_error(&x, &mat64![1.0; 2.0], 1e-12));
+    /// ```
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the row count of `b` isn't the one of `A`.
+    /// 2. Singular matrix - if `A` is rank deficient, i.e. the diagonal of
+    ///    `R` is zero up to rounding errors.
+    pub fn solve(&self, b: &T) -> Result<T, JolinError> {
+        let m = self.q.row();
+        let n = self.r.column();
+        if b.row() != m {
+            return Err(JolinError::shape_mismatching_with((m, b.column()), (b.row(), b.column())))
+        }
+        let max_diag = (0..n).map(|i| self.r.elem(i, i).abs())
+            .fold(T::Elem::zero(), |a, b| if b > a { b } else { a });
+        let tol = max_diag * T::Elem::epsilon().times_real(m as f64);
+        for i in 0..n {
+            if self.r.elem(i, i).abs() <= tol {
+                return Err(JolinError::singular_matrix_at(i))
+            }
+        }
+        let mut x = T::zero(n, b.column());
+        for c in 0..b.column() {
+            for i in (0..n).rev() {
+                let mut t = vector_dot_product(self.q.data_column(i), b.data_column(c));
+                for j in (i + 1)..n {
+                    t = t - self.r.elem(i, j) * x.elem(j, c);
+                }
+                *x.elem_mut(i, c) = t / self.r.elem(i, i);
+            }
+        }
+        Ok(x)
+    }
+
+    /// Update the decomposition to the one of `A` with the row vector `row`
+    /// appended below, in `O(m n)` with Givens rotations. It suits least
+    /// squares on streaming observations.
+    /// ```
+    /// # use jolin::matrix::{*};
+    /// # use jolin::mat64;
+    /// # use jolin::decomp::qr::qr_househoulder;
+    /// let mut qr = qr_househoulder(&mat64![1.0, 0.0; 1.0, 1.0]).unwrap();
+    /// qr.update_add_row(&mat64![1.0, 2.0]).unwrap();
+    /// let x = qr.solve(&mat64![1.0; 3.0; 5.0]).unwrap();
+    /// assert!(eq_with_error(&x, &mat64![1.0; 2.0], 1e-12));
+    /// ```
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if `row` is not a row vector of the column
+    ///    count of `A`, or `Q` is not square.
+    pub fn update_add_row(&mut self, row: &T) -> Result<(), JolinError> {
+        let m = self.q.row();
+        let n = self.r.column();
+        if (row.row(), row.column()) != (1, n) {
+            return Err(JolinError::shape_mismatching_with((1, n), (row.row(), row.column())))
+        }
+        if self.q.column() != m {
+            return Err(JolinError::shape_mismatching())
+        }
+        // Q' = [Q, 0; 0, 1] and R' = [R; row]
+        let mut q = T::zero(m + 1, m + 1);
+        for c in 0..m {
+            q.data_mut()[(c * (m + 1))..(c * (m + 1) + m)].copy_from_slice(self.q.data_column(c));
+        }
+        *q.elem_mut(m, m) = T::Elem::one();
+        let mut r = T::zero(m + 1, n);
+        for c in 0..n {
+            r.data_mut()[(c * (m + 1))..(c * (m + 1) + m)].copy_from_slice(self.r.data_column(c));
+            *r.elem_mut(m, c) = row.elem(0, c);
+        }
+        // zero the new row of R' with row j of R'
+        for j in 0..usize::min(n, m) {
+            if r.elem(m, j) != T::Elem::zero() {
+                let (c, s) = givens(r.elem(j, j), r.elem(m, j));
+                rotate_rows(&mut r, j, m, c, s);
+                rotate_columns(&mut q, j, m, c, s);
+                *r.elem_mut(m, j) = T::Elem::zero();
+            }
+        }
+        self.q = q;
+        self.r = r;
+        Ok(())
+    }
+
+    /// Update the decomposition to the one of `A` with row `k` removed, in
+    /// `O(m^2 + m n)` with Givens rotations. Together with `update_add_row`,
+    /// it allows least squares on a sliding window.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the answer would have fewer rows than
+    ///    columns, or `Q` is not square.
+    /// 2. Invalid value - if `k` is not a row index of `A`.
+    pub fn update_remove_row(&mut self, k: usize) -> Result<(), JolinError> {
+        let m = self.q.row();
+        let n = self.r.column();
+        if self.q.column() != m || m <= n {
+            return Err(JolinError::shape_mismatching())
+        }
+        if k >= m {
+            return Err(JolinError::invalid_value())
+        }
+        let mut q = self.q.clone();
+        let mut r = self.r.clone();
+        // rotate row k of Q into +-e_0, then column 0 of Q is +-e_k
+        for i in (1..m).rev() {
+            if q.elem(k, i) != T::Elem::zero() {
+                let (c, s) = givens(q.elem(k, i - 1), q.elem(k, i));
+                rotate_columns(&mut q, i - 1, i, c, s);
+                rotate_rows(&mut r, i - 1, i, c, s);
+                *q.elem_mut(k, i) = T::Elem::zero();
+            }
+        }
+        let mut q1 = Vec::with_capacity((m - 1) * (m - 1));
+        for c in 1..m {
+            let col = q.data_column(c);
+            q1.extend_from_slice(&col[..k]);
+            q1.extend_from_slice(&col[(k + 1)..]);
+        }
+        let mut r1 = Vec::with_capacity((m - 1) * n);
+        for c in 0..n {
+            r1.extend_from_slice(&r.data_column(c)[1..]);
+        }
+        self.q = T::from_vec(m - 1, m - 1, q1);
+        self.r = T::from_vec(m - 1, n, r1);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct QRDecompositionData<T> {
+    q: T,
+    r: T,
+}
+
+/// The column count of `Q` is checked to match the row count of `R`.
+#[cfg(feature = "serde")]
+impl<'de, T: Matrix + serde::Deserialize<'de>> serde::Deserialize<'de> for QRDecomposition<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let raw = QRDecompositionData::<T>::deserialize(deserializer)?;
+        if raw.q.column() != raw.r.row() {
+            return Err(D::Error::custom("the shapes of Q and R don't match"))
+        }
+        Ok(QRDecomposition { q: raw.q, r: raw.r })
+    }
+}
+
 /// Compute QR decomputation of the matrix with Gram-Schmidt process
 /// 
 /// This method is numbercially unstable, however, it's easy to understand.
 /// 
 /// See <https://en.wikipedia.org/wiki/Gram-Schmidt_process> for details
 /// about Gram-Schmidt process.
+#[allow(clippy::needless_range_loop)]
 pub fn qr_gram_schmidt<T: Matrix>(mat: &T) -> Result<QRDecomposition<T>, JolinError> {
     if mat.row() < mat.column() {
         return Err(JolinError::shape_mismatching());
@@ -54,7 +217,7 @@ pub fn qr_gram_schmidt<T: Matrix>(mat: &T) -> Result<QRDecomposition<T>, JolinEr
     let mut rmat = T::zero(m, n);
     for c in 0..n {
         for r in 0..(c+1) {
-            *rmat.elem_mut(r, c) = vector_dot_product(&q.data_column(r), &mat.data_column(c));
+            *rmat.elem_mut(r, c) = vector_dot_product(q.data_column(r), mat.data_column(c));
         }
     }
     
@@ -65,52 +228,327 @@ pub fn qr_gram_schmidt<T: Matrix>(mat: &T) -> Result<QRDecomposition<T>, JolinEr
 
 /// QR decomposition based on [Householder Reflection](https://en.wikipedia.org/wiki/Householder_transformation).
 /// 
-/// This method features better numerical stability, but it costs more time and memory bandwidth.
+/// This method features better numerical stability. It forms the full `m * m`
+/// matrix Q from the reflectors of `qr_householder_compact`, so use the compact
+/// form directly when Q is only multiplied with other matrices.
 pub fn qr_househoulder<T: Matrix>(mat: &T) -> Result<QRDecomposition<T>, JolinError> {
     if mat.row() < mat.column() {
         return Err(JolinError::shape_mismatching());
     }
     let m = mat.row();
     let n = mat.column();
-    let mut a = mat.clone();
+    if cfg!(feature = "blas") {
+        let mut a = mat.clone();
+        if let Some(q) = T::Elem::geqrf(m, n, a.data_mut()) {
+            return Ok(QRDecomposition { q: T::from_vec(m, m, q), r: a })
+        }
+    }
+    let compact = qr_householder_compact(mat)?;
+    Ok(QRDecomposition {
+        q: compact.q(), r: compact.r()
+    })
+}
+
+/// QR decomposition with [Givens rotations](https://en.wikipedia.org/wiki/Givens_rotation).
+///
+/// Each rotation zeros one element below the diagonal, and elements that are
+/// already zero are skipped, so it's cheap for sparse or nearly triangular
+/// matrices, e.g. Hessenberg ones.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::decomp::qr::qr_givens;
+/// let a = mat64![3.0, 1.0; 4.0, 2.0; 0.0, 5.0];
+/// let qr = qr_givens(&a).unwrap();
+/// assert!(eq_with_error(&mul(&qr.q, &qr.r).unwrap(), &a, 1e-12));
+/// assert_eq!(qr.r.elem(1, 0), 0.0);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the matrix has more columns than rows.
+pub fn qr_givens<T: Matrix>(mat: &T) -> Result<QRDecomposition<T>, JolinError> {
+    if mat.row() < mat.column() {
+        return Err(JolinError::shape_mismatching());
+    }
+    let m = mat.row();
     let mut q = T::identity(m);
-    let full_iteration = if m-1 < n {
-        m-1
-    } else {
-        n
-    };
-    for i in 0..full_iteration {
-        // create Householder vector
+    let mut r = mat.clone();
+    for j in 0..mat.column() {
+        for i in ((j + 1)..m).rev() {
+            if r.elem(i, j) != T::Elem::zero() {
+                let (c, s) = givens(r.elem(i - 1, j), r.elem(i, j));
+                rotate_rows(&mut r, i - 1, i, c, s);
+                rotate_columns(&mut q, i - 1, i, c, s);
+                *r.elem_mut(i, j) = T::Elem::zero();
+            }
+        }
+    }
+    Ok(QRDecomposition { q, r })
+}
+
+/// The compact answer of Householder QR decomposition, which keeps the
+/// reflectors instead of the matrix Q.
+///
+/// Q is the product `H_0 H_1 ... H_{k-1}` of the reflectors
+/// `H_i = I - tau_i v_i v_i^T`, where `v_i` is zero above row `i`, one at row
+/// `i`, and stored below the diagonal of column `i` of `packed`.
+#[derive(Debug, Clone)]
+pub struct CompactQR<T: Matrix> {
+    /// R in the upper triangle and the Householder vectors below the diagonal
+    pub packed: T,
+    /// Scalars of the reflectors
+    pub tau: Vec<T::Elem>,
+}
+
+/// Compute the compact QR decomposition with Householder reflections, which
+/// costs `O(m n^2)` time and no extra matrix. With the `parallel` feature, the
+/// update of the remaining columns by each reflector is split across threads
+/// when it is large enough by the global settings.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::decomp::qr::qr_householder_compact;
+/// let a = mat64![3.0, 1.0; 4.0, 2.0; 0.0, 5.0];
+/// let qr = qr_householder_compact(&a).unwrap();
+/// let qr_a = qr.apply_q(&qr.r()).unwrap();
+/// assert!(eq_with_error(&qr_a, &a, 1e-12));
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the matrix has more columns than rows.
+pub fn qr_householder_compact<T: Matrix>(mat: &T) -> Result<CompactQR<T>, JolinError> {
+    if mat.row() < mat.column() {
+        return Err(JolinError::shape_mismatching());
+    }
+    let m = mat.row();
+    let n = mat.column();
+    let mut a = mat.clone();
+    let settings = settings::get();
+    let steps = usize::min(m.saturating_sub(1), n);
+    let mut tau = Vec::with_capacity(steps);
+    for i in 0..steps {
+        // reflect the column to alpha e_0 with v[0] = 1
         let x = &a.data_column(i)[i..m];
-        let alpha = -l2_norm_of_vector(x) * x[0].sign();
-        let mut u = Vec::from(x);
-        u[0] = u[0] - alpha;
-        let u_norm = l2_norm_of_vector(&u);
-        for v in u.iter_mut() {
-            *v = *v / u_norm;
-        }
-
-        // Householder matrix Q_i = I - 2v*v^T
-        let mut q_i = T::identity(m);
-        for j in 0..(m-i) {
-            for k in 0..(m-i) {
-                let q_i_v = q_i.elem(i + j, i + k);
-                let vvt = u[j] * u[k];
-                *q_i.elem_mut(i + j, i + k)= q_i_v - vvt - vvt;
+        let norm = l2_norm_of_vector(x);
+        if norm == T::Elem::zero() {
+            tau.push(T::Elem::zero());
+            continue;
+        }
+        let alpha = -norm * x[0].sign();
+        let (v, t) = householder_vector(x);
+
+        // apply the reflector to the remaining columns
+        reflect_columns(&mut a.data_mut()[((i + 1) * m)..], m, i, &v, t, &settings);
+        let col = &mut a.data_mut()[(i + i * m)..((i + 1) * m)];
+        col[0] = alpha;
+        col[1..].copy_from_slice(&v[1..]);
+        tau.push(t);
+    }
+    Ok(CompactQR { packed: a, tau })
+}
+
+impl<T: Matrix> CompactQR<T> {
+    /// Get the upper triangular matrix R of the same shape as the input
+    pub fn r(&self) -> T {
+        let mut r = self.packed.clone();
+        for c in 0..r.column() {
+            for i in (c + 1)..r.row() {
+                *r.elem_mut(i, c) = T::Elem::zero();
             }
         }
-        
-        // accumulate Q_i to Q and multiply Q_i onto A to eliminate elements
-        q = mul(&q_i,&q).unwrap();
-        a = mul(&q_i, &a).unwrap();
+        r
     }
 
-    // At the end, we have `A = QX`
-    // So `Q^T A = X`, which means that A is the R matrix
-    // And Q^T is the actual Q matrix we want to have.
-    Ok(QRDecomposition {
-        q: tr(&q), r: a
-    })
+    /// Form the full orthogonal matrix Q of `m * m`
+    pub fn q(&self) -> T {
+        self.apply_q(&T::identity(self.packed.row())).unwrap()
+    }
+
+    /// Compute `Q B` without forming Q.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the row count of `B` differs from the one of the decomposed matrix.
+    pub fn apply_q(&self, b: &T) -> Result<T, JolinError> {
+        self.apply(b, true)
+    }
+
+    /// Compute `Q^T B` without forming Q, e.g. to solve least squares
+    /// problems.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the row count of `B` differs from the one of the decomposed matrix.
+    pub fn apply_qt(&self, b: &T) -> Result<T, JolinError> {
+        self.apply(b, false)
+    }
+
+    fn apply(&self, b: &T, reversed: bool) -> Result<T, JolinError> {
+        let m = self.packed.row();
+        if b.row() != m {
+            return Err(JolinError::shape_mismatching());
+        }
+        let settings = settings::get();
+        let mut ans = b.clone();
+        let mut v = Vec::with_capacity(m);
+        for step in 0..self.tau.len() {
+            let i = if reversed { self.tau.len() - 1 - step } else { step };
+            if self.tau[i] == T::Elem::zero() {
+                continue;
+            }
+            v.clear();
+            v.push(T::Elem::one());
+            v.extend_from_slice(&self.packed.data_column(i)[(i + 1)..m]);
+            reflect_columns(ans.data_mut(), m, i, &v, self.tau[i], &settings);
+        }
+        Ok(ans)
+    }
+}
+
+/// The answer of QR decomposition with column pivoting, `A P = Q R`
+#[derive(Debug, Clone)]
+pub struct PivotedQR<T: Matrix> {
+    /// Orthogonal matrix of `m * m`
+    pub q: T,
+    /// Upper triangular matrix of `m * n`, whose diagonal is non-increasing
+    /// in absolute value
+    pub r: T,
+    /// Permutation index, i.e. column `j` of `Q R` is column `p[j]` of `A`
+    pub p: Vec<usize>,
+}
+
+/// Householder QR decomposition with column pivoting. At each step the
+/// remaining column of the largest norm is moved to the front, so the
+/// diagonal of R reveals the numerical rank. Unlike the other QR
+/// decompositions, the matrix can have more columns than rows.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::decomp::qr::qr_pivoted;
+/// let a = mat64![1.0, 3.0; 0.0, 4.0];
+/// let qr = qr_pivoted(&a).unwrap();
+/// assert_eq!(qr.p, vec![1, 0]);
+/// assert!((qr.r.elem(0, 0).abs() - 5.0).abs() < 1e-12);
+/// ```
+pub fn qr_pivoted<T: Matrix>(mat: &T) -> Result<PivotedQR<T>, JolinError> {
+    let m = mat.row();
+    let n = mat.column();
+    let mut a = mat.clone();
+    let mut qt = T::identity(m);
+    let mut p: Vec<usize> = (0..n).collect();
+    let settings = settings::get();
+    for i in 0..usize::min(m, n) {
+        // move the column of the largest remaining norm to column i
+        let norms: Vec<T::Elem> = (i..n).map(|c| l2_norm_of_vector(&a.data_column(c)[i..m])).collect();
+        let mut pivot = 0;
+        for (j, v) in norms.iter().enumerate() {
+            if *v > norms[pivot] {
+                pivot = j;
+            }
+        }
+        let pivot = pivot + i;
+        if pivot != i {
+            p.swap(i, pivot);
+            for r in 0..m {
+                let idx1 = a.idx(r, i);
+                let idx2 = a.idx(r, pivot);
+                a.data_mut().swap(idx1, idx2);
+            }
+        }
+        if i + 1 == m || norms[pivot - i] == T::Elem::zero() {
+            continue;
+        }
+        let x = &a.data_column(i)[i..m];
+        let alpha = -norms[pivot - i] * x[0].sign();
+        let (v, t) = householder_vector(x);
+        reflect_columns(&mut a.data_mut()[((i + 1) * m)..], m, i, &v, t, &settings);
+        reflect_columns(qt.data_mut(), m, i, &v, t, &settings);
+        let col = &mut a.data_mut()[(i * m)..((i + 1) * m)];
+        col[i] = alpha;
+        for r in col[(i + 1)..].iter_mut() {
+            *r = T::Elem::zero();
+        }
+    }
+    Ok(PivotedQR { q: tr(&qt), r: a, p })
+}
+
+/// Numerical rank from the diagonal of R of a pivoted QR decomposition, i.e.
+/// the count of the diagonal elements larger than `tol` in absolute value.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::decomp::qr::{qr_pivoted, rank_from_qr};
+/// let qr = qr_pivoted(&mat64![1.0, 2.0; 2.0, 4.0; 3.0, 6.0]).unwrap();
+/// assert_eq!(rank_from_qr(&qr, 1e-10), 1);
+/// ```
+pub fn rank_from_qr<T: Matrix>(qr: &PivotedQR<T>, tol: T::Elem) -> usize {
+    let k = usize::min(qr.r.row(), qr.r.column());
+    // the diagonal is non-increasing, so it stops at the first small one
+    (0..k).take_while(|i| qr.r.elem(*i, *i).abs() > tol).count()
+}
+
+/// Orthonormal basis of the column space of the matrix, as the columns of
+/// the answer. The rank is decided by pivoted QR decomposition with the
+/// tolerance `max(m, n) * eps * |R[0, 0]|`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::decomp::qr::column_space_basis;
+/// let basis = column_space_basis(&mat64![1.0, 2.0; 2.0, 4.0]).unwrap();
+/// assert_eq!(basis.column(), 1);
+/// ```
+pub fn column_space_basis<T: Matrix>(mat: &T) -> Result<T, JolinError> {
+    let qr = qr_pivoted(mat)?;
+    let m = mat.row();
+    let largest = if m > 0 && mat.column() > 0 { qr.r.elem(0, 0).abs() } else { T::Elem::zero() };
+    let tol = largest * T::Elem::epsilon().times_real(usize::max(m, mat.column()) as f64);
+    let rank = rank_from_qr(&qr, tol);
+    Ok(T::from_vec(m, rank, qr.q.data()[..(m * rank)].to_vec()))
+}
+
+/// Reflect rows `i..m` of each column in the column-major `cols` of `m` rows
+/// by `I - tau v v^T`. The columns are independent, so large updates are split
+/// across threads with the `parallel` feature.
+fn reflect_columns<T: LikeNumber>(cols: &mut [T], m: usize, i: usize, v: &[T], tau: T, settings: &Settings) {
+    if m == 0 {
+        return
+    }
+    let parallel = match settings.kernel {
+        KernelChoice::Auto => settings.max_threads != 1 && (m - i) * (cols.len() / m) >= settings.parallel_threshold,
+        KernelChoice::Parallel => true,
+        _ => false,
+    };
+    if parallel {
+        reflect_columns_parallel(cols, m, i, v, tau, settings);
+        return
+    }
+    for col in cols.chunks_exact_mut(m) {
+        reflect(&mut col[i..], v, tau);
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn reflect_columns_parallel<T: LikeNumber>(cols: &mut [T], m: usize, i: usize, v: &[T], tau: T, settings: &Settings) {
+    use rayon::prelude::*;
+    let columns = (cols.len() / m).div_ceil(crate::matrix::kernel::thread_count(settings)).max(1);
+    cols.par_chunks_mut(m * columns).for_each(|chunk| {
+        for col in chunk.chunks_exact_mut(m) {
+            reflect(&mut col[i..], v, tau);
+        }
+    });
+}
+
+#[cfg(not(feature = "parallel"))]
+fn reflect_columns_parallel<T: LikeNumber>(cols: &mut [T], m: usize, i: usize, v: &[T], tau: T, _settings: &Settings) {
+    for col in cols.chunks_exact_mut(m) {
+        reflect(&mut col[i..], v, tau);
+    }
+}
+
+/// Compute `x = (I - tau v v^T) x`
+fn reflect<T: LikeNumber>(x: &mut [T], v: &[T], tau: T) {
+    let w = tau * vector_dot_product(v, x);
+    for (x, v) in x.iter_mut().zip(v.iter()) {
+        *x = *x - w * *v;
+    }
 }
 
 fn l2_norm_of_vector<T: LikeNumber>(v: &[T]) -> T {
@@ -190,4 +628,147 @@ mod test{
         let qmr = mul(&ans.q, &ans.r).unwrap();
         assert!(eq_with_error(&qmr, &x, 1e-7));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_qr_givens() {
+        let x = mat64![1.0, 2.0, 3.0; 1.0, 1.0, 4.0; 5.0, 6.0, 2.0; 0.0, 1.0, 1.0];
+        let ans = qr_givens(&x).unwrap();
+        let qtq = mul(&tr(&ans.q), &ans.q).unwrap();
+        assert!(eq_with_error(&qtq, &Mat64::identity(4), 1e-12));
+        assert!(is_right_triangle(&ans.r, 0.0));
+        assert!(eq_with_error(&mul(&ans.q, &ans.r).unwrap(), &x, 1e-12));
+        // an upper triangular matrix needs no rotation
+        let u = mat64![2.0, 1.0; 0.0, 3.0];
+        let ans = qr_givens(&u).unwrap();
+        assert_eq!((ans.q, ans.r), (Mat64::identity(2), u));
+        assert!(qr_givens(&Mat64::zero(2, 3)).is_err());
+    }
+
+    #[test]
+    fn test_qr_householder_compact() {
+        let x = mat64![1.0, 2.0; 3.0, -1.0; 0.0, 4.0; 2.0, 2.0];
+        let qr = qr_householder_compact(&x).unwrap();
+        let full = qr_househoulder(&x).unwrap();
+        assert!(eq_with_error(&qr.r(), &full.r, 1e-12));
+        assert!(eq_with_error(&qr.q(), &full.q, 1e-12));
+        assert!(is_right_triangle(&qr.r(), 0.0));
+
+        // Q^T Q B = B and Q R = X
+        let b = mat64![1.0; 2.0; 3.0; 4.0];
+        let qtb = qr.apply_qt(&b).unwrap();
+        assert!(eq_with_error(&qr.apply_q(&qtb).unwrap(), &b, 1e-12));
+        assert!(eq_with_error(&qtb, &mul(&tr(&full.q), &b).unwrap(), 1e-12));
+        assert!(eq_with_error(&qr.apply_q(&qr.r()).unwrap(), &x, 1e-12));
+        assert!(qr.apply_q(&Mat64::zero(3, 1)).is_err());
+
+        // zero columns need no reflection
+        let z = mat64![0.0, 1.0; 0.0, 2.0; 0.0, 2.0];
+        let qr = qr_householder_compact(&z).unwrap();
+        assert_eq!(qr.tau[0], 0.0);
+        assert!(eq_with_error(&qr.apply_q(&qr.r()).unwrap(), &z, 1e-12));
+        assert!(qr_householder_compact(&Mat64::zero(2, 3)).is_err());
+    }
+
+    #[test]
+    fn test_qr_householder_compact_parallel() {
+        let (m, n) = (40, 12);
+        let x = Mat64::from_vec(m, n, (0..(m * n)).map(|v| ((v * 7919) % 101) as f64 - 50.0).collect());
+        let serial = Settings { kernel: KernelChoice::Naive, ..settings::get() };
+        let mut a = x.clone();
+        let mut b = x.clone();
+        let v: Vec<f64> = (0..m).map(|r| 1.0 / (r + 1) as f64).collect();
+        reflect_columns(a.data_mut(), m, 3, &v[3..], 0.5, &serial);
+        reflect_columns(b.data_mut(), m, 3, &v[3..], 0.5, &Settings { kernel: KernelChoice::Parallel, max_threads: 3, ..serial });
+        assert_eq!(a, b);
+
+        let qr = qr_householder_compact(&x).unwrap();
+        assert!(eq_with_error(&qr.apply_q(&qr.r()).unwrap(), &x, 1e-9));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_qr_serde() {
+        let ans = qr_househoulder(&mat64![1.0, 2.0; 3.0, 4.0; 5.0, 6.0]).unwrap();
+        let json = serde_json::to_string(&ans).unwrap();
+        let back: QRDecomposition<Mat64> = serde_json::from_str(&json).unwrap();
+        assert_eq!((back.q, back.r), (ans.q, ans.r));
+
+        let bad = r#"{"q":{"row":2,"column":2,"data":[1,0,0,1]},"r":{"row":1,"column":1,"data":[1]}}"#;
+        assert!(serde_json::from_str::<QRDecomposition<Mat64>>(bad).is_err());
+    }
+
+    #[test]
+    fn test_qr_solve() {
+        let a = mat64![1.0, 1.0; 1.0, 2.0; 1.0, 3.0; 1.0, 4.0];
+        let b = mat64![1.0, 2.0; 3.0, 4.0; 2.0, 6.0; 4.0, 8.0];
+        for qr in [qr_househoulder(&a).unwrap(), qr_gram_schmidt(&a).unwrap()] {
+            let x = qr.solve(&b).unwrap();
+            assert!(eq_with_error(&x, &mat64![0.5, 0.0; 0.8, 2.0], 1e-10));
+        }
+        let square = mat64![2.0, 1.0; 1.0, 3.0];
+        let x = qr_househoulder(&square).unwrap().solve(&mat64![3.0; 4.0]).unwrap();
+        assert!(eq_with_error(&x, &mat64![1.0; 1.0], 1e-12));
+
+        let qr = qr_househoulder(&a).unwrap();
+        assert_eq!(qr.solve(&Mat64::zero(3, 1)).unwrap_err().kind(), crate::error::JolinErrorKind::ShapeMismatching);
+        let deficient = qr_househoulder(&mat64![1.0, 2.0; 2.0, 4.0; 3.0, 6.0]).unwrap();
+        assert_eq!(deficient.solve(&Mat64::zero(3, 1)).unwrap_err().context(), JolinError::singular_matrix_at(1).context());
+    }
+
+    #[test]
+    fn test_qr_pivoted() {
+        let a = mat64![
+            1.0, 2.0, 0.0, 3.0;
+            2.0, 4.0, 1.0, 6.0;
+            3.0, 6.0, 0.0, 9.5];
+        let qr = qr_pivoted(&a).unwrap();
+        assert!(eq_with_error(&mul(&tr(&qr.q), &qr.q).unwrap(), &Mat64::identity(3), 1e-12));
+        assert!(is_right_triangle(&qr.r, 1e-12));
+        let rebuild = mul(&qr.q, &qr.r).unwrap();
+        for c in 0..4 {
+            for r in 0..3 {
+                assert!((rebuild.elem(r, c) - a.elem(r, qr.p[c])).abs() < 1e-12);
+            }
+        }
+        for i in 1..3 {
+            assert!(qr.r.elem(i, i).abs() <= qr.r.elem(i - 1, i - 1).abs());
+        }
+        assert_eq!(rank_from_qr(&qr, 1e-10), 3);
+
+        let deficient = mat64![1.0, 2.0, 1.0; 2.0, 4.0, 0.0; 3.0, 6.0, 1.0; 1.0, 2.0, 0.0];
+        assert_eq!(rank_from_qr(&qr_pivoted(&deficient).unwrap(), 1e-10), 2);
+        let basis = column_space_basis(&deficient).unwrap();
+        assert_eq!((basis.row(), basis.column()), (4, 2));
+        // projecting the columns onto the basis doesn't change them
+        let projection = mul(&basis, &mul(&tr(&basis), &deficient).unwrap()).unwrap();
+        assert!(eq_with_error(&projection, &deficient, 1e-12));
+
+        assert_eq!(column_space_basis(&Mat64::zero(3, 2)).unwrap().column(), 0);
+    }
+
+    #[test]
+    fn test_qr_update_rows() {
+        let a = mat64![1.0, 2.0; 3.0, -1.0; 0.5, 4.0];
+        let mut qr = qr_househoulder(&a).unwrap();
+        qr.update_add_row(&mat64![2.0, 2.0]).unwrap();
+        let a2 = mat64![1.0, 2.0; 3.0, -1.0; 0.5, 4.0; 2.0, 2.0];
+        assert!(eq_with_error(&mul(&qr.q, &qr.r).unwrap(), &a2, 1e-12));
+        assert!(eq_with_error(&trmul(&qr.q, &qr.q).unwrap(), &Mat64::identity(4), 1e-12));
+        assert!(is_right_triangle(&qr.r, 1e-12));
+
+        qr.update_remove_row(1).unwrap();
+        let a3 = mat64![1.0, 2.0; 0.5, 4.0; 2.0, 2.0];
+        assert!(eq_with_error(&mul(&qr.q, &qr.r).unwrap(), &a3, 1e-12));
+        assert!(eq_with_error(&trmul(&qr.q, &qr.q).unwrap(), &Mat64::identity(3), 1e-12));
+        assert!(is_right_triangle(&qr.r, 1e-12));
+        let x = qr.solve(&mat64![1.0; 2.0; 3.0]).unwrap();
+        let expected = qr_househoulder(&a3).unwrap().solve(&mat64![1.0; 2.0; 3.0]).unwrap();
+        assert!(eq_with_error(&x, &expected, 1e-12));
+
+        assert!(qr.update_remove_row(3).is_err());
+        assert!(qr.update_add_row(&mat64![1.0, 2.0, 3.0]).is_err());
+        qr.update_remove_row(0).unwrap();
+        // two rows are left for two columns
+        assert!(qr.update_remove_row(0).is_err());
+    }
+}
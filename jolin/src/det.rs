@@ -6,37 +6,74 @@
  * See LICENSE file in the root of the repo.
  */
 
-use crate::matrix::{Matrix, LikeNumber};
+use crate::matrix::{Matrix, MatrixView, LikeNumber};
 use crate::error::JolinError;
-use crate::decomp::lu::{lu, LUDecomposable};
+use crate::decomp::lu::{lu, lu_inplace, LUDecomposable};
+use crate::decomp::cholesky::cholesky;
 use crate::Mat64;
 
 /// Compute the determinant of the matrix
+///
+/// Matrices up to `3 * 3` use the closed forms, and triangular matrices use
+/// the product of the diagonal. Symmetric positive definite matrices are
+/// detected by trying Cholesky decomposition. Others use LU decomposition.
 pub fn det<T: Matrix>(mat: &T) -> Result<T::Elem, JolinError> {
     if mat.row() != mat.column() {
         return Err(JolinError::shape_mismatching())
     }
-    return match mat.row() {
-        2 => {
-            Ok(mat.elem(0, 0) * mat.elem(1, 1) - mat.elem(0, 1) * mat.elem(1, 0))
-        }
-        _ => {
+    match fast_det(mat) {
+        Some(v) => Ok(v),
+        None => {
             match lu(mat) {
                 Err(_err) => Ok(T::Elem::zero()),
-                Ok(lud) => {
-                    let detlu = diagonal_product(&lud.l) * diagonal_product(&lud.u);
-                    if permutation_order(&lud.p) % 2 == 0 {
-                        Ok(detlu)
-                    } else {
-                        Ok(-detlu)
-                    }
-                }
+                Ok(lud) => Ok(lud.det()),
             }
         }
     }
 }
 
+/// Compute the sign and the natural logarithm of the absolute value of the
+/// determinant, which doesn't overflow or underflow for large matrices. The
+/// determinant is `sign * exp(logabsdet)`.
+///
+/// The sign is zero and the logarithm is negative infinity for singular
+/// matrices.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::det::slogdet;
+/// let (sign, logabsdet) = slogdet(&mat64![1.0, 2.0; 3.0, 4.0]).unwrap();
+/// assert_eq!(sign, -1.0);
+/// assert!((logabsdet - 2.0f64.ln()).abs() < 1e-12);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the matrix is not square.
+pub fn slogdet<T: Matrix>(mat: &T) -> Result<(T::Elem, T::Elem), JolinError> {
+    if mat.row() != mat.column() {
+        return Err(JolinError::shape_mismatching())
+    }
+    let mut packed = mat.clone();
+    let p = match lu_inplace(&mut packed) {
+        Err(_err) => {
+            return Ok((T::Elem::zero(), T::Elem::one().times_real(f64::NEG_INFINITY)))
+        }
+        Ok(p) => p,
+    };
+    // the diagonal of L is one, so only U counts
+    let mut sign = if permutation_order(&p).is_multiple_of(2) { T::Elem::one() } else { -T::Elem::one() };
+    let mut logabsdet = T::Elem::zero();
+    for i in 0..packed.row() {
+        let v = packed.elem(i, i);
+        sign = sign * v.sign();
+        logabsdet = logabsdet + v.abs().ln();
+    }
+    Ok((sign, logabsdet))
+}
+
 /// Type-specific determinant algorithm.
+// Only the tests call it so far; it stays private rather than becoming API.
+#[allow(dead_code)]
 trait DeterminantComputable: Matrix {
     /// Compute the determinant of the matrix.
     fn det(mat: &Self) -> Result<Self::Elem, JolinError>;
@@ -47,27 +84,60 @@ impl DeterminantComputable for Mat64 {
         if mat.row() != mat.column() {
             return Err(JolinError::shape_mismatching())
         }
-        return match mat.row() {
-            2 => {
-                Ok(mat.elem(0, 0) * mat.elem(1, 1) - mat.elem(0, 1) * mat.elem(1, 0))
-            }
-            _ => {
+        match fast_det(mat) {
+            Some(v) => Ok(v),
+            None => {
                 match Mat64::lu_decomp(mat) {
                     Err(_err) => Ok(0.0),
-                    Ok(lud) => {
-                        let detlu = diagonal_product(&lud.l) * diagonal_product(&lud.u);
-                        if permutation_order(&lud.p) % 2 == 0 {
-                            Ok(detlu)
-                        } else {
-                            Ok(-detlu)
-                        }
-                    }
+                    Ok(lud) => Ok(lud.det()),
                 }
             }
         }
     }
 }
 
+/// Determinant by the closed forms of small matrices, triangular matrices
+/// and symmetric positive definite matrices. Returns `None` if none of them
+/// applies. The matrix must be square.
+fn fast_det<T: Matrix>(mat: &T) -> Option<T::Elem> {
+    let e = |r, c| mat.elem(r, c);
+    match mat.row() {
+        0 => return Some(T::Elem::one()),
+        1 => return Some(e(0, 0)),
+        2 => return Some(e(0, 0) * e(1, 1) - e(0, 1) * e(1, 0)),
+        3 => {
+            return Some(
+                e(0, 0) * (e(1, 1) * e(2, 2) - e(1, 2) * e(2, 1))
+                - e(0, 1) * (e(1, 0) * e(2, 2) - e(1, 2) * e(2, 0))
+                + e(0, 2) * (e(1, 0) * e(2, 1) - e(1, 1) * e(2, 0))
+            )
+        }
+        _ => {}
+    }
+    let n = mat.row();
+    let zero = T::Elem::zero();
+    let mut upper = true;
+    let mut lower = true;
+    let mut symmetric = true;
+    for c in 0..n {
+        for r in 0..c {
+            upper = upper && e(c, r) == zero;
+            lower = lower && e(r, c) == zero;
+            symmetric = symmetric && e(r, c) == e(c, r);
+        }
+    }
+    if upper || lower {
+        return Some(diagonal_product(mat))
+    }
+    if symmetric {
+        // det(A) = det(L)^2, and it falls back to LU if A is indefinite
+        if let Ok(ch) = cholesky(mat) {
+            return Some(ch.det())
+        }
+    }
+    None
+}
+
 fn diagonal_product<T: Matrix>(mat: &T) -> T::Elem {
     let mut ans = mat.elem(0, 0);
     for i in 1..mat.row() {
@@ -78,15 +148,15 @@ fn diagonal_product<T: Matrix>(mat: &T) -> T::Elem {
 
 /// Given a permutation, compute how many steps of exchanges does it take
 /// to reach the permutation.
-fn permutation_order(p: &Vec<usize>) -> usize {
+pub(crate) fn permutation_order(p: &[usize]) -> usize {
     let mut ans = 0;
-    let mut a = p.clone();
+    let mut a = p.to_vec();
     for i in 0..p.len() {
         while a[i] != i {
             let tmp = a[i];
             a[i] = a[a[i]];
             a[tmp] = tmp;
-            ans = ans + 1;
+            ans += 1;
         }
     }
     ans
@@ -95,7 +165,7 @@ fn permutation_order(p: &Vec<usize>) -> usize {
 #[cfg(test)]
 mod test {
     use crate::mat64;
-    use crate::det::{det, DeterminantComputable};
+    use crate::det::{det, slogdet, DeterminantComputable};
     use crate::matrix::{Matrix, Mat64};
     
     #[test]
@@ -147,4 +217,50 @@ mod test {
             0.0, 0.0, 0.0, 1.0]
         ), Ok(-1.0));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_slogdet() {
+        let (sign, logabsdet) = slogdet(&mat64![1.0, 2.0, 3.0; 2.0, 4.0, 2.0; 2.0, 3.0, 1.0]).unwrap();
+        assert_eq!(sign, -1.0);
+        assert!((logabsdet - 4.0f64.ln()).abs() < 1e-12);
+        // the determinant 1e-400 underflows
+        let mut small = Mat64::identity(100);
+        small.apply(|v| v * 1e-4);
+        assert_eq!(det(&small).unwrap(), 0.0);
+        let (sign, logabsdet) = slogdet(&small).unwrap();
+        assert_eq!(sign, 1.0);
+        assert!((logabsdet - 100.0 * 1e-4f64.ln()).abs() < 1e-9);
+        let (sign, logabsdet) = slogdet(&mat64![1.0, 2.0; 2.0, 4.0]).unwrap();
+        assert_eq!(sign, 0.0);
+        assert_eq!(logabsdet, f64::NEG_INFINITY);
+        assert!(slogdet(&Mat64::zero(2, 3)).is_err());
+        assert_eq!(slogdet(&Mat64::zero(0, 0)), Ok((1.0, 0.0)));
+    }
+
+    #[test]
+    fn test_det_fast_paths() {
+        assert_eq!(det(&mat64![-3.0]), Ok(-3.0));
+        assert_eq!(det(&Mat64::zero(0, 0)), Ok(1.0));
+        assert_eq!(det(&mat64![2.0, 0.0, 1.0; 1.0, 3.0, 0.0; 0.0, 1.0, 4.0]), Ok(25.0));
+        let upper = mat64![
+            2.0, 1.0, 5.0, 7.0;
+            0.0, 3.0, 1.0, 1.0;
+            0.0, 0.0, -1.0, 2.0;
+            0.0, 0.0, 0.0, 0.5];
+        assert_eq!(det(&upper), Ok(-3.0));
+        assert_eq!(Mat64::det(&crate::matrix::tr(&upper)), Ok(-3.0));
+        let spd = mat64![
+            4.0, 2.0, 0.0, 0.0;
+            2.0, 5.0, 1.0, 0.0;
+            0.0, 1.0, 2.0, 0.5;
+            0.0, 0.0, 0.5, 1.0];
+        assert!((det(&spd).unwrap() - 24.0).abs() < 1e-12);
+        // symmetric but indefinite, so it goes through LU
+        let indefinite = mat64![
+            0.0, 1.0, 0.0, 0.0;
+            1.0, 0.0, 0.0, 0.0;
+            0.0, 0.0, 1.0, 0.0;
+            0.0, 0.0, 0.0, 1.0];
+        assert_eq!(det(&indefinite), Ok(-1.0));
+    }
+}
@@ -6,6 +6,8 @@
  * See LICENSE file in the root of the repo.
  */
 
+use std::fmt;
+
 #[derive(Debug, PartialEq, Copy, Clone, Eq)]
 pub enum JolinErrorKind {
     /// The shape of input matrices doesn't match or satisfy the requirements.
@@ -13,34 +15,161 @@ pub enum JolinErrorKind {
     /// Not enough input is provided.
     NotEnoughInput,
     /// Singular matrix is encountered.
-    SingularMatrix
+    SingularMatrix,
+    /// Some input value is out of the valid range.
+    InvalidValue,
+    /// Reading or writing a file failed.
+    IOError
+}
+
+/// Where an error occurred, recorded by the operations that know it
+#[derive(Debug, PartialEq, Copy, Clone, Eq)]
+pub enum JolinErrorContext {
+    /// The shape the operation expected and the shape it got, as pairs of
+    /// the row and column count.
+    Shape {
+        expected: (usize, usize),
+        actual: (usize, usize),
+    },
+    /// The column whose pivot broke down.
+    Pivot(usize),
+    /// The count of items, e.g. the matrices of a batch, the operation
+    /// expected and the count it got.
+    Length {
+        expected: usize,
+        actual: usize,
+    },
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy)]
 pub struct JolinError {
-    _kind: JolinErrorKind
+    _kind: JolinErrorKind,
+    _context: Option<JolinErrorContext>,
 }
 
+/// Errors are equal if they are of the same kind. The context is only
+/// informative, compare `context()` to check it.
+impl PartialEq for JolinError {
+    fn eq(&self, other: &JolinError) -> bool {
+        self._kind == other._kind
+    }
+}
+
+impl Eq for JolinError {}
+
 impl JolinError {
     pub fn shape_mismatching() -> JolinError {
         JolinError {
-            _kind: JolinErrorKind::ShapeMismatching
+            _kind: JolinErrorKind::ShapeMismatching,
+            _context: None,
         }
     }
 
     pub fn not_enough_input() -> JolinError {
         JolinError {
-            _kind: JolinErrorKind::NotEnoughInput
+            _kind: JolinErrorKind::NotEnoughInput,
+            _context: None,
         }
     }
 
     pub fn singular_matrix() -> JolinError {
         JolinError {
-            _kind: JolinErrorKind::SingularMatrix
+            _kind: JolinErrorKind::SingularMatrix,
+            _context: None,
+        }
+    }
+
+    pub fn invalid_value() -> JolinError {
+        JolinError {
+            _kind: JolinErrorKind::InvalidValue,
+            _context: None,
+        }
+    }
+
+    pub fn io_error() -> JolinError {
+        JolinError {
+            _kind: JolinErrorKind::IOError,
+            _context: None,
+        }
+    }
+
+    /// Shape mismatching error recording the expected and the actual shape
+    pub fn shape_mismatching_with(expected: (usize, usize), actual: (usize, usize)) -> JolinError {
+        JolinError {
+            _kind: JolinErrorKind::ShapeMismatching,
+            _context: Some(JolinErrorContext::Shape { expected, actual }),
+        }
+    }
+
+    /// Shape mismatching error recording the expected and the actual count
+    /// of items
+    pub fn length_mismatching(expected: usize, actual: usize) -> JolinError {
+        JolinError {
+            _kind: JolinErrorKind::ShapeMismatching,
+            _context: Some(JolinErrorContext::Length { expected, actual }),
+        }
+    }
+
+    /// Singular matrix error recording the column whose pivot broke down
+    pub fn singular_matrix_at(column: usize) -> JolinError {
+        JolinError {
+            _kind: JolinErrorKind::SingularMatrix,
+            _context: Some(JolinErrorContext::Pivot(column)),
         }
     }
 
     pub fn kind(&self) -> JolinErrorKind {
         self._kind
     }
-}
\ No newline at end of file
+
+    /// Where the error occurred, if the operation recorded it
+    pub fn context(&self) -> Option<JolinErrorContext> {
+        self._context
+    }
+}
+
+impl fmt::Display for JolinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self._kind {
+            JolinErrorKind::ShapeMismatching => "shape mismatching",
+            JolinErrorKind::NotEnoughInput => "not enough input",
+            JolinErrorKind::SingularMatrix => "singular matrix",
+            JolinErrorKind::InvalidValue => "invalid value",
+            JolinErrorKind::IOError => "IO error",
+        };
+        match self._context {
+            None => write!(f, "{}", kind),
+            Some(JolinErrorContext::Shape { expected, actual }) => write!(
+                f, "{}: expected {}x{}, got {}x{}", kind, expected.0, expected.1, actual.0, actual.1
+            ),
+            Some(JolinErrorContext::Pivot(column)) => write!(f, "{} at pivot column {}", kind, column),
+            Some(JolinErrorContext::Length { expected, actual }) => write!(
+                f, "{}: expected {} items, got {}", kind, expected, actual
+            ),
+        }
+    }
+}
+
+impl std::error::Error for JolinError {}
+impl From<std::io::Error> for JolinError {
+    fn from(_: std::io::Error) -> JolinError {
+        JolinError::io_error()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::{*};
+
+    #[test]
+    fn test_error_context() {
+        let e = JolinError::shape_mismatching_with((2, 3), (3, 2));
+        assert_eq!(e.kind(), JolinErrorKind::ShapeMismatching);
+        assert_eq!(e.context(), Some(JolinErrorContext::Shape { expected: (2, 3), actual: (3, 2) }));
+        assert_eq!(e.to_string(), "shape mismatching: expected 2x3, got 3x2");
+        let e = JolinError::singular_matrix_at(4);
+        assert_eq!(e.to_string(), "singular matrix at pivot column 4");
+        assert_eq!(JolinError::invalid_value().context(), None);
+        assert_eq!(JolinError::io_error().to_string(), "IO error");
+    }
+}
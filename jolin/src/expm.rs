@@ -0,0 +1,202 @@
+/*
+ * expm.rs
+ * Matrix exponential and its action on vectors.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber, mul};
+use crate::error::JolinError;
+use crate::iterative::arnoldi;
+use crate::operator::LinearOperator;
+
+/// Dimension of the Krylov space in each step of `expm_multiply`
+const KRYLOV_DIMENSION: usize = 30;
+
+/// Maximal count of halving a time step of `expm_multiply`
+const MAX_STEP_HALVING: usize = 60;
+
+/// Maximal count of time steps of `expm_multiply`
+const MAX_STEPS: usize = 10000;
+
+/// Compute `e^{tA} v` for a square operator `A` and a column vector `v`
+/// without forming `e^{tA}`, so only the products of `A` with vectors are
+/// needed.
+///
+/// The time is split into steps, and in each step the exponential is
+/// approximated on the Krylov space of the current vector with Arnoldi
+/// iteration, i.e. `e^{tA} w ~ |w| V e^{tH} e_0`. A step is halved until the
+/// estimated error is around a thousand times the machine epsilon relative
+/// to `|w|`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::expm::expm_multiply;
+/// // rotation by the angle t
+/// let a = mat64![0.0, -1.0; 1.0, 0.0];
+/// let w = expm_multiply(&a, &mat64![1.0; 0.0], 0.5).unwrap();
+/// assert!(eq_with_error(&w, &mat64![0.5f64.cos(); 0.5f64.sin()], 1e-12));
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the operator is not square, or `v` is not a
+///    column vector of its size.
+/// 2. Invalid value - if `t` is not finite, or a step can't reach the
+///    tolerance, e.g. when the operator contains NaN, or the steps stop
+///    making progress.
+pub fn expm_multiply<T: Matrix, O: LinearOperator<T>>(op: &O, v: &T, t: T::Elem) -> Result<T, JolinError> {
+    let (n, n2) = op.shape();
+    if n != n2 {
+        return Err(JolinError::shape_mismatching_with((n, n), (n, n2)))
+    }
+    if (v.row(), v.column()) != (n, 1) {
+        return Err(JolinError::shape_mismatching_with((n, 1), (v.row(), v.column())))
+    }
+    if !t.to_f64().is_finite() {
+        return Err(JolinError::invalid_value())
+    }
+    let zero = T::Elem::zero();
+    let tol = T::Elem::epsilon().times_real(1000.0);
+    let mut w = v.clone();
+    let mut remaining = t;
+    for _step in 0..MAX_STEPS {
+        if remaining == zero {
+            return Ok(w)
+        }
+        let beta = w.data().iter().map(|x| *x * *x).sum::<T::Elem>().sqrt();
+        if beta == zero {
+            return Ok(w)
+        }
+        let krylov = arnoldi(op, &w, usize::min(n, KRYLOV_DIMENSION))?;
+        let m = krylov.hessenberg.column();
+        let exact = krylov.hessenberg.row() == m;
+        let h = T::from_vec(m, m, (0..m).flat_map(|c| krylov.hessenberg.data_column(c)[..m].to_vec()).collect());
+        let next_h = if exact { zero } else { krylov.hessenberg.elem(m, m - 1) };
+
+        let mut tau = remaining;
+        let mut e = expm_dense(&scaled(&h, tau));
+        // the residual of the Krylov approximation is driven by the
+        // coupling to the next basis vector
+        let mut converged = exact || (tau * next_h * e.elem(m - 1, 0)).abs() <= tol;
+        for _halving in 0..MAX_STEP_HALVING {
+            if converged {
+                break
+            }
+            tau = tau.times_real(0.5);
+            e = expm_dense(&scaled(&h, tau));
+            converged = (tau * next_h * e.elem(m - 1, 0)).abs() <= tol;
+        }
+        if !converged {
+            return Err(JolinError::invalid_value())
+        }
+        let mut next = T::zero(n, 1);
+        for j in 0..m {
+            let coef = beta * e.elem(j, 0);
+            for (x, b) in next.data_mut().iter_mut().zip(krylov.basis.data_column(j).iter()) {
+                *x = *x + coef * *b;
+            }
+        }
+        w = next;
+        if exact {
+            return Ok(w)
+        }
+        let next_remaining = remaining - tau;
+        if next_remaining.abs() >= remaining.abs() {
+            // the step is negligible against the remaining time
+            return Err(JolinError::invalid_value())
+        }
+        remaining = next_remaining;
+    }
+    Err(JolinError::invalid_value())
+}
+
+/// Matrix exponential of a small dense matrix by scaling and squaring with
+/// the Taylor series
+pub(crate) fn expm_dense<T: Matrix>(a: &T) -> T {
+    let n = a.row();
+    let norm = (0..a.column()).map(|c| a.data_column(c).iter().map(|x| x.abs()).sum::<T::Elem>())
+        .fold(T::Elem::zero(), |acc, v| if v > acc { v } else { acc });
+    // scale the matrix to the norm of at most one half
+    let mut squarings = 0;
+    let mut scale = T::Elem::one();
+    let half = T::Elem::one().times_real(0.5);
+    while norm * scale > half {
+        scale = scale * half;
+        squarings += 1;
+    }
+    let b = scaled(a, scale);
+    let mut ans = T::identity(n);
+    let mut term = T::identity(n);
+    for k in 1..40 {
+        term = scaled(&mul(&term, &b).unwrap(), T::Elem::one().times_real(1.0 / k as f64));
+        let mut term_norm = T::Elem::zero();
+        for (x, y) in ans.data_mut().iter_mut().zip(term.data().iter()) {
+            *x = *x + *y;
+            term_norm = term_norm + y.abs();
+        }
+        if term_norm <= T::Elem::epsilon() {
+            break
+        }
+    }
+    for _i in 0..squarings {
+        ans = mul(&ans, &ans).unwrap();
+    }
+    ans
+}
+
+fn scaled<T: Matrix>(a: &T, s: T::Elem) -> T {
+    let mut ans = a.clone();
+    ans.apply(|x| x * s);
+    ans
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::decomp::eig::eig_symmetric;
+    use crate::expm::{*};
+    use crate::error::JolinErrorKind;
+
+    #[test]
+    fn test_expm_dense() {
+        let a = mat64![0.0, 1.0; 0.0, 0.0];
+        assert_eq!(expm_dense(&a), mat64![1.0, 1.0; 0.0, 1.0]);
+        let e = expm_dense(&mat64![3.0, 0.0; 0.0, -20.0]);
+        assert!((e.elem(0, 0) - 3.0f64.exp()).abs() < 1e-12 * 3.0f64.exp());
+        assert!((e.elem(1, 1) - (-20.0f64).exp()).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_expm_multiply() {
+        // a larger operator than one Krylov space
+        let n = 50;
+        let a = elemwise_indexed(&Mat64::zero(n, n), |r, c, _| {
+            if r == c { -2.0 } else if r + 1 == c || c + 1 == r { 1.0 } else { 0.0 }
+        });
+        let v = Mat64::from_vec(n, 1, (0..n).map(|i| ((i * 7) % 5) as f64 - 2.0).collect());
+        let eig = eig_symmetric(&a).unwrap();
+        for t in [0.1, 2.0, -0.5] {
+            let w = expm_multiply(&a, &v, t).unwrap();
+            // V e^{t Lambda} V^T v
+            let mut coef = trmul(&eig.vectors, &v).unwrap();
+            for i in 0..n {
+                *coef.elem_mut(i, 0) *= (t * eig.values[i]).exp();
+            }
+            let expected = mul(&eig.vectors, &coef).unwrap();
+            assert!(eq_with_error(&w, &expected, 1e-9));
+        }
+        assert_eq!(expm_multiply(&a, &v, 0.0).unwrap(), v);
+        assert_eq!(expm_multiply(&a, &Mat64::zero(n, 1), 1.0).unwrap(), Mat64::zero(n, 1));
+        let err = expm_multiply(&a, &Mat64::zero(3, 1), 1.0).unwrap_err();
+        assert_eq!(err.context(), JolinError::shape_mismatching_with((n, 1), (3, 1)).context());
+
+        for t in [f64::NAN, f64::INFINITY] {
+            assert_eq!(expm_multiply(&a, &v, t).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        }
+        let mut with_nan = a.clone();
+        *with_nan.elem_mut(3, 4) = f64::NAN;
+        assert_eq!(expm_multiply(&with_nan, &v, 1.0).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+    }
+}
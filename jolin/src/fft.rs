@@ -0,0 +1,181 @@
+/*
+ * fft.rs
+ * Fast Fourier transform used by the structured matrices.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::f64::consts::PI;
+use std::ops::{Add, Sub, Mul, Div};
+
+/// Complex number of `f64` parts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Complex {
+        Complex { re, im }
+    }
+
+    /// `e^{i theta}`
+    pub fn from_angle(theta: f64) -> Complex {
+        Complex { re: theta.cos(), im: theta.sin() }
+    }
+
+    pub fn conj(&self) -> Complex {
+        Complex { re: self.re, im: -self.im }
+    }
+
+    pub fn abs(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    pub fn scale(&self, v: f64) -> Complex {
+        Complex { re: self.re * v, im: self.im * v }
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, rhs: Complex) -> Complex {
+        Complex { re: self.re + rhs.re, im: self.im + rhs.im }
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, rhs: Complex) -> Complex {
+        Complex { re: self.re - rhs.re, im: self.im - rhs.im }
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, rhs: Complex) -> Complex {
+        Complex {
+            re: self.re * rhs.re - self.im * rhs.im,
+            im: self.re * rhs.im + self.im * rhs.re,
+        }
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+    fn div(self, rhs: Complex) -> Complex {
+        let d = rhs.re * rhs.re + rhs.im * rhs.im;
+        (self * rhs.conj()).scale(1.0 / d)
+    }
+}
+
+/// Discrete Fourier transform `X_k = sum_j x_j e^{-2 pi i jk / n}` of any
+/// length in `O(n log n)`. Lengths of powers of two use the radix-2
+/// algorithm, and others use Bluestein's algorithm on top of it.
+pub(crate) fn fft(x: &[Complex]) -> Vec<Complex> {
+    let n = x.len();
+    if n <= 1 {
+        return x.to_vec()
+    }
+    if n.is_power_of_two() {
+        let mut a = x.to_vec();
+        fft_radix2(&mut a);
+        return a
+    }
+    // jk = (j^2 + k^2 - (k - j)^2) / 2 turns the transform into a
+    // convolution with the chirp w_k = e^{-pi i k^2 / n}
+    let chirp: Vec<Complex> = (0..n).map(|k| {
+        // k^2 mod 2n keeps the angle small and accurate
+        let k2 = (k as u128 * k as u128 % (2 * n as u128)) as f64;
+        Complex::from_angle(-PI * k2 / n as f64)
+    }).collect();
+    let m = (2 * n - 1).next_power_of_two();
+    let mut a = vec![Complex::new(0.0, 0.0); m];
+    let mut b = vec![Complex::new(0.0, 0.0); m];
+    for k in 0..n {
+        a[k] = x[k] * chirp[k];
+        b[k] = chirp[k].conj();
+        if k > 0 {
+            b[m - k] = chirp[k].conj();
+        }
+    }
+    fft_radix2(&mut a);
+    fft_radix2(&mut b);
+    let mut c: Vec<Complex> = a.iter().zip(b.iter()).map(|(u, v)| (*u * *v).conj()).collect();
+    // the inverse transform of the product, by conjugating around the forward one
+    fft_radix2(&mut c);
+    (0..n).map(|k| c[k].conj().scale(1.0 / m as f64) * chirp[k]).collect()
+}
+
+/// Inverse discrete Fourier transform, including the `1 / n` factor
+pub(crate) fn ifft(x: &[Complex]) -> Vec<Complex> {
+    let n = x.len();
+    let conj: Vec<Complex> = x.iter().map(|v| v.conj()).collect();
+    fft(&conj).iter().map(|v| v.conj().scale(1.0 / n as f64)).collect()
+}
+
+/// In-place iterative radix-2 transform. The length must be a power of two.
+fn fft_radix2(a: &mut [Complex]) {
+    let n = a.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            a.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let w = Complex::from_angle(-2.0 * PI / len as f64);
+        for start in (0..n).step_by(len) {
+            let mut wk = Complex::new(1.0, 0.0);
+            for k in 0..(len / 2) {
+                let u = a[start + k];
+                let v = a[start + k + len / 2] * wk;
+                a[start + k] = u + v;
+                a[start + k + len / 2] = u - v;
+                wk = wk * w;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fft::{*};
+
+    fn dft(x: &[Complex]) -> Vec<Complex> {
+        let n = x.len();
+        (0..n).map(|k| {
+            (0..n).fold(Complex::new(0.0, 0.0), |acc, j| {
+                acc + x[j] * Complex::from_angle(-2.0 * PI * ((j * k) % n) as f64 / n as f64)
+            })
+        }).collect()
+    }
+
+    #[test]
+    fn test_fft() {
+        for n in [1, 2, 5, 8, 12, 17] {
+            let x: Vec<Complex> = (0..n).map(|v| Complex::new(v as f64 - 2.0, (v * v % 7) as f64)).collect();
+            let expected = dft(&x);
+            let y = fft(&x);
+            for (a, b) in y.iter().zip(expected.iter()) {
+                assert!((*a - *b).abs() < 1e-9);
+            }
+            let z = ifft(&y);
+            for (a, b) in z.iter().zip(x.iter()) {
+                assert!((*a - *b).abs() < 1e-9);
+            }
+        }
+        assert!(fft(&[]).is_empty());
+        assert_eq!(Complex::new(1.0, 2.0) / Complex::new(1.0, 2.0), Complex::new(1.0, 0.0));
+    }
+}
@@ -0,0 +1,218 @@
+/*
+ * fit/diagnostics.rs
+ * Residual analysis and regression diagnostics.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber};
+use crate::error::JolinError;
+use super::{design_qr, residual_of_projection};
+
+/// Diagnostics of an ordinary least squares fit `y ~ X`.
+#[derive(Debug, Clone)]
+pub struct RegressionDiagnostics<T: Matrix> {
+    /// Diagonal of the hat matrix `H = X (X^T X)^-1 X^T`, a.k.a. the leverages
+    pub leverage: Vec<T::Elem>,
+    /// Raw residuals `y - X * beta` as a column vector
+    pub residuals: T,
+    /// Internally studentized residuals `e_i / (s * sqrt(1 - h_i))`
+    pub standardized_residuals: T,
+    /// Cook's distance of each observation
+    pub cooks_distance: Vec<T::Elem>,
+}
+
+/// Compute the diagonal of the hat matrix of the design matrix `x`.
+///
+/// The leverages are the squared row norms of the thin Q factor of `x`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::fit::diagnostics::hat_diagonal;
+/// let x = mat64![1.0, 0.0; 1.0, 1.0; 1.0, 2.0];
+/// let h = hat_diagonal(&x).unwrap();
+/// // Leverages always sum up to the column count of the design matrix
+/// assert!((h.iter().sum::<f64>() - 2.0).abs() < 1e-10);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if there are fewer rows than columns.
+/// 2. Singular matrix - if the columns of `x` are linearly dependent.
+pub fn hat_diagonal<T: Matrix>(x: &T) -> Result<Vec<T::Elem>, JolinError> {
+    let qr = design_qr(x)?;
+    Ok(leverage_from_q(&qr.q, x.column()))
+}
+
+/// Compute the residual diagnostics of the ordinary least squares fit of
+/// the column vector `y` on the design matrix `x`.
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `y` is not a column vector with the same row
+///    count as `x`, or there are no more rows than columns in `x`.
+/// 2. Singular matrix - if the columns of `x` are linearly dependent.
+/// 3. Invalid value - if some observation has leverage 1 or the fit is exact,
+///    where the standardized residuals and Cook's distances are undefined.
+pub fn diagnostics<T: Matrix>(x: &T, y: &T) -> Result<RegressionDiagnostics<T>, JolinError> {
+    let n = x.row();
+    let p = x.column();
+    if y.column() != 1 || y.row() != n || n <= p {
+        return Err(JolinError::shape_mismatching())
+    }
+    let qr = design_qr(x)?;
+    let leverage = leverage_from_q(&qr.q, p);
+    let residuals = residual_of_projection(&qr.q, p, y.data());
+
+    // s^2 = RSS / (n - p)
+    let rss: T::Elem = residuals.iter().map(|e| (*e) * (*e)).sum();
+    let tss: T::Elem = y.data().iter().map(|v| (*v) * (*v)).sum();
+    let tol = T::Elem::epsilon().times_real(n as f64);
+    if rss <= tss * T::Elem::epsilon() || leverage.iter().any(|h| T::Elem::one() - *h <= tol) {
+        return Err(JolinError::invalid_value())
+    }
+    let s2 = rss / T::Elem::one().times_real((n - p) as f64);
+    let s = s2.sqrt();
+
+    let mut standardized = Vec::new();
+    let mut cooks_distance = Vec::new();
+    standardized.reserve_exact(n);
+    cooks_distance.reserve_exact(n);
+    for i in 0..n {
+        let h = leverage[i];
+        let one_minus_h = T::Elem::one() - h;
+        let t = residuals[i] / (s * one_minus_h.sqrt());
+        standardized.push(t);
+        cooks_distance.push(t * t * h / (one_minus_h.times_real(p as f64)));
+    }
+    Ok(RegressionDiagnostics {
+        leverage,
+        residuals: T::from_vec(n, 1, residuals),
+        standardized_residuals: T::from_vec(n, 1, standardized),
+        cooks_distance,
+    })
+}
+
+/// Compute the variance inflation factor (VIF) of each column of `x`.
+///
+/// The VIF of column j is `1 / (1 - R_j^2)`, where `R_j^2` is the coefficient
+/// of determination of regressing column j on the other columns. Columns are
+/// centered beforehand, so an intercept is implied and `x` should not contain
+/// a constant column.
+///
+/// Potential errors:
+/// 1. Shape mismatching - if there are no more rows than columns.
+/// 2. Singular matrix - if some column is constant or is a linear combination
+///    of the other columns.
+#[allow(clippy::needless_range_loop)]
+pub fn vif<T: Matrix>(x: &T) -> Result<Vec<T::Elem>, JolinError> {
+    let n = x.row();
+    let p = x.column();
+    if n <= p {
+        return Err(JolinError::shape_mismatching())
+    }
+    let centered: Vec<Vec<T::Elem>> = (0..p).map(|c| {
+        let col = x.data_column(c);
+        let mean = col.iter().copied().sum::<T::Elem>() / T::Elem::one().times_real(n as f64);
+        col.iter().map(|v| *v - mean).collect()
+    }).collect();
+
+    let mut ans = Vec::new();
+    ans.reserve_exact(p);
+    for j in 0..p {
+        let tss: T::Elem = centered[j].iter().map(|v| (*v) * (*v)).sum();
+        if tss == T::Elem::zero() {
+            return Err(JolinError::singular_matrix())
+        }
+        if p == 1 {
+            ans.push(T::Elem::one());
+            continue;
+        }
+        let mut others: Vec<T::Elem> = Vec::new();
+        others.reserve_exact(n * (p - 1));
+        for c in 0..p {
+            if c != j {
+                others.extend_from_slice(&centered[c]);
+            }
+        }
+        let others = T::from_vec(n, p - 1, others);
+        let qr = design_qr(&others)?;
+        let res = residual_of_projection(&qr.q, p - 1, &centered[j]);
+        let rss: T::Elem = res.iter().map(|v| (*v) * (*v)).sum();
+        if rss <= tss * T::Elem::epsilon() {
+            return Err(JolinError::singular_matrix())
+        }
+        ans.push(tss / rss);
+    }
+    Ok(ans)
+}
+
+fn leverage_from_q<T: Matrix>(q: &T, p: usize) -> Vec<T::Elem> {
+    (0..q.row()).map(|i| {
+        (0..p).map(|j| q.elem(i, j) * q.elem(i, j)).sum()
+    }).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::MatrixView;
+    use crate::error::JolinErrorKind;
+    use crate::fit::diagnostics::{*};
+
+    #[test]
+    fn test_hat_diagonal() {
+        // Simple linear regression: h_i = 1/n + (x_i - mean)^2 / Sxx
+        let x = mat64![1.0, 1.0; 1.0, 2.0; 1.0, 3.0; 1.0, 4.0];
+        let h = hat_diagonal(&x).unwrap();
+        let expected = [0.7, 0.3, 0.3, 0.7];
+        for i in 0..4 {
+            assert!((h[i] - expected[i]).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    #[allow(clippy::needless_range_loop)]
+    fn test_diagnostics() {
+        let x = mat64![1.0, 1.0; 1.0, 2.0; 1.0, 3.0; 1.0, 4.0];
+        let y = mat64![1.0; 3.0; 2.0; 4.0];
+        let d = diagnostics(&x, &y).unwrap();
+        // Fitted line is y = 0.5 + 0.8x
+        let expected_residuals = [-0.3, 0.9, -0.9, 0.3];
+        for i in 0..4 {
+            assert!((d.residuals.elem(i, 0) - expected_residuals[i]).abs() < 1e-10);
+        }
+        // s^2 = 1.8 / 2
+        let s = 0.9f64.sqrt();
+        let t0 = -0.3 / (s * 0.3f64.sqrt());
+        assert!((d.standardized_residuals.elem(0, 0) - t0).abs() < 1e-10);
+        let cook0 = t0 * t0 * 0.7 / (2.0 * 0.3);
+        assert!((d.cooks_distance[0] - cook0).abs() < 1e-10);
+
+        assert!(diagnostics(&x, &mat64![1.0; 2.0; 3.0]).is_err());
+
+        // an exact fit leaves no residual variance to standardize with
+        let exact = mat64![1.5; 2.5; 3.5; 4.5];
+        assert_eq!(diagnostics(&x, &exact).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        // the last observation alone determines the second coefficient
+        let x = mat64![1.0, 0.0; 1.0, 0.0; 1.0, 0.0; 1.0, 1.0];
+        assert_eq!(diagnostics(&x, &y).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn test_vif() {
+        // Orthogonal centered columns have no collinearity at all
+        let x = mat64![1.0, 1.0; -1.0, 1.0; 1.0, -1.0; -1.0, -1.0];
+        let v = vif(&x).unwrap();
+        assert!((v[0] - 1.0).abs() < 1e-10);
+        assert!((v[1] - 1.0).abs() < 1e-10);
+
+        // With two columns, VIF = 1 / (1 - r^2) where r is their correlation
+        let x = mat64![1.0, 1.0; -1.0, 0.0; 0.0, -1.0; 1.0, 0.0; -1.0, 0.0];
+        let v = vif(&x).unwrap();
+        let r = 1.0 / (4.0f64.sqrt() * 2.0f64.sqrt());
+        assert!((v[0] - 1.0 / (1.0 - r * r)).abs() < 1e-10);
+
+        let collinear = mat64![1.0, 2.0; 2.0, 4.0; 3.0, 6.0];
+        assert!(vif(&collinear).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+/*
+ * fit/glm.rs
+ * Generalized linear models solved by iteratively reweighted least squares.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber, mul};
+use crate::error::JolinError;
+use super::linear::wls;
+
+/// Distribution family of the response together with its canonical link.
+#[derive(Debug, PartialEq, Copy, Clone, Eq)]
+pub enum GLMFamily {
+    /// Binomial response in `[0, 1]` with the logit link, i.e. logistic regression
+    Logistic,
+    /// Non-negative count response with the log link
+    Poisson,
+}
+
+/// Options of the IRLS solver
+#[derive(Debug, Clone, Copy)]
+pub struct GLMOptions {
+    /// Maximal count of IRLS iterations
+    pub max_iter: usize,
+    /// Convergence threshold on the relative change of the deviance
+    pub tol: f64,
+}
+
+impl Default for GLMOptions {
+    fn default() -> Self {
+        GLMOptions { max_iter: 25, tol: 1e-8 }
+    }
+}
+
+/// The answer of fitting a generalized linear model
+#[derive(Debug, Clone)]
+pub struct GLMResult<T: Matrix> {
+    /// Coefficients as a column vector
+    pub coefficients: T,
+    /// Deviance of the fitted model
+    pub deviance: T::Elem,
+    /// Count of IRLS iterations performed
+    pub iterations: usize,
+    /// Whether the deviance converged within `max_iter` iterations
+    pub converged: bool,
+}
+
+/// Fit a generalized linear model of the column vector `y` on the design
+/// matrix `x` with iteratively reweighted least squares.
+///
+/// Each iteration solves a weighted least squares problem on the working
+/// response `z = eta + (y - mu) / w`. Iteration stops when the relative change
+/// of the deviance falls below `options.tol`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::fit::glm::{glm, GLMFamily, GLMOptions};
+/// let x = mat64![1.0, 0.0; 1.0, 1.0; 1.0, 2.0; 1.0, 3.0];
+/// let y = mat64![0.0; 1.0; 0.0; 1.0];
+/// let ans = glm(&x, &y, GLMFamily::Logistic, &GLMOptions::default()).unwrap();
+/// assert!(ans.converged);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `y` is not a column vector with the same row
+///    count as `x`, or there are fewer rows than columns in `x`.
+/// 2. Invalid value - if `y` is out of the domain of the family.
+/// 3. Singular matrix - if the design matrix is rank deficient.
+pub fn glm<T: Matrix>(x: &T, y: &T, family: GLMFamily, options: &GLMOptions) -> Result<GLMResult<T>, JolinError> {
+    let n = x.row();
+    if y.column() != 1 || y.row() != n || n < x.column() {
+        return Err(JolinError::shape_mismatching())
+    }
+    let one = T::Elem::one();
+    let zero = T::Elem::zero();
+    let valid = match family {
+        GLMFamily::Logistic => y.data().iter().all(|v| *v >= zero && *v <= one),
+        GLMFamily::Poisson => y.data().iter().all(|v| *v >= zero),
+    };
+    if !valid {
+        return Err(JolinError::invalid_value())
+    }
+
+    // Start from the response itself, moved away from the domain boundary
+    let mut mu: Vec<T::Elem> = y.data().iter().map(|v| match family {
+        GLMFamily::Logistic => (*v + one.times_real(0.5)).times_real(0.5),
+        GLMFamily::Poisson => *v + one.times_real(0.1),
+    }).collect();
+    let mut eta: Vec<T::Elem> = mu.iter().map(|m| link(family, *m)).collect();
+    let mut dev = deviance(family, y.data(), &mu);
+    let tol = one.times_real(options.tol);
+    let mut coefficients = T::zero(x.column(), 1);
+
+    for iter in 0..options.max_iter {
+        let mut w = Vec::new();
+        let mut z = Vec::new();
+        w.reserve_exact(n);
+        z.reserve_exact(n);
+        for i in 0..n {
+            // Both families use the canonical link, where the IRLS weight is
+            // the variance function and d(eta)/d(mu) = 1 / variance.
+            let var = match family {
+                GLMFamily::Logistic => mu[i] * (one - mu[i]),
+                GLMFamily::Poisson => mu[i],
+            };
+            let var = if var < T::Elem::epsilon() { T::Elem::epsilon() } else { var };
+            w.push(var);
+            z.push(eta[i] + (y.elem(i, 0) - mu[i]) / var);
+        }
+        coefficients = wls(x, &T::from_vec(n, 1, z), &w)?;
+        eta = mul(x, &coefficients)?.data().to_vec();
+        mu = eta.iter().map(|e| inverse_link(family, *e)).collect();
+
+        let new_dev = deviance(family, y.data(), &mu);
+        let change = (new_dev - dev).abs() / (new_dev.abs() + one.times_real(0.1));
+        dev = new_dev;
+        if change < tol {
+            return Ok(GLMResult { coefficients, deviance: dev, iterations: iter + 1, converged: true })
+        }
+    }
+    Ok(GLMResult { coefficients, deviance: dev, iterations: options.max_iter, converged: false })
+}
+
+fn link<E: LikeNumber>(family: GLMFamily, mu: E) -> E {
+    match family {
+        GLMFamily::Logistic => (mu / (E::one() - mu)).ln(),
+        GLMFamily::Poisson => mu.ln(),
+    }
+}
+
+fn inverse_link<E: LikeNumber>(family: GLMFamily, eta: E) -> E {
+    match family {
+        GLMFamily::Logistic => E::one() / (E::one() + (-eta).exp()),
+        GLMFamily::Poisson => eta.exp(),
+    }
+}
+
+/// `a * ln(a / b)` with the convention `0 * ln(0) = 0`
+fn xlogy<E: LikeNumber>(a: E, b: E) -> E {
+    if a == E::zero() {
+        E::zero()
+    } else {
+        a * (a / b).ln()
+    }
+}
+
+fn deviance<E: LikeNumber>(family: GLMFamily, y: &[E], mu: &[E]) -> E {
+    let one = E::one();
+    let sum: E = y.iter().zip(mu.iter()).map(|(y, m)| match family {
+        GLMFamily::Logistic => xlogy(*y, *m) + xlogy(one - *y, one - *m),
+        GLMFamily::Poisson => xlogy(*y, *m) - (*y - *m),
+    }).sum();
+    sum.times_real(2.0)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use super::{*};
+
+    #[test]
+    fn test_logistic_regression() {
+        let x = mat64![
+            1.0, -2.0; 1.0, -1.0; 1.0, -0.5; 1.0, 0.0;
+            1.0, 0.5; 1.0, 1.0; 1.0, 1.5; 1.0, 2.0];
+        let y = mat64![0.0; 0.0; 1.0; 0.0; 1.0; 0.0; 1.0; 1.0];
+        let ans = glm(&x, &y, GLMFamily::Logistic, &GLMOptions::default()).unwrap();
+        assert!(ans.converged);
+        // At the maximum likelihood the score X^T (y - mu) vanishes
+        let beta = &ans.coefficients;
+        for c in 0..2 {
+            let mut score = 0.0;
+            for r in 0..8 {
+                let eta = x.elem(r, 0) * beta.elem(0, 0) + x.elem(r, 1) * beta.elem(1, 0);
+                let mu = 1.0 / (1.0 + (-eta).exp());
+                score += x.elem(r, c) * (y.elem(r, 0) - mu);
+            }
+            assert!(score.abs() < 1e-6);
+        }
+        assert!(beta.elem(1, 0) > 0.0);
+    }
+
+    #[test]
+    fn test_poisson_regression() {
+        // Intercept-only Poisson model estimates ln(mean(y))
+        let x = mat64![1.0; 1.0; 1.0; 1.0];
+        let y = mat64![1.0; 3.0; 2.0; 6.0];
+        let ans = glm(&x, &y, GLMFamily::Poisson, &GLMOptions::default()).unwrap();
+        assert!(ans.converged);
+        assert!((ans.coefficients.elem(0, 0) - 3.0f64.ln()).abs() < 1e-8);
+
+        let bad = mat64![1.0; -3.0; 2.0; 6.0];
+        assert!(glm(&x, &bad, GLMFamily::Poisson, &GLMOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+/*
+ * fit/lasso.rs
+ * LASSO and elastic-net regression by cyclical coordinate descent.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber};
+use crate::error::JolinError;
+
+/// Options of the coordinate descent solver
+#[derive(Debug, Clone, Copy)]
+pub struct CoordinateDescentOptions {
+    /// Maximal count of sweeps over the coordinates
+    pub max_iter: usize,
+    /// Convergence threshold on the maximal change of a coefficient in a sweep
+    pub tol: f64,
+}
+
+impl Default for CoordinateDescentOptions {
+    fn default() -> Self {
+        CoordinateDescentOptions { max_iter: 1000, tol: 1e-10 }
+    }
+}
+
+/// LASSO regression of the column vector `y` on `x`, minimizing
+/// `1/(2n) * |y - X beta|^2 + lambda * |beta|_1`.
+///
+/// No intercept is fitted, so center `x` and `y` beforehand if needed. The
+/// answer is a column vector of coefficients.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::fit::lasso;
+/// let x = mat64![1.0, 1.0; 1.0, -1.0; -1.0, 1.0; -1.0, -1.0];
+/// let y = mat64![3.0; 1.0; -1.0; -3.0];
+/// // Least squares coefficients are [2, 1], each shrunk by lambda
+/// let beta = lasso(&x, &y, 0.5).unwrap();
+/// assert!(eq_with_error(&beta, &mat64![1.5; 0.5], 1e-8));
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `y` is not a column vector with the same row
+///    count as `x`.
+/// 2. Invalid value - if `lambda` is negative.
+pub fn lasso<T: Matrix>(x: &T, y: &T, lambda: T::Elem) -> Result<T, JolinError> {
+    elastic_net(x, y, lambda, T::Elem::zero())
+}
+
+/// Elastic-net regression of the column vector `y` on `x`, minimizing
+/// `1/(2n) * |y - X beta|^2 + l1 * |beta|_1 + l2 / 2 * |beta|_2^2`.
+///
+/// No intercept is fitted, so center `x` and `y` beforehand if needed. The
+/// answer is a column vector of coefficients.
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `y` is not a column vector with the same row
+///    count as `x`.
+/// 2. Invalid value - if `l1` or `l2` is negative.
+pub fn elastic_net<T: Matrix>(x: &T, y: &T, l1: T::Elem, l2: T::Elem) -> Result<T, JolinError> {
+    elastic_net_path(x, y, &[l1], l2, &CoordinateDescentOptions::default())
+}
+
+/// Compute the elastic-net coefficients for each L1 penalty in `l1s`.
+///
+/// The penalties are solved in the given order, each warm-started from the
+/// previous answer, so passing them in decreasing order is the most efficient.
+/// The answer is a matrix whose column k holds the coefficients for `l1s[k]`.
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `y` is not a column vector with the same row
+///    count as `x`.
+/// 2. Invalid value - if some penalty is negative.
+/// 3. Not enough input - if `l1s` is empty.
+pub fn elastic_net_path<T: Matrix>(x: &T, y: &T, l1s: &[T::Elem], l2: T::Elem, options: &CoordinateDescentOptions) -> Result<T, JolinError> {
+    let n = x.row();
+    let p = x.column();
+    if y.column() != 1 || y.row() != n {
+        return Err(JolinError::shape_mismatching())
+    }
+    if l1s.is_empty() {
+        return Err(JolinError::not_enough_input())
+    }
+    if l2 < T::Elem::zero() || l1s.iter().any(|v| *v < T::Elem::zero()) {
+        return Err(JolinError::invalid_value())
+    }
+
+    let inv_n = T::Elem::one() / T::Elem::one().times_real(n as f64);
+    // z_j = |x_j|^2 / n
+    let z: Vec<T::Elem> = (0..p).map(|j| {
+        x.data_column(j).iter().map(|v| (*v) * (*v)).sum::<T::Elem>() * inv_n
+    }).collect();
+    let tol = T::Elem::one().times_real(options.tol);
+
+    let mut beta = vec![T::Elem::zero(); p];
+    let mut residual = Vec::from(y.data());
+    let mut path = Vec::new();
+    path.reserve_exact(p * l1s.len());
+    for l1 in l1s.iter() {
+        let mut sweeps = 0;
+        while sweeps < options.max_iter {
+            // A full sweep over all coordinates to update the active set
+            sweeps += 1;
+            let delta = sweep(x, &z, &mut beta, &mut residual, *l1, l2, inv_n, |_| true);
+            if delta < tol {
+                break;
+            }
+            // Iterate on the active set only until it converges
+            while sweeps < options.max_iter {
+                sweeps += 1;
+                let active: Vec<bool> = beta.iter().map(|b| *b != T::Elem::zero()).collect();
+                let delta = sweep(x, &z, &mut beta, &mut residual, *l1, l2, inv_n, |j| active[j]);
+                if delta < tol {
+                    break;
+                }
+            }
+        }
+        path.extend_from_slice(&beta);
+    }
+    Ok(T::from_vec(p, l1s.len(), path))
+}
+
+/// Run one coordinate descent sweep over the coordinates selected by
+/// `selected`, keeping `residual = y - X * beta` up to date. Returns the
+/// maximal absolute change of the coefficients.
+#[allow(clippy::too_many_arguments)]
+fn sweep<T: Matrix, F: Fn(usize) -> bool>(x: &T, z: &[T::Elem], beta: &mut [T::Elem], residual: &mut [T::Elem],
+        l1: T::Elem, l2: T::Elem, inv_n: T::Elem, selected: F) -> T::Elem {
+    let mut max_delta = T::Elem::zero();
+    for j in 0..beta.len() {
+        if !selected(j) || z[j] == T::Elem::zero() {
+            continue;
+        }
+        let xj = x.data_column(j);
+        let rho = xj.iter().zip(residual.iter()).map(|(a, b)| (*a) * (*b)).sum::<T::Elem>() * inv_n
+            + z[j] * beta[j];
+        let new_beta = soft_threshold(rho, l1) / (z[j] + l2);
+        let delta = new_beta - beta[j];
+        if delta != T::Elem::zero() {
+            for i in 0..residual.len() {
+                residual[i] = residual[i] - xj[i] * delta;
+            }
+            beta[j] = new_beta;
+        }
+        if delta.abs() > max_delta {
+            max_delta = delta.abs();
+        }
+    }
+    max_delta
+}
+
+fn soft_threshold<E: LikeNumber>(v: E, t: E) -> E {
+    if v > t {
+        v - t
+    } else if v < -t {
+        v + t
+    } else {
+        E::zero()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use super::{*};
+
+    #[test]
+    fn test_lasso_orthogonal_design() {
+        // Columns are orthogonal with |x_j|^2 / n = 1, so the answer is the
+        // soft-thresholded least squares solution [2, 1].
+        let x = mat64![1.0, 1.0; 1.0, -1.0; -1.0, 1.0; -1.0, -1.0];
+        let y = mat64![3.0; 1.0; -1.0; -3.0];
+        assert!(eq_with_error(&lasso(&x, &y, 0.0).unwrap(), &mat64![2.0; 1.0], 1e-8));
+        assert!(eq_with_error(&lasso(&x, &y, 1.5).unwrap(), &mat64![0.5; 0.0], 1e-8));
+        assert!(eq_with_error(&lasso(&x, &y, 3.0).unwrap(), &mat64![0.0; 0.0], 1e-8));
+        assert!(eq_with_error(&elastic_net(&x, &y, 0.5, 1.0).unwrap(), &mat64![0.75; 0.25], 1e-8));
+        assert!(lasso(&x, &y, -1.0).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::needless_range_loop)]
+    fn test_elastic_net_path() {
+        let x = mat64![1.0, 2.0, 0.5; 2.0, -1.0, 1.0; 0.0, 1.0, -1.0; 1.0, 0.0, 2.0; -1.0, 1.0, 0.0];
+        let y = mat64![2.0; 1.0; -1.0; 3.0; -1.0];
+        let l1s = [2.0, 0.5, 0.1, 0.0];
+        let path = elastic_net_path(&x, &y, &l1s, 0.0, &CoordinateDescentOptions::default()).unwrap();
+        assert_eq!(path.row(), 3);
+        assert_eq!(path.column(), 4);
+        // Large penalty kills all coefficients
+        assert_eq!(path.data_column(0), &[0.0, 0.0, 0.0]);
+        // Each column matches the individually solved problem
+        for k in 1..4 {
+            let beta = lasso(&x, &y, l1s[k]).unwrap();
+            let col = Mat64::new(3, 1, path.data_column(k));
+            assert!(eq_with_error(&beta, &col, 1e-8));
+        }
+        // Without penalty it is least squares: X^T (y - X beta) = 0
+        let beta = Mat64::new(3, 1, path.data_column(3));
+        let res = sub(&y, &mul(&x, &beta).unwrap()).unwrap();
+        let grad = mul(&tr(&x), &res).unwrap();
+        assert!(eq_with_error(&grad, &Mat64::zero(3, 1), 1e-8));
+    }
+}
@@ -0,0 +1,256 @@
+/*
+ * fit/linear.rs
+ * Linear least squares solvers.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber, mul, sub, stack_regularizer};
+use crate::error::JolinError;
+use super::design_qr;
+
+/// Weighted least squares: find `beta` minimizing `sum_i w_i (y_i - x_i * beta)^2`.
+///
+/// `y` must be a column vector with the same row count as `x`, and `w` holds
+/// one non-negative weight per row. The answer is a column vector of
+/// coefficients.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::fit::wls;
+/// let x = mat64![1.0, 0.0; 1.0, 1.0; 1.0, 2.0];
+/// let y = mat64![1.0; 3.0; 5.0];
+/// let beta = wls(&x, &y, &[1.0, 2.0, 1.0]).unwrap();
+/// assert!(eq_with_error(&beta, &mat64![1.0; 2.0], 1e-10));
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the shapes of `x`, `y` and `w` don't match, or
+///    there are fewer rows than columns in `x`.
+/// 2. Invalid value - if some weight is negative.
+/// 3. Singular matrix - if the weighted design matrix is rank deficient.
+#[allow(clippy::needless_range_loop)]
+pub fn wls<T: Matrix>(x: &T, y: &T, w: &[T::Elem]) -> Result<T, JolinError> {
+    let n = x.row();
+    let p = x.column();
+    if y.column() != 1 || y.row() != n || w.len() != n {
+        return Err(JolinError::shape_mismatching())
+    }
+    if w.iter().any(|v| *v < T::Elem::zero()) {
+        return Err(JolinError::invalid_value())
+    }
+    let sqrt_w: Vec<T::Elem> = w.iter().map(|v| v.sqrt()).collect();
+    let mut xw = x.clone();
+    for c in 0..p {
+        for r in 0..n {
+            *xw.elem_mut(r, c) = x.elem(r, c) * sqrt_w[r];
+        }
+    }
+    let yw: Vec<T::Elem> = (0..n).map(|r| y.elem(r, 0) * sqrt_w[r]).collect();
+
+    // beta = R^-1 * Q^T * y
+    design_qr(&xw)?.solve(&T::from_vec(n, 1, yw))
+}
+
+/// Ridge regression of the column vector `y` on `x`, i.e. the Tikhonov
+/// regularized least squares solution of `(X^T X + lambda I) beta = X^T y`.
+///
+/// It's solved as the least squares problem `[X; sqrt(lambda) I] beta = [y; 0]`
+/// with QR decomposition, which avoids squaring the condition number of `X`
+/// as the normal equations do. No intercept is fitted, and the answer is a
+/// column vector of coefficients.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::fit::ridge;
+/// let x = mat64![1.0, 0.0; 0.0, 1.0];
+/// let beta = ridge(&x, &mat64![2.0; 4.0], 1.0).unwrap();
+/// assert!(eq_with_error(&beta, &mat64![1.0; 2.0], 1e-12));
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `y` is not a column vector with the same row
+///    count as `x`.
+/// 2. Invalid value - if `lambda` is negative or NaN.
+/// 3. Singular matrix - if `lambda` is zero and `x` is rank deficient.
+pub fn ridge<T: Matrix>(x: &T, y: &T, lambda: T::Elem) -> Result<T, JolinError> {
+    if y.column() != 1 || y.row() != x.row() {
+        return Err(JolinError::shape_mismatching())
+    }
+    let augmented = stack_regularizer(x, lambda)?;
+    let mut rhs = y.data().to_vec();
+    rhs.resize(augmented.row(), T::Elem::zero());
+    design_qr(&augmented)?.solve(&T::from_vec(augmented.row(), 1, rhs))
+}
+
+/// The answer of an ordinary least squares fit `y ~ X beta`
+#[derive(Debug, Clone)]
+pub struct LinearFit<T: Matrix> {
+    /// Coefficients `beta` as a column vector
+    pub coefficients: T,
+    /// Residuals `y - X beta` as a column vector
+    pub residuals: T,
+    /// Residual sum of squares
+    pub rss: T::Elem,
+    /// Residual standard error `sqrt(RSS / (n - p))`, which is zero if
+    /// there are no more observations than coefficients
+    pub sigma: T::Elem,
+    /// Coefficient of determination `1 - RSS / TSS` with the total sum of
+    /// squares around the mean of `y`. It's meaningful if the model has an
+    /// intercept, and is one if `y` is constant and fitted exactly.
+    pub r_squared: T::Elem,
+}
+
+/// Ordinary least squares fit of the column vector `y` on the design matrix
+/// `x` with QR decomposition. Add a column of ones to `x` for an intercept.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::fit::linreg;
+/// let x = mat64![1.0, 0.0; 1.0, 1.0; 1.0, 2.0];
+/// let fit = linreg(&x, &mat64![1.0; 3.0; 5.0]).unwrap();
+/// assert!(eq_with_error(&fit.coefficients, &mat64![1.0; 2.0], 1e-10));
+/// assert!((fit.r_squared - 1.0).abs() < 1e-12);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `y` is not a column vector with the same row
+///    count as `x`, or there are fewer rows than columns in `x`.
+/// 2. Singular matrix - if the columns of `x` are linearly dependent.
+pub fn linreg<T: Matrix>(x: &T, y: &T) -> Result<LinearFit<T>, JolinError> {
+    let n = x.row();
+    let p = x.column();
+    if y.column() != 1 || y.row() != n {
+        return Err(JolinError::shape_mismatching())
+    }
+    let coefficients = design_qr(x)?.solve(y)?;
+    let fitted = mul(x, &coefficients)?;
+    let residuals = sub(y, &fitted)?;
+    let rss: T::Elem = residuals.data().iter().map(|e| (*e) * (*e)).sum();
+    let sigma = if n > p {
+        (rss / T::Elem::one().times_real((n - p) as f64)).sqrt()
+    } else {
+        T::Elem::zero()
+    };
+    let mean = y.data().iter().cloned().sum::<T::Elem>() / T::Elem::one().times_real(n as f64);
+    let tss: T::Elem = y.data().iter().map(|v| (*v - mean) * (*v - mean)).sum();
+    let r_squared = if tss == T::Elem::zero() {
+        if rss == T::Elem::zero() { T::Elem::one() } else { T::Elem::zero() }
+    } else {
+        T::Elem::one() - rss / tss
+    };
+    Ok(LinearFit { coefficients, residuals, rss, sigma, r_squared })
+}
+
+/// Least squares fit of the polynomial `c_0 + c_1 x + ... + c_d x^d` of
+/// degree `d` to the points `(x_i, y_i)`. The coefficients of the answer are
+/// in the ascending order of the powers. `x` and `y` are vectors of the
+/// same length in either orientation.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::fit::polyfit;
+/// let fit = polyfit(&mat64![0.0, 1.0, 2.0, 3.0], &mat64![1.0, 2.0, 5.0, 10.0], 2).unwrap();
+/// assert!(eq_with_error(&fit.coefficients, &mat64![1.0; 0.0; 1.0], 1e-10));
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `x` or `y` is not a vector, or their lengths
+///    differ.
+/// 2. Not enough input - if there are no more points than the degree.
+/// 3. Singular matrix - if there are no more distinct `x` than the degree.
+pub fn polyfit<T: Matrix>(x: &T, y: &T, degree: usize) -> Result<LinearFit<T>, JolinError> {
+    let is_vector = |m: &T| m.row() == 1 || m.column() == 1;
+    let n = x.data().len();
+    if !is_vector(x) || !is_vector(y) || y.data().len() != n {
+        return Err(JolinError::shape_mismatching())
+    }
+    if n <= degree {
+        return Err(JolinError::not_enough_input())
+    }
+    let mut vandermonde = T::zero(n, degree + 1);
+    for (r, v) in x.data().iter().enumerate() {
+        let mut power = T::Elem::one();
+        for c in 0..(degree + 1) {
+            *vandermonde.elem_mut(r, c) = power;
+            power = power * *v;
+        }
+    }
+    linreg(&vandermonde, &T::from_vec(n, 1, y.data().to_vec()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+    use super::{wls, ridge, linreg, polyfit};
+
+    #[test]
+    fn test_wls_unit_weight() {
+        // With unit weights it is ordinary least squares: y = 0.5 + 0.8x
+        let x = mat64![1.0, 1.0; 1.0, 2.0; 1.0, 3.0; 1.0, 4.0];
+        let y = mat64![1.0; 3.0; 2.0; 4.0];
+        let beta = wls(&x, &y, &[1.0; 4]).unwrap();
+        assert!(eq_with_error(&beta, &mat64![0.5; 0.8], 1e-10));
+    }
+
+    #[test]
+    fn test_wls_zero_weight() {
+        // A zero weight removes the observation from the fit
+        let x = mat64![1.0, 1.0; 1.0, 2.0; 1.0, 3.0; 1.0, 4.0];
+        let y = mat64![1.0; 3.0; 100.0; 7.0];
+        let beta = wls(&x, &y, &[1.0, 1.0, 0.0, 1.0]).unwrap();
+        assert!(eq_with_error(&beta, &mat64![-1.0; 2.0], 1e-10));
+
+        let err = wls(&x, &y, &[1.0, -1.0, 1.0, 1.0]).unwrap_err();
+        assert_eq!(err.kind(), JolinErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn test_linreg_polyfit() {
+        let x = mat64![1.0, 1.0; 1.0, 2.0; 1.0, 3.0; 1.0, 4.0];
+        let y = mat64![1.0; 3.0; 2.0; 4.0];
+        let fit = linreg(&x, &y).unwrap();
+        assert!(eq_with_error(&fit.coefficients, &mat64![0.5; 0.8], 1e-10));
+        assert!((fit.rss - 1.8).abs() < 1e-10);
+        assert!((fit.sigma - 0.9f64.sqrt()).abs() < 1e-10);
+        assert!((fit.r_squared - 0.64).abs() < 1e-10);
+        assert!(eq_with_error(&fit.residuals, &mat64![-0.3; 0.9; -0.9; 0.3], 1e-10));
+        assert_eq!(linreg(&x, &mat64![1.0; 2.0]).unwrap_err().kind(), JolinErrorKind::ShapeMismatching);
+
+        // y = 2 - x + 0.5 x^3
+        let xs = mat64![-2.0; -1.0; 0.0; 0.5; 1.0; 3.0];
+        let ys = elemwise(&xs, |v| 2.0 - v + 0.5 * v * v * v);
+        let fit = polyfit(&xs, &ys, 3).unwrap();
+        assert!(eq_with_error(&fit.coefficients, &mat64![2.0; -1.0; 0.0; 0.5], 1e-10));
+        assert!(fit.rss < 1e-20);
+        let fit = polyfit(&mat64![1.0, 2.0], &mat64![3.0, 3.0], 0).unwrap();
+        assert_eq!(fit.coefficients, mat64![3.0]);
+        assert_eq!(fit.r_squared, 1.0);
+        assert_eq!(polyfit(&xs, &ys, 6).unwrap_err().kind(), JolinErrorKind::NotEnoughInput);
+        assert_eq!(polyfit(&mat64![1.0, 1.0, 1.0], &mat64![1.0, 2.0, 3.0], 1).unwrap_err().kind(), JolinErrorKind::SingularMatrix);
+        assert!(polyfit(&Mat64::zero(2, 2), &mat64![1.0; 2.0; 3.0; 4.0], 1).is_err());
+    }
+
+    #[test]
+    fn test_ridge() {
+        let x = mat64![1.0, 2.0; 2.0, 4.1; 3.0, 5.9; 4.0, 8.0];
+        let y = mat64![1.0; 2.0; 3.5; 4.0];
+        let lambda = 0.5;
+        let beta = ridge(&x, &y, lambda).unwrap();
+        // compare with the normal equations
+        let mut xtx = trmul(&x, &x).unwrap();
+        for i in 0..2 {
+            *xtx.elem_mut(i, i) += lambda;
+        }
+        let residual = sub(&mul(&xtx, &beta).unwrap(), &trmul(&x, &y).unwrap()).unwrap();
+        assert!(residual.data().iter().all(|v| v.abs() < 1e-10));
+        // no regularization is ordinary least squares
+        assert!(eq_with_error(&ridge(&x, &y, 0.0).unwrap(), &linreg(&x, &y).unwrap().coefficients, 1e-10));
+        assert!(ridge(&mat64![1.0, 1.0; 1.0, 1.0], &mat64![1.0; 2.0], 1e-3).is_ok());
+        assert_eq!(ridge(&x, &y, -1.0).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        assert_eq!(ridge(&x, &mat64![1.0; 2.0], 1.0).unwrap_err().kind(), JolinErrorKind::ShapeMismatching);
+    }
+}
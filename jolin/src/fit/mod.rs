@@ -0,0 +1,61 @@
+/*
+ * fit/mod.rs
+ * Root of regression and model fitting algorithms
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber};
+use crate::decomp::qr::{qr_househoulder, QRDecomposition};
+use crate::error::JolinError;
+
+/// Residual analysis and regression diagnostics of ordinary least squares
+pub mod diagnostics;
+/// Linear least squares solvers
+pub mod linear;
+/// Generalized linear models
+pub mod glm;
+/// LASSO and elastic-net regression
+pub mod lasso;
+/// Non-negative least squares
+pub mod nnls;
+
+pub use self::linear::{wls, ridge, linreg, polyfit, LinearFit};
+pub use self::glm::glm;
+pub use self::lasso::{lasso, elastic_net, elastic_net_path};
+pub use self::nnls::nnls;
+
+/// Compute the QR decomposition of the design matrix for least squares.
+///
+/// Potential errors:
+/// 1. Shape mismatching - if there are fewer rows than columns.
+/// 2. Singular matrix - if the columns of the design matrix are linearly
+///    dependent.
+fn design_qr<T: Matrix>(x: &T) -> Result<QRDecomposition<T>, JolinError> {
+    let qr = qr_househoulder(x)?;
+    let p = x.column();
+    let max_diag = (0..p).map(|i| qr.r.elem(i, i).abs())
+        .fold(T::Elem::zero(), |a, b| if b > a { b } else { a });
+    let tol = max_diag * T::Elem::epsilon().times_real(x.row() as f64);
+    for i in 0..p {
+        if qr.r.elem(i, i).abs() <= tol {
+            return Err(JolinError::singular_matrix());
+        }
+    }
+    Ok(qr)
+}
+
+/// Project the column vector `y` onto the orthogonal complement of the first
+/// `p` columns of `q`, i.e. compute the least squares residuals.
+fn residual_of_projection<T: Matrix>(q: &T, p: usize, y: &[T::Elem]) -> Vec<T::Elem> {
+    let mut res = Vec::from(y);
+    for j in 0..p {
+        let qj = q.data_column(j);
+        let coef: T::Elem = qj.iter().zip(y.iter()).map(|(a, b)| (*a) * (*b)).sum();
+        for i in 0..res.len() {
+            res[i] = res[i] - coef * qj[i];
+        }
+    }
+    res
+}
@@ -0,0 +1,149 @@
+/*
+ * fit/nnls.rs
+ * Non-negative least squares.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber, mul, sub, trmul};
+use crate::error::JolinError;
+use super::design_qr;
+
+/// Non-negative least squares: find `beta >= 0` minimizing `|y - X beta|`
+/// with the active set method of Lawson and Hanson.
+///
+/// Coefficients are moved one by one from the active set, where they are
+/// held at zero, to the passive set, where they are solved by unconstrained
+/// least squares, in the order of the gradient. The answer is a column
+/// vector of coefficients.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::fit::nnls;
+/// let x = mat64![1.0, 0.0; 0.0, 1.0];
+/// // the unconstrained solution is [2, -1]
+/// let beta = nnls(&x, &mat64![2.0; -1.0]).unwrap();
+/// assert!(eq_with_error(&beta, &mat64![2.0; 0.0], 1e-12));
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `y` is not a column vector with the same row
+///    count as `x`.
+/// 2. Singular matrix - if the columns of the passive set are linearly
+///    dependent.
+pub fn nnls<T: Matrix>(x: &T, y: &T) -> Result<T, JolinError> {
+    let n = x.row();
+    let p = x.column();
+    if y.column() != 1 || y.row() != n {
+        return Err(JolinError::shape_mismatching())
+    }
+    let zero = T::Elem::zero();
+    let norm1 = (0..p).map(|c| x.data_column(c).iter().map(|v| v.abs()).sum::<T::Elem>())
+        .fold(zero, |a, b| if b > a { b } else { a });
+    let tol = T::Elem::epsilon().times_real(10.0 * usize::max(n, p) as f64) * norm1;
+
+    let mut beta = vec![zero; p];
+    let mut passive = vec![false; p];
+    // each coefficient rarely enters the passive set more than a few times
+    for _outer in 0..(3 * p) {
+        let w = gradient(x, y, &beta)?;
+        let mut best: Option<usize> = None;
+        for j in 0..p {
+            if !passive[j] && w[j] > tol && best.is_none_or(|b| w[j] > w[b]) {
+                best = Some(j);
+            }
+        }
+        let Some(j) = best else {
+            break
+        };
+        passive[j] = true;
+        loop {
+            let s = passive_solution(x, y, &passive)?;
+            if (0..p).all(|i| !passive[i] || s[i] > zero) {
+                beta = s;
+                break
+            }
+            // move towards s until the first passive coefficient hits zero
+            let mut alpha = T::Elem::one();
+            for i in 0..p {
+                if passive[i] && s[i] <= zero {
+                    let a = beta[i] / (beta[i] - s[i]);
+                    if a < alpha {
+                        alpha = a;
+                    }
+                }
+            }
+            for i in 0..p {
+                beta[i] = beta[i] + alpha * (s[i] - beta[i]);
+                if passive[i] && beta[i] <= tol {
+                    passive[i] = false;
+                    beta[i] = zero;
+                }
+            }
+        }
+    }
+    Ok(T::from_vec(p, 1, beta))
+}
+
+/// `X^T (y - X beta)`, the negative gradient of the half squared residual
+fn gradient<T: Matrix>(x: &T, y: &T, beta: &[T::Elem]) -> Result<Vec<T::Elem>, JolinError> {
+    let residual = sub(y, &mul(x, &T::from_vec(beta.len(), 1, beta.to_vec()))?)?;
+    Ok(trmul(x, &residual)?.data().to_vec())
+}
+
+/// Least squares solution on the passive columns, with zeros elsewhere
+fn passive_solution<T: Matrix>(x: &T, y: &T, passive: &[bool]) -> Result<Vec<T::Elem>, JolinError> {
+    let columns: Vec<usize> = (0..passive.len()).filter(|i| passive[*i]).collect();
+    let mut data = Vec::with_capacity(x.row() * columns.len());
+    for c in columns.iter() {
+        data.extend_from_slice(x.data_column(*c));
+    }
+    let coef = design_qr(&T::from_vec(x.row(), columns.len(), data))?.solve(y)?;
+    let mut ans = vec![T::Elem::zero(); passive.len()];
+    for (k, c) in columns.iter().enumerate() {
+        ans[*c] = coef.data()[k];
+    }
+    Ok(ans)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+    use super::nnls;
+
+    #[test]
+    fn test_nnls() {
+        let x = mat64![
+            1.0, 2.0, 0.5;
+            1.0, 0.0, 1.0;
+            0.0, 1.0, 2.0;
+            2.0, 1.0, 0.0;
+            1.0, 1.0, 1.0];
+        // a non-negative exact solution is found as is
+        let beta = mat64![0.5; 0.0; 1.5];
+        let y = mul(&x, &beta).unwrap();
+        assert!(eq_with_error(&nnls(&x, &y).unwrap(), &beta, 1e-10));
+
+        // the unconstrained solution has a negative coefficient
+        let y = mat64![1.0; -2.0; 3.0; 0.5; -1.0];
+        let ls = crate::fit::linreg(&x, &y).unwrap().coefficients;
+        assert!(ls.data().iter().any(|v| *v < 0.0));
+        let ans = nnls(&x, &y).unwrap();
+        assert!(ans.data().iter().all(|v| *v >= 0.0));
+        // KKT conditions: the gradient is zero on the positive coefficients
+        // and non-positive on the zero ones
+        let w = trmul(&x, &sub(&y, &mul(&x, &ans).unwrap()).unwrap()).unwrap();
+        for i in 0..3 {
+            if ans.data()[i] > 0.0 {
+                assert!(w.data()[i].abs() < 1e-10);
+            } else {
+                assert!(w.data()[i] < 1e-10);
+            }
+        }
+        assert_eq!(nnls(&x, &mat64![-1.0; -1.0; -1.0; -1.0; -1.0]).unwrap(), Mat64::zero(3, 1));
+        assert_eq!(nnls(&x, &mat64![1.0; 2.0]).unwrap_err().kind(), JolinErrorKind::ShapeMismatching);
+    }
+}
@@ -0,0 +1,189 @@
+/*
+ * io/binary.rs
+ * Compact binary format of matrices for checkpointing.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber};
+use crate::error::JolinError;
+
+const MAGIC: &[u8] = b"JOLN";
+const VERSION: u8 = 1;
+pub(crate) const HEADER_SIZE: usize = 24;
+
+const DTYPE_F32: u8 = 1;
+const DTYPE_F64: u8 = 2;
+
+const LAYOUT_COLUMN_MAJOR: u8 = 0;
+const LAYOUT_ROW_MAJOR: u8 = 1;
+
+/// Encode a matrix in the compact binary format. The header is 24 bytes:
+/// the magic string `JOLN`, the version, the data type (1 for `f32`, 2 for
+/// `f64`), the layout (0 for column-major, 1 for row-major), a reserved
+/// byte, then the row count and the column count as little-endian `u64`.
+/// The elements follow in little-endian, and are always written in
+/// column-major order.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::io::binary::{to_bytes, from_bytes};
+/// let a = mat64![1.0, 2.0; 3.0, 4.0];
+/// let bytes = to_bytes(&a);
+/// assert_eq!(bytes.len(), 24 + 4 * 8);
+/// let b: Mat64 = from_bytes(&bytes).unwrap();
+/// assert_eq!(a, b);
+/// ```
+pub fn to_bytes<T: Matrix>(mat: &T) -> Vec<u8> {
+    let size = std::mem::size_of::<T::Elem>();
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + std::mem::size_of_val(mat.data()));
+    bytes.extend_from_slice(&header(mat.row(), mat.column(), size));
+    for x in mat.data().iter() {
+        if size == 8 {
+            bytes.extend_from_slice(&x.to_f64().to_le_bytes());
+        } else {
+            bytes.extend_from_slice(&(x.to_f64() as f32).to_le_bytes());
+        }
+    }
+    bytes
+}
+
+/// Decode a matrix from the compact binary format, see `to_bytes`. Data of
+/// either type is converted to the element type of the matrix, and both
+/// layouts are accepted.
+///
+/// Potential errors:
+/// 1. Invalid value - if the header is malformed, or the version, the data
+///    type or the layout is unknown, or there are bytes after the data.
+/// 2. Not enough input - if the data is shorter than the shape.
+pub fn from_bytes<T: Matrix>(bytes: &[u8]) -> Result<T, JolinError> {
+    let header = parse_header(bytes)?;
+    let (row, column, size) = (header.row, header.column, header.size);
+    let len = header.data_len()?;
+    let data = &bytes[HEADER_SIZE..];
+    if data.len() < len {
+        return Err(JolinError::not_enough_input())
+    }
+    if data.len() > len {
+        return Err(JolinError::invalid_value())
+    }
+    let values: Vec<T::Elem> = data.chunks_exact(size).map(|b| {
+        let v = if size == 8 {
+            f64::from_le_bytes(b.try_into().unwrap())
+        } else {
+            f32::from_le_bytes(b.try_into().unwrap()) as f64
+        };
+        T::Elem::one().times_real(v)
+    }).collect();
+    if !header.row_major {
+        return T::try_from_vec(row, column, values)
+    }
+    let mut ans = T::zero(row, column);
+    for (i, v) in values.into_iter().enumerate() {
+        *ans.elem_mut(i / column, i % column) = v;
+    }
+    Ok(ans)
+}
+
+/// Header of the compact binary format, see `to_bytes`.
+pub(crate) struct Header {
+    pub row: usize,
+    pub column: usize,
+    /// Size in bytes of an element
+    pub size: usize,
+    pub row_major: bool,
+}
+
+impl Header {
+    /// Length in bytes of the data after the header.
+    pub fn data_len(&self) -> Result<usize, JolinError> {
+        self.row.checked_mul(self.column).and_then(|n| n.checked_mul(self.size))
+            .ok_or(JolinError::invalid_value())
+    }
+}
+
+/// Encode the header of a column-major matrix whose elements are `size`
+/// bytes.
+pub(crate) fn header(row: usize, column: usize, size: usize) -> [u8; HEADER_SIZE] {
+    let mut bytes = [0u8; HEADER_SIZE];
+    bytes[0..4].copy_from_slice(MAGIC);
+    bytes[4] = VERSION;
+    bytes[5] = if size == 8 { DTYPE_F64 } else { DTYPE_F32 };
+    bytes[6] = LAYOUT_COLUMN_MAJOR;
+    bytes[8..16].copy_from_slice(&(row as u64).to_le_bytes());
+    bytes[16..24].copy_from_slice(&(column as u64).to_le_bytes());
+    bytes
+}
+
+/// Decode the header at the beginning of `bytes`.
+pub(crate) fn parse_header(bytes: &[u8]) -> Result<Header, JolinError> {
+    if bytes.len() < HEADER_SIZE || &bytes[0..4] != MAGIC || bytes[4] != VERSION {
+        return Err(JolinError::invalid_value())
+    }
+    let size = match bytes[5] {
+        DTYPE_F32 => 4,
+        DTYPE_F64 => 8,
+        _ => return Err(JolinError::invalid_value()),
+    };
+    let row_major = match bytes[6] {
+        LAYOUT_COLUMN_MAJOR => false,
+        LAYOUT_ROW_MAJOR => true,
+        _ => return Err(JolinError::invalid_value()),
+    };
+    let row = usize::try_from(u64::from_le_bytes(bytes[8..16].try_into().unwrap()))
+        .map_err(|_| JolinError::invalid_value())?;
+    let column = usize::try_from(u64::from_le_bytes(bytes[16..24].try_into().unwrap()))
+        .map_err(|_| JolinError::invalid_value())?;
+    Ok(Header { row, column, size, row_major })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+    use crate::io::binary::{*};
+
+    #[test]
+    fn test_binary_round_trip() {
+        let a = mat64![1.0, -2.5, 3.25; 1e-300, f64::MAX, 0.0];
+        let bytes = to_bytes(&a);
+        assert_eq!(&bytes[0..8], &[b'J', b'O', b'L', b'N', 1, 2, 0, 0]);
+        assert_eq!(from_bytes::<Mat64>(&bytes).unwrap(), a);
+
+        let b = Mat32::new(2, 1, &[0.5, -1.0]);
+        let bytes = to_bytes(&b);
+        assert_eq!(bytes.len(), 24 + 2 * 4);
+        assert_eq!(from_bytes::<Mat32>(&bytes).unwrap(), b);
+        assert_eq!(from_bytes::<Mat64>(&bytes).unwrap(), mat64![0.5; -1.0]);
+
+        // the same data in row-major layout
+        let mut row_major = to_bytes(&tr(&a));
+        row_major[6] = 1;
+        row_major[8..16].copy_from_slice(&2u64.to_le_bytes());
+        row_major[16..24].copy_from_slice(&3u64.to_le_bytes());
+        assert_eq!(from_bytes::<Mat64>(&row_major).unwrap(), a);
+
+        assert_eq!(from_bytes::<Mat64>(&to_bytes(&Mat64::zero(0, 3))).unwrap(), Mat64::zero(0, 3));
+    }
+
+    #[test]
+    fn test_from_bytes_errors() {
+        let bytes = to_bytes(&mat64![1.0, 2.0]);
+        let err = from_bytes::<Mat64>(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), JolinErrorKind::NotEnoughInput);
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert_eq!(from_bytes::<Mat64>(&longer).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        for i in [0, 4, 5, 6] {
+            let mut bad = bytes.clone();
+            bad[i] = 9;
+            assert_eq!(from_bytes::<Mat64>(&bad).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        }
+        let mut huge = bytes.clone();
+        huge[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(from_bytes::<Mat64>(&huge).is_err());
+        assert!(from_bytes::<Mat64>(&bytes[..10]).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+/*
+ * io/csv.rs
+ * Reading and writing matrices as comma-separated values.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use crate::matrix::{Matrix, MatrixView, Mat64};
+use crate::error::JolinError;
+
+/// Options of reading and writing CSV
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    /// Separator of the fields
+    pub delimiter: char,
+    /// Whether the first line holds the column names
+    pub has_header: bool,
+    /// Fields read as missing values, compared after trimming the spaces. The
+    /// first one is written for NaN.
+    pub missing: Vec<String>,
+    /// Value of the missing fields
+    pub missing_value: f64,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: ',',
+            has_header: false,
+            missing: vec![String::new(), "NA".to_string()],
+            missing_value: f64::NAN,
+        }
+    }
+}
+
+/// Read a matrix from CSV, one row per line. Blank lines are skipped, fields
+/// may be quoted with `"`, and the header is skipped if there is one.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::io::csv::{read_csv, CsvOptions};
+/// let text = "x;y\n1;2\n3;NA\n";
+/// let options = CsvOptions { delimiter: ';', has_header: true, ..CsvOptions::default() };
+/// let a = read_csv(text.as_bytes(), &options).unwrap();
+/// assert_eq!(a.elem(1, 0), 3.0);
+/// assert!(a.elem(1, 1).is_nan());
+/// ```
+///
+/// Potential errors:
+/// 1. IO error - if reading fails.
+/// 2. Shape mismatching - if the lines have different counts of fields.
+/// 3. Invalid value - if a field is neither a number nor a missing value.
+pub fn read_csv<R: Read>(reader: R, options: &CsvOptions) -> Result<Mat64, JolinError> {
+    read_csv_with_header(reader, options).map(|(_, mat)| mat)
+}
+
+/// Read a matrix from CSV like `read_csv`, together with the column names in
+/// the header. The names are empty if `has_header` is not set.
+///
+/// Potential errors:
+/// 1. IO error - if reading fails.
+/// 2. Shape mismatching - if the lines have different counts of fields.
+/// 3. Invalid value - if a field is neither a number nor a missing value.
+pub fn read_csv_with_header<R: Read>(reader: R, options: &CsvOptions) -> Result<(Vec<String>, Mat64), JolinError> {
+    let mut header = Vec::new();
+    let mut rows: Vec<Vec<f64>> = Vec::new();
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_fields(&line, options.delimiter)?;
+        if options.has_header && header.is_empty() {
+            header = fields;
+            continue;
+        }
+        let row = fields.iter().map(|f| parse_field(f, options)).collect::<Result<Vec<f64>, JolinError>>()?;
+        if !rows.is_empty() && row.len() != rows[0].len() {
+            return Err(JolinError::shape_mismatching())
+        }
+        rows.push(row);
+    }
+    if !header.is_empty() && !rows.is_empty() && header.len() != rows[0].len() {
+        return Err(JolinError::shape_mismatching())
+    }
+    let column = rows.first().map_or(header.len(), |row| row.len());
+    let mut ans = Mat64::zero(rows.len(), column);
+    for (r, row) in rows.iter().enumerate() {
+        for (c, x) in row.iter().enumerate() {
+            *ans.elem_mut(r, c) = *x;
+        }
+    }
+    Ok((header, ans))
+}
+
+/// Read a matrix from a CSV file, see `read_csv`.
+///
+/// Potential errors:
+/// 1. IO error - if the file can't be read.
+/// 2. Shape mismatching - if the lines have different counts of fields.
+/// 3. Invalid value - if a field is neither a number nor a missing value.
+pub fn read_csv_file<P: AsRef<Path>>(path: P, options: &CsvOptions) -> Result<Mat64, JolinError> {
+    read_csv(File::open(path)?, options)
+}
+
+/// Write a matrix as CSV, one row per line, with an optional header. NaN is
+/// written as the first missing value of the options, or `NaN` if there is
+/// none. In a single column, where it would leave a blank line, NaN is
+/// written as the first non-blank missing value instead. The numbers are written in the shortest form that reads back to the
+/// same value.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::io::csv::{write_csv, CsvOptions};
+/// let mut out = Vec::new();
+/// write_csv(&mat64![1.0, 0.5; f64::NAN, 4.0], Some(&["a", "b"]), &mut out, &CsvOptions::default()).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), "a,b\n1,0.5\n,4\n");
+/// ```
+///
+/// Potential errors:
+/// 1. IO error - if writing fails.
+/// 2. Shape mismatching - if the count of names differs from the column count.
+pub fn write_csv<W: Write>(mat: &Mat64, header: Option<&[&str]>, writer: W, options: &CsvOptions) -> Result<(), JolinError> {
+    let mut w = BufWriter::new(writer);
+    let delimiter = options.delimiter.to_string();
+    if let Some(names) = header {
+        if names.len() != mat.column() {
+            return Err(JolinError::shape_mismatching())
+        }
+        let quoted: Vec<String> = names.iter().map(|n| quote_field(n, options.delimiter)).collect();
+        writeln!(w, "{}", quoted.join(&delimiter))?;
+    }
+    let nan = options.missing.first().map_or("NaN", |s| s.as_str());
+    // a blank line is skipped when reading, so a row of one missing value is
+    // written with a visible token
+    let visible_nan = options.missing.iter().find(|s| !s.trim().is_empty()).map_or("NaN", |s| s.as_str());
+    for r in 0..mat.row() {
+        let fields: Vec<String> = (0..mat.column()).map(|c| {
+            let x = mat.elem(r, c);
+            if x.is_nan() { nan.to_string() } else { x.to_string() }
+        }).collect();
+        let line = fields.join(&delimiter);
+        if line.trim().is_empty() && mat.column() == 1 {
+            writeln!(w, "{}", visible_nan)?;
+        } else {
+            writeln!(w, "{}", line)?;
+        }
+    }
+    w.flush()?;
+    Ok(())
+}
+
+/// Write a matrix to a CSV file, see `write_csv`.
+///
+/// Potential errors:
+/// 1. IO error - if the file can't be written.
+/// 2. Shape mismatching - if the count of names differs from the column count.
+pub fn write_csv_file<P: AsRef<Path>>(mat: &Mat64, header: Option<&[&str]>, path: P, options: &CsvOptions) -> Result<(), JolinError> {
+    write_csv(mat, header, File::create(path)?, options)
+}
+
+/// Split a line into fields. A quoted field may contain the delimiter, and
+/// `""` inside it stands for a quote.
+fn split_fields(line: &str, delimiter: char) -> Result<Vec<String>, JolinError> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if quoted {
+            if ch == '"' {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            } else {
+                field.push(ch);
+            }
+        } else if ch == '"' {
+            quoted = true;
+        } else if ch == delimiter {
+            fields.push(std::mem::take(&mut field));
+        } else {
+            field.push(ch);
+        }
+    }
+    if quoted {
+        return Err(JolinError::invalid_value())
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+fn parse_field(field: &str, options: &CsvOptions) -> Result<f64, JolinError> {
+    let field = field.trim();
+    if options.missing.iter().any(|m| m.trim() == field) {
+        return Ok(options.missing_value)
+    }
+    field.parse::<f64>().map_err(|_| JolinError::invalid_value())
+}
+
+fn quote_field(field: &str, delimiter: char) -> String {
+    if field.contains(delimiter) || field.contains('"') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+    use crate::io::csv::{*};
+
+    #[test]
+    fn test_read_csv() {
+        let text = "\"name, with comma\",b\n 1.5 , -2\n\n3e2,NA\n";
+        let options = CsvOptions { has_header: true, ..CsvOptions::default() };
+        let (header, a) = read_csv_with_header(text.as_bytes(), &options).unwrap();
+        assert_eq!(header, vec!["name, with comma".to_string(), "b".to_string()]);
+        assert_eq!((a.row(), a.column()), (2, 2));
+        assert_eq!(a.data_column(0), &[1.5, 300.0]);
+        assert!(a.elem(1, 1).is_nan());
+
+        let zero_filled = CsvOptions { missing_value: 0.0, ..CsvOptions::default() };
+        assert_eq!(read_csv("1,\n,2".as_bytes(), &zero_filled).unwrap(), mat64![1.0, 0.0; 0.0, 2.0]);
+        assert_eq!(read_csv("".as_bytes(), &zero_filled).unwrap(), Mat64::zero(0, 0));
+
+        let err = read_csv("1,2\n3".as_bytes(), &CsvOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), JolinErrorKind::ShapeMismatching);
+        let err = read_csv("1,x".as_bytes(), &CsvOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), JolinErrorKind::InvalidValue);
+        assert!(read_csv("\"1,2".as_bytes(), &CsvOptions::default()).is_err());
+        let err = read_csv_file("/nonexistent/jolin.csv", &CsvOptions::default()).unwrap_err();
+        assert_eq!(err.kind(), JolinErrorKind::IOError);
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let a = mat64![1.0, -0.1; 2.5e10, f64::NAN];
+        let options = CsvOptions { delimiter: '\t', missing: vec!["NA".to_string()], ..CsvOptions::default() };
+        let mut out = Vec::new();
+        write_csv(&a, Some(&["x", "say \"y\""]), &mut out, &options).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text, "x\t\"say \"\"y\"\"\"\n1\t-0.1\n25000000000\tNA\n");
+
+        let options = CsvOptions { has_header: true, ..options };
+        let (header, b) = read_csv_with_header(text.as_bytes(), &options).unwrap();
+        assert_eq!(header[1], "say \"y\"");
+        assert_eq!(b.data_column(0), a.data_column(0));
+        assert!(b.elem(1, 1).is_nan());
+        assert!(write_csv(&a, Some(&["x"]), Vec::new(), &options).is_err());
+    }
+
+    #[test]
+    fn test_csv_single_column_nan() {
+        let a = mat64![1.0; f64::NAN; 3.0];
+        for missing in [vec![String::new(), "NA".to_string()], vec![String::new()]] {
+            let options = CsvOptions { missing, ..CsvOptions::default() };
+            let mut out = Vec::new();
+            write_csv(&a, None, &mut out, &options).unwrap();
+            let b = read_csv(out.as_slice(), &options).unwrap();
+            assert_eq!(b.row(), 3);
+            assert_eq!((b.elem(0, 0), b.elem(2, 0)), (1.0, 3.0));
+            assert!(b.elem(1, 0).is_nan());
+        }
+    }
+}
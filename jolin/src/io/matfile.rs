@@ -0,0 +1,267 @@
+/*
+ * io/matfile.rs
+ * Reading numeric arrays from MATLAB .mat (v5) files.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use flate2::read::ZlibDecoder;
+use crate::matrix::{Matrix, Mat64};
+use crate::error::JolinError;
+
+const HEADER_SIZE: usize = 128;
+
+// data types of the elements
+const MI_INT8: u32 = 1;
+const MI_UINT8: u32 = 2;
+const MI_INT16: u32 = 3;
+const MI_UINT16: u32 = 4;
+const MI_INT32: u32 = 5;
+const MI_UINT32: u32 = 6;
+const MI_SINGLE: u32 = 7;
+const MI_DOUBLE: u32 = 9;
+const MI_INT64: u32 = 12;
+const MI_UINT64: u32 = 13;
+const MI_MATRIX: u32 = 14;
+const MI_COMPRESSED: u32 = 15;
+
+// array classes from mxDOUBLE_CLASS to mxUINT64_CLASS are numeric
+const NUMERIC_CLASSES: std::ops::RangeInclusive<u32> = 6..=15;
+const COMPLEX_FLAG: u32 = 0x0800;
+
+/// Read all real numeric 2-D arrays of a MATLAB .mat (v5) file as pairs of
+/// the variable name and the matrix, in the order of the file. Compressed
+/// variables are supported. Variables of other kinds, e.g. strings, cells,
+/// structs, sparse, complex or N-D arrays, are skipped.
+///
+/// Potential errors:
+/// 1. IO error - if reading fails.
+/// 2. Invalid value - if the file is not a .mat v5 file or it is malformed.
+pub fn read_mat<R: Read>(mut reader: R) -> Result<Vec<(String, Mat64)>, JolinError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if bytes.len() < HEADER_SIZE {
+        return Err(JolinError::invalid_value())
+    }
+    let big_endian = match &bytes[126..128] {
+        b"IM" => false,
+        b"MI" => true,
+        _ => return Err(JolinError::invalid_value()),
+    };
+    let mut ans = Vec::new();
+    let mut pos = HEADER_SIZE;
+    while pos < bytes.len() {
+        let (data_type, data, next) = read_element(&bytes, pos, big_endian)?;
+        pos = next;
+        let variable = match data_type {
+            MI_MATRIX => read_matrix(data, big_endian)?,
+            MI_COMPRESSED => {
+                let mut inflated = Vec::new();
+                ZlibDecoder::new(data).read_to_end(&mut inflated).map_err(|_| JolinError::invalid_value())?;
+                match read_element(&inflated, 0, big_endian)? {
+                    (MI_MATRIX, data, _) => read_matrix(data, big_endian)?,
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if let Some(v) = variable {
+            ans.push(v);
+        }
+    }
+    Ok(ans)
+}
+
+/// Read the real numeric 2-D array of the name from a MATLAB .mat (v5)
+/// file, see `read_mat`.
+///
+/// Potential errors:
+/// 1. IO error - if reading fails.
+/// 2. Invalid value - if the file is malformed, or it has no supported
+///    variable of the name.
+pub fn read_mat_variable<R: Read>(reader: R, name: &str) -> Result<Mat64, JolinError> {
+    read_mat(reader)?.into_iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, mat)| mat)
+        .ok_or(JolinError::invalid_value())
+}
+
+/// Read all real numeric 2-D arrays of a MATLAB .mat (v5) file, see
+/// `read_mat`.
+///
+/// Potential errors:
+/// 1. IO error - if the file can't be read.
+/// 2. Invalid value - if the file is not a .mat v5 file or it is malformed.
+pub fn read_mat_file<P: AsRef<Path>>(path: P) -> Result<Vec<(String, Mat64)>, JolinError> {
+    read_mat(File::open(path)?)
+}
+
+fn read_u32(bytes: &[u8], pos: usize, big_endian: bool) -> Result<u32, JolinError> {
+    let b: [u8; 4] = bytes.get(pos..(pos + 4))
+        .ok_or(JolinError::invalid_value())?
+        .try_into().unwrap();
+    Ok(if big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) })
+}
+
+/// Read the element at `pos`, and get its data type, its data and the
+/// position of the next element
+fn read_element(bytes: &[u8], pos: usize, big_endian: bool) -> Result<(u32, &[u8], usize), JolinError> {
+    let first = read_u32(bytes, pos, big_endian)?;
+    let (data_type, start, size, next) = if first >> 16 != 0 {
+        // small data element with the data in the tag
+        let size = (first >> 16) as usize;
+        if size > 4 {
+            return Err(JolinError::invalid_value())
+        }
+        (first & 0xFFFF, pos + 4, size, pos + 8)
+    } else {
+        let size = read_u32(bytes, pos + 4, big_endian)? as usize;
+        let end = pos + 8 + size;
+        // compressed elements are not padded to 8 bytes
+        let next = if first == MI_COMPRESSED { end } else { end.div_ceil(8) * 8 };
+        (first, pos + 8, size, next)
+    };
+    let data = bytes.get(start..(start + size)).ok_or(JolinError::invalid_value())?;
+    Ok((data_type, data, next))
+}
+
+/// Read an array element, or `None` if it is not a real numeric 2-D array
+fn read_matrix(bytes: &[u8], big_endian: bool) -> Result<Option<(String, Mat64)>, JolinError> {
+    let (_, flags, pos) = read_element(bytes, 0, big_endian)?;
+    let flags = read_u32(flags, 0, big_endian)?;
+    let class = flags & 0xFF;
+    if !NUMERIC_CLASSES.contains(&class) || flags & COMPLEX_FLAG != 0 {
+        return Ok(None)
+    }
+    let (_, dims, pos) = read_element(bytes, pos, big_endian)?;
+    if dims.len() != 8 {
+        return Ok(None)
+    }
+    let row = read_u32(dims, 0, big_endian)? as usize;
+    let column = read_u32(dims, 4, big_endian)? as usize;
+    let (_, name, pos) = read_element(bytes, pos, big_endian)?;
+    let name = String::from_utf8(name.to_vec()).map_err(|_| JolinError::invalid_value())?;
+    let (data_type, data, _) = read_element(bytes, pos, big_endian)?;
+    let values = to_f64s(data_type, data, big_endian)?;
+    let mat = Mat64::try_from_vec(row, column, values).map_err(|_| JolinError::invalid_value())?;
+    Ok(Some((name, mat)))
+}
+
+/// Convert the data of an element to `f64`. MATLAB may store an array in a
+/// smaller data type than its class, e.g. doubles holding small integers.
+fn to_f64s(data_type: u32, data: &[u8], big_endian: bool) -> Result<Vec<f64>, JolinError> {
+    let size = match data_type {
+        MI_INT8 | MI_UINT8 => 1,
+        MI_INT16 | MI_UINT16 => 2,
+        MI_INT32 | MI_UINT32 | MI_SINGLE => 4,
+        MI_DOUBLE | MI_INT64 | MI_UINT64 => 8,
+        _ => return Err(JolinError::invalid_value()),
+    };
+    if !data.len().is_multiple_of(size) {
+        return Err(JolinError::invalid_value())
+    }
+    Ok(data.chunks_exact(size).map(|b| {
+        let mut buf = [0u8; 8];
+        buf[..size].copy_from_slice(b);
+        if big_endian {
+            buf[..size].reverse();
+        }
+        let [b0, b1, b2, b3, ..] = buf;
+        match data_type {
+            MI_INT8 => b0 as i8 as f64,
+            MI_UINT8 => b0 as f64,
+            MI_INT16 => i16::from_le_bytes([b0, b1]) as f64,
+            MI_UINT16 => u16::from_le_bytes([b0, b1]) as f64,
+            MI_INT32 => i32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            MI_UINT32 => u32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            MI_SINGLE => f32::from_le_bytes([b0, b1, b2, b3]) as f64,
+            MI_INT64 => i64::from_le_bytes(buf) as f64,
+            MI_UINT64 => u64::from_le_bytes(buf) as f64,
+            _ => f64::from_le_bytes(buf),
+        }
+    }).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Write;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use crate::mat64;
+    use crate::error::JolinErrorKind;
+    use crate::io::matfile::{*};
+
+    fn header() -> Vec<u8> {
+        let mut bytes = b"MATLAB 5.0 MAT-file, created by a test".to_vec();
+        bytes.resize(124, b' ');
+        bytes.extend_from_slice(&[0x00, 0x01, b'I', b'M']);
+        bytes
+    }
+
+    fn element(data_type: u32, data: &[u8]) -> Vec<u8> {
+        let mut bytes = data_type.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(data);
+        bytes.resize(bytes.len().div_ceil(8) * 8, 0);
+        bytes
+    }
+
+    fn array(class: u32, dims: &[u32], name: &str, data_type: u32, data: &[u8]) -> Vec<u8> {
+        let mut body = element(MI_UINT32, &[class.to_le_bytes(), [0; 4]].concat());
+        body.extend(element(MI_INT32, &dims.iter().flat_map(|d| d.to_le_bytes()).collect::<Vec<u8>>()));
+        if name.len() <= 4 {
+            // small data element
+            body.extend_from_slice(&((name.len() as u32) << 16 | MI_INT8).to_le_bytes());
+            let mut padded = name.as_bytes().to_vec();
+            padded.resize(4, 0);
+            body.extend(padded);
+        } else {
+            body.extend(element(MI_INT8, name.as_bytes()));
+        }
+        body.extend(element(data_type, data));
+        element(MI_MATRIX, &body)
+    }
+
+    #[test]
+    fn test_read_mat() {
+        let mut bytes = header();
+        let doubles: Vec<u8> = [1.0f64, 4.0, 2.0, 5.0, 3.0, 6.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        bytes.extend(array(6, &[2, 3], "a", MI_DOUBLE, &doubles));
+        // a char array is skipped
+        bytes.extend(array(4, &[1, 2], "text", MI_UINT16, &[104, 0, 105, 0]));
+        // a compressed int16 matrix stored as uint8
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&array(10, &[2, 1], "counts", MI_UINT8, &[7, 200])).unwrap();
+        let compressed = encoder.finish().unwrap();
+        bytes.extend_from_slice(&MI_COMPRESSED.to_le_bytes());
+        bytes.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        bytes.extend(compressed);
+
+        let vars = read_mat(bytes.as_slice()).unwrap();
+        assert_eq!(vars.len(), 2);
+        assert_eq!(vars[0], ("a".to_string(), mat64![1.0, 2.0, 3.0; 4.0, 5.0, 6.0]));
+        assert_eq!(vars[1], ("counts".to_string(), mat64![7.0; 200.0]));
+        assert_eq!(read_mat_variable(bytes.as_slice(), "counts").unwrap(), mat64![7.0; 200.0]);
+        let err = read_mat_variable(bytes.as_slice(), "text").unwrap_err();
+        assert_eq!(err.kind(), JolinErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn test_read_mat_errors() {
+        assert!(read_mat(&b"MATLAB 5.0"[..]).is_err());
+        let mut bytes = header();
+        bytes[126] = b'X';
+        assert!(read_mat(bytes.as_slice()).is_err());
+        let mut bytes = header();
+        bytes.extend(array(6, &[2, 2], "a", MI_DOUBLE, &[0; 8]));
+        assert_eq!(read_mat(bytes.as_slice()).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        let mut truncated = header();
+        truncated.extend(&array(6, &[1, 1], "a", MI_DOUBLE, &[0; 8])[..20]);
+        assert!(read_mat(truncated.as_slice()).is_err());
+        assert_eq!(read_mat(header().as_slice()).unwrap(), vec![]);
+    }
+}
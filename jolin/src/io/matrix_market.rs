@@ -0,0 +1,362 @@
+/*
+ * io/matrix_market.rs
+ * Reading and writing matrices in the Matrix Market exchange format.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use crate::matrix::{Matrix, MatrixView, Mat64};
+use crate::error::JolinError;
+
+/// Largest element count of the dense matrix `read_matrix_market` forms from
+/// a coordinate file, i.e. 2 GiB of `f64`. Larger matrices are read with
+/// `read_matrix_market_coordinate`.
+pub const MAX_DENSE_LEN: usize = 1 << 28;
+
+/// Layout of the entries of a Matrix Market file
+#[derive(Debug, PartialEq, Copy, Clone, Eq)]
+pub enum MatrixMarketFormat {
+    /// All entries in column-major order, for dense matrices
+    Array,
+    /// The nonzero entries with their positions, for sparse matrices
+    Coordinate,
+}
+
+/// A sparse matrix read from a coordinate Matrix Market file
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoordinateMatrix {
+    /// Row count
+    pub row: usize,
+    /// Column count
+    pub column: usize,
+    /// Entries `(row, column, value)` with indices from zero. The entries
+    /// implied by the symmetry are included, and duplicates are kept.
+    pub entries: Vec<(usize, usize, f64)>,
+}
+
+#[derive(Debug, PartialEq, Copy, Clone, Eq)]
+enum Symmetry {
+    General,
+    Symmetric,
+    SkewSymmetric,
+}
+
+struct Header {
+    format: MatrixMarketFormat,
+    pattern: bool,
+    symmetry: Symmetry,
+}
+
+/// Read a dense matrix from a Matrix Market file of either format. The
+/// entries of a coordinate file are summed into a dense matrix. Real, integer
+/// and pattern fields are supported, as well as general, symmetric and
+/// skew-symmetric matrices.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::io::matrix_market::read_matrix_market;
+/// let text = "%%MatrixMarket matrix coordinate real symmetric\n% a comment\n2 2 2\n1 1 4.0\n2 1 -1.5\n";
+/// assert_eq!(read_matrix_market(text.as_bytes()).unwrap(), mat64![4.0, -1.5; -1.5, 0.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. IO error - if reading fails.
+/// 2. Invalid value - if the header, the size or an entry is malformed or
+///    unsupported, or an index is out of range, or a coordinate matrix has
+///    more than `MAX_DENSE_LEN` elements.
+/// 3. Not enough input - if there are fewer entries than declared.
+pub fn read_matrix_market<R: Read>(reader: R) -> Result<Mat64, JolinError> {
+    let mut lines = BufReader::new(reader).lines();
+    let header = read_header(&mut lines)?;
+    match header.format {
+        MatrixMarketFormat::Array => read_array(&mut lines, &header),
+        MatrixMarketFormat::Coordinate => {
+            let coo = read_entries(&mut lines, &header)?;
+            // the size of a coordinate file isn't backed by its data, so it
+            // is bounded before the dense matrix is allocated
+            if coo.row.checked_mul(coo.column).is_none_or(|len| len > MAX_DENSE_LEN) {
+                return Err(JolinError::invalid_value())
+            }
+            let mut ans = Mat64::zero(coo.row, coo.column);
+            for (r, c, v) in coo.entries.iter() {
+                *ans.elem_mut(*r, *c) += *v;
+            }
+            Ok(ans)
+        }
+    }
+}
+
+/// Read a coordinate Matrix Market file without forming the dense matrix.
+///
+/// Potential errors:
+/// 1. IO error - if reading fails.
+/// 2. Invalid value - if the file is not in the coordinate format, or it is
+///    malformed or unsupported, or an index is out of range.
+/// 3. Not enough input - if there are fewer entries than declared.
+pub fn read_matrix_market_coordinate<R: Read>(reader: R) -> Result<CoordinateMatrix, JolinError> {
+    let mut lines = BufReader::new(reader).lines();
+    let header = read_header(&mut lines)?;
+    if header.format != MatrixMarketFormat::Coordinate {
+        return Err(JolinError::invalid_value())
+    }
+    read_entries(&mut lines, &header)
+}
+
+/// Read a dense matrix from a Matrix Market file, see `read_matrix_market`.
+///
+/// Potential errors:
+/// 1. IO error - if the file can't be read.
+/// 2. Invalid value - if the file is malformed or unsupported.
+/// 3. Not enough input - if there are fewer entries than declared.
+pub fn read_matrix_market_file<P: AsRef<Path>>(path: P) -> Result<Mat64, JolinError> {
+    read_matrix_market(File::open(path)?)
+}
+
+/// Write a matrix as a general real Matrix Market file. The coordinate
+/// format only keeps the nonzero entries.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::io::matrix_market::{write_matrix_market, MatrixMarketFormat};
+/// let mut out = Vec::new();
+/// write_matrix_market(&mat64![1.0, 0.0; 0.0, 2.5], MatrixMarketFormat::Coordinate, &mut out).unwrap();
+/// let text = String::from_utf8(out).unwrap();
+/// assert_eq!(text, "%%MatrixMarket matrix coordinate real general\n2 2 2\n1 1 1\n2 2 2.5\n");
+/// ```
+///
+/// Potential errors:
+/// 1. IO error - if writing fails.
+pub fn write_matrix_market<W: Write>(mat: &Mat64, format: MatrixMarketFormat, writer: W) -> Result<(), JolinError> {
+    let mut w = BufWriter::new(writer);
+    match format {
+        MatrixMarketFormat::Array => {
+            writeln!(w, "%%MatrixMarket matrix array real general")?;
+            writeln!(w, "{} {}", mat.row(), mat.column())?;
+            for x in mat.data().iter() {
+                writeln!(w, "{}", x)?;
+            }
+        }
+        MatrixMarketFormat::Coordinate => {
+            writeln!(w, "%%MatrixMarket matrix coordinate real general")?;
+            let nnz = mat.data().iter().filter(|x| **x != 0.0).count();
+            writeln!(w, "{} {} {}", mat.row(), mat.column(), nnz)?;
+            for c in 0..mat.column() {
+                for r in 0..mat.row() {
+                    let x = mat.elem(r, c);
+                    if x != 0.0 {
+                        writeln!(w, "{} {} {}", r + 1, c + 1, x)?;
+                    }
+                }
+            }
+        }
+    }
+    w.flush()?;
+    Ok(())
+}
+
+/// Write a matrix to a Matrix Market file, see `write_matrix_market`.
+///
+/// Potential errors:
+/// 1. IO error - if the file can't be written.
+pub fn write_matrix_market_file<P: AsRef<Path>>(mat: &Mat64, format: MatrixMarketFormat, path: P) -> Result<(), JolinError> {
+    write_matrix_market(mat, format, File::create(path)?)
+}
+
+fn read_header<I: Iterator<Item = std::io::Result<String>>>(lines: &mut I) -> Result<Header, JolinError> {
+    let line = match lines.next() {
+        Some(line) => line?.to_lowercase(),
+        None => return Err(JolinError::invalid_value()),
+    };
+    let words: Vec<&str> = line.split_whitespace().collect();
+    if words.len() != 5 || words[0] != "%%matrixmarket" || words[1] != "matrix" {
+        return Err(JolinError::invalid_value())
+    }
+    let format = match words[2] {
+        "array" => MatrixMarketFormat::Array,
+        "coordinate" => MatrixMarketFormat::Coordinate,
+        _ => return Err(JolinError::invalid_value()),
+    };
+    let pattern = match words[3] {
+        "real" | "double" | "integer" => false,
+        "pattern" if format == MatrixMarketFormat::Coordinate => true,
+        _ => return Err(JolinError::invalid_value()),
+    };
+    let symmetry = match words[4] {
+        "general" => Symmetry::General,
+        "symmetric" => Symmetry::Symmetric,
+        "skew-symmetric" => Symmetry::SkewSymmetric,
+        _ => return Err(JolinError::invalid_value()),
+    };
+    Ok(Header { format, pattern, symmetry })
+}
+
+/// Next line which is neither a comment nor blank, split into words
+fn next_data_line<I: Iterator<Item = std::io::Result<String>>>(lines: &mut I) -> Result<Option<Vec<String>>, JolinError> {
+    for line in lines {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('%') {
+            continue;
+        }
+        return Ok(Some(line.split_whitespace().map(|s| s.to_string()).collect()))
+    }
+    Ok(None)
+}
+
+fn parse<T: std::str::FromStr>(word: &str) -> Result<T, JolinError> {
+    word.parse::<T>().map_err(|_| JolinError::invalid_value())
+}
+
+fn read_size<I: Iterator<Item = std::io::Result<String>>>(lines: &mut I, count: usize) -> Result<Vec<usize>, JolinError> {
+    match next_data_line(lines)? {
+        Some(words) if words.len() == count => words.iter().map(|w| parse::<usize>(w)).collect(),
+        _ => Err(JolinError::invalid_value()),
+    }
+}
+
+fn read_array<I: Iterator<Item = std::io::Result<String>>>(lines: &mut I, header: &Header) -> Result<Mat64, JolinError> {
+    let size = read_size(lines, 2)?;
+    let (m, n) = (size[0], size[1]);
+    if header.symmetry != Symmetry::General && m != n {
+        return Err(JolinError::invalid_value())
+    }
+    if m.checked_mul(n).is_none() {
+        return Err(JolinError::invalid_value())
+    }
+    // positions stored in the file, column by column
+    let positions = (0..n).flat_map(|c| {
+        let first = match header.symmetry {
+            Symmetry::General => 0,
+            Symmetry::Symmetric => c,
+            Symmetry::SkewSymmetric => c + 1,
+        };
+        (first..m).map(move |r| (r, c))
+    });
+    // the values are read before the matrix is allocated, so a malformed
+    // size fails on the missing entries instead of the allocation
+    let mut values = Vec::new();
+    for _ in positions.clone() {
+        let words = next_data_line(lines)?.ok_or(JolinError::not_enough_input())?;
+        if words.len() != 1 {
+            return Err(JolinError::invalid_value())
+        }
+        values.push(parse::<f64>(&words[0])?);
+    }
+    let mut ans = Mat64::zero(m, n);
+    for ((r, c), v) in positions.zip(values) {
+        *ans.elem_mut(r, c) = v;
+        match header.symmetry {
+            Symmetry::General => {},
+            Symmetry::Symmetric => *ans.elem_mut(c, r) = v,
+            Symmetry::SkewSymmetric => *ans.elem_mut(c, r) = -v,
+        }
+    }
+    Ok(ans)
+}
+
+fn read_entries<I: Iterator<Item = std::io::Result<String>>>(lines: &mut I, header: &Header) -> Result<CoordinateMatrix, JolinError> {
+    let size = read_size(lines, 3)?;
+    let (m, n, nnz) = (size[0], size[1], size[2]);
+    if header.symmetry != Symmetry::General && m != n {
+        return Err(JolinError::invalid_value())
+    }
+    // not preallocated, since `nnz` is not validated yet
+    let mut entries = Vec::new();
+    for _ in 0..nnz {
+        let words = next_data_line(lines)?.ok_or(JolinError::not_enough_input())?;
+        if words.len() != if header.pattern { 2 } else { 3 } {
+            return Err(JolinError::invalid_value())
+        }
+        let (r, c): (usize, usize) = (parse(&words[0])?, parse(&words[1])?);
+        if r == 0 || c == 0 || r > m || c > n {
+            return Err(JolinError::invalid_value())
+        }
+        let v = if header.pattern { 1.0 } else { parse(&words[2])? };
+        entries.push((r - 1, c - 1, v));
+        if r != c {
+            match header.symmetry {
+                Symmetry::General => {},
+                Symmetry::Symmetric => entries.push((c - 1, r - 1, v)),
+                Symmetry::SkewSymmetric => entries.push((c - 1, r - 1, -v)),
+            }
+        }
+    }
+    Ok(CoordinateMatrix { row: m, column: n, entries })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+    use crate::io::matrix_market::{*};
+
+    #[test]
+    fn test_read_array() {
+        let text = "%%MatrixMarket matrix array real general\n%\n2 3\n1\n2\n3\n4\n5\n6\n";
+        assert_eq!(read_matrix_market(text.as_bytes()).unwrap(), Mat64::new(2, 3, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]));
+        let text = "%%MatrixMarket matrix array integer skew-symmetric\n2 2\n3\n";
+        assert_eq!(read_matrix_market(text.as_bytes()).unwrap(), mat64![0.0, -3.0; 3.0, 0.0]);
+        let text = "%%MatrixMarket matrix array real symmetric\n2 2\n1\n2\n";
+        let err = read_matrix_market(text.as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), JolinErrorKind::NotEnoughInput);
+    }
+
+    #[test]
+    fn test_read_coordinate() {
+        let text = "%%MatrixMarket matrix coordinate pattern general\n3 2 3\n1 1\n3 2\n3 2\n";
+        let coo = read_matrix_market_coordinate(text.as_bytes()).unwrap();
+        assert_eq!((coo.row, coo.column, coo.entries.len()), (3, 2, 3));
+        assert_eq!(coo.entries[1], (2, 1, 1.0));
+        // duplicates are summed
+        assert_eq!(read_matrix_market(text.as_bytes()).unwrap(), mat64![1.0, 0.0; 0.0, 0.0; 0.0, 2.0]);
+
+        for bad in [
+            "%%MatrixMarket matrix coordinate complex general\n1 1 1\n1 1 1 0\n",
+            "%%MatrixMarket matrix coordinate real general\n2 2 1\n3 1 1.0\n",
+            "%%MatrixMarket matrix coordinate real general\n2 2\n",
+            "%%MatrixMarket matrix coordinate real general\n2 2 1\n1 1 x\n",
+            "%%MatrixMarket vector coordinate real general\n2 2 1\n1 1 1\n",
+            "1 1 1\n1 1 1\n",
+        ] {
+            assert_eq!(read_matrix_market(bad.as_bytes()).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        }
+        let array = "%%MatrixMarket matrix array real general\n1 1\n1\n";
+        assert!(read_matrix_market_coordinate(array.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_read_hostile_size() {
+        let huge = usize::MAX;
+        let text = format!("%%MatrixMarket matrix coordinate real general\n2 2 {}\n1 1 1.0\n", huge);
+        assert_eq!(read_matrix_market(text.as_bytes()).unwrap_err().kind(), JolinErrorKind::NotEnoughInput);
+        let text = format!("%%MatrixMarket matrix coordinate real general\n{} {} 1\n1 1 1.0\n", huge, huge);
+        assert_eq!(read_matrix_market_coordinate(text.as_bytes()).unwrap().entries, vec![(0, 0, 1.0)]);
+        assert_eq!(read_matrix_market(text.as_bytes()).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        // 8 TB if it were allocated
+        let text = "%%MatrixMarket matrix coordinate real general\n1000000 1000000 0\n";
+        assert_eq!(read_matrix_market(text.as_bytes()).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        assert!(read_matrix_market_coordinate(text.as_bytes()).unwrap().entries.is_empty());
+        let text = format!("%%MatrixMarket matrix array real general\n{} {}\n1\n", huge, huge);
+        assert_eq!(read_matrix_market(text.as_bytes()).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        let text = "%%MatrixMarket matrix array real general\n1099511627776 1024\n1\n";
+        assert_eq!(read_matrix_market(text.as_bytes()).unwrap_err().kind(), JolinErrorKind::NotEnoughInput);
+    }
+
+    #[test]
+    fn test_write_matrix_market() {
+        let a = mat64![1.5, 0.0, -2.0; 0.0, 1e-20, 0.0];
+        for format in [MatrixMarketFormat::Array, MatrixMarketFormat::Coordinate] {
+            let mut out = Vec::new();
+            write_matrix_market(&a, format, &mut out).unwrap();
+            assert_eq!(read_matrix_market(out.as_slice()).unwrap(), a);
+        }
+        let mut out = Vec::new();
+        write_matrix_market(&a, MatrixMarketFormat::Coordinate, &mut out).unwrap();
+        assert!(String::from_utf8(out).unwrap().contains("\n2 3 3\n"));
+    }
+}
@@ -0,0 +1,311 @@
+/*
+ * io/mmap.rs
+ * Memory-mapped matrices and out-of-core multiplication.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+use memmap2::{Mmap, MmapMut};
+use crate::matrix::{Matrix, MatrixView, Mat64, gemm};
+use crate::io::binary::{header, parse_header, HEADER_SIZE};
+use crate::error::JolinError;
+use crate::settings;
+
+/// Read-only 64-bit real matrix mapped from a file in the compact binary
+/// format of `jolin::io::binary`, so the operating system pages the data in
+/// on demand and the matrix may be larger than the memory. The file must hold
+/// `f64` elements in column-major layout, and must not be modified while it
+/// is mapped.
+///
+/// It implements the read-only `MatrixView`, so it can be passed to functions
+/// which only read their input, like those of `jolin::checks`. It can't be a
+/// `Matrix`, which needs mutable data and constructors of matrices in memory.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::io::binary::to_bytes;
+/// # use jolin::io::mmap::MmapMat64;
+/// # use jolin::checks::is_finite;
+/// let path = std::env::temp_dir().join("jolin_doc_mmap_open.bin");
+/// std::fs::write(&path, to_bytes(&mat64![1.0, 2.0; 3.0, 4.0])).unwrap();
+/// // SAFETY: the file is not modified while it is mapped.
+/// let a = unsafe { MmapMat64::open(&path) }.unwrap();
+/// assert_eq!(a.row(), 2);
+/// assert_eq!(a.data_column(1), &[2.0, 4.0]);
+/// assert_eq!(a.elem(1, 0), 3.0);
+/// assert!(is_finite(&a));
+/// assert_eq!(a.to_mat64(), mat64![1.0, 2.0; 3.0, 4.0]);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct MmapMat64 {
+    row: usize,
+    column: usize,
+    map: Mmap,
+}
+
+impl MmapMat64 {
+    /// Map the matrix in the file at `path`.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified, truncated or replaced in place, by this
+    /// or another process, while the matrix or any slice borrowed from it is
+    /// alive. Otherwise the data may change under shared references, or the
+    /// access may fail with a bus error. Replacing the file by renaming
+    /// another one over it is fine.
+    ///
+    /// Potential errors:
+    /// 1. IO error - if the file can't be opened or mapped.
+    /// 2. Invalid value - if the header is malformed, the elements are not
+    ///    `f64` in column-major layout, the length of the file doesn't match
+    ///    the shape, or the target is big-endian.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<MmapMat64, JolinError> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is read-only, and the caller keeps the file
+        // unchanged while it is mapped, as required by this function.
+        let map = unsafe { Mmap::map(&file)? };
+        let header = parse_header(&map)?;
+        if cfg!(target_endian = "big") || header.size != 8 || header.row_major
+            || map.len() - HEADER_SIZE != header.data_len()? {
+            return Err(JolinError::invalid_value())
+        }
+        Ok(MmapMat64 { row: header.row, column: header.column, map })
+    }
+
+    /// Copy the block of `rows` rows and `columns` columns from row `r0` and
+    /// column `c0` into memory.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the block exceeds the matrix.
+    pub fn block(&self, r0: usize, c0: usize, rows: usize, columns: usize) -> Result<Mat64, JolinError> {
+        copy_block(self, r0, c0, rows, columns)
+    }
+
+    /// Copy the whole matrix into memory.
+    pub fn to_mat64(&self) -> Mat64 {
+        Mat64::new(self.row, self.column, self.data())
+    }
+}
+
+impl MatrixView for MmapMat64 {
+    type Elem = f64;
+
+    fn row(&self) -> usize {
+        self.row
+    }
+
+    fn column(&self) -> usize {
+        self.column
+    }
+
+    fn data(&self) -> &[f64] {
+        // SAFETY: every bit pattern is a valid f64, and the data starts 24
+        // bytes after the page-aligned start of the mapping, so it is aligned.
+        let (prefix, data, _) = unsafe { self.map[HEADER_SIZE..].align_to::<f64>() };
+        debug_assert!(prefix.is_empty());
+        data
+    }
+}
+
+/// Copy the block of `rows` rows and `columns` columns from row `r0` and
+/// column `c0` of `a` into memory.
+fn copy_block<A: MatrixView<Elem = f64>>(a: &A, r0: usize, c0: usize, rows: usize, columns: usize) -> Result<Mat64, JolinError> {
+    if r0 + rows > a.row() || c0 + columns > a.column() {
+        return Err(JolinError::shape_mismatching())
+    }
+    let mut data = Vec::with_capacity(rows * columns);
+    for c in c0..(c0 + columns) {
+        data.extend_from_slice(&a.data_column(c)[r0..(r0 + rows)]);
+    }
+    Ok(Mat64::from_vec(rows, columns, data))
+}
+
+/// Multiply two matrices, usually mapped ones, and write the product to a
+/// new file at `path` in the compact binary format, then map it. The product
+/// is computed block by block with the block sizes `block_rows` and
+/// `block_depth` of `jolin::settings`, so only a few blocks of each matrix are
+/// in memory at a time.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::io::binary::to_bytes;
+/// # use jolin::io::mmap::{MmapMat64, mul_to_file};
+/// let dir = std::env::temp_dir();
+/// std::fs::write(dir.join("jolin_doc_mmap_a.bin"), to_bytes(&mat64![1.0, 2.0; 3.0, 4.0])).unwrap();
+/// std::fs::write(dir.join("jolin_doc_mmap_b.bin"), to_bytes(&mat64![1.0; 1.0])).unwrap();
+/// // SAFETY: the files are not modified while they are mapped.
+/// let a = unsafe { MmapMat64::open(dir.join("jolin_doc_mmap_a.bin")) }.unwrap();
+/// let b = unsafe { MmapMat64::open(dir.join("jolin_doc_mmap_b.bin")) }.unwrap();
+/// let c = unsafe { mul_to_file(&a, &b, dir.join("jolin_doc_mmap_c.bin")) }.unwrap();
+/// assert_eq!(c.to_mat64(), mat64![3.0; 7.0]);
+/// # for name in ["a", "b", "c"] {
+/// #     std::fs::remove_file(dir.join(format!("jolin_doc_mmap_{}.bin", name))).unwrap();
+/// # }
+/// ```
+///
+/// The product is written to a temporary file in the directory of `path`,
+/// which is then renamed to `path`, so `path` may be the file of `a` or `b`:
+/// their mappings keep the old content. On platforms where a mapped file
+/// can't be replaced, this is an IO error.
+///
+/// # Safety
+///
+/// The answer maps the file at `path`, so the requirement of
+/// `MmapMat64::open` applies to it.
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the column count of `a` is not the row count of `b`.
+/// 2. IO error - if the file can't be created, resized, mapped or renamed.
+/// 3. Invalid value - if `path` has no file name.
+pub unsafe fn mul_to_file<A, B, P>(a: &A, b: &B, path: P) -> Result<MmapMat64, JolinError>
+where A: MatrixView<Elem = f64>, B: MatrixView<Elem = f64>, P: AsRef<Path> {
+    let s = settings::get();
+    // SAFETY: the requirement on the answer is passed on to the caller.
+    unsafe { mul_blocked(a, b, path, s.block_rows, s.block_depth) }
+}
+
+/// `mul_to_file` with blocks of the product of `nb` by `nb`, summed over `kb`
+/// columns of `a` at a time, under the same requirement.
+unsafe fn mul_blocked<A, B, P>(a: &A, b: &B, path: P, nb: usize, kb: usize) -> Result<MmapMat64, JolinError>
+where A: MatrixView<Elem = f64>, B: MatrixView<Elem = f64>, P: AsRef<Path> {
+    if a.column() != b.row() {
+        return Err(JolinError::shape_mismatching_with((a.column(), b.column()), (b.row(), b.column())))
+    }
+    let (m, n) = (a.row(), b.column());
+    let len = m.checked_mul(n).and_then(|x| x.checked_mul(8))
+        .ok_or(JolinError::invalid_value())?;
+    // The product is written to a new file next to `path` and renamed over
+    // it at last, so `path` may be the file behind `a`, `b` or another live
+    // mapping, which then keeps the old content.
+    let path = path.as_ref();
+    let name = path.file_name().ok_or(JolinError::invalid_value())?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp = path.with_file_name(temp_name);
+    let file = OpenOptions::new().read(true).write(true).create_new(true).open(&temp)?;
+    let written = file.set_len((HEADER_SIZE + len) as u64).map_err(JolinError::from)
+        .and_then(|_| write_product(a, b, &file, nb, kb))
+        .and_then(|_| Ok(std::fs::rename(&temp, path)?));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    written?;
+    // SAFETY: the requirement on the answer is passed on to the caller.
+    unsafe { MmapMat64::open(path) }
+}
+
+/// Write the header and the product of `a` and `b` to `file`, which has the
+/// length of the product.
+fn write_product<A, B>(a: &A, b: &B, file: &File, nb: usize, kb: usize) -> Result<(), JolinError>
+where A: MatrixView<Elem = f64>, B: MatrixView<Elem = f64> {
+    let (m, k, n) = (a.row(), a.column(), b.column());
+    // SAFETY: the file was created by `mul_blocked` with `create_new` under a
+    // name private to this process, and is only accessed through this mapping
+    // until it is dropped.
+    let mut out = unsafe { MmapMut::map_mut(file)? };
+    out[..HEADER_SIZE].copy_from_slice(&header(m, n, 8));
+    let (nb, kb) = (usize::max(nb, 1), usize::max(kb, 1));
+    for j0 in (0..n).step_by(nb) {
+        let nj = usize::min(nb, n - j0);
+        for i0 in (0..m).step_by(nb) {
+            let mi = usize::min(nb, m - i0);
+            let mut c = Mat64::zero(mi, nj);
+            for p0 in (0..k).step_by(kb) {
+                let kp = usize::min(kb, k - p0);
+                let (ab, bb) = (copy_block(a, i0, p0, mi, kp)?, copy_block(b, p0, j0, kp, nj)?);
+                gemm(1.0, &ab, false, &bb, false, 1.0, &mut c)?;
+            }
+            for j in 0..nj {
+                for (i, x) in c.data_column(j).iter().enumerate() {
+                    let offset = HEADER_SIZE + ((j0 + j) * m + i0 + i) * 8;
+                    out[offset..(offset + 8)].copy_from_slice(&x.to_le_bytes());
+                }
+            }
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+    use crate::io::binary::to_bytes;
+    use crate::io::mmap::{*};
+    use crate::checks::has_nan;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("jolin_test_mmap_{}_{}.bin", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_mmap_open() {
+        let a = mat64![1.0, 2.0, 3.0; 4.0, 5.0, 6.0];
+        let path = temp_path("open");
+        std::fs::write(&path, to_bytes(&a)).unwrap();
+        let mapped = unsafe { MmapMat64::open(&path) }.unwrap();
+        assert_eq!((mapped.row(), mapped.column()), (2, 3));
+        assert_eq!(mapped.data(), a.data());
+        assert_eq!(mapped.block(0, 1, 2, 2).unwrap(), mat64![2.0, 3.0; 5.0, 6.0]);
+        assert_eq!(mapped.block(1, 2, 1, 2).unwrap_err().kind(), JolinErrorKind::ShapeMismatching);
+        assert!(!has_nan(&mapped));
+        assert_eq!(find(&mapped, |x| *x > 4.5), vec![(1, 1), (1, 2)]);
+        drop(mapped);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(unsafe { MmapMat64::open(&path) }.err().unwrap().kind(), JolinErrorKind::IOError);
+
+        // malformed files are rejected before any element is read
+        let path = temp_path("open_f32");
+        std::fs::write(&path, to_bytes(&Mat32::new(1, 1, &[1.0]))).unwrap();
+        assert_eq!(unsafe { MmapMat64::open(&path) }.err().unwrap().kind(), JolinErrorKind::InvalidValue);
+        std::fs::remove_file(&path).unwrap();
+        let path = temp_path("open_truncated");
+        let mut truncated = to_bytes(&a);
+        truncated.pop();
+        std::fs::write(&path, truncated).unwrap();
+        assert_eq!(unsafe { MmapMat64::open(&path) }.err().unwrap().kind(), JolinErrorKind::InvalidValue);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_mul_to_file() {
+        let data: Vec<f64> = (0..(7 * 5)).map(|i| (i as f64 * 0.7).sin()).collect();
+        let a = Mat64::new(7, 5, &data);
+        let b = Mat64::new(5, 6, &data[..30]);
+        let (path_a, path_b, path_c) = (temp_path("a"), temp_path("b"), temp_path("c"));
+        std::fs::write(&path_a, to_bytes(&a)).unwrap();
+        std::fs::write(&path_b, to_bytes(&b)).unwrap();
+        let ma = unsafe { MmapMat64::open(&path_a) }.unwrap();
+        let mb = unsafe { MmapMat64::open(&path_b) }.unwrap();
+
+        let expected = mul(&a, &b).unwrap();
+        let c = unsafe { mul_to_file(&ma, &mb, &path_c) }.unwrap();
+        assert!(allclose(&c.to_mat64(), &expected, 0.0, 1e-12));
+        // blocks smaller than the matrices
+        let c = unsafe { mul_blocked(&ma, &mb, &path_c, 3, 2) }.unwrap();
+        assert!(allclose(&c.to_mat64(), &expected, 0.0, 1e-12));
+        // a matrix in memory as an operand
+        let c = unsafe { mul_blocked(&ma, &b, &path_c, 2, 2) }.unwrap();
+        assert!(allclose(&c.to_mat64(), &expected, 0.0, 1e-12));
+        assert_eq!(unsafe { mul_to_file(&mb, &mb, &path_c) }.err().unwrap().kind(), JolinErrorKind::ShapeMismatching);
+
+        // the output replaces the file of an input, whose mapping is intact
+        let square = Mat64::new(5, 5, &data[..25]);
+        let path_s = temp_path("square");
+        std::fs::write(&path_s, to_bytes(&square)).unwrap();
+        let ms = unsafe { MmapMat64::open(&path_s) }.unwrap();
+        let c = unsafe { mul_blocked(&ms, &ms, &path_s, 2, 3) }.unwrap();
+        assert_eq!(ms.to_mat64(), square);
+        assert!(allclose(&c.to_mat64(), &mul(&square, &square).unwrap(), 0.0, 1e-12));
+        assert_eq!(unsafe { MmapMat64::open(&path_s) }.unwrap().to_mat64(), c.to_mat64());
+        for path in [path_a, path_b, path_c, path_s] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+}
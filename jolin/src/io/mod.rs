@@ -0,0 +1,27 @@
+/*
+ * io/mod.rs
+ * Root of reading and writing matrices in file formats
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+/// Compact binary format for checkpointing
+pub mod binary;
+
+/// Comma-separated values
+pub mod csv;
+
+/// Matrix Market exchange format
+pub mod matrix_market;
+
+/// NumPy .npy format
+pub mod npy;
+
+/// MATLAB .mat (v5) format
+#[cfg(feature = "matfile")]
+pub mod matfile;
+
+/// Memory-mapped matrices and out-of-core multiplication
+#[cfg(feature = "mmap")]
+pub mod mmap;
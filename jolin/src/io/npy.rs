@@ -0,0 +1,265 @@
+/*
+ * io/npy.rs
+ * Reading and writing matrices in NumPy's .npy format.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use crate::matrix::{Matrix, LikeNumber};
+use crate::error::JolinError;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+/// Longest header accepted by `read_npy`, which is the limit of version 1.0
+const MAX_HEADER_LEN: usize = 65535;
+
+/// Read a matrix from a .npy file. Arrays of `f8`, `f4`, `i8` and `i4` in
+/// either byte order are converted to the element type of the matrix, and
+/// both C and Fortran order are supported. A 1-D array is read as a column
+/// vector.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::io::npy::{read_npy, write_npy};
+/// let a = mat64![1.0, 2.0; 3.0, 4.0];
+/// let mut bytes = Vec::new();
+/// write_npy(&a, &mut bytes).unwrap();
+/// let b: Mat32 = read_npy(bytes.as_slice()).unwrap();
+/// assert_eq!(b, Mat32::new(2, 2, &[1.0, 3.0, 2.0, 4.0]));
+/// ```
+///
+/// Potential errors:
+/// 1. IO error - if reading fails.
+/// 2. Invalid value - if the file is malformed, or the data type or the
+///    dimension is not supported.
+/// 3. Not enough input - if the header or the data is shorter than declared.
+pub fn read_npy<T: Matrix, R: Read>(reader: R) -> Result<T, JolinError> {
+    let mut reader = BufReader::new(reader);
+    let mut prefix = [0u8; 8];
+    reader.read_exact(&mut prefix)?;
+    if &prefix[0..6] != MAGIC {
+        return Err(JolinError::invalid_value())
+    }
+    let header_len = match prefix[6] {
+        1 => {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len)?;
+            u32::from_le_bytes(len) as usize
+        }
+        _ => return Err(JolinError::invalid_value()),
+    };
+    if header_len > MAX_HEADER_LEN {
+        return Err(JolinError::invalid_value())
+    }
+    let mut header = Vec::new();
+    (&mut reader).take(header_len as u64).read_to_end(&mut header)?;
+    if header.len() < header_len {
+        return Err(JolinError::not_enough_input())
+    }
+    let header = String::from_utf8(header).map_err(|_| JolinError::invalid_value())?;
+
+    let descr = header_value(&header, "descr")?;
+    let descr = descr.trim_matches(|ch| ch == '\'' || ch == '"');
+    let fortran_order = match header_value(&header, "fortran_order")? {
+        "True" => true,
+        "False" => false,
+        _ => return Err(JolinError::invalid_value()),
+    };
+    let shape = header_value(&header, "shape")?;
+    let dims = shape.trim_start_matches('(').trim_end_matches(')').split(',')
+        .map(|s| s.trim()).filter(|s| !s.is_empty())
+        .map(|s| s.parse::<usize>().map_err(|_| JolinError::invalid_value()))
+        .collect::<Result<Vec<usize>, JolinError>>()?;
+    let (row, column) = match dims.len() {
+        1 => (dims[0], 1),
+        2 => (dims[0], dims[1]),
+        _ => return Err(JolinError::invalid_value()),
+    };
+
+    let (little_endian, kind) = match (descr.get(..1), descr.get(1..)) {
+        (Some("<"), Some(kind)) | (Some("|"), Some(kind)) => (true, kind),
+        (Some(">"), Some(kind)) => (false, kind),
+        _ => return Err(JolinError::invalid_value()),
+    };
+    let size = match kind {
+        "f8" | "i8" => 8,
+        "f4" | "i4" => 4,
+        _ => return Err(JolinError::invalid_value()),
+    };
+    let len = row.checked_mul(column).and_then(|n| n.checked_mul(size))
+        .ok_or(JolinError::invalid_value())?;
+    // the buffer grows with the data actually read, not the claimed shape
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() < len {
+        return Err(JolinError::not_enough_input())
+    }
+    let values: Vec<T::Elem> = bytes.chunks_exact(size).map(|b| {
+        let mut buf = [0u8; 8];
+        buf[..size].copy_from_slice(b);
+        if !little_endian {
+            buf[..size].reverse();
+        }
+        let v = match kind {
+            "f8" => f64::from_le_bytes(buf),
+            "i8" => i64::from_le_bytes(buf) as f64,
+            "f4" => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            _ => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+        };
+        T::Elem::one().times_real(v)
+    }).collect();
+    if fortran_order || column == 1 {
+        return T::try_from_vec(row, column, values)
+    }
+    let mut ans = T::zero(row, column);
+    for (i, v) in values.into_iter().enumerate() {
+        *ans.elem_mut(i / column, i % column) = v;
+    }
+    Ok(ans)
+}
+
+/// Read a matrix from a .npy file, see `read_npy`.
+///
+/// Potential errors:
+/// 1. IO error - if the file can't be read.
+/// 2. Invalid value - if the file is malformed or not supported.
+/// 3. Not enough input - if the header or the data is shorter than declared.
+pub fn read_npy_file<T: Matrix, P: AsRef<Path>>(path: P) -> Result<T, JolinError> {
+    read_npy(File::open(path)?)
+}
+
+/// Write a matrix as a 2-D .npy array in Fortran order, with the data type
+/// `<f8` for `Mat64` and `<f4` for `Mat32`.
+///
+/// Potential errors:
+/// 1. IO error - if writing fails.
+pub fn write_npy<T: Matrix, W: Write>(mat: &T, writer: W) -> Result<(), JolinError> {
+    let mut w = BufWriter::new(writer);
+    let size = std::mem::size_of::<T::Elem>();
+    let mut header = format!(
+        "{{'descr': '<f{}', 'fortran_order': True, 'shape': ({}, {}), }}",
+        size, mat.row(), mat.column()
+    );
+    // the data starts at a multiple of 64 bytes, after the magic string,
+    // the version, the header length and the header ending with '\n'
+    let unpadded = MAGIC.len() + 4 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - unpadded % 64) % 64));
+    header.push('\n');
+    w.write_all(MAGIC)?;
+    w.write_all(&[1, 0])?;
+    w.write_all(&(header.len() as u16).to_le_bytes())?;
+    w.write_all(header.as_bytes())?;
+    for x in mat.data().iter() {
+        if size == 8 {
+            w.write_all(&x.to_f64().to_le_bytes())?;
+        } else {
+            w.write_all(&(x.to_f64() as f32).to_le_bytes())?;
+        }
+    }
+    w.flush()?;
+    Ok(())
+}
+
+/// Write a matrix to a .npy file, see `write_npy`.
+///
+/// Potential errors:
+/// 1. IO error - if the file can't be written.
+pub fn write_npy_file<T: Matrix, P: AsRef<Path>>(mat: &T, path: P) -> Result<(), JolinError> {
+    write_npy(mat, File::create(path)?)
+}
+
+/// The value of a key in the header, which is a Python dict literal
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str, JolinError> {
+    let quoted = format!("'{}'", key);
+    let start = header.find(&quoted).ok_or(JolinError::invalid_value())? + quoted.len();
+    let rest = header[start..].trim_start().strip_prefix(':').ok_or(JolinError::invalid_value())?.trim_start();
+    let end = if rest.starts_with('(') {
+        rest.find(')').ok_or(JolinError::invalid_value())? + 1
+    } else {
+        rest.find([',', '}']).ok_or(JolinError::invalid_value())?
+    };
+    Ok(rest[..end].trim())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+    use crate::io::npy::{*};
+
+    fn npy_bytes(header: &str, data: &[u8]) -> Vec<u8> {
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn test_read_npy_c_order() {
+        let data: Vec<u8> = [1i32, 2, 3, 4, 5, 6].iter().flat_map(|v| v.to_be_bytes()).collect();
+        let bytes = npy_bytes("{'descr': '>i4', 'fortran_order': False, 'shape': (2, 3), }\n", &data);
+        let a: Mat64 = read_npy(bytes.as_slice()).unwrap();
+        assert_eq!(a, mat64![1.0, 2.0, 3.0; 4.0, 5.0, 6.0]);
+
+        let data: Vec<u8> = [0.5f32, -1.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        let bytes = npy_bytes("{'descr': '<f4', 'fortran_order': False, 'shape': (2,), }\n", &data);
+        let v: Mat64 = read_npy(bytes.as_slice()).unwrap();
+        assert_eq!(v, mat64![0.5; -1.0]);
+    }
+
+    #[test]
+    fn test_npy_round_trip() {
+        let a = mat64![1.0, -2.5, 3.25; 1e-300, f64::MAX, 0.0];
+        let mut bytes = Vec::new();
+        write_npy(&a, &mut bytes).unwrap();
+        assert_eq!((bytes.len() - 6 * 8) % 64, 0);
+        let b: Mat64 = read_npy(bytes.as_slice()).unwrap();
+        assert_eq!(a, b);
+
+        let c = Mat32::new(1, 3, &[1.5, 2.0, -3.0]);
+        let mut bytes = Vec::new();
+        write_npy(&c, &mut bytes).unwrap();
+        assert!(String::from_utf8_lossy(&bytes).contains("'<f4'"));
+        let d: Mat32 = read_npy(bytes.as_slice()).unwrap();
+        assert_eq!(c, d);
+    }
+
+    #[test]
+    fn test_read_npy_errors() {
+        let bad_type = npy_bytes("{'descr': '<c16', 'fortran_order': False, 'shape': (1,), }\n", &[0; 16]);
+        assert_eq!(read_npy::<Mat64, _>(bad_type.as_slice()).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        let three_dims = npy_bytes("{'descr': '<f8', 'fortran_order': False, 'shape': (1, 1, 1), }\n", &[0; 8]);
+        assert!(read_npy::<Mat64, _>(three_dims.as_slice()).is_err());
+        let no_type = npy_bytes("{'descr': '', 'fortran_order': False, 'shape': (1,), }\n", &[0; 8]);
+        assert!(read_npy::<Mat64, _>(no_type.as_slice()).is_err());
+        let short = npy_bytes("{'descr': '<f8', 'fortran_order': True, 'shape': (2, 1), }\n", &[0; 8]);
+        assert_eq!(read_npy::<Mat64, _>(short.as_slice()).unwrap_err().kind(), JolinErrorKind::NotEnoughInput);
+        assert_eq!(read_npy::<Mat64, _>(&b"not a npy file"[..]).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn test_read_npy_hostile_header() {
+        let overflow = format!("{{'descr': '<f8', 'fortran_order': True, 'shape': ({}, {}), }}\n", usize::MAX, 2);
+        let bytes = npy_bytes(&overflow, &[0; 8]);
+        assert_eq!(read_npy::<Mat64, _>(bytes.as_slice()).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        // the claimed data is not allocated before it is read
+        let huge = npy_bytes("{'descr': '<f8', 'fortran_order': True, 'shape': (1099511627776, 1024), }\n", &[0; 8]);
+        assert_eq!(read_npy::<Mat64, _>(huge.as_slice()).unwrap_err().kind(), JolinErrorKind::NotEnoughInput);
+
+        let mut long_header = b"\x93NUMPY\x02\x00".to_vec();
+        long_header.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(read_npy::<Mat64, _>(long_header.as_slice()).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        let truncated = &npy_bytes("{'descr': '<f8', 'fortran_order': True, 'shape': (1,), }\n", &[])[..20];
+        assert_eq!(read_npy::<Mat64, _>(truncated).unwrap_err().kind(), JolinErrorKind::NotEnoughInput);
+    }
+}
@@ -0,0 +1,655 @@
+/*
+ * iterative.rs
+ * Iterative eigenvalue algorithms.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber, mul, trmul};
+use crate::error::JolinError;
+use crate::decomp::lu::lu;
+use crate::decomp::eig::{eig_symmetric, EigenDecomposition};
+use crate::operator::LinearOperator;
+
+/// Maximal count of iterations of the orthogonal iteration
+const MAX_ORTHOGONAL_ITERATIONS: usize = 10000;
+
+/// An approximate invariant subspace `A Q = Q H`
+#[derive(Debug, Clone)]
+pub struct InvariantSubspace<T: Matrix> {
+    /// Orthonormal basis `Q` of the subspace stored as columns
+    pub basis: T,
+    /// Projection `H = Q^T A Q` of the matrix onto the subspace. For a
+    /// symmetric matrix, its eigenvalues approximate the dominant eigenvalues.
+    pub projection: T,
+    /// Count of iterations
+    pub iterations: usize,
+    /// Whether the iteration converged within the iteration limit
+    pub converged: bool,
+}
+
+/// Find the dominant `k`-dimensional invariant subspace of a square matrix
+/// with orthogonal iteration, i.e. the block power method with a QR
+/// re-orthonormalization in every step.
+///
+/// The iteration stops once the residual satisfies
+/// `|A Q - Q H|_F <= tol * |A Q|_F`. The convergence rate depends on the gap
+/// `|lambda_{k+1}| / |lambda_k|` between the eigenvalues inside and outside
+/// the subspace.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::iterative::orthogonal_iteration;
+/// let a = mat64![5.0, 0.0, 0.0; 0.0, 1.0, 0.0; 0.0, 0.0, 3.0];
+/// let ans = orthogonal_iteration(&a, 2, 1e-12).unwrap();
+/// assert!(ans.converged);
+/// // the subspace is spanned by e_0 and e_2
+/// assert!(ans.basis.elem(1, 0).abs() < 1e-10 && ans.basis.elem(1, 1).abs() < 1e-10);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the matrix is not square.
+/// 2. Invalid value - if `k` is zero or larger than the size of the matrix.
+pub fn orthogonal_iteration<T: Matrix>(mat: &T, k: usize, tol: T::Elem) -> Result<InvariantSubspace<T>, JolinError> {
+    if mat.row() != mat.column() {
+        return Err(JolinError::shape_mismatching())
+    }
+    let n = mat.row();
+    if k == 0 || k > n {
+        return Err(JolinError::invalid_value())
+    }
+    let mut q = start_block::<T>(n, k);
+    orthonormalize_columns(&mut q);
+    for iterations in 1..(MAX_ORTHOGONAL_ITERATIONS + 1) {
+        let z = mul(mat, &q)?;
+        let h = trmul(&q, &z)?;
+        let qh = mul(&q, &h)?;
+        let residual = frobenius_norm(z.data().iter().zip(qh.data().iter()).map(|(a, b)| *a - *b));
+        if residual <= tol * frobenius_norm(z.data().iter().cloned()) {
+            return Ok(InvariantSubspace { basis: q, projection: h, iterations, converged: true })
+        }
+        q = z;
+        orthonormalize_columns(&mut q);
+    }
+    let z = mul(mat, &q)?;
+    let h = trmul(&q, &z)?;
+    Ok(InvariantSubspace { basis: q, projection: h, iterations: MAX_ORTHOGONAL_ITERATIONS, converged: false })
+}
+
+/// An approximate eigenpair `A v = lambda v`
+#[derive(Debug, Clone)]
+pub struct EigenPair<T: Matrix> {
+    /// Eigenvalue `lambda`
+    pub value: T::Elem,
+    /// Eigenvector `v` of unit length as a column vector
+    pub vector: T,
+    /// Count of iterations
+    pub iterations: usize,
+    /// Whether the iteration converged within the iteration limit
+    pub converged: bool,
+}
+
+/// Find the dominant eigenvalue, i.e. the one of the largest absolute value,
+/// and its eigenvector with power iteration. The eigenvalue is estimated by
+/// the Rayleigh quotient.
+///
+/// The iteration stops once `|A v - lambda v| <= tol * |A v|`, or after
+/// `max_iter` iterations. It converges at the rate `|lambda_2 / lambda_1|`,
+/// and doesn't converge if two dominant eigenvalues have the same absolute
+/// value, e.g. `1` and `-1`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::iterative::power_iteration;
+/// let a = mat64![2.0, 1.0; 1.0, 2.0];
+/// let ans = power_iteration(&a, 1e-12, 1000).unwrap();
+/// assert!(ans.converged);
+/// assert!((ans.value - 3.0).abs() < 1e-10);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the matrix is not square.
+/// 2. Invalid value - if the matrix is empty.
+pub fn power_iteration<T: Matrix>(mat: &T, tol: T::Elem, max_iter: usize) -> Result<EigenPair<T>, JolinError> {
+    if mat.row() != mat.column() {
+        return Err(JolinError::shape_mismatching())
+    }
+    let n = mat.row();
+    if n == 0 {
+        return Err(JolinError::invalid_value())
+    }
+    let mut v = start_block::<T>(n, 1);
+    orthonormalize_columns(&mut v);
+    let mut value = T::Elem::zero();
+    for iterations in 1..(max_iter + 1) {
+        let w = mul(mat, &v)?;
+        value = dot_of_columns(&v, &w);
+        let norm = norm_of_column(&w, 0);
+        let residual = frobenius_norm(w.data().iter().zip(v.data().iter()).map(|(a, b)| *a - value * *b));
+        if residual <= tol * norm {
+            return Ok(EigenPair { value, vector: v, iterations, converged: true })
+        }
+        v = w;
+        for x in v.data_mut().iter_mut() {
+            *x = *x / norm;
+        }
+    }
+    Ok(EigenPair { value, vector: v, iterations: max_iter, converged: false })
+}
+
+/// Find the eigenvalue nearest to `shift` and its eigenvector with inverse
+/// iteration, i.e. power iteration on `(A - shift I)^-1`. The matrix
+/// `A - shift I` is factorized once and reused in every iteration, and the
+/// eigenvalue is estimated by the Rayleigh quotient of `A`.
+///
+/// The iteration stops once `|A v - lambda v| <= tol * |A v|`, or after
+/// `max_iter` iterations. If `shift` is exactly an eigenvalue, it's moved
+/// by a rounding error so that the factorization exists.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::iterative::inverse_power_iteration;
+/// let a = mat64![2.0, 1.0; 1.0, 2.0];
+/// let ans = inverse_power_iteration(&a, 0.5, 1e-12, 1000).unwrap();
+/// assert!(ans.converged);
+/// assert!((ans.value - 1.0).abs() < 1e-10);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the matrix is not square.
+/// 2. Invalid value - if the matrix is empty.
+pub fn inverse_power_iteration<T: Matrix>(mat: &T, shift: T::Elem, tol: T::Elem, max_iter: usize) -> Result<EigenPair<T>, JolinError> {
+    if mat.row() != mat.column() {
+        return Err(JolinError::shape_mismatching())
+    }
+    let n = mat.row();
+    if n == 0 {
+        return Err(JolinError::invalid_value())
+    }
+    let lud = match lu(&shifted(mat, shift)) {
+        Ok(lud) => lud,
+        Err(_err) => {
+            let scale = mat.data().iter().fold(shift.abs(), |acc, x| if x.abs() > acc { x.abs() } else { acc });
+            let nudge = if scale == T::Elem::zero() { T::Elem::one() } else { scale };
+            lu(&shifted(mat, shift + nudge * T::Elem::epsilon().times_real(n as f64)))?
+        }
+    };
+    let mut v = start_block::<T>(n, 1);
+    orthonormalize_columns(&mut v);
+    let mut value = shift;
+    for iterations in 1..(max_iter + 1) {
+        let av = mul(mat, &v)?;
+        value = dot_of_columns(&v, &av);
+        let residual = frobenius_norm(av.data().iter().zip(v.data().iter()).map(|(a, b)| *a - value * *b));
+        if residual <= tol * norm_of_column(&av, 0) {
+            return Ok(EigenPair { value, vector: v, iterations, converged: true })
+        }
+        v = lud.solve(&v)?;
+        let norm = norm_of_column(&v, 0);
+        for x in v.data_mut().iter_mut() {
+            *x = *x / norm;
+        }
+    }
+    Ok(EigenPair { value, vector: v, iterations: max_iter, converged: false })
+}
+
+/// The end of the spectrum wanted by an iterative eigensolver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Extreme {
+    /// The algebraically largest eigenvalues
+    Largest,
+    /// The algebraically smallest eigenvalues
+    Smallest,
+}
+
+/// Find `k` extreme eigenvalues and their eigenvectors of a symmetric
+/// operator with the Lanczos method. Only the products of the operator with
+/// vectors are needed, so it suits matrices too large to factorize.
+///
+/// The Krylov basis is fully reorthogonalized in every step to avoid the
+/// spurious copies of eigenvalues of the plain Lanczos recurrence. The
+/// iteration stops once the residual `|A y - theta y|` of each wanted Ritz
+/// pair is at most `tol` times the largest absolute Ritz value, or the basis
+/// spans the whole space. The answer holds the eigenvalues in ascending
+/// order.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::iterative::{lanczos, Extreme};
+/// let a = mat64![2.0, 1.0, 0.0; 1.0, 2.0, 1.0; 0.0, 1.0, 2.0];
+/// let ans = lanczos(&a, 1, Extreme::Largest, 1e-12).unwrap();
+/// assert!((ans.values[0] - (2.0 + 2.0f64.sqrt())).abs() < 1e-10);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the operator is not square.
+/// 2. Invalid value - if `k` is zero or larger than the size of the operator.
+pub fn lanczos<T: Matrix, O: LinearOperator<T>>(op: &O, k: usize, which: Extreme, tol: T::Elem) -> Result<EigenDecomposition<T>, JolinError> {
+    let (n, n2) = op.shape();
+    if n != n2 {
+        return Err(JolinError::shape_mismatching())
+    }
+    if k == 0 || k > n {
+        return Err(JolinError::invalid_value())
+    }
+    let mut basis: Vec<T> = Vec::with_capacity(n);
+    let mut alpha: Vec<T::Elem> = Vec::with_capacity(n);
+    let mut beta: Vec<T::Elem> = Vec::with_capacity(n);
+    let mut q = start_block::<T>(n, 1);
+    orthonormalize_columns(&mut q);
+    loop {
+        let mut w = op.matvec(&q)?;
+        if (w.row(), w.column()) != (n, 1) {
+            return Err(JolinError::shape_mismatching_with((n, 1), (w.row(), w.column())))
+        }
+        alpha.push(dot_of_columns(&q, &w));
+        basis.push(q);
+        // w is orthogonalized against the whole basis, twice
+        for _pass in 0..2 {
+            for b in basis.iter() {
+                let dot = dot_of_columns(b, &w);
+                for (x, y) in w.data_mut().iter_mut().zip(b.data().iter()) {
+                    *x = *x - dot * *y;
+                }
+            }
+        }
+        let norm = norm_of_column(&w, 0);
+        let m = basis.len();
+        if m >= k && (m == n || (m - k).is_multiple_of(4)) {
+            let ritz = eig_symmetric(&tridiagonal::<T>(&alpha, &beta))?;
+            let wanted: Vec<usize> = match which {
+                Extreme::Largest => ((m - k)..m).collect(),
+                Extreme::Smallest => (0..k).collect(),
+            };
+            let scale = ritz.values.iter().fold(T::Elem::zero(), |acc, v| if v.abs() > acc { v.abs() } else { acc });
+            // the residual of the Ritz pair (theta, V y) is |w| * |y[m - 1]|
+            let converged = wanted.iter().all(|i| norm * ritz.vectors.elem(m - 1, *i).abs() <= tol * scale);
+            if converged || m == n {
+                let mut vectors = T::zero(n, k);
+                for (c, i) in wanted.iter().enumerate() {
+                    for (j, b) in basis.iter().enumerate() {
+                        let y = ritz.vectors.elem(j, *i);
+                        for r in 0..n {
+                            *vectors.elem_mut(r, c) = vectors.elem(r, c) + y * b.elem(r, 0);
+                        }
+                    }
+                }
+                let values = wanted.iter().map(|i| ritz.values[*i]).collect();
+                return Ok(EigenDecomposition { values, vectors })
+            }
+        }
+        if norm <= T::Elem::epsilon().times_real(n as f64) * alpha.iter().fold(T::Elem::zero(), |acc, v| acc + v.abs()) {
+            // the basis spans an invariant subspace, so continue with a new
+            // direction orthogonal to it
+            let mut block = T::zero(n, m + 1);
+            for (j, b) in basis.iter().enumerate() {
+                block.data_mut()[(j * n)..((j + 1) * n)].copy_from_slice(b.data());
+            }
+            block.data_mut()[(m * n)..].copy_from_slice(start_block::<T>(n, 2).data_column(1));
+            orthonormalize_columns(&mut block);
+            q = T::from_vec(n, 1, block.data_column(m).to_vec());
+            beta.push(T::Elem::zero());
+        } else {
+            let mut next = w;
+            for x in next.data_mut().iter_mut() {
+                *x = *x / norm;
+            }
+            q = next;
+            beta.push(norm);
+        }
+    }
+}
+
+/// The answer of Arnoldi iteration `A V_k = V_{k+1} H`
+#[derive(Debug, Clone)]
+pub struct ArnoldiFactorization<T: Matrix> {
+    /// Orthonormal basis `V_{k+1}` of the Krylov space stored as columns
+    pub basis: T,
+    /// Upper Hessenberg matrix `H` of `(k + 1) * k`. It's square with one
+    /// column less in the basis if the iteration stopped early at an
+    /// invariant subspace, where `A V = V H` exactly.
+    pub hessenberg: T,
+}
+
+/// Arnoldi iteration of `k` steps from the vector `start`, which builds an
+/// orthonormal basis of the Krylov space `span{b, A b, ..., A^k b}` and the
+/// projection of the operator onto it. It's the engine of GMRES and of the
+/// eigenvalue estimation of non-symmetric operators, as the eigenvalues of
+/// the square part of `H` approximate the ones of `A`.
+///
+/// The iteration stops early if the Krylov space becomes invariant under the
+/// operator.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::iterative::arnoldi;
+/// let a = mat64![1.0, 2.0, 0.0; 0.0, 3.0, 1.0; 1.0, 0.0, 2.0];
+/// let ans = arnoldi(&a, &mat64![1.0; 0.0; 0.0], 2).unwrap();
+/// assert_eq!((ans.basis.column(), ans.hessenberg.row(), ans.hessenberg.column()), (3, 3, 2));
+/// let av = mul(&a, &Mat64::from_vec(3, 2, ans.basis.data()[..6].to_vec())).unwrap();
+/// assert!(eq_with_error(&av, &mul(&ans.basis, &ans.hessenberg).unwrap(), 1e-12));
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the operator is not square, or `start` is not
+///    a column vector of its size.
+/// 2. Invalid value - if `k` is zero or `start` is zero.
+pub fn arnoldi<T: Matrix, O: LinearOperator<T>>(op: &O, start: &T, k: usize) -> Result<ArnoldiFactorization<T>, JolinError> {
+    let (n, n2) = op.shape();
+    if n != n2 || (start.row(), start.column()) != (n, 1) {
+        return Err(JolinError::shape_mismatching())
+    }
+    let norm = norm_of_column(start, 0);
+    if k == 0 || norm == T::Elem::zero() {
+        return Err(JolinError::invalid_value())
+    }
+    let mut basis: Vec<T::Elem> = start.data().iter().map(|x| *x / norm).collect();
+    let mut h = T::zero(k + 1, k);
+    for j in 0..k {
+        let q = T::from_vec(n, 1, basis[(j * n)..((j + 1) * n)].to_vec());
+        let mut w = op.matvec(&q)?;
+        if (w.row(), w.column()) != (n, 1) {
+            return Err(JolinError::shape_mismatching_with((n, 1), (w.row(), w.column())))
+        }
+        let norm_before = norm_of_column(&w, 0);
+        // modified Gram-Schmidt, twice for the orthogonality in floating point
+        for _pass in 0..2 {
+            for i in 0..(j + 1) {
+                let v = &basis[(i * n)..((i + 1) * n)];
+                let dot: T::Elem = v.iter().zip(w.data().iter()).map(|(a, b)| *a * *b).sum();
+                for (x, y) in w.data_mut().iter_mut().zip(v.iter()) {
+                    *x = *x - dot * *y;
+                }
+                *h.elem_mut(i, j) = h.elem(i, j) + dot;
+            }
+        }
+        let norm = norm_of_column(&w, 0);
+        if norm <= T::Elem::epsilon().times_real(n as f64) * norm_before {
+            let m = j + 1;
+            let hessenberg = T::from_vec(m, m, (0..m).flat_map(|c| h.data_column(c)[..m].to_vec()).collect());
+            return Ok(ArnoldiFactorization { basis: T::from_vec(n, m, basis), hessenberg })
+        }
+        *h.elem_mut(j + 1, j) = norm;
+        basis.extend(w.data().iter().map(|x| *x / norm));
+    }
+    Ok(ArnoldiFactorization { basis: T::from_vec(n, k + 1, basis), hessenberg: h })
+}
+
+/// Symmetric tridiagonal matrix with the diagonal `alpha` and the first
+/// `alpha.len() - 1` elements of `beta` off the diagonal
+fn tridiagonal<T: Matrix>(alpha: &[T::Elem], beta: &[T::Elem]) -> T {
+    let m = alpha.len();
+    let mut t = T::zero(m, m);
+    for i in 0..m {
+        *t.elem_mut(i, i) = alpha[i];
+        if i + 1 < m {
+            *t.elem_mut(i, i + 1) = beta[i];
+            *t.elem_mut(i + 1, i) = beta[i];
+        }
+    }
+    t
+}
+
+/// `A - shift I`
+fn shifted<T: Matrix>(mat: &T, shift: T::Elem) -> T {
+    let mut ans = mat.clone();
+    for i in 0..mat.row() {
+        *ans.elem_mut(i, i) = mat.elem(i, i) - shift;
+    }
+    ans
+}
+
+fn dot_of_columns<T: Matrix>(a: &T, b: &T) -> T::Elem {
+    a.data_column(0).iter().zip(b.data_column(0).iter()).map(|(x, y)| *x * *y).sum()
+}
+
+/// Orthonormalize the columns of the matrix in place with the modified
+/// Gram-Schmidt process, i.e. compute the `Q` of its thin QR decomposition.
+///
+/// Each column is orthogonalized twice to keep the orthogonality in floating
+/// point. A column depending on the previous ones is replaced by a unit
+/// vector orthogonal to them, so the answer always has orthonormal columns.
+/// The matrix should have no more columns than rows.
+fn orthonormalize_columns<T: Matrix>(q: &mut T) {
+    let n = q.row();
+    let eps = T::Elem::epsilon().times_real(n as f64);
+    for j in 0..q.column() {
+        let norm_before = norm_of_column(q, j);
+        project_out(q, j);
+        let mut norm = norm_of_column(q, j);
+        if norm <= eps * norm_before || norm == T::Elem::zero() {
+            // take the unit vector with the largest component orthogonal to
+            // the previous columns
+            let mut best = (T::Elem::zero(), 0);
+            for candidate in 0..n {
+                set_unit_column(q, j, candidate);
+                project_out(q, j);
+                let candidate_norm = norm_of_column(q, j);
+                if candidate_norm > best.0 {
+                    best = (candidate_norm, candidate);
+                }
+            }
+            set_unit_column(q, j, best.1);
+            project_out(q, j);
+            norm = norm_of_column(q, j);
+        }
+        for r in 0..n {
+            let v = q.elem(r, j);
+            *q.elem_mut(r, j) = v / norm;
+        }
+    }
+}
+
+fn set_unit_column<T: Matrix>(q: &mut T, j: usize, i: usize) {
+    for r in 0..q.row() {
+        *q.elem_mut(r, j) = if r == i { T::Elem::one() } else { T::Elem::zero() };
+    }
+}
+
+/// Remove the components along the columns before column `j`, twice.
+fn project_out<T: Matrix>(q: &mut T, j: usize) {
+    for _pass in 0..2 {
+        for i in 0..j {
+            let dot: T::Elem = (0..q.row()).map(|r| q.elem(r, i) * q.elem(r, j)).sum();
+            for r in 0..q.row() {
+                let v = q.elem(r, j) - dot * q.elem(r, i);
+                *q.elem_mut(r, j) = v;
+            }
+        }
+    }
+}
+
+fn norm_of_column<T: Matrix>(q: &T, j: usize) -> T::Elem {
+    frobenius_norm(q.data_column(j).iter().cloned())
+}
+
+fn frobenius_norm<E: LikeNumber, I: Iterator<Item = E>>(values: I) -> E {
+    values.map(|x| x * x).sum::<E>().sqrt()
+}
+
+/// A deterministic pseudo-random starting block. Starting from the unit
+/// vectors would fail whenever they are orthogonal to the wanted subspace.
+fn start_block<T: Matrix>(n: usize, k: usize) -> T {
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let data = (0..(n * k)).map(|_| {
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        T::Elem::one().times_real((state >> 11) as f64 / (1u64 << 53) as f64 - 0.5)
+    }).collect();
+    T::from_vec(n, k, data)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::decomp::eig::eig_symmetric;
+    use crate::iterative::{*};
+
+    #[test]
+    fn test_orthogonal_iteration_symmetric() {
+        let a = mat64![
+            4.0, 1.0, 0.0, 0.0;
+            1.0, 3.0, 1.0, 0.0;
+            0.0, 1.0, 2.0, 1.0;
+            0.0, 0.0, 1.0, 1.0];
+        let ans = orthogonal_iteration(&a, 2, 1e-12).unwrap();
+        assert!(ans.converged);
+        let qtq = trmul(&ans.basis, &ans.basis).unwrap();
+        assert!(eq_with_error(&qtq, &Mat64::identity(2), 1e-12));
+
+        let expected = eig_symmetric(&a).unwrap().values;
+        let got = eig_symmetric(&ans.projection).unwrap().values;
+        assert!((got[0] - expected[2]).abs() < 1e-10);
+        assert!((got[1] - expected[3]).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_orthogonal_iteration_rank_deficient() {
+        // rank one matrix, the second basis vector spans part of the null space
+        let a = mat64![1.0, 1.0, 1.0; 1.0, 1.0, 1.0; 1.0, 1.0, 1.0];
+        let ans = orthogonal_iteration(&a, 2, 1e-12).unwrap();
+        assert!(ans.converged);
+        let qtq = trmul(&ans.basis, &ans.basis).unwrap();
+        assert!(eq_with_error(&qtq, &Mat64::identity(2), 1e-12));
+        let values = eig_symmetric(&ans.projection).unwrap().values;
+        assert!(values[0].abs() < 1e-10);
+        assert!((values[1] - 3.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_orthogonal_iteration_errors() {
+        let a = Mat64::identity(3);
+        assert!(orthogonal_iteration(&a, 0, 1e-8).is_err());
+        assert!(orthogonal_iteration(&a, 4, 1e-8).is_err());
+        assert!(orthogonal_iteration(&Mat64::zero(2, 3), 1, 1e-8).is_err());
+    }
+
+    #[test]
+    fn test_power_iteration() {
+        let a = mat64![
+            4.0, 1.0, 0.0, 0.0;
+            1.0, 3.0, 1.0, 0.0;
+            0.0, 1.0, 2.0, 1.0;
+            0.0, 0.0, 1.0, 1.0];
+        let ans = power_iteration(&a, 1e-12, 10000).unwrap();
+        assert!(ans.converged);
+        let expected = eig_symmetric(&a).unwrap().values[3];
+        assert!((ans.value - expected).abs() < 1e-10);
+        let mut lv = ans.vector.clone();
+        lv.apply(|x| x * ans.value);
+        assert!(eq_with_error(&mul(&a, &ans.vector).unwrap(), &lv, 1e-10));
+
+        // the dominant eigenvalue is negative
+        let ans = power_iteration(&mat64![-5.0, 0.0; 1.0, 2.0], 1e-12, 10000).unwrap();
+        assert!(ans.converged && (ans.value + 5.0).abs() < 1e-10);
+        // 1 and -1 don't converge
+        let ans = power_iteration(&mat64![0.0, 1.0; 1.0, 0.0], 1e-12, 50).unwrap();
+        assert!(!ans.converged);
+        assert_eq!(ans.iterations, 50);
+        let ans = power_iteration(&Mat64::zero(2, 2), 1e-12, 10).unwrap();
+        assert!(ans.converged && ans.value == 0.0);
+        assert!(power_iteration(&Mat64::zero(2, 3), 1e-12, 10).is_err());
+        assert!(power_iteration(&Mat64::zero(0, 0), 1e-12, 10).is_err());
+    }
+
+    #[test]
+    fn test_inverse_power_iteration() {
+        let a = mat64![
+            4.0, 1.0, 0.0, 0.0;
+            1.0, 3.0, 1.0, 0.0;
+            0.0, 1.0, 2.0, 1.0;
+            0.0, 0.0, 1.0, 1.0];
+        let expected = eig_symmetric(&a).unwrap().values;
+        for (shift, i) in [(0.0, 0), (1.9, 1), (3.0, 2), (10.0, 3)] {
+            let ans = inverse_power_iteration(&a, shift, 1e-12, 1000).unwrap();
+            assert!(ans.converged);
+            assert!((ans.value - expected[i]).abs() < 1e-10);
+        }
+        // the shift is exactly an eigenvalue
+        let d = mat64![1.0, 0.0, 0.0; 0.0, 2.0, 0.0; 0.0, 0.0, 3.0];
+        let ans = inverse_power_iteration(&d, 2.0, 1e-12, 100).unwrap();
+        assert!(ans.converged && (ans.value - 2.0).abs() < 1e-12);
+        assert!((ans.vector.elem(1, 0).abs() - 1.0).abs() < 1e-12);
+        assert!(inverse_power_iteration(&Mat64::zero(2, 3), 0.0, 1e-12, 10).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::needless_range_loop)]
+    fn test_lanczos() {
+        // the second difference matrix of 30 points
+        let n = 30;
+        let a = elemwise_indexed(&Mat64::zero(n, n), |r, c, _| {
+            if r == c { 2.0 } else if r + 1 == c || c + 1 == r { -1.0 } else { 0.0 }
+        });
+        let expected = eig_symmetric(&a).unwrap().values;
+        let largest = lanczos(&a, 3, Extreme::Largest, 1e-10).unwrap();
+        let smallest = lanczos(&a, 2, Extreme::Smallest, 1e-10).unwrap();
+        for i in 0..3 {
+            assert!((largest.values[i] - expected[n - 3 + i]).abs() < 1e-8);
+        }
+        for i in 0..2 {
+            assert!((smallest.values[i] - expected[i]).abs() < 1e-8);
+        }
+        let av = mul(&a, &largest.vectors).unwrap();
+        for c in 0..3 {
+            for r in 0..n {
+                assert!((av.elem(r, c) - largest.values[c] * largest.vectors.elem(r, c)).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    #[allow(clippy::needless_range_loop)]
+    fn test_lanczos_invariant_subspace() {
+        // the eigenvalue 2 is repeated, so the Krylov space of one start
+        // vector breaks down after three steps
+        let a = mat64![
+            2.0, 0.0, 0.0, 0.0;
+            0.0, 2.0, 0.0, 0.0;
+            0.0, 0.0, 5.0, 1.0;
+            0.0, 0.0, 1.0, 5.0];
+        let ans = lanczos(&a, 4, Extreme::Smallest, 1e-12).unwrap();
+        let expected = [2.0, 2.0, 4.0, 6.0];
+        for i in 0..4 {
+            assert!((ans.values[i] - expected[i]).abs() < 1e-10);
+        }
+        assert!(lanczos(&a, 0, Extreme::Largest, 1e-8).is_err());
+        assert!(lanczos(&a, 5, Extreme::Largest, 1e-8).is_err());
+        assert!(lanczos(&Mat64::zero(2, 3), 1, Extreme::Largest, 1e-8).is_err());
+    }
+
+    #[test]
+    fn test_arnoldi() {
+        let a = mat64![
+            4.0, 1.0, -2.0, 0.5;
+            0.0, 3.0, 1.0, 1.0;
+            1.0, -1.0, 2.0, 0.0;
+            2.0, 0.0, 1.0, 1.0];
+        let start = mat64![1.0; 1.0; 0.0; 0.0];
+        let ans = arnoldi(&a, &start, 3).unwrap();
+        let v = &ans.basis;
+        assert!(eq_with_error(&trmul(v, v).unwrap(), &Mat64::identity(4), 1e-12));
+        let vk = Mat64::from_vec(4, 3, v.data()[..12].to_vec());
+        assert!(eq_with_error(&mul(&a, &vk).unwrap(), &mul(v, &ans.hessenberg).unwrap(), 1e-12));
+        for c in 0..3 {
+            for r in (c + 2)..4 {
+                assert_eq!(ans.hessenberg.elem(r, c), 0.0);
+            }
+        }
+        assert!((v.elem(0, 0) - 0.5f64.sqrt()).abs() < 1e-15);
+
+        // the space of e_0 and e_1 is invariant
+        let block = mat64![1.0, 2.0, 0.0; 3.0, 4.0, 0.0; 0.0, 0.0, 5.0];
+        let ans = arnoldi(&block, &mat64![1.0; 0.0; 0.0], 3).unwrap();
+        assert_eq!((ans.basis.column(), ans.hessenberg.row(), ans.hessenberg.column()), (2, 2, 2));
+        assert!(eq_with_error(&mul(&block, &ans.basis).unwrap(), &mul(&ans.basis, &ans.hessenberg).unwrap(), 1e-12));
+
+        assert!(arnoldi(&a, &Mat64::zero(4, 1), 2).is_err());
+        assert!(arnoldi(&a, &start, 0).is_err());
+        assert!(arnoldi(&a, &mat64![1.0; 2.0], 2).is_err());
+    }
+}
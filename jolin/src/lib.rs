@@ -16,15 +16,66 @@ pub mod decomp;
 pub mod rand;
 /// Determinants
 pub mod det;
+/// Regression and model fitting
+pub mod fit;
+/// Positive semi-definite matrix utilities
+pub mod psd;
+/// Linear system solvers
+pub mod solve;
+/// Debug-time shape tracing of matrix operations
+pub mod trace;
+/// Visualization of matrices
+pub mod viz;
+/// Optional BLAS/LAPACK backend
+pub mod backend;
+/// Statistics of matrices
+pub mod stats;
+/// Row- and column-stochastic matrices
+pub mod stochastic;
+/// Iterative eigenvalue algorithms
+pub mod iterative;
+/// Fuzzy comparison of matrices for tests
+pub mod compare;
+/// Global settings of computational kernels
+pub mod settings;
+/// Partitioned matrices with named blocks
+pub mod block;
+/// BLAS level-1 and level-2 primitives
+pub mod primitives;
+/// Reading and writing matrices in file formats
+pub mod io;
+/// Constructors of special matrices
+pub mod special;
+/// Circulant matrices with fast multiplication
+pub mod circulant;
+/// Linear operators defined by their action on vectors
+pub mod operator;
+/// Matrix exponential and its action on vectors
+pub mod expm;
+/// Predicates of matrix structures and values
+pub mod checks;
+/// Element-wise mathematical and trigonometric functions
+pub mod math;
+/// Activation functions of neural networks
+pub mod nn;
+/// Stacks of matrices of the same shape with batched operations
+pub mod batch;
+/// Convolution of signals
+pub mod signal;
+/// Elementary orthogonal transformations
+pub mod orthogonal;
+/// Fast Fourier transform of the structured matrices
+mod fft;
 
 pub use matrix::Mat32;
 pub use matrix::Mat64;
 pub use matrix::Matrix;
+pub use matrix::MatrixView;
 
 /// Create a 64-bit real matrix where data written row by row, seperated by ';'.
 ///
 /// ```
-/// # use jolin::matrix::{Matrix, Mat64};
+/// # use jolin::matrix::{Matrix, MatrixView, Mat64};
 /// # use jolin::mat64;
 /// let a = mat64![1.0, 2.0, 3.0; 4.0, 5.0, 6.0];
 /// assert_eq!(a.row(), 2);
@@ -46,9 +97,9 @@ macro_rules! mat64 {
                     $(
                     {
                         items.push($x);
-                        current_row = current_row + 1;
+                        current_row += 1;
                     })*
-                    col = col + 1;
+                    col += 1;
                     if current_row == 0 {
                         panic!("Zero element row is not allowed for matrix!");
                     }
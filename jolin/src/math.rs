@@ -0,0 +1,155 @@
+/*
+ * math.rs
+ * Element-wise mathematical and trigonometric functions of matrices.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber, elemwise, zip_with};
+use crate::error::JolinError;
+
+/// Element-wise exponential function `e^x`
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::math::exp;
+/// assert_eq!(exp(&mat64![0.0, 1.0]), mat64![1.0, 1.0f64.exp()]);
+/// ```
+pub fn exp<T: Matrix>(mat: &T) -> T {
+    elemwise(mat, |x| x.exp())
+}
+
+/// Element-wise natural logarithm. Negative elements become NaN and zeros
+/// become negative infinity.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::math::ln;
+/// assert_eq!(ln(&mat64![1.0, 1.0f64.exp()]), mat64![0.0, 1.0]);
+/// ```
+pub fn ln<T: Matrix>(mat: &T) -> T {
+    elemwise(mat, |x| x.ln())
+}
+
+/// Element-wise absolute value
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::math::abs;
+/// assert_eq!(abs(&mat64![-1.0, 2.0]), mat64![1.0, 2.0]);
+/// ```
+pub fn abs<T: Matrix>(mat: &T) -> T {
+    elemwise(mat, |x| x.abs())
+}
+
+/// Element-wise power function `x^p` with a real exponent. Negative elements
+/// with a non-integer exponent become NaN.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::math::powf;
+/// assert_eq!(powf(&mat64![4.0, -2.0], 2.0), mat64![16.0, 4.0]);
+/// assert_eq!(powf(&mat64![4.0, 9.0], 0.5), mat64![2.0, 3.0]);
+/// ```
+pub fn powf<T: Matrix>(mat: &T, p: T::Elem) -> T {
+    elemwise(mat, |x| x.powf(p))
+}
+
+/// Element-wise square root. Negative elements become NaN.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::math::sqrt_elem;
+/// assert_eq!(sqrt_elem(&mat64![4.0, 9.0]), mat64![2.0, 3.0]);
+/// ```
+pub fn sqrt_elem<T: Matrix>(mat: &T) -> T {
+    elemwise(mat, |x| x.sqrt())
+}
+
+/// Element-wise sine function
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::math::sin;
+/// assert_eq!(sin(&mat64![0.0, 1.0]), mat64![0.0, 1.0f64.sin()]);
+/// ```
+pub fn sin<T: Matrix>(mat: &T) -> T {
+    elemwise(mat, |x| x.sin())
+}
+
+/// Element-wise cosine function
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::math::cos;
+/// assert_eq!(cos(&mat64![0.0, 1.0]), mat64![1.0, 1.0f64.cos()]);
+/// ```
+pub fn cos<T: Matrix>(mat: &T) -> T {
+    elemwise(mat, |x| x.cos())
+}
+
+/// Element-wise tangent function
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::math::tan;
+/// assert_eq!(tan(&mat64![0.0, 1.0]), mat64![0.0, 1.0f64.tan()]);
+/// ```
+pub fn tan<T: Matrix>(mat: &T) -> T {
+    elemwise(mat, |x| x.tan())
+}
+
+/// Element-wise four-quadrant arctangent of `y / x` in `[-pi, pi]`, i.e. the
+/// angle of the point `(x, y)`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::math::atan2;
+/// let angle = atan2(&mat64![1.0, -1.0], &mat64![0.0, -1.0]).unwrap();
+/// assert_eq!(angle, mat64![std::f64::consts::FRAC_PI_2, -3.0 * std::f64::consts::FRAC_PI_4]);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the shapes of `y` and `x` don't match.
+pub fn atan2<T: Matrix>(y: &T, x: &T) -> Result<T, JolinError> {
+    zip_with(y, x, |y, x| y.atan2(*x))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::math::{*};
+
+    #[test]
+    fn test_math() {
+        let a = mat64![0.5, 2.0; 3.0, 0.25];
+        assert!(eq_with_error(&ln(&exp(&a)), &a, 1e-12));
+        assert!(eq_with_error(&exp(&ln(&a)), &a, 1e-12));
+        assert!(eq_with_error(&powf(&sqrt_elem(&a), 2.0), &a, 1e-12));
+        assert_eq!(powf(&a, 1.0), a);
+        assert_eq!(abs(&neg(&a)), a);
+
+        let b = Mat32::new(1, 3, &[-1.0, 0.0, 4.0]);
+        let r = sqrt_elem(&b);
+        assert!(r.data()[0].is_nan());
+        assert_eq!(&r.data()[1..], &[0.0, 2.0]);
+        assert_eq!(ln(&b).data()[1], f32::NEG_INFINITY);
+        assert_eq!(exp(&Mat32::zero(0, 2)), Mat32::zero(0, 2));
+    }
+
+    #[test]
+    fn test_trigonometric() {
+        let a = mat64![0.1, -0.7; 1.2, 3.0];
+        let (s, c) = (sin(&a), cos(&a));
+        let one = add(&hadamard(&s, &s).unwrap(), &hadamard(&c, &c).unwrap()).unwrap();
+        assert!(eq_with_error(&one, &Mat64::ones(2, 2), 1e-12));
+        assert!(eq_with_error(&tan(&a), &elem_div(&s, &c).unwrap(), 1e-12));
+        // the angle is recovered from the point on the unit circle
+        assert!(eq_with_error(&atan2(&s, &c).unwrap(), &a, 1e-12));
+        assert!(atan2(&s, &Mat64::zero(2, 1)).is_err());
+        let q = atan2(&Mat32::new(1, 2, &[0.0, 0.0]), &Mat32::new(1, 2, &[1.0, -1.0])).unwrap();
+        assert_eq!(q.data(), &[0.0, std::f32::consts::PI]);
+    }
+}
@@ -0,0 +1,61 @@
+/*
+ * matrix/approx_interop.rs
+ * Approximate equality of matrices with the approx crate.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use approx::{AbsDiffEq, RelativeEq};
+use super::{MatrixView, Mat64, Mat32};
+
+macro_rules! impl_approx_interop {
+    ($mat: ty, $t: ty) => {
+        /// Matrices of different shapes are never equal, otherwise all
+        /// elements are compared with the same epsilon.
+        impl AbsDiffEq for $mat {
+            type Epsilon = $t;
+
+            fn default_epsilon() -> $t {
+                <$t>::default_epsilon()
+            }
+
+            fn abs_diff_eq(&self, other: &Self, epsilon: $t) -> bool {
+                self.row() == other.row() && self.column() == other.column() && self.data().iter()
+                    .zip(other.data().iter()).all(|(x, y)| x.abs_diff_eq(y, epsilon))
+            }
+        }
+
+        impl RelativeEq for $mat {
+            fn default_max_relative() -> $t {
+                <$t>::default_max_relative()
+            }
+
+            fn relative_eq(&self, other: &Self, epsilon: $t, max_relative: $t) -> bool {
+                self.row() == other.row() && self.column() == other.column() && self.data().iter()
+                    .zip(other.data().iter()).all(|(x, y)| x.relative_eq(y, epsilon, max_relative))
+            }
+        }
+    };
+}
+
+impl_approx_interop!(Mat64, f64);
+impl_approx_interop!(Mat32, f32);
+
+#[cfg(test)]
+mod test {
+    use approx::{assert_abs_diff_eq, assert_relative_eq, assert_relative_ne};
+    use crate::mat64;
+    use crate::matrix::{*};
+
+    #[test]
+    fn test_approx() {
+        let a = mat64![1.0, 2.0; 3.0, 1e6];
+        let b = mat64![1.0, 2.0 + 1e-10; 3.0, 1e6 + 1e-4];
+        assert_abs_diff_eq!(a, b, epsilon = 1e-3);
+        assert_relative_eq!(a, b, max_relative = 1e-9);
+        assert_relative_ne!(a, b);
+        assert_relative_ne!(a, mat64![1.0, 2.0, 3.0, 1e6], epsilon = 1.0);
+        assert_abs_diff_eq!(Mat32::new(1, 1, &[1.0]), Mat32::new(1, 1, &[1.0 + 1e-8]));
+    }
+}
@@ -0,0 +1,112 @@
+/*
+ * matrix/grid.rs
+ * Evenly spaced values and coordinate grids.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use super::{Matrix, LikeNumber};
+use crate::error::JolinError;
+
+/// Row vector of `n` evenly spaced values from `start` to `stop`, both
+/// included.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let x: Mat64 = linspace(0.0, 1.0, 5);
+/// assert_eq!(x, mat64![0.0, 0.25, 0.5, 0.75, 1.0]);
+/// ```
+pub fn linspace<T: Matrix>(start: f64, stop: f64, n: usize) -> T {
+    let step = if n > 1 { (stop - start) / (n - 1) as f64 } else { 0.0 };
+    let data = (0..n).map(|i| {
+        // the last value is exactly `stop`
+        let v = if i + 1 == n && n > 1 { stop } else { start + step * i as f64 };
+        T::Elem::one().times_real(v)
+    }).collect();
+    T::from_vec(1, n, data)
+}
+
+/// Row vector of the values `start, start + step, ...` before `stop`,
+/// which is excluded. It's empty if `stop` can't be reached with the step.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let x: Mat64 = arange(1.0, 2.0, 0.25).unwrap();
+/// assert_eq!(x, mat64![1.0, 1.25, 1.5, 1.75]);
+/// ```
+///
+/// Potential errors:
+/// 1. Invalid value - if `step` is zero, or any input is not finite.
+pub fn arange<T: Matrix>(start: f64, stop: f64, step: f64) -> Result<T, JolinError> {
+    if step == 0.0 || !start.is_finite() || !stop.is_finite() || !step.is_finite() {
+        return Err(JolinError::invalid_value())
+    }
+    let n = ((stop - start) / step).ceil().max(0.0) as usize;
+    let data = (0..n).map(|i| T::Elem::one().times_real(start + step * i as f64)).collect();
+    Ok(T::from_vec(1, n, data))
+}
+
+/// Coordinate matrices of the grid of the vectors `x` and `y`. Both answers
+/// have `y.len()` rows and `x.len()` columns, where each row of the first
+/// is `x` and each column of the second is `y`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let (xx, yy) = meshgrid(&mat64![1.0, 2.0, 3.0], &mat64![5.0, 6.0]).unwrap();
+/// assert_eq!(xx, mat64![1.0, 2.0, 3.0; 1.0, 2.0, 3.0]);
+/// assert_eq!(yy, mat64![5.0, 5.0, 5.0; 6.0, 6.0, 6.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `x` or `y` is not a row or column vector.
+pub fn meshgrid<T: Matrix>(x: &T, y: &T) -> Result<(T, T), JolinError> {
+    if (x.row() != 1 && x.column() != 1) || (y.row() != 1 && y.column() != 1) {
+        return Err(JolinError::shape_mismatching())
+    }
+    let xs = x.data();
+    let ys = y.data();
+    let mut xx = T::zero(ys.len(), xs.len());
+    let mut yy = T::zero(ys.len(), xs.len());
+    for (c, xv) in xs.iter().enumerate() {
+        for (r, yv) in ys.iter().enumerate() {
+            *xx.elem_mut(r, c) = *xv;
+            *yy.elem_mut(r, c) = *yv;
+        }
+    }
+    Ok((xx, yy))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+
+    #[test]
+    fn test_linspace_arange() {
+        let x: Mat64 = linspace(1.0, -1.0, 3);
+        assert_eq!(x, mat64![1.0, 0.0, -1.0]);
+        let x: Mat64 = linspace(0.0, 0.3, 4);
+        assert_eq!(x.elem(0, 3), 0.3);
+        assert_eq!(linspace::<Mat32>(2.0, 3.0, 1), Mat32::new(1, 1, &[2.0]));
+        assert_eq!(linspace::<Mat64>(2.0, 3.0, 0), Mat64::zero(1, 0));
+
+        assert_eq!(arange::<Mat64>(3.0, 0.0, -1.0).unwrap(), mat64![3.0, 2.0, 1.0]);
+        assert_eq!(arange::<Mat64>(0.0, 1.0, 0.5).unwrap(), mat64![0.0, 0.5]);
+        assert_eq!(arange::<Mat64>(0.0, 1.0, -1.0).unwrap(), Mat64::zero(1, 0));
+        let err = arange::<Mat64>(0.0, 1.0, 0.0).unwrap_err();
+        assert_eq!(err.kind(), JolinErrorKind::InvalidValue);
+        assert!(arange::<Mat64>(0.0, f64::INFINITY, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_meshgrid() {
+        let (xx, yy) = meshgrid(&mat64![1.0; 2.0], &mat64![3.0; 4.0; 5.0]).unwrap();
+        assert_eq!((xx.row(), xx.column()), (3, 2));
+        assert_eq!(xx.data_column(1), &[2.0, 2.0, 2.0]);
+        assert_eq!(yy.data_column(0), &[3.0, 4.0, 5.0]);
+        let err = meshgrid(&Mat64::zero(2, 2), &mat64![1.0]).unwrap_err();
+        assert_eq!(err.kind(), JolinErrorKind::ShapeMismatching);
+    }
+}
@@ -0,0 +1,258 @@
+/*
+ * matrix/index.rs
+ * Selection of rows, columns and elements by indices and boolean masks.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use super::{Matrix, MatrixView, LikeNumber};
+use crate::error::JolinError;
+
+/// Gather the rows of the given indices into a new matrix. Indices may
+/// repeat and appear in any order.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0; 3.0, 4.0; 5.0, 6.0];
+/// assert_eq!(select_rows(&a, &[2, 0]).unwrap(), mat64![5.0, 6.0; 1.0, 2.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Invalid value - if an index is out of range.
+pub fn select_rows<T: Matrix>(mat: &T, rows: &[usize]) -> Result<T, JolinError> {
+    check_indices(rows, mat.row())?;
+    let mut data = Vec::with_capacity(rows.len() * mat.column());
+    for c in 0..mat.column() {
+        let col = mat.data_column(c);
+        data.extend(rows.iter().map(|r| col[*r]));
+    }
+    Ok(T::from_vec(rows.len(), mat.column(), data))
+}
+
+/// Gather the columns of the given indices into a new matrix. Indices may
+/// repeat and appear in any order.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0, 3.0; 4.0, 5.0, 6.0];
+/// assert_eq!(select_columns(&a, &[1, 1]).unwrap(), mat64![2.0, 2.0; 5.0, 5.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Invalid value - if an index is out of range.
+pub fn select_columns<T: Matrix>(mat: &T, columns: &[usize]) -> Result<T, JolinError> {
+    check_indices(columns, mat.column())?;
+    let mut data = Vec::with_capacity(mat.row() * columns.len());
+    for c in columns {
+        data.extend_from_slice(mat.data_column(*c));
+    }
+    Ok(T::from_vec(mat.row(), columns.len(), data))
+}
+
+/// Gather the submatrix at the crossings of the given rows and columns, i.e.
+/// `ans[i, j] = mat[rows[i], columns[j]]`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0, 3.0; 4.0, 5.0, 6.0; 7.0, 8.0, 9.0];
+/// assert_eq!(select(&a, &[0, 2], &[2, 0]).unwrap(), mat64![3.0, 1.0; 9.0, 7.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Invalid value - if an index is out of range.
+pub fn select<T: Matrix>(mat: &T, rows: &[usize], columns: &[usize]) -> Result<T, JolinError> {
+    check_indices(rows, mat.row())?;
+    check_indices(columns, mat.column())?;
+    let mut data = Vec::with_capacity(rows.len() * columns.len());
+    for c in columns {
+        let col = mat.data_column(*c);
+        data.extend(rows.iter().map(|r| col[*r]));
+    }
+    Ok(T::from_vec(rows.len(), columns.len(), data))
+}
+
+/// Boolean mask of the elements of a matrix satisfying the predicate, in
+/// the column-major order of the data vector. It can be used with
+/// `masked_fill`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, -2.0; -3.0, 4.0];
+/// assert_eq!(mask(&a, |x| *x < 0.0), vec![false, true, true, false]);
+/// ```
+pub fn mask<T: Matrix, F: FnMut(&T::Elem) -> bool>(mat: &T, f: F) -> Vec<bool> {
+    mat.data().iter().map(f).collect()
+}
+
+/// Keep the rows whose entries in the mask are true.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0; 3.0, 4.0; 5.0, 6.0];
+/// assert_eq!(filter_rows(&a, &[true, false, true]).unwrap(), mat64![1.0, 2.0; 5.0, 6.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the length of the mask is not the row count.
+pub fn filter_rows<T: Matrix>(mat: &T, row_mask: &[bool]) -> Result<T, JolinError> {
+    if row_mask.len() != mat.row() {
+        return Err(JolinError::shape_mismatching_with((mat.row(), 1), (row_mask.len(), 1)))
+    }
+    let rows: Vec<usize> = (0..mat.row()).filter(|r| row_mask[*r]).collect();
+    select_rows(mat, &rows)
+}
+
+/// Set the elements whose entries in the mask are true to the value. The mask
+/// is in the column-major order of the data vector, like the one from `mask`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let mut a = mat64![1.0, f64::NAN; 3.0, 4.0];
+/// let nan = mask(&a, |x| x.is_nan());
+/// masked_fill(&mut a, &nan, 0.0).unwrap();
+/// assert_eq!(a, mat64![1.0, 0.0; 3.0, 4.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the length of the mask is not the element count.
+pub fn masked_fill<T: Matrix>(mat: &mut T, elem_mask: &[bool], value: T::Elem) -> Result<(), JolinError> {
+    if elem_mask.len() != mat.data().len() {
+        return Err(JolinError::shape_mismatching())
+    }
+    for (x, m) in mat.data_mut().iter_mut().zip(elem_mask.iter()) {
+        if *m {
+            *x = value;
+        }
+    }
+    Ok(())
+}
+
+/// Find the `(row, column)` indices of the elements satisfying the predicate
+/// in column-major order.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![0.0, 5.0; -7.0, 0.0];
+/// assert_eq!(find(&a, |x| *x != 0.0), vec![(1, 0), (0, 1)]);
+/// ```
+pub fn find<T: MatrixView, F: FnMut(&T::Elem) -> bool>(mat: &T, mut f: F) -> Vec<(usize, usize)> {
+    let row = mat.row();
+    mat.data().iter().enumerate()
+        .filter(|(_, x)| f(x))
+        .map(|(idx, _)| (idx % row, idx / row))
+        .collect()
+}
+
+/// Count the elements satisfying the predicate.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![0.0, 5.0; -7.0, 0.0];
+/// assert_eq!(count_where(&a, |x| x.abs() > 1.0), 2);
+/// ```
+pub fn count_where<T: MatrixView, F: FnMut(&T::Elem) -> bool>(mat: &T, mut f: F) -> usize {
+    mat.data().iter().filter(|x| f(x)).count()
+}
+
+/// Remove the duplicated rows, where two rows are duplicated if their
+/// elements differ by at most `tol`. The answer keeps the first occurrence
+/// of each row in the original order, together with the mapping from each
+/// original row to its row in the answer.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0; 3.0, 4.0; 1.0, 2.0 + 1e-12];
+/// let (u, mapping) = unique_rows(&a, 1e-9);
+/// assert_eq!(u, mat64![1.0, 2.0; 3.0, 4.0]);
+/// assert_eq!(mapping, vec![0, 1, 0]);
+/// ```
+pub fn unique_rows<T: Matrix>(mat: &T, tol: T::Elem) -> (T, Vec<usize>) {
+    let same = |a: usize, b: usize| (0..mat.column())
+        .all(|c| (mat.elem(a, c) - mat.elem(b, c)).abs() <= tol);
+    let mut kept: Vec<usize> = Vec::new();
+    let mut mapping = Vec::with_capacity(mat.row());
+    for r in 0..mat.row() {
+        match kept.iter().position(|k| same(*k, r)) {
+            Some(u) => mapping.push(u),
+            None => {
+                mapping.push(kept.len());
+                kept.push(r);
+            }
+        }
+    }
+    (select_rows(mat, &kept).unwrap(), mapping)
+}
+
+fn check_indices(indices: &[usize], n: usize) -> Result<(), JolinError> {
+    if indices.iter().any(|i| *i >= n) {
+        return Err(JolinError::invalid_value())
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+
+    #[test]
+    fn test_select() {
+        let a = Mat64::new(3, 2, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(select_rows(&a, &[1]).unwrap(), Mat64::new(1, 2, &[2.0, 5.0]));
+        assert_eq!(select_rows(&a, &[]).unwrap(), Mat64::zero(0, 2));
+        assert_eq!(select_columns(&a, &[1, 0]).unwrap(), Mat64::new(3, 2, &[4.0, 5.0, 6.0, 1.0, 2.0, 3.0]));
+        assert_eq!(select_columns(&a, &[]).unwrap(), Mat64::zero(3, 0));
+        let s = select(&a, &[2, 2, 0], &[1]).unwrap();
+        assert_eq!(s, Mat64::new(3, 1, &[6.0, 6.0, 4.0]));
+        assert_eq!(s, select_columns(&select_rows(&a, &[2, 2, 0]).unwrap(), &[1]).unwrap());
+
+        assert_eq!(select_rows(&a, &[3]).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+        assert!(select_columns(&a, &[2]).is_err());
+        assert!(select(&a, &[0], &[2]).is_err());
+    }
+
+    #[test]
+    fn test_mask() {
+        let a = Mat32::new(3, 2, &[1.0, -2.0, 3.0, -4.0, 5.0, f32::NAN]);
+        let m = mask(&a, |x| *x > 0.0);
+        assert_eq!(m, vec![true, false, true, false, true, false]);
+        let f = filter_rows(&a, &m[..3]).unwrap();
+        assert_eq!((f.row(), f.column()), (2, 2));
+        assert_eq!(&f.data()[..3], &[1.0, 3.0, -4.0]);
+        assert!(f.data()[3].is_nan());
+        assert_eq!(filter_rows(&a, &[false; 3]).unwrap(), Mat32::zero(0, 2));
+        assert_eq!(filter_rows(&a, &m).unwrap_err().kind(), JolinErrorKind::ShapeMismatching);
+
+        let mut b = a.clone();
+        masked_fill(&mut b, &mask(&a, |x| x.is_nan() || *x < 0.0), 0.0).unwrap();
+        assert_eq!(b, Mat32::new(3, 2, &[1.0, 0.0, 3.0, 0.0, 5.0, 0.0]));
+        assert!(masked_fill(&mut b, &[true], 0.0).is_err());
+    }
+
+    #[test]
+    fn test_find() {
+        let a = Mat64::new(2, 3, &[1.0, 10.0, -20.0, 2.0, 3.0, 30.0]);
+        let outliers = find(&a, |x| x.abs() > 5.0);
+        assert_eq!(outliers, vec![(1, 0), (0, 1), (1, 2)]);
+        assert_eq!(count_where(&a, |x| x.abs() > 5.0), outliers.len());
+        assert!(find(&a, |x| *x > 100.0).is_empty());
+        assert_eq!(count_where(&Mat64::zero(0, 3), |_| true), 0);
+        assert!(find(&Mat64::zero(0, 3), |_| true).is_empty());
+    }
+
+    #[test]
+    fn test_unique_rows() {
+        let a = Mat64::new(4, 2, &[0.0, 1.0, 0.05, 1.0, 0.0, 2.0, 0.0, 2.0]);
+        let (u, mapping) = unique_rows(&a, 0.1);
+        assert_eq!(u, Mat64::new(2, 2, &[0.0, 1.0, 0.0, 2.0]));
+        assert_eq!(mapping, vec![0, 1, 0, 1]);
+        assert_eq!(select_rows(&u, &mapping).unwrap().row(), a.row());
+
+        let (u, mapping) = unique_rows(&a, 0.0);
+        assert_eq!(u, Mat64::new(3, 2, &[0.0, 1.0, 0.05, 0.0, 2.0, 0.0]));
+        assert_eq!(mapping, vec![0, 1, 2, 1]);
+        let (u, mapping) = unique_rows(&Mat64::zero(0, 2), 0.0);
+        assert_eq!((u, mapping), (Mat64::zero(0, 2), vec![]));
+    }
+}
@@ -0,0 +1,319 @@
+/*
+ * matrix/kernel.rs
+ * Computational kernels of matrix operations on column slices.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::ops::{Add, Mul};
+use crate::settings::{self, Settings, KernelChoice};
+
+/// Vector kernels on contiguous slices, which may be specialized with SIMD
+/// instructions for an element type.
+///
+/// With the `simd` feature, `f64` and `f32` use AVX instructions on x86_64
+/// CPUs supporting them. The SIMD kernels add the products in the same order
+/// as the scalar ones, so the answers are exactly the same.
+pub trait Kernel: Copy + Send + Sync + Add<Self, Output = Self> + Mul<Self, Output = Self> {
+    /// Compute `y = y + alpha * x`. Only the common length of `x` and `y` is
+    /// used.
+    fn axpy(alpha: Self, x: &[Self], y: &mut [Self]) {
+        for (yi, xi) in y.iter_mut().zip(x.iter()) {
+            *yi = *yi + alpha * (*xi);
+        }
+    }
+}
+
+#[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+impl Kernel for f64 {}
+#[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+impl Kernel for f32 {}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+impl Kernel for f64 {
+    fn axpy(alpha: f64, x: &[f64], y: &mut [f64]) {
+        if is_x86_feature_detected!("avx") {
+            unsafe { simd::axpy_f64(alpha, x, y) }
+        } else {
+            for (yi, xi) in y.iter_mut().zip(x.iter()) {
+                *yi += alpha * (*xi);
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+impl Kernel for f32 {
+    fn axpy(alpha: f32, x: &[f32], y: &mut [f32]) {
+        if is_x86_feature_detected!("avx") {
+            unsafe { simd::axpy_f32(alpha, x, y) }
+        } else {
+            for (yi, xi) in y.iter_mut().zip(x.iter()) {
+                *yi += alpha * (*xi);
+            }
+        }
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod simd {
+    use std::arch::x86_64::*;
+
+    // Multiply and add are kept separate rather than fused, so that the
+    // rounding is the same as the scalar kernel.
+    #[target_feature(enable = "avx")]
+    pub unsafe fn axpy_f64(alpha: f64, x: &[f64], y: &mut [f64]) {
+        let n = usize::min(x.len(), y.len());
+        let a = _mm256_set1_pd(alpha);
+        let mut i = 0;
+        while i + 4 <= n {
+            let xv = _mm256_loadu_pd(x.as_ptr().add(i));
+            let yv = _mm256_loadu_pd(y.as_ptr().add(i));
+            _mm256_storeu_pd(y.as_mut_ptr().add(i), _mm256_add_pd(yv, _mm256_mul_pd(a, xv)));
+            i += 4;
+        }
+        while i < n {
+            y[i] += alpha * x[i];
+            i += 1;
+        }
+    }
+
+    #[target_feature(enable = "avx")]
+    pub unsafe fn axpy_f32(alpha: f32, x: &[f32], y: &mut [f32]) {
+        let n = usize::min(x.len(), y.len());
+        let a = _mm256_set1_ps(alpha);
+        let mut i = 0;
+        while i + 8 <= n {
+            let xv = _mm256_loadu_ps(x.as_ptr().add(i));
+            let yv = _mm256_loadu_ps(y.as_ptr().add(i));
+            _mm256_storeu_ps(y.as_mut_ptr().add(i), _mm256_add_ps(yv, _mm256_mul_ps(a, xv)));
+            i += 8;
+        }
+        while i < n {
+            y[i] += alpha * x[i];
+            i += 1;
+        }
+    }
+}
+
+/// Select the matrix multiplication kernel of an `m * k` and a `k * n`
+/// matrix with elements of `elem_size` bytes. Pinned choices are returned as
+/// they are.
+///
+/// Each column of `C` streams the whole `A` through the cache, so the naive
+/// kernel runs at full speed only while `A` stays in the L2 cache. Beyond that
+/// its speed is bounded by the memory bandwidth, and the blocked kernel reuses
+/// each packed panel of `A` for all columns. Products large enough to amortize
+/// the threading overhead run in parallel.
+pub(crate) fn select_mul_kernel(m: usize, n: usize, k: usize, elem_size: usize, settings: &Settings) -> KernelChoice {
+    if settings.kernel != KernelChoice::Auto {
+        return settings.kernel
+    }
+    let hw = settings::calibration();
+    if cfg!(feature = "parallel") && hw.cores > 1 && settings.max_threads != 1
+        && m * n * k >= settings.parallel_threshold {
+        KernelChoice::Parallel
+    } else if m * k * elem_size <= hw.l2_cache / 2 {
+        KernelChoice::Naive
+    } else {
+        KernelChoice::Blocked
+    }
+}
+
+/// Compute `C = C + A B` on column-major data, where `A` is `m * k`, `B` is
+/// `k * n` and `C` is `m * n`, with the kernel selected by the settings.
+///
+/// Columns of `C` are accumulated from the columns of `A` with `axpy`. The
+/// blocked kernel splits `A` into blocks of `block_rows * block_depth` of the
+/// settings, and each block is packed into a contiguous panel which stays in
+/// the cache while it is multiplied with all columns of `B`. The parallel
+/// kernel splits the columns of `C` across at most `max_threads` threads.
+///
+/// Every element of `C` adds the products in the order of `k`, so all kernels
+/// give exactly the same answer as the naive triple loop.
+pub(crate) fn gemm<E: Kernel>(m: usize, n: usize, k: usize, a: &[E], b: &[E], c: &mut [E], settings: &Settings) {
+    if m == 0 || k == 0 {
+        return
+    }
+    match select_mul_kernel(m, n, k, std::mem::size_of::<E>(), settings) {
+        KernelChoice::Naive => gemm_naive(m, n, k, a, b, c),
+        KernelChoice::Parallel => gemm_parallel(m, n, k, a, b, c, settings),
+        _ => gemm_blocked(m, n, k, a, b, c, settings),
+    }
+}
+
+fn gemm_naive<E: Kernel>(m: usize, n: usize, k: usize, a: &[E], b: &[E], c: &mut [E]) {
+    for (c_col, b_col) in c.chunks_exact_mut(m).zip(b.chunks_exact(k)).take(n) {
+        for (p, a_col) in a.chunks_exact(m).enumerate().take(k) {
+            E::axpy(b_col[p], a_col, c_col);
+        }
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn gemm_parallel<E: Kernel>(m: usize, n: usize, k: usize, a: &[E], b: &[E], c: &mut [E], settings: &Settings) {
+    use rayon::prelude::*;
+    let columns = n.div_ceil(thread_count(settings)).max(1);
+    c.par_chunks_mut(m * columns)
+        .zip(b.par_chunks(k * columns))
+        .for_each(|(c_block, b_block)| gemm_blocked(m, c_block.len() / m, k, a, b_block, c_block, settings));
+}
+
+/// Count of threads to split an operation into, limited by `max_threads` of
+/// the settings
+#[cfg(feature = "parallel")]
+pub(crate) fn thread_count(settings: &Settings) -> usize {
+    match settings.max_threads {
+        0 => rayon::current_num_threads(),
+        t => usize::min(t, rayon::current_num_threads()),
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+fn gemm_parallel<E: Kernel>(m: usize, n: usize, k: usize, a: &[E], b: &[E], c: &mut [E], settings: &Settings) {
+    gemm_blocked(m, n, k, a, b, c, settings)
+}
+
+fn gemm_blocked<E: Kernel>(m: usize, n: usize, k: usize, a: &[E], b: &[E], c: &mut [E], settings: &Settings) {
+    let block_rows = settings.block_rows.max(1);
+    let block_depth = settings.block_depth.max(1);
+    let mut panel: Vec<E> = Vec::with_capacity(usize::min(m, block_rows) * usize::min(k, block_depth));
+    for p0 in (0..k).step_by(block_depth) {
+        let kc = usize::min(block_depth, k - p0);
+        for i0 in (0..m).step_by(block_rows) {
+            let mc = usize::min(block_rows, m - i0);
+            panel.clear();
+            for p in p0..(p0 + kc) {
+                panel.extend_from_slice(&a[(i0 + p * m)..(i0 + mc + p * m)]);
+            }
+            for j in 0..n {
+                let c_col = &mut c[(i0 + j * m)..(i0 + mc + j * m)];
+                for (p, a_col) in panel.chunks_exact(mc).enumerate() {
+                    E::axpy(b[p0 + p + j * k], a_col, c_col);
+                }
+            }
+        }
+    }
+}
+
+/// Tile size of the blocked transpose
+const TRANSPOSE_TILE: usize = 32;
+
+/// Transpose the `m * n` column-major matrix `a` into `b`, with the kernel
+/// selected by the settings. The blocked kernel works on square tiles, so
+/// both the reads and the writes stay in the cache.
+pub(crate) fn transpose<E: Copy>(m: usize, n: usize, a: &[E], b: &mut [E], settings: &Settings) {
+    let blocked = match settings.kernel {
+        KernelChoice::Auto => m * n * std::mem::size_of::<E>() > settings::calibration().l1_cache,
+        KernelChoice::Naive => false,
+        _ => true,
+    };
+    let tile = if blocked { TRANSPOSE_TILE } else { usize::max(m, n).max(1) };
+    for c0 in (0..n).step_by(tile) {
+        for r0 in (0..m).step_by(tile) {
+            for c in c0..usize::min(c0 + tile, n) {
+                for r in r0..usize::min(r0 + tile, m) {
+                    b[c + r * n] = a[r + c * m];
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matrix::kernel::{*};
+    use crate::settings::{self, Settings, KernelChoice};
+
+    #[test]
+    fn test_axpy() {
+        let x: Vec<f64> = (0..11).map(|v| v as f64).collect();
+        let mut y = vec![1.0f64; 11];
+        f64::axpy(0.5, &x, &mut y);
+        let expected: Vec<f64> = (0..11).map(|v| 1.0 + 0.5 * v as f64).collect();
+        assert_eq!(y, expected);
+
+        let x: Vec<f32> = (0..19).map(|v| v as f32).collect();
+        let mut y = vec![0.0f32; 19];
+        f32::axpy(-2.0, &x, &mut y);
+        assert_eq!(y[18], -36.0);
+    }
+
+    #[test]
+    fn test_gemm_kernel() {
+        // [1 3; 2 4] * [1; 1] = [4; 6], accumulated onto [1; 1]
+        let mut c = vec![1.0f64, 1.0];
+        gemm(2, 1, 2, &[1.0, 2.0, 3.0, 4.0], &[1.0, 1.0], &mut c, &Settings::default());
+        assert_eq!(c, vec![5.0, 7.0]);
+
+        // zero inner dimension leaves C untouched
+        let mut c = vec![1.0f64; 4];
+        gemm(2, 2, 0, &[], &[], &mut c, &Settings::default());
+        assert_eq!(c, vec![1.0; 4]);
+    }
+
+    fn check_gemm(m: usize, n: usize, k: usize, settings: &Settings) {
+        let a: Vec<f64> = (0..(m * k)).map(|v| ((v * 7) % 13) as f64 - 6.0).collect();
+        let b: Vec<f64> = (0..(k * n)).map(|v| ((v * 3) % 11) as f64 * 0.25).collect();
+        let mut c = vec![1.0f64; m * n];
+        gemm(m, n, k, &a, &b, &mut c, settings);
+        for col in 0..n {
+            for row in 0..m {
+                let mut expected = 1.0;
+                for p in 0..k {
+                    expected += a[row + p * m] * b[p + col * k];
+                }
+                assert_eq!(c[row + col * m], expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gemm_blocked() {
+        // crosses the block boundaries in both directions
+        let settings = Settings::default();
+        check_gemm(settings.block_rows + 3, 4, settings.block_depth + 5, &settings);
+        let small_blocks = Settings { kernel: KernelChoice::Blocked, block_rows: 3, block_depth: 2, ..Settings::default() };
+        check_gemm(10, 3, 7, &small_blocks);
+        check_gemm(10, 3, 7, &Settings { kernel: KernelChoice::Naive, ..Settings::default() });
+        check_gemm(10, 3, 7, &Settings { kernel: KernelChoice::Parallel, ..small_blocks });
+    }
+
+    #[test]
+    fn test_select_mul_kernel() {
+        let auto = Settings::default();
+        assert_eq!(select_mul_kernel(4, 4, 4, 8, &auto), KernelChoice::Naive);
+        let huge = settings::calibration().l2_cache;
+        assert_ne!(select_mul_kernel(huge, 1, huge, 8, &auto), KernelChoice::Naive);
+        let pinned = Settings { kernel: KernelChoice::Blocked, ..auto };
+        assert_eq!(select_mul_kernel(4, 4, 4, 8, &pinned), KernelChoice::Blocked);
+    }
+
+    #[test]
+    fn test_transpose_kernels() {
+        let (m, n) = (45, 70);
+        let a: Vec<f64> = (0..(m * n)).map(|v| v as f64).collect();
+        for kernel in [KernelChoice::Naive, KernelChoice::Blocked] {
+            let mut b = vec![0.0; m * n];
+            transpose(m, n, &a, &mut b, &Settings { kernel, ..Settings::default() });
+            for r in 0..m {
+                for c in 0..n {
+                    assert_eq!(b[c + r * n], a[r + c * m]);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_gemm_parallel() {
+        // large enough to be split across threads
+        let settings = Settings::default();
+        let (m, n, k) = (70, 65, 60);
+        assert!(m * n * k >= settings.parallel_threshold);
+        check_gemm(m, n, k, &settings);
+        check_gemm(m, n, k, &Settings { max_threads: 3, ..settings });
+        check_gemm(10, 9, 4, &Settings { kernel: KernelChoice::Parallel, max_threads: 2, ..settings });
+    }
+}
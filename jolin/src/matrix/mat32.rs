@@ -6,12 +6,18 @@
  * See LICENSE file in the root of the repo.
  */
 
-use super::{Matrix, LikeNumber};
+use super::{Matrix, MatrixView, LikeNumber};
 
 impl LikeNumber for f32 {
     fn zero() -> Self {
         0.0f32
     }
+    fn one() -> Self {
+        1.0f32
+    }
+    fn epsilon() -> Self {
+        f32::EPSILON
+    }
     fn abs(&self) -> Self {
         if *self > 0.0f32 {
             *self
@@ -35,12 +41,27 @@ impl LikeNumber for f32 {
     fn cos(&self) -> Self {
         f32::cos(*self)
     }
+    fn tan(&self) -> Self {
+        f32::tan(*self)
+    }
+    fn atan2(&self, x: Self) -> Self {
+        f32::atan2(*self, x)
+    }
     fn ln(&self) -> Self {
         f32::ln(*self)
     }
+    fn exp(&self) -> Self {
+        f32::exp(*self)
+    }
+    fn powf(&self, p: Self) -> Self {
+        f32::powf(*self, p)
+    }
     fn times_real(&self, v: f64) -> Self {
         (*self) * (v as f32)
     }
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
 }
 
 /// 32-bit float point real number matrix
@@ -66,7 +87,7 @@ impl PartialEq for Mat32 {
     }
 }
 
-impl Matrix for Mat32 {
+impl MatrixView for Mat32 {
     type Elem = f32;
 
     fn row(&self) -> usize {
@@ -81,13 +102,15 @@ impl Matrix for Mat32 {
         &self._data
     }
 
-    fn data_mut(&mut self) -> &mut [Self::Elem] {
-        return &mut self._data
-    }
-
     fn data_column(&self, c: usize) -> &[Self::Elem] {
         &self._data[c*self.row() .. (c+1)*self.row()]
     }
+}
+
+impl Matrix for Mat32 {
+    fn data_mut(&mut self) -> &mut [Self::Elem] {
+        &mut self._data
+    }
 
     fn new(row: usize, column: usize, data: &[f32]) -> Mat32 {
         let n = row * column;
@@ -122,6 +145,6 @@ impl Matrix for Mat32 {
             let idx: usize = mat.idx(c, c);
             mat._data[idx] = 1.0f32;
         }        
-        return mat
+        mat
     }
 }
\ No newline at end of file
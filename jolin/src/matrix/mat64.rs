@@ -6,12 +6,19 @@
  * See LICENSE file in the root of the repo.
  */
 
-use super::{Matrix, LikeNumber};
+use super::{Matrix, MatrixView, LikeNumber};
+use crate::error::JolinError;
 
 impl LikeNumber for f64 {
     fn zero() -> Self {
         0.0
     }
+    fn one() -> Self {
+        1.0
+    }
+    fn epsilon() -> Self {
+        f64::EPSILON
+    }
     fn abs(&self) -> Self {
         if *self > 0.0 {
             *self
@@ -35,11 +42,26 @@ impl LikeNumber for f64 {
     fn cos(&self) -> Self {
         f64::cos(*self)
     }
+    fn tan(&self) -> Self {
+        f64::tan(*self)
+    }
+    fn atan2(&self, x: Self) -> Self {
+        f64::atan2(*self, x)
+    }
     fn ln(&self) -> Self {
         f64::ln(*self)
     }
+    fn exp(&self) -> Self {
+        f64::exp(*self)
+    }
+    fn powf(&self, p: Self) -> Self {
+        f64::powf(*self, p)
+    }
     fn times_real(&self, v: f64) -> Self {
-        return (*self) * v;
+        (*self) * v
+    }
+    fn to_f64(&self) -> f64 {
+        *self
     }
 }
 
@@ -66,7 +88,7 @@ impl PartialEq for Mat64 {
     }
 }
 
-impl Matrix for Mat64 {
+impl MatrixView for Mat64 {
     type Elem = f64;
 
     fn row(&self) -> usize {
@@ -81,13 +103,15 @@ impl Matrix for Mat64 {
         &self._data
     }
 
-    fn data_mut(&mut self) -> &mut [Self::Elem] {
-        return &mut self._data
-    }
-
     fn data_column(&self, c: usize) -> &[Self::Elem] {
         &self._data[c*self.row() .. (c+1)*self.row()]
     }
+}
+
+impl Matrix for Mat64 {
+    fn data_mut(&mut self) -> &mut [Self::Elem] {
+        &mut self._data
+    }
 
     fn new(row: usize, column: usize, data: &[f64]) -> Mat64 {
         let n = row * column;
@@ -122,14 +146,54 @@ impl Matrix for Mat64 {
             let idx: usize = mat.idx(c, c);
             mat._data[idx] = 1.0;
         }        
-        return mat
+        mat
+    }
+}
+
+/// Build a matrix from rows. Panics if the rows have different lengths, use
+/// `Mat64::try_from` to get an error instead.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = Mat64::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+/// assert_eq!(a, mat64![1.0, 2.0; 3.0, 4.0]);
+/// ```
+impl From<Vec<Vec<f64>>> for Mat64 {
+    fn from(rows: Vec<Vec<f64>>) -> Self {
+        let rows: Vec<&[f64]> = rows.iter().map(|r| r.as_slice()).collect();
+        match Mat64::try_from(rows.as_slice()) {
+            Ok(mat) => mat,
+            Err(_) => panic!("Found different row lengths"),
+        }
+    }
+}
+
+/// Build a matrix from rows.
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the rows have different lengths.
+impl TryFrom<&[&[f64]]> for Mat64 {
+    type Error = JolinError;
+
+    fn try_from(rows: &[&[f64]]) -> Result<Self, JolinError> {
+        let column = rows.first().map_or(0, |r| r.len());
+        if rows.iter().any(|r| r.len() != column) {
+            return Err(JolinError::shape_mismatching())
+        }
+        let mut mat = Mat64::zero(rows.len(), column);
+        for (r, values) in rows.iter().enumerate() {
+            for (c, v) in values.iter().enumerate() {
+                *mat.elem_mut(r, c) = *v;
+            }
+        }
+        Ok(mat)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::Mat64;
-    use super::Matrix;
+    use super::{Matrix, MatrixView};
     
     #[test]
     fn test_matrix_eq() {
@@ -163,4 +227,23 @@ mod test {
         assert_eq!(i3.data_column(1), &[0.0, 1.0, 0.0]);
         assert_eq!(i3.data_column(2), &[0.0, 0.0, 1.0]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_from_rows() {
+        let a = Mat64::from(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]);
+        assert_eq!(a, Mat64::new(2, 3, &[1.0, 4.0, 2.0, 5.0, 3.0, 6.0]));
+        let rows: &[&[f64]] = &[&[1.0], &[2.0]];
+        assert_eq!(Mat64::try_from(rows).unwrap(), Mat64::new(2, 1, &[1.0, 2.0]));
+        assert_eq!(Mat64::from(Vec::<Vec<f64>>::new()), Mat64::zero(0, 0));
+
+        let ragged: &[&[f64]] = &[&[1.0, 2.0], &[3.0]];
+        let err = Mat64::try_from(ragged).unwrap_err();
+        assert_eq!(err.kind(), crate::error::JolinErrorKind::ShapeMismatching);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_ragged_rows() {
+        let _ = Mat64::from(vec![vec![1.0], vec![2.0, 3.0]]);
+    }
+}
@@ -9,13 +9,42 @@
 use std::ops::{Add, Sub, Mul, Div, Neg};
 use std::iter::Sum;
 use crate::error::{*};
+use crate::trace;
+use crate::settings::{self, Settings, KernelChoice};
+use crate::backend::Backend;
+use self::kernel::Kernel;
 /// 64-bit float point real matrix definition
 pub mod mat64;
 /// 32-bit float point real matrix definition
 pub mod mat32;
+/// Reductions along rows or columns
+pub mod reduce;
+/// Promotion between matrices of different precisions
+pub mod promote;
+/// Computational kernels of matrix operations
+pub mod kernel;
+/// Evenly spaced values and coordinate grids
+pub mod grid;
+/// Selection of rows, columns and elements by indices and boolean masks
+pub mod index;
+/// Serialization of matrices with serde
+#[cfg(feature = "serde")]
+mod serialize;
+/// Conversions from and to ndarray arrays
+#[cfg(feature = "ndarray")]
+mod ndarray_interop;
+/// Approximate equality of matrices with the approx crate
+#[cfg(feature = "approx")]
+mod approx_interop;
 
 pub use self::mat64::Mat64;
 pub use self::mat32::Mat32;
+pub use self::reduce::{Axis, sum_axis, mean_axis, min_axis, max_axis, argmax_axis};
+pub use self::promote::{PromoteTo64, add_promote, sub_promote, mul_promote, hadamard_promote,
+    eq_promote, eq_with_error_promote};
+pub use self::grid::{linspace, arange, meshgrid};
+pub use self::index::{select_rows, select_columns, select, mask, filter_rows, masked_fill,
+    find, count_where, unique_rows};
 
 /// Trait for numbers that can be used as the elements of the matrix.
 /// 
@@ -27,9 +56,15 @@ pub trait LikeNumber: Copy + PartialEq + PartialOrd
         + Div<Self, Output = Self>
         + Neg<Output = Self>
         + Sum
+        + Backend
+        + Kernel
 {
     /// Obtained the zero element
     fn zero() -> Self;
+    /// Obtained the unit element
+    fn one() -> Self;
+    /// Machine epsilon of the number type
+    fn epsilon() -> Self;
     /// Get the absolute value (or modulus) of the number
     fn abs(&self) -> Self;
     /// Square root of the number
@@ -40,16 +75,28 @@ pub trait LikeNumber: Copy + PartialEq + PartialOrd
     fn sin(&self) -> Self;
     /// Trigonometric cosine function `cos(x)` of the number
     fn cos(&self) -> Self;
+    /// Trigonometric tangent function `tan(x)` of the number
+    fn tan(&self) -> Self;
+    /// Four-quadrant arctangent `atan2(self, x)` in `[-pi, pi]`, where the
+    /// number is the y coordinate
+    fn atan2(&self, x: Self) -> Self;
     /// Natural logarithm
     fn ln(&self) -> Self;
+    /// Exponential function `e^x`
+    fn exp(&self) -> Self;
+    /// Power function `x^p` with a real exponent
+    fn powf(&self, p: Self) -> Self;
     /// Return self times v in f64.
     fn times_real(&self, v: f64) -> Self;
+    /// Convert the number to f64
+    fn to_f64(&self) -> f64;
 }
 
-/// Trait for all jolin matrices
-/// 
-/// All basic operations on matrices will be declared here.
-pub trait Matrix: PartialEq + Clone {
+/// Read-only access to a column-major matrix, the part of `Matrix` which
+/// doesn't create or modify matrices. Functions only reading their input can
+/// accept it, so they also work on matrices which can't be built in memory,
+/// like `jolin::io::mmap::MmapMat64`.
+pub trait MatrixView {
     /// Element type, must be f64 or f32
     type Elem: LikeNumber;
 
@@ -68,22 +115,45 @@ pub trait Matrix: PartialEq + Clone {
     /// Get the reference to the data vector
     fn data(&self) -> &[Self::Elem];
 
-    /// Get the mutable reference to the data vector
-    fn data_mut(&mut self) -> &mut [Self::Elem];
-
     /// Get the element at [r, c]
     fn elem(&self, r: usize, c: usize) -> Self::Elem {
         self.data()[self.idx(r, c)]
     }
 
+    /// Get reference to the column of c. No copy will occur as we are in column-major.
+    fn data_column(&self, c: usize) -> &[Self::Elem] {
+        &self.data()[(c * self.row())..((c + 1) * self.row())]
+    }
+}
+
+/// Trait for all jolin matrices
+/// 
+/// All basic operations on matrices will be declared here.
+pub trait Matrix: MatrixView + PartialEq + Clone {
+    /// Get the mutable reference to the data vector
+    fn data_mut(&mut self) -> &mut [Self::Elem];
+
     /// Get the mut reference on the element at [r, c]
     fn elem_mut(&mut self, r: usize, c: usize) -> &mut Self::Elem {
         let idx = self.idx(r, c);
         &mut self.data_mut()[idx]
     }
 
-    /// Get reference to the column of c. No copy will occur as we are in column-major.
-    fn data_column(&self, c: usize) -> &[Self::Elem];
+    /// Transform every element in place. It is the non-allocating version of
+    /// `elemwise`.
+    ///
+    /// ```
+    /// # use jolin::matrix::{*};
+    /// # use jolin::mat64;
+    /// let mut a = mat64![1.0, -2.0; -3.0, 4.0];
+    /// a.apply(|x| x.max(0.0));
+    /// assert_eq!(a, mat64![1.0, 0.0; 0.0, 4.0]);
+    /// ```
+    fn apply<F: FnMut(Self::Elem) -> Self::Elem>(&mut self, mut f: F) {
+        for x in self.data_mut().iter_mut() {
+            *x = f(*x);
+        }
+    }
 
     /// Create a matrix. Data should be stored in the **column-major** order.
     fn new(row: usize, column: usize, data: &[Self::Elem]) -> Self;
@@ -93,11 +163,67 @@ pub trait Matrix: PartialEq + Clone {
     /// Data should be stored in the **column-major** order.
     fn from_vec(row: usize, column: usize, data: Vec<Self::Elem>) -> Self;
 
+    /// Create a matrix like `new`, but return an error instead of panicking
+    /// if the data length doesn't match the shape.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the data length is not `row * column`.
+    fn try_new(row: usize, column: usize, data: &[Self::Elem]) -> Result<Self, JolinError> {
+        Self::try_from_vec(row, column, data.to_vec())
+    }
+
+    /// Create a matrix like `from_vec`, but return an error instead of
+    /// panicking if the data length doesn't match the shape.
+    /// ```
+    /// # use jolin::matrix::{*};
+    /// assert!(Mat64::try_from_vec(2, 2, vec![1.0, 2.0, 3.0, 4.0]).is_ok());
+    /// assert!(Mat64::try_from_vec(2, 2, vec![1.0, 2.0, 3.0]).is_err());
+    /// ```
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the data length is not `row * column`.
+    fn try_from_vec(row: usize, column: usize, data: Vec<Self::Elem>) -> Result<Self, JolinError> {
+        if row.checked_mul(column) != Some(data.len()) {
+            return Err(JolinError::shape_mismatching())
+        }
+        Ok(Self::from_vec(row, column, data))
+    }
+
     /// Zero matrix
     fn zero(row: usize, column: usize) -> Self;
 
     /// Identity matrix of shape n*n
     fn identity(n: usize) -> Self;
+
+    /// Matrix of the shape filled with one
+    fn ones(row: usize, column: usize) -> Self {
+        Self::full(row, column, Self::Elem::one())
+    }
+
+    /// Matrix of the shape filled with the value
+    /// ```
+    /// # use jolin::matrix::{*};
+    /// let a = Mat64::full(2, 3, 0.5);
+    /// assert_eq!(a, Mat64::new(2, 3, &[0.5; 6]));
+    /// assert_eq!(Mat32::ones(1, 2), Mat32::new(1, 2, &[1.0, 1.0]));
+    /// ```
+    fn full(row: usize, column: usize, value: Self::Elem) -> Self {
+        Self::from_vec(row, column, vec![value; row * column])
+    }
+
+    /// Convert the matrix to another element type, e.g. between `Mat32` and
+    /// `Mat64`. Elements are rounded to the nearest value when the precision
+    /// is reduced.
+    /// ```
+    /// # use jolin::matrix::{*};
+    /// let a = Mat64::new(1, 2, &[0.5, 0.1]);
+    /// let b: Mat32 = a.cast();
+    /// assert_eq!(b, Mat32::new(1, 2, &[0.5, 0.1]));
+    /// ```
+    fn cast<U: Matrix>(&self) -> U {
+        U::from_vec(self.row(), self.column(),
+            self.data().iter().map(|x| U::Elem::one().times_real(x.to_f64())).collect())
+    }
 }
 
 
@@ -115,14 +241,18 @@ pub trait Matrix: PartialEq + Clone {
 /// ```
 /// 
 /// A shape mismatching error will be returned if the column counts of the input matrices don't match.
-pub fn hcat<T: Matrix>(mat: &[&T]) -> Result<T, JolinError>{
-    if mat.len() < 1 {
+pub fn hcat<T: Matrix>(mat: &[&T]) -> Result<T, JolinError> {
+    trace::traced("hcat", mat, || hcat_impl(mat))
+}
+
+fn hcat_impl<T: Matrix>(mat: &[&T]) -> Result<T, JolinError>{
+    if mat.is_empty() {
         return Err(JolinError::not_enough_input())
     }
     let new_row = mat[0].row();
     for m in mat.iter() {
         if m.row() != new_row {
-            return Err(JolinError::shape_mismatching())
+            return Err(JolinError::shape_mismatching_with((new_row, m.column()), (m.row(), m.column())))
         }
     }
 
@@ -150,14 +280,18 @@ pub fn hcat<T: Matrix>(mat: &[&T]) -> Result<T, JolinError>{
 /// ```
 /// 
 /// A shape mismatching error will be returned if the column counts of the input matrices don't match.
-pub fn vcat<T: Matrix>(mat: &[&T]) -> Result<T, JolinError>{
-    if mat.len() < 1 {
+pub fn vcat<T: Matrix>(mat: &[&T]) -> Result<T, JolinError> {
+    trace::traced("vcat", mat, || vcat_impl(mat))
+}
+
+fn vcat_impl<T: Matrix>(mat: &[&T]) -> Result<T, JolinError>{
+    if mat.is_empty() {
         return Err(JolinError::not_enough_input())
     }
     let new_column = mat[0].column();
     for m in mat.iter() {
         if m.column() != new_column {
-            return Err(JolinError::shape_mismatching())
+            return Err(JolinError::shape_mismatching_with((m.row(), new_column), (m.row(), m.column())))
         }
     }
 
@@ -175,6 +309,58 @@ pub fn vcat<T: Matrix>(mat: &[&T]) -> Result<T, JolinError>{
     Ok(T::from_vec(new_row, new_column, data))
 }
 
+/// Augment a matrix with columns on the right, i.e. `[A | b]`, e.g. to build
+/// the augmented matrix of a linear system before Gaussian elimination.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0; 3.0, 4.0];
+/// let b = mat64![5.0; 6.0];
+/// assert_eq!(augment(&a, &b).unwrap(), mat64![1.0, 2.0, 5.0; 3.0, 4.0, 6.0]);
+/// ```
+///
+/// A shape mismatching error will be returned if the row counts of the matrices don't match.
+pub fn augment<T: Matrix>(a: &T, b: &T) -> Result<T, JolinError> {
+    hcat(&[a, b])
+}
+
+/// Append an identity matrix on the right of a square matrix, i.e. `[A | I]`,
+/// whose reduced row echelon form is `[I | A^-1]` for an invertible `A`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0; 3.0, 4.0];
+/// assert_eq!(append_identity(&a).unwrap(), mat64![1.0, 2.0, 1.0, 0.0; 3.0, 4.0, 0.0, 1.0]);
+/// ```
+///
+/// A shape mismatching error will be returned if the matrix is not square.
+pub fn append_identity<T: Matrix>(a: &T) -> Result<T, JolinError> {
+    if a.row() != a.column() {
+        return Err(JolinError::shape_mismatching())
+    }
+    hcat(&[a, &T::identity(a.row())])
+}
+
+/// Stack a scaled identity matrix below a matrix, i.e. `[A; sqrt(lambda) I]`.
+/// The least squares solution of `[A; sqrt(lambda) I] x = [b; 0]` is the
+/// ridge regression solution of `A x = b`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0];
+/// assert_eq!(stack_regularizer(&a, 4.0).unwrap(), mat64![1.0, 2.0; 2.0, 0.0; 0.0, 2.0]);
+/// ```
+///
+/// An invalid value error will be returned if `lambda` is negative or NaN.
+pub fn stack_regularizer<T: Matrix>(a: &T, lambda: T::Elem) -> Result<T, JolinError> {
+    if lambda < T::Elem::zero() || lambda.partial_cmp(&lambda).is_none() {
+        return Err(JolinError::invalid_value())
+    }
+    let mut scaled = T::identity(a.column());
+    scaled.apply(|x| x * lambda.sqrt());
+    vcat(&[a, &scaled])
+}
+
 /// Adding two matrices of the same shape
 /// 
 /// ```
@@ -187,8 +373,12 @@ pub fn vcat<T: Matrix>(mat: &[&T]) -> Result<T, JolinError>{
 /// 
 /// A shape mismatching error will be returned if their shapes don't match.
 pub fn add<T: Matrix>(a: &T, b: &T) -> Result<T, JolinError> {
+    trace::traced("add", &[a, b], || add_impl(a, b))
+}
+
+fn add_impl<T: Matrix>(a: &T, b: &T) -> Result<T, JolinError> {
     if a.row() != b.row() || a.column() != b.column() {
-        return Err(JolinError::shape_mismatching())
+        return Err(JolinError::shape_mismatching_with((a.row(), a.column()), (b.row(), b.column())))
     }
 
     let mut data: Vec<T::Elem> = Vec::new();
@@ -201,7 +391,7 @@ pub fn add<T: Matrix>(a: &T, b: &T) -> Result<T, JolinError> {
         }
     }
     
-    return Ok(T::from_vec(row, column, data));
+    Ok(T::from_vec(row, column, data))
 }
 
 /// Get the negative of the matrix
@@ -227,8 +417,12 @@ pub fn neg<T:Matrix>(a: &T) -> T {
 /// assert_eq!(c, Mat64::new(1, 2, &[0.5, 2.5]));
 /// ```
 pub fn sub<T:Matrix>(left: &T, right: &T) -> Result<T, JolinError> {
+    trace::traced("sub", &[left, right], || sub_impl(left, right))
+}
+
+fn sub_impl<T:Matrix>(left: &T, right: &T) -> Result<T, JolinError> {
     if left.row() != right.row() || left.column() != right.column() {
-        return Err(JolinError::shape_mismatching())
+        return Err(JolinError::shape_mismatching_with((left.row(), left.column()), (right.row(), right.column())))
     }
 
     let mut data: Vec<T::Elem> = Vec::new();
@@ -243,7 +437,37 @@ pub fn sub<T:Matrix>(left: &T, right: &T) -> Result<T, JolinError> {
     Ok(T::from_vec(row, column, data))
 }
 
-/// Multiple two matrices. 
+/// Element-wise (Hadamard) product of two matrices of the same shape
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0; 3.0, 4.0];
+/// let b = mat64![0.5, 2.0; -1.0, 0.0];
+/// assert_eq!(hadamard(&a, &b).unwrap(), mat64![0.5, 4.0; -3.0, 0.0]);
+/// ```
+///
+/// A shape mismatching error will be returned if their shapes don't match.
+pub fn hadamard<T: Matrix>(a: &T, b: &T) -> Result<T, JolinError> {
+    trace::traced("hadamard", &[a, b], || zip_with_impl(a, b, |x, y| (*x) * (*y)))
+}
+
+/// Element-wise division of two matrices of the same shape
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0; 3.0, 4.0];
+/// let b = mat64![0.5, 2.0; -1.0, 8.0];
+/// assert_eq!(elem_div(&a, &b).unwrap(), mat64![2.0, 1.0; -3.0, 0.5]);
+/// ```
+///
+/// A shape mismatching error will be returned if their shapes don't match.
+pub fn elem_div<T: Matrix>(left: &T, right: &T) -> Result<T, JolinError> {
+    trace::traced("elem_div", &[left, right], || zip_with_impl(left, right, |x, y| (*x) / (*y)))
+}
+
+/// Multiple two matrices.
 /// 
 /// ```
 /// # use jolin::matrix::{*};
@@ -253,22 +477,41 @@ pub fn sub<T:Matrix>(left: &T, right: &T) -> Result<T, JolinError> {
 /// let c = mul(&a, &b).unwrap();
 /// assert_eq!(c, mat64![0.5; 1.5]);
 /// ```
-
 pub fn mul<T: Matrix>(left: &T, right: &T) -> Result<T, JolinError> {
+    let settings = settings::get();
+    trace::traced("mul", &[left, right], || mul_impl(left, right, &settings))
+}
+
+/// Multiple two matrices with the kernel settings overriding the global ones.
+/// With the `blas` feature, the external BLAS is only used if `kernel` is
+/// `KernelChoice::Auto` and `max_threads` is zero, otherwise the requested
+/// pure Rust kernel runs.
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::settings::{self, Settings};
+/// let a = mat64![1.0, 0.0; 1.0, 1.0];
+/// let single_thread = Settings { max_threads: 1, ..settings::get() };
+/// assert_eq!(mul_with(&a, &a, &single_thread).unwrap(), mat64![1.0, 0.0; 2.0, 1.0]);
+/// ```
+pub fn mul_with<T: Matrix>(left: &T, right: &T, settings: &Settings) -> Result<T, JolinError> {
+    trace::traced("mul", &[left, right], || mul_impl(left, right, settings))
+}
+
+fn mul_impl<T: Matrix>(left: &T, right: &T, settings: &Settings) -> Result<T, JolinError> {
     if left.column() != right.row() {
-        return Err(JolinError::shape_mismatching())
+        return Err(JolinError::shape_mismatching_with((left.column(), right.column()), (right.row(), right.column())))
     }
     
     let mut ans = T::zero(left.row(), right.column());
-    for c in 0..ans.column() {
-        for r in 0..ans.row() {
-            let mut t = ans.elem(r, c); // must be a zero elem of T::Elem
-            for k in 0..left.column() {
-                t = t + left.elem(r, k) * right.elem(k, c)
-            }
-            *ans.elem_mut(r, c) = t;
-        }
+    // an external BLAS picks its own kernel and threads, so it only runs when
+    // the settings don't ask for either
+    let overridden = settings.kernel != KernelChoice::Auto || settings.max_threads != 0;
+    if !overridden && T::Elem::gemm(left.row(), right.column(), left.column(), left.data(), right.data(), ans.data_mut()) {
+        return Ok(ans)
     }
+    kernel::gemm(left.row(), right.column(), left.column(), left.data(), right.data(), ans.data_mut(), settings);
     Ok(ans)
 }
 
@@ -282,11 +525,7 @@ pub fn mul<T: Matrix>(left: &T, right: &T) -> Result<T, JolinError> {
 /// ```
 pub fn tr<T:Matrix>(a: &T) -> T {
     let mut ans = T::zero(a.column(), a.row());
-    for r in 0..a.row() {
-        for c in 0..a.column() {
-            *ans.elem_mut(c, r) = a.elem(r, c);
-        }
-    }
+    kernel::transpose(a.row(), a.column(), a.data(), ans.data_mut(), &settings::get());
     ans
 }
 
@@ -302,13 +541,17 @@ pub fn tr<T:Matrix>(a: &T) -> T {
 /// assert_eq!(c, mat64![2.0]);
 /// ```
 pub fn trmul<T: Matrix>(left: &T, right: &T) -> Result<T, JolinError> {
+    trace::traced("trmul", &[left, right], || trmul_impl(left, right))
+}
+
+fn trmul_impl<T: Matrix>(left: &T, right: &T) -> Result<T, JolinError> {
     if left.row() != right.row() {
-        return Err(JolinError::shape_mismatching()); 
+        return Err(JolinError::shape_mismatching_with((left.row(), right.column()), (right.row(), right.column())));
     }
 
     let mut ans = T::zero(left.column(), right.column());
-    for c in 0.. ans.column() {
-        for r in 0..ans.column() {
+    for c in 0..ans.column() {
+        for r in 0..ans.row() {
             let mut t = ans.elem(r, c);
             for k in 0..left.row() {
                 t = t + left.elem(k, r) * right.elem(k, c);
@@ -319,6 +562,144 @@ pub fn trmul<T: Matrix>(left: &T, right: &T) -> Result<T, JolinError> {
     Ok(ans)
 }
 
+/// Gram matrix `A^T A` of the columns. Only one triangle is computed and
+/// mirrored, which is about half the work of `trmul(&a, &a)`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0; 3.0, 4.0; 5.0, 6.0];
+/// assert_eq!(ata(&a), mat64![35.0, 44.0; 44.0, 56.0]);
+/// ```
+pub fn ata<T: Matrix>(a: &T) -> T {
+    let n = a.column();
+    let mut ans = T::zero(n, n);
+    for c in 0..n {
+        for r in 0..(c + 1) {
+            let v: T::Elem = a.data_column(r).iter().zip(a.data_column(c).iter()).map(|(x, y)| *x * *y).sum();
+            *ans.elem_mut(r, c) = v;
+            *ans.elem_mut(c, r) = v;
+        }
+    }
+    ans
+}
+
+/// Gram matrix `A A^T` of the rows. Only one triangle is computed and
+/// mirrored, which is about half the work of `mul(&a, &tr(&a))`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0; 3.0, 4.0];
+/// assert_eq!(aat(&a), mat64![5.0, 11.0; 11.0, 25.0]);
+/// ```
+#[allow(clippy::needless_range_loop)]
+pub fn aat<T: Matrix>(a: &T) -> T {
+    let m = a.row();
+    let mut ans = T::zero(m, m);
+    // rank-1 updates of the lower triangle by each column
+    for k in 0..a.column() {
+        let col = a.data_column(k);
+        for j in 0..m {
+            let v = col[j];
+            if v == T::Elem::zero() {
+                continue;
+            }
+            for i in j..m {
+                *ans.elem_mut(i, j) = ans.elem(i, j) + col[i] * v;
+            }
+        }
+    }
+    for j in 0..m {
+        for i in (j + 1)..m {
+            *ans.elem_mut(j, i) = ans.elem(i, j);
+        }
+    }
+    ans
+}
+
+/// General matrix multiplication `C = alpha op(A) op(B) + beta C` in the
+/// style of BLAS, where `op(X)` is `X^T` if its transpose flag is set and `X`
+/// otherwise. The transposes are never formed. When `beta` is zero, `C` is
+/// overwritten, so it may contain NaN.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0; 3.0, 4.0];
+/// let b = mat64![1.0, 0.0; 1.0, 1.0];
+/// let mut c = Mat64::identity(2);
+/// // C = A^T B^T + C
+/// gemm(1.0, &a, true, &b, true, 1.0, &mut c).unwrap();
+/// assert_eq!(c, mat64![2.0, 4.0; 2.0, 7.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `op(A)` and `op(B)` can't be multiplied, or `C` doesn't have the shape of their product.
+pub fn gemm<T: Matrix>(alpha: T::Elem, a: &T, trans_a: bool, b: &T, trans_b: bool, beta: T::Elem, c: &mut T) -> Result<(), JolinError> {
+    let (m, k) = if trans_a { (a.column(), a.row()) } else { (a.row(), a.column()) };
+    let (kb, n) = if trans_b { (b.column(), b.row()) } else { (b.row(), b.column()) };
+    if k != kb {
+        return Err(JolinError::shape_mismatching_with((k, n), (kb, n)))
+    }
+    if c.row() != m || c.column() != n {
+        return Err(JolinError::shape_mismatching_with((m, n), (c.row(), c.column())))
+    }
+    let settings = settings::get();
+    let product = match (trans_a, trans_b) {
+        (false, false) => mul_impl(a, b, &settings)?,
+        (true, false) => trmul_impl(a, b)?,
+        (false, true) => {
+            let mut ans = T::zero(m, n);
+            for j in 0..n {
+                for p in 0..k {
+                    let (a_col, ans_col) = (a.data_column(p), &mut ans.data_mut()[(j * m)..((j + 1) * m)]);
+                    T::Elem::axpy(b.elem(j, p), a_col, ans_col);
+                }
+            }
+            ans
+        }
+        (true, true) => {
+            let mut ans = T::zero(m, n);
+            for j in 0..n {
+                for i in 0..m {
+                    *ans.elem_mut(i, j) = (0..k).map(|p| a.elem(p, i) * b.elem(j, p)).sum();
+                }
+            }
+            ans
+        }
+    };
+    let zero = T::Elem::zero();
+    for (x, p) in c.data_mut().iter_mut().zip(product.data().iter()) {
+        *x = if beta == zero { alpha * *p } else { alpha * *p + beta * *x };
+    }
+    Ok(())
+}
+
+/// Kronecker product of two matrices
+///
+/// For a `m*n` matrix A and a `p*q` matrix B, the result is a `mp*nq` block
+/// matrix where the block at `[i, j]` is `A[i, j] * B`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0];
+/// let b = mat64![1.0; 10.0];
+/// assert_eq!(kron(&a, &b), mat64![1.0, 2.0; 10.0, 20.0]);
+/// ```
+pub fn kron<T: Matrix>(a: &T, b: &T) -> T {
+    let row = a.row() * b.row();
+    let column = a.column() * b.column();
+    let mut data: Vec<T::Elem> = Vec::new();
+    data.reserve_exact(row * column);
+    for ca in 0..a.column() {
+        for cb in 0..b.column() {
+            for ra in 0..a.row() {
+                let v = a.elem(ra, ca);
+                data.extend(b.data_column(cb).iter().map(|x| v * (*x)));
+            }
+        }
+    }
+    T::from_vec(row, column, data)
+}
+
 /// Apply element-wise operation on a matrix to create a new matrix
 /// 
 /// ```
@@ -333,6 +714,184 @@ pub fn elemwise<T: Matrix, F: FnMut(&T::Elem) -> T::Elem>(a: &T, f: F) -> T {
     T::from_vec(a.row(), a.column(), new_data)
 }
 
+/// Apply element-wise operation depending on the position of each element on
+/// a matrix to create a new matrix. The operation receives the row index, the
+/// column index and the element.
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0; 3.0, 4.0];
+/// let lower = elemwise_indexed(&a, |r, c, x| if c > r { 0.0 } else { *x });
+/// assert_eq!(lower, mat64![1.0, 0.0; 3.0, 4.0]);
+/// ```
+pub fn elemwise_indexed<T: Matrix, F: FnMut(usize, usize, &T::Elem) -> T::Elem>(a: &T, mut f: F) -> T {
+    let row = a.row();
+    let new_data: Vec<T::Elem> = a.data().iter().enumerate()
+        .map(|(i, x)| f(i % row, i / row, x))
+        .collect();
+    T::from_vec(row, a.column(), new_data)
+}
+
+/// Apply element-wise binary operation on two matrices of the same shape to
+/// create a new matrix
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0; 3.0, 4.0];
+/// let b = mat64![4.0, 3.0; 2.0, 1.0];
+/// let m = zip_with(&a, &b, |x, y| if x > y { *x } else { *y }).unwrap();
+/// assert_eq!(m, mat64![4.0, 3.0; 3.0, 4.0]);
+/// ```
+///
+/// A shape mismatching error will be returned if their shapes don't match.
+pub fn zip_with<T: Matrix, F: FnMut(&T::Elem, &T::Elem) -> T::Elem>(a: &T, b: &T, f: F) -> Result<T, JolinError> {
+    trace::traced("zip_with", &[a, b], || zip_with_impl(a, b, f))
+}
+
+fn zip_with_impl<T: Matrix, F: FnMut(&T::Elem, &T::Elem) -> T::Elem>(a: &T, b: &T, mut f: F) -> Result<T, JolinError> {
+    if a.row() != b.row() || a.column() != b.column() {
+        return Err(JolinError::shape_mismatching_with((a.row(), a.column()), (b.row(), b.column())))
+    }
+    let new_data: Vec<T::Elem> = a.data().iter().zip(b.data().iter())
+        .map(|(x, y)| f(x, y)).collect();
+    Ok(T::from_vec(a.row(), a.column(), new_data))
+}
+
+/// Limit each element of the matrix to the range `[lo, hi]`. NaN elements
+/// stay NaN.
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![-2.0, 0.5; 3.0, 1.0];
+/// assert_eq!(clamp(&a, 0.0, 1.0).unwrap(), mat64![0.0, 0.5; 1.0, 1.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Invalid value - if `lo > hi` or either bound is NaN.
+pub fn clamp<T: Matrix>(a: &T, lo: T::Elem, hi: T::Elem) -> Result<T, JolinError> {
+    if lo.partial_cmp(&hi).is_none_or(|o| o == std::cmp::Ordering::Greater) {
+        return Err(JolinError::invalid_value())
+    }
+    Ok(elemwise(a, |x| if *x < lo { lo } else if *x > hi { hi } else { *x }))
+}
+
+/// Element-wise maximum of the matrix and a scalar. NaN elements stay NaN.
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![-2.0, 0.5; 3.0, -1.0];
+/// assert_eq!(maximum_scalar(&a, 0.0), mat64![0.0, 0.5; 3.0, 0.0]);
+/// ```
+pub fn maximum_scalar<T: Matrix>(a: &T, s: T::Elem) -> T {
+    elemwise(a, |x| if *x < s { s } else { *x })
+}
+
+/// Element-wise minimum of the matrix and a scalar. NaN elements stay NaN.
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![-2.0, 0.5; 3.0, -1.0];
+/// assert_eq!(minimum_scalar(&a, 0.0), mat64![-2.0, 0.0; 0.0, -1.0]);
+/// ```
+pub fn minimum_scalar<T: Matrix>(a: &T, s: T::Elem) -> T {
+    elemwise(a, |x| if *x > s { s } else { *x })
+}
+
+/// Apply an operation on each column of a matrix to create a new matrix. The
+/// operation receives the column index and the column, and the returned
+/// vectors become the columns of the answer.
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0; 3.0, 6.0];
+/// // normalize each column to sum to one
+/// let p = map_columns(&a, |_, col| {
+///     let s: f64 = col.iter().sum();
+///     col.iter().map(|x| x / s).collect()
+/// }).unwrap();
+/// assert_eq!(p, mat64![0.25, 0.25; 0.75, 0.75]);
+/// ```
+///
+/// A shape mismatching error will be returned if the returned vectors have
+/// different lengths.
+pub fn map_columns<T: Matrix, F: FnMut(usize, &[T::Elem]) -> Vec<T::Elem>>(a: &T, mut f: F) -> Result<T, JolinError> {
+    let mut data: Vec<T::Elem> = Vec::new();
+    let mut row = 0;
+    for c in 0..a.column() {
+        let col = f(c, a.data_column(c));
+        if c == 0 {
+            row = col.len();
+        } else if col.len() != row {
+            return Err(JolinError::shape_mismatching())
+        }
+        data.extend(col);
+    }
+    Ok(T::from_vec(row, a.column(), data))
+}
+
+/// Apply an operation on each row of a matrix to create a new matrix. The
+/// operation receives the row index and the row, and the returned vectors
+/// become the rows of the answer.
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0; 3.0, 4.0];
+/// // append the sum of each row as a new feature
+/// let b = map_rows(&a, |_, row| vec![row[0], row[1], row[0] + row[1]]).unwrap();
+/// assert_eq!(b, mat64![1.0, 2.0, 3.0; 3.0, 4.0, 7.0]);
+/// ```
+///
+/// A shape mismatching error will be returned if the returned vectors have
+/// different lengths.
+pub fn map_rows<T: Matrix, F: FnMut(usize, &[T::Elem]) -> Vec<T::Elem>>(a: &T, mut f: F) -> Result<T, JolinError> {
+    let mut rows: Vec<Vec<T::Elem>> = Vec::with_capacity(a.row());
+    let mut buf: Vec<T::Elem> = Vec::with_capacity(a.column());
+    for r in 0..a.row() {
+        buf.clear();
+        buf.extend((0..a.column()).map(|c| a.elem(r, c)));
+        let row = f(r, &buf);
+        if r > 0 && row.len() != rows[0].len() {
+            return Err(JolinError::shape_mismatching())
+        }
+        rows.push(row);
+    }
+    let column = rows.first().map_or(0, |row| row.len());
+    let mut ans = T::zero(a.row(), column);
+    for (r, row) in rows.iter().enumerate() {
+        for (c, x) in row.iter().enumerate() {
+            *ans.elem_mut(r, c) = *x;
+        }
+    }
+    Ok(ans)
+}
+
+/// Modify each column of a matrix in place. The operation receives the
+/// column index and the column.
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let mut a = mat64![1.0, 2.0; 3.0, 4.0];
+/// for_each_column_mut(&mut a, |c, col| col.iter_mut().for_each(|x| *x *= (c + 1) as f64));
+/// assert_eq!(a, mat64![1.0, 4.0; 3.0, 8.0]);
+/// ```
+pub fn for_each_column_mut<T: Matrix, F: FnMut(usize, &mut [T::Elem])>(a: &mut T, mut f: F) {
+    let row = a.row();
+    if row == 0 {
+        return
+    }
+    for (c, col) in a.data_mut().chunks_exact_mut(row).enumerate() {
+        f(c, col);
+    }
+}
+
 /// Whether two matrices are equal with the allowed error
 pub fn eq_with_error<T:Matrix>(a: &T, b:&T, eps: T::Elem) -> bool {
     // different shape
@@ -348,5 +907,25 @@ pub fn eq_with_error<T:Matrix>(a: &T, b:&T, eps: T::Elem) -> bool {
     true
 }
 
+/// Whether two matrices are equal with NumPy `allclose` semantics, i.e.
+/// `|a - b| <= atol + rtol * |b|` for all elements. Unlike `eq_with_error`,
+/// the allowed error scales with the magnitude of the elements. The check is
+/// not symmetric as `b` is treated as the reference. NaN is never close to
+/// anything, and infinities are only close to themselves.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1e-8, 1e8];
+/// assert!(allclose(&a, &mat64![1.1e-8, 1e8 + 1.0], 1e-5, 1e-8));
+/// assert!(!allclose(&a, &mat64![1e-8, 1.1e8], 1e-5, 1e-8));
+/// ```
+pub fn allclose<T: Matrix>(a: &T, b: &T, rtol: T::Elem, atol: T::Elem) -> bool {
+    if a.row() != b.row() || a.column() != b.column() {
+        return false
+    }
+    a.data().iter().zip(b.data().iter())
+        .all(|(x, y)| x == y || (y.to_f64().is_finite() && (*x - *y).abs() <= atol + rtol * y.abs()))
+}
+
 #[cfg(test)]
 mod test;
\ No newline at end of file
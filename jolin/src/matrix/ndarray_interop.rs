@@ -0,0 +1,98 @@
+/*
+ * matrix/ndarray_interop.rs
+ * Conversions between matrices and ndarray arrays.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use ndarray::{Array2, ArrayView2, ArrayViewMut2, ShapeBuilder};
+use super::{Matrix, MatrixView, Mat64, Mat32};
+
+macro_rules! impl_ndarray_interop {
+    ($mat: ty, $t: ty) => {
+        /// The array is moved without copying if it is contiguous in
+        /// column-major (Fortran) order, otherwise it is copied.
+        impl From<Array2<$t>> for $mat {
+            fn from(array: Array2<$t>) -> Self {
+                let (row, column) = array.dim();
+                if !array.t().is_standard_layout() {
+                    return <$mat>::from_vec(row, column, array.t().iter().cloned().collect())
+                }
+                let n = row * column;
+                let (mut data, offset) = array.into_raw_vec_and_offset();
+                let offset = offset.unwrap_or(0);
+                if offset != 0 || data.len() != n {
+                    data = data[offset..(offset + n)].to_vec();
+                }
+                <$mat>::from_vec(row, column, data)
+            }
+        }
+
+        impl From<ArrayView2<'_, $t>> for $mat {
+            fn from(view: ArrayView2<'_, $t>) -> Self {
+                let (row, column) = view.dim();
+                <$mat>::from_vec(row, column, view.t().iter().cloned().collect())
+            }
+        }
+
+        impl $mat {
+            /// Copy the matrix to an ndarray array in column-major order
+            pub fn to_ndarray(&self) -> Array2<$t> {
+                self.as_ndarray().to_owned()
+            }
+
+            /// Borrow the matrix as an ndarray view without copying
+            pub fn as_ndarray(&self) -> ArrayView2<'_, $t> {
+                ArrayView2::from_shape((self.row(), self.column()).f(), self.data()).unwrap()
+            }
+
+            /// Borrow the matrix as a mutable ndarray view without copying
+            pub fn as_ndarray_mut(&mut self) -> ArrayViewMut2<'_, $t> {
+                let shape = (self.row(), self.column()).f();
+                ArrayViewMut2::from_shape(shape, self.data_mut()).unwrap()
+            }
+        }
+    };
+}
+
+impl_ndarray_interop!(Mat64, f64);
+impl_ndarray_interop!(Mat32, f32);
+
+#[cfg(test)]
+mod test {
+    use ndarray::{array, s, Array2, ShapeBuilder};
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::decomp::lu::lu;
+
+    #[test]
+    fn test_ndarray_conversions() {
+        let a = array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+        let expected = mat64![1.0, 2.0, 3.0; 4.0, 5.0, 6.0];
+        assert_eq!(Mat64::from(a.clone()), expected);
+        assert_eq!(Mat64::from(a.view()), expected);
+        assert_eq!(Mat64::from(a.slice(s![.., 1..])), mat64![2.0, 3.0; 5.0, 6.0]);
+        assert_eq!(expected.to_ndarray(), a);
+
+        // column-major arrays are moved, including sliced ones
+        let f = Array2::from_shape_vec((2, 3).f(), vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]).unwrap();
+        let ptr = f.as_ptr();
+        let m = Mat64::from(f.clone());
+        assert_eq!(m, expected);
+        let moved = Mat64::from(f);
+        assert_eq!(moved.data().as_ptr(), ptr);
+        let mut g = Array2::from_shape_vec((2, 3).f(), vec![1.0, 4.0, 2.0, 5.0, 3.0, 6.0]).unwrap();
+        g.slice_collapse(s![.., 1..]);
+        assert_eq!(Mat64::from(g), mat64![2.0, 3.0; 5.0, 6.0]);
+
+        let mut b = expected.clone();
+        b.as_ndarray_mut()[[1, 2]] = 0.0;
+        assert_eq!(b.elem(1, 2), 0.0);
+        assert_eq!(b.as_ndarray().sum(), 15.0);
+
+        let c = Mat32::from(array![[2.0f32, 1.0], [4.0, 3.0]]);
+        let ans = lu(&c).unwrap();
+        assert_eq!(ans.u.to_ndarray(), array![[4.0f32, 3.0], [0.0, -0.5]]);
+    }
+}
@@ -0,0 +1,146 @@
+/*
+ * matrix/promote.rs
+ * Promotion rules of operations between matrices of different precisions.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use super::{Matrix, Mat32, Mat64, add, sub, mul, hadamard, eq_with_error};
+use crate::error::JolinError;
+
+/// Matrices that can be promoted to a 64-bit matrix without losing precision.
+///
+/// The promotion rule of jolin is simple: whenever the operands of an
+/// operation have different precisions, both are promoted to 64-bit and the
+/// answer is a `Mat64`. Promoting a `Mat32` is exact, so the answer is the
+/// same as computing in 64-bit on manually converted operands.
+pub trait PromoteTo64: Matrix {
+    /// Get the 64-bit copy of the matrix
+    fn promote(&self) -> Mat64;
+}
+
+impl PromoteTo64 for Mat64 {
+    fn promote(&self) -> Mat64 {
+        self.clone()
+    }
+}
+
+impl PromoteTo64 for Mat32 {
+    fn promote(&self) -> Mat64 {
+        self.to_f64()
+    }
+}
+
+impl Mat32 {
+    /// Get the 64-bit copy of the matrix, which is exact
+    pub fn to_f64(&self) -> Mat64 {
+        self.cast()
+    }
+}
+
+impl Mat64 {
+    /// Get the 32-bit copy of the matrix, rounding each element to the
+    /// nearest `f32`
+    /// ```
+    /// # use jolin::matrix::{*};
+    /// let a = Mat64::new(1, 2, &[1.0, 1e-50]);
+    /// assert_eq!(a.to_f32(), Mat32::new(1, 2, &[1.0, 0.0]));
+    /// ```
+    pub fn to_f32(&self) -> Mat32 {
+        self.cast()
+    }
+}
+
+/// Add two matrices of possibly different precisions
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// let a = Mat32::new(1, 2, &[1.0, 2.0]);
+/// let b = Mat64::new(1, 2, &[0.25, 0.5]);
+/// assert_eq!(add_promote(&a, &b).unwrap(), Mat64::new(1, 2, &[1.25, 2.5]));
+/// ```
+///
+/// A shape mismatching error will be returned if their shapes don't match.
+pub fn add_promote<A: PromoteTo64, B: PromoteTo64>(a: &A, b: &B) -> Result<Mat64, JolinError> {
+    add(&a.promote(), &b.promote())
+}
+
+/// Subtract a matrix from another one of possibly different precision
+///
+/// A shape mismatching error will be returned if their shapes don't match.
+pub fn sub_promote<A: PromoteTo64, B: PromoteTo64>(left: &A, right: &B) -> Result<Mat64, JolinError> {
+    sub(&left.promote(), &right.promote())
+}
+
+/// Multiply two matrices of possibly different precisions
+///
+/// A shape mismatching error will be returned if the column count of the
+/// left matrix doesn't match the row count of the right matrix.
+pub fn mul_promote<A: PromoteTo64, B: PromoteTo64>(left: &A, right: &B) -> Result<Mat64, JolinError> {
+    mul(&left.promote(), &right.promote())
+}
+
+/// Element-wise product of two matrices of possibly different precisions
+///
+/// A shape mismatching error will be returned if their shapes don't match.
+pub fn hadamard_promote<A: PromoteTo64, B: PromoteTo64>(a: &A, b: &B) -> Result<Mat64, JolinError> {
+    hadamard(&a.promote(), &b.promote())
+}
+
+/// Whether two matrices of possibly different precisions are exactly equal
+/// after promotion
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// let a = Mat32::new(1, 2, &[0.5, 0.1]);
+/// assert!(eq_promote(&a, &Mat64::new(1, 2, &[0.5, 0.1f32 as f64])));
+/// // 0.1 is not exactly representable, so the f32 and f64 roundings differ
+/// assert!(!eq_promote(&a, &Mat64::new(1, 2, &[0.5, 0.1])));
+/// ```
+pub fn eq_promote<A: PromoteTo64, B: PromoteTo64>(a: &A, b: &B) -> bool {
+    a.promote() == b.promote()
+}
+
+/// Whether two matrices of possibly different precisions are equal with the
+/// allowed error after promotion
+pub fn eq_with_error_promote<A: PromoteTo64, B: PromoteTo64>(a: &A, b: &B, eps: f64) -> bool {
+    eq_with_error(&a.promote(), &b.promote(), eps)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matrix::{*};
+
+    #[test]
+    fn test_promote_operations() {
+        let a = Mat32::new(2, 2, &[1.0, 2.0, 3.0, 4.0]);
+        let b = Mat64::new(2, 2, &[0.5, 0.5, -0.5, -0.5]);
+        assert_eq!(add_promote(&a, &b).unwrap(), Mat64::new(2, 2, &[1.5, 2.5, 2.5, 3.5]));
+        assert_eq!(sub_promote(&b, &a).unwrap(), Mat64::new(2, 2, &[-0.5, -1.5, -3.5, -4.5]));
+        assert_eq!(mul_promote(&a, &b).unwrap(), mul(&a.promote(), &b).unwrap());
+        assert_eq!(hadamard_promote(&a, &a).unwrap(), Mat64::new(2, 2, &[1.0, 4.0, 9.0, 16.0]));
+        assert!(add_promote(&a, &Mat64::zero(1, 2)).is_err());
+    }
+
+    #[test]
+    fn test_promote_comparison() {
+        let a = Mat32::new(1, 2, &[0.1, 0.2]);
+        let b = Mat64::new(1, 2, &[0.1, 0.2]);
+        assert!(!eq_promote(&a, &b));
+        assert!(eq_with_error_promote(&a, &b, 1e-7));
+        assert!(!eq_with_error_promote(&a, &Mat64::zero(2, 1), 1e-7));
+    }
+
+    #[test]
+    fn test_cast() {
+        let a = Mat64::new(2, 2, &[1.0, 0.1, -2.5, 3e40]);
+        let b = a.to_f32();
+        assert_eq!(b.data(), &[1.0, 0.1, -2.5, f32::INFINITY]);
+        assert_eq!(b.to_f64().data()[1], 0.1f32 as f64);
+        assert_eq!(b.to_f64(), b.promote());
+        let c: Mat64 = a.cast();
+        assert_eq!(c, a);
+        assert_eq!(Mat32::zero(0, 3).to_f64(), Mat64::zero(0, 3));
+    }
+}
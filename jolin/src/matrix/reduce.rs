@@ -0,0 +1,177 @@
+/*
+ * matrix/reduce.rs
+ * Reductions of matrices along rows or columns.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use super::{Matrix, LikeNumber};
+use crate::error::JolinError;
+
+/// The direction along which a reduction is computed.
+#[derive(Debug, PartialEq, Copy, Clone, Eq)]
+pub enum Axis {
+    /// Reduce each row, the answer is a column vector with one element per row
+    Row,
+    /// Reduce each column, the answer is a row vector with one element per column
+    Column,
+}
+
+/// Sum of the elements of each row or column
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0, 3.0; 4.0, 5.0, 6.0];
+/// assert_eq!(sum_axis(&a, Axis::Column), mat64![5.0, 7.0, 9.0]);
+/// assert_eq!(sum_axis(&a, Axis::Row), mat64![6.0; 15.0]);
+/// ```
+pub fn sum_axis<T: Matrix>(a: &T, axis: Axis) -> T {
+    reduce_axis(a, axis, |v| v.iter().copied().sum())
+}
+
+/// Arithmetic mean of the elements of each row or column
+///
+/// The mean over an empty row or column is not a number.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 2.0, 3.0; 4.0, 5.0, 6.0];
+/// assert_eq!(mean_axis(&a, Axis::Column), mat64![2.5, 3.5, 4.5]);
+/// assert_eq!(mean_axis(&a, Axis::Row), mat64![2.0; 5.0]);
+/// ```
+pub fn mean_axis<T: Matrix>(a: &T, axis: Axis) -> T {
+    reduce_axis(a, axis, |v| {
+        v.iter().copied().sum::<T::Elem>() / T::Elem::one().times_real(v.len() as f64)
+    })
+}
+
+/// Minimal element of each row or column
+///
+/// A not enough input error will be returned if the rows or columns are empty.
+pub fn min_axis<T: Matrix>(a: &T, axis: Axis) -> Result<T, JolinError> {
+    let idx = argmin_axis(a, axis)?;
+    Ok(gather_axis(a, axis, &idx))
+}
+
+/// Maximal element of each row or column
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 8.0, 3.0; 4.0, 5.0, 6.0];
+/// assert_eq!(max_axis(&a, Axis::Column).unwrap(), mat64![4.0, 8.0, 6.0]);
+/// assert_eq!(max_axis(&a, Axis::Row).unwrap(), mat64![8.0; 6.0]);
+/// ```
+///
+/// A not enough input error will be returned if the rows or columns are empty.
+pub fn max_axis<T: Matrix>(a: &T, axis: Axis) -> Result<T, JolinError> {
+    let idx = argmax_axis(a, axis)?;
+    Ok(gather_axis(a, axis, &idx))
+}
+
+/// Index of the maximal element of each row or column. The first index is
+/// returned for ties.
+///
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// let a = mat64![1.0, 8.0, 3.0; 4.0, 5.0, 6.0];
+/// assert_eq!(argmax_axis(&a, Axis::Column).unwrap(), vec![1, 0, 1]);
+/// assert_eq!(argmax_axis(&a, Axis::Row).unwrap(), vec![1, 2]);
+/// ```
+///
+/// A not enough input error will be returned if the rows or columns are empty.
+pub fn argmax_axis<T: Matrix>(a: &T, axis: Axis) -> Result<Vec<usize>, JolinError> {
+    arg_select_axis(a, axis, |candidate, best| candidate > best)
+}
+
+fn argmin_axis<T: Matrix>(a: &T, axis: Axis) -> Result<Vec<usize>, JolinError> {
+    arg_select_axis(a, axis, |candidate, best| candidate < best)
+}
+
+/// Find the index of the best element of each row or column, where
+/// `better(candidate, best)` tells whether the candidate wins.
+fn arg_select_axis<T: Matrix, F: Fn(T::Elem, T::Elem) -> bool>(a: &T, axis: Axis, better: F) -> Result<Vec<usize>, JolinError> {
+    let (outer, inner) = match axis {
+        Axis::Row => (a.row(), a.column()),
+        Axis::Column => (a.column(), a.row()),
+    };
+    if inner == 0 {
+        return Err(JolinError::not_enough_input())
+    }
+    let get = |o: usize, i: usize| match axis {
+        Axis::Row => a.elem(o, i),
+        Axis::Column => a.elem(i, o),
+    };
+    Ok((0..outer).map(|o| {
+        let mut best = 0;
+        for i in 1..inner {
+            if better(get(o, i), get(o, best)) {
+                best = i;
+            }
+        }
+        best
+    }).collect())
+}
+
+/// Pick one element from each row or column with the given indices.
+fn gather_axis<T: Matrix>(a: &T, axis: Axis, idx: &[usize]) -> T {
+    match axis {
+        Axis::Row => {
+            let data = idx.iter().enumerate().map(|(r, c)| a.elem(r, *c)).collect();
+            T::from_vec(a.row(), 1, data)
+        }
+        Axis::Column => {
+            let data = idx.iter().enumerate().map(|(c, r)| a.elem(*r, c)).collect();
+            T::from_vec(1, a.column(), data)
+        }
+    }
+}
+
+/// Apply the reduction `f` on each row or column.
+fn reduce_axis<T: Matrix, F: Fn(&[T::Elem]) -> T::Elem>(a: &T, axis: Axis, f: F) -> T {
+    match axis {
+        Axis::Column => {
+            let data = (0..a.column()).map(|c| f(a.data_column(c))).collect();
+            T::from_vec(1, a.column(), data)
+        }
+        Axis::Row => {
+            let mut buf = Vec::new();
+            buf.reserve_exact(a.column());
+            let data = (0..a.row()).map(|r| {
+                buf.clear();
+                buf.extend((0..a.column()).map(|c| a.elem(r, c)));
+                f(&buf)
+            }).collect();
+            T::from_vec(a.row(), 1, data)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+
+    #[test]
+    fn test_min_max_axis() {
+        let a = Mat32::new(2, 3, &[3.0, -1.0, 2.0, 2.0, -5.0, 7.0]);
+        assert_eq!(min_axis(&a, Axis::Column).unwrap(), Mat32::new(1, 3, &[-1.0, 2.0, -5.0]));
+        assert_eq!(min_axis(&a, Axis::Row).unwrap(), Mat32::new(2, 1, &[-5.0, -1.0]));
+        assert_eq!(max_axis(&a, Axis::Column).unwrap(), Mat32::new(1, 3, &[3.0, 2.0, 7.0]));
+        assert_eq!(max_axis(&a, Axis::Row).unwrap(), Mat32::new(2, 1, &[3.0, 7.0]));
+        // ties resolve to the first index
+        assert_eq!(argmax_axis(&a, Axis::Column).unwrap(), vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn test_empty_axis() {
+        let a = Mat64::zero(0, 3);
+        assert_eq!(sum_axis(&a, Axis::Column), Mat64::zero(1, 3));
+        assert_eq!(sum_axis(&a, Axis::Row), Mat64::zero(0, 1));
+        assert_eq!(max_axis(&a, Axis::Column).unwrap_err().kind(), JolinErrorKind::NotEnoughInput);
+        assert_eq!(argmax_axis(&a, Axis::Row).unwrap(), Vec::<usize>::new());
+    }
+}
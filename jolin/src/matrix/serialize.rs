@@ -0,0 +1,85 @@
+/*
+ * matrix/serialize.rs
+ * Serialization of matrices with serde.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::Error;
+use super::{Matrix, Mat64, Mat32};
+
+/// A matrix is serialized as its shape and its data in column-major order
+#[derive(Serialize)]
+struct MatrixRef<'a, E> {
+    row: usize,
+    column: usize,
+    data: &'a [E],
+}
+
+#[derive(Deserialize)]
+struct MatrixData<E> {
+    row: usize,
+    column: usize,
+    data: Vec<E>,
+}
+
+fn serialize_matrix<T: Matrix, S: Serializer>(mat: &T, serializer: S) -> Result<S::Ok, S::Error>
+where T::Elem: Serialize {
+    MatrixRef { row: mat.row(), column: mat.column(), data: mat.data() }.serialize(serializer)
+}
+
+fn deserialize_matrix<'de, T: Matrix, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error>
+where T::Elem: Deserialize<'de> {
+    let raw = MatrixData::<T::Elem>::deserialize(deserializer)?;
+    let len = raw.data.len();
+    T::try_from_vec(raw.row, raw.column, raw.data).map_err(|_| D::Error::custom(format!(
+        "{} elements don't fit a {}x{} matrix", len, raw.row, raw.column
+    )))
+}
+
+impl Serialize for Mat64 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_matrix(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Mat64 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_matrix(deserializer)
+    }
+}
+
+impl Serialize for Mat32 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_matrix(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Mat32 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_matrix(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+
+    #[test]
+    fn test_serde_matrix() {
+        let a = mat64![1.0, 2.0, 3.0; 4.0, 5.0, 6.0];
+        let json = serde_json::to_string(&a).unwrap();
+        assert_eq!(json, r#"{"row":2,"column":3,"data":[1.0,4.0,2.0,5.0,3.0,6.0]}"#);
+        assert_eq!(serde_json::from_str::<Mat64>(&json).unwrap(), a);
+
+        let b = Mat32::new(1, 2, &[0.5, -1.5]);
+        let json = serde_json::to_string(&b).unwrap();
+        assert_eq!(serde_json::from_str::<Mat32>(&json).unwrap(), b);
+
+        assert!(serde_json::from_str::<Mat64>(r#"{"row":2,"column":2,"data":[1.0,2.0,3.0]}"#).is_err());
+        assert!(serde_json::from_str::<Mat64>(r#"{"row":2,"data":[1.0,2.0]}"#).is_err());
+    }
+}
@@ -29,4 +29,213 @@ fn test_vcat() {
     let c = Mat32::new(1, 2, &[7.0, 8.0]);
     let cat = vcat(&[&a, &b, &c]).unwrap();
     assert_eq!(cat, Mat32::new(4, 2, &[1.0, 2.0, 5.0, 7.0, 3.0, 4.0, 6.0, 8.0]));
-}
\ No newline at end of file
+}
+#[test]
+fn test_augment() {
+    let a = Mat64::new(2, 2, &[1.0, 3.0, 2.0, 4.0]);
+    let b = Mat64::new(2, 1, &[5.0, 6.0]);
+    assert_eq!(augment(&a, &b).unwrap(), Mat64::new(2, 3, &[1.0, 3.0, 2.0, 4.0, 5.0, 6.0]));
+    assert!(augment(&a, &Mat64::zero(3, 1)).is_err());
+    assert_eq!(append_identity(&a).unwrap(), Mat64::new(2, 4, &[1.0, 3.0, 2.0, 4.0, 1.0, 0.0, 0.0, 1.0]));
+    assert!(append_identity(&b).is_err());
+    let stacked = stack_regularizer(&b, 9.0).unwrap();
+    assert_eq!(stacked, Mat64::new(3, 1, &[5.0, 6.0, 3.0]));
+    assert_eq!(stack_regularizer(&a, 0.0).unwrap().row(), 4);
+    assert_eq!(stack_regularizer(&a, -1.0).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+    assert!(stack_regularizer(&a, f64::NAN).is_err());
+}
+
+#[test]
+fn test_trmul() {
+    let a = Mat64::new(3, 2, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    let b = Mat64::new(3, 1, &[1.0, 0.0, -1.0]);
+    assert_eq!(trmul(&a, &b).unwrap(), mul(&tr(&a), &b).unwrap());
+    assert_eq!(trmul(&b, &a).unwrap(), Mat64::new(1, 2, &[-2.0, -2.0]));
+    assert_eq!(trmul(&a, &a).unwrap(), mul(&tr(&a), &a).unwrap());
+}
+
+#[test]
+fn test_gemm() {
+    let a = Mat64::new(3, 2, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    let b = Mat64::new(2, 3, &[1.0, -1.0, 0.5, 2.0, 0.0, 3.0]);
+    let at = tr(&a);
+    let bt = tr(&b);
+    let c0 = Mat64::new(3, 3, &[1.0; 9]);
+    for (trans_a, trans_b) in [(false, false), (true, false), (false, true), (true, true)] {
+        let x = if trans_a { &at } else { &a };
+        let y = if trans_b { &bt } else { &b };
+        let mut c = c0.clone();
+        gemm(2.0, x, trans_a, y, trans_b, -1.0, &mut c).unwrap();
+        let expected = sub(&elemwise(&mul(&a, &b).unwrap(), |v| 2.0 * v), &c0).unwrap();
+        assert_eq!(c, expected);
+    }
+    let mut c = Mat64::new(2, 2, &[f64::NAN; 4]);
+    gemm(1.0, &a, true, &a, false, 0.0, &mut c).unwrap();
+    assert_eq!(c, trmul(&a, &a).unwrap());
+    assert!(gemm(1.0, &a, false, &b, true, 0.0, &mut c).is_err());
+    assert!(gemm(1.0, &a, false, &b, false, 0.0, &mut c).is_err());
+}
+
+#[test]
+fn test_kron() {
+    let a = Mat64::new(2, 2, &[1.0, 3.0, 2.0, 4.0]);
+    let b = Mat64::new(2, 2, &[0.0, 1.0, 5.0, 6.0]);
+    let k = kron(&a, &b);
+    assert_eq!(k.row(), 4);
+    assert_eq!(k.column(), 4);
+    assert_eq!(k, crate::mat64![
+        0.0, 5.0, 0.0, 10.0;
+        1.0, 6.0, 2.0, 12.0;
+        0.0, 15.0, 0.0, 20.0;
+        3.0, 18.0, 4.0, 24.0
+    ]);
+
+    let v = Mat64::new(3, 1, &[1.0, 2.0, 3.0]);
+    assert_eq!(kron(&Mat64::identity(1), &v), v);
+}
+
+#[test]
+fn test_hadamard_and_elem_div() {
+    let a = Mat32::new(2, 2, &[1.0, 2.0, 3.0, 4.0]);
+    let b = Mat32::new(2, 2, &[2.0, 4.0, 6.0, 8.0]);
+    let p = hadamard(&a, &b).unwrap();
+    assert_eq!(p, Mat32::new(2, 2, &[2.0, 8.0, 18.0, 32.0]));
+    assert_eq!(elem_div(&p, &b).unwrap(), a);
+
+    let c = Mat32::new(1, 4, &[1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(hadamard(&a, &c).unwrap_err(), JolinError::shape_mismatching());
+    assert_eq!(elem_div(&a, &c).unwrap_err(), JolinError::shape_mismatching());
+}
+
+#[test]
+fn test_zip_with() {
+    let a = Mat64::new(2, 2, &[1.0, 2.0, 3.0, 4.0]);
+    let b = Mat64::new(2, 2, &[3.0, 1.0, 4.0, 1.0]);
+    let diff_sq = zip_with(&a, &b, |x, y| (*x - *y) * (*x - *y)).unwrap();
+    assert_eq!(diff_sq, Mat64::new(2, 2, &[4.0, 1.0, 1.0, 9.0]));
+    assert_eq!(zip_with(&a, &b, |x, y| *x + *y).unwrap(), add(&a, &b).unwrap());
+
+    let c = Mat64::new(4, 1, &[1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(zip_with(&a, &c, |x, _| *x).unwrap_err(), JolinError::shape_mismatching());
+}
+
+#[test]
+fn test_elemwise_indexed() {
+    let a = Mat64::new(3, 2, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    let pos = elemwise_indexed(&a, |r, c, _| (r * 10 + c) as f64);
+    assert_eq!(pos, Mat64::new(3, 2, &[0.0, 10.0, 20.0, 1.0, 11.0, 21.0]));
+    let weighted = elemwise_indexed(&a, |r, c, x| *x * (r.abs_diff(c) as f64));
+    assert_eq!(weighted, Mat64::new(3, 2, &[0.0, 2.0, 6.0, 4.0, 0.0, 6.0]));
+    assert_eq!(elemwise_indexed(&Mat64::zero(0, 3), |_, _, x| *x), Mat64::zero(0, 3));
+}
+
+#[test]
+fn test_map_columns_rows() {
+    let a = Mat64::new(2, 3, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    let centered = map_columns(&a, |_, col| {
+        let mean = col.iter().sum::<f64>() / col.len() as f64;
+        col.iter().map(|x| x - mean).collect()
+    }).unwrap();
+    assert_eq!(centered, Mat64::new(2, 3, &[-0.5, 0.5, -0.5, 0.5, -0.5, 0.5]));
+    let sums = map_columns(&a, |c, col| vec![col[0] + col[1], c as f64]).unwrap();
+    assert_eq!(sums, Mat64::new(2, 3, &[3.0, 0.0, 7.0, 1.0, 11.0, 2.0]));
+    assert!(map_columns(&a, |c, col| col[..(c % 2 + 1)].to_vec()).is_err());
+
+    let picked = map_rows(&a, |r, row| vec![row[2 - r]]).unwrap();
+    assert_eq!(picked, Mat64::new(2, 1, &[5.0, 4.0]));
+    assert_eq!(map_rows(&a, |_, row| row.to_vec()).unwrap(), a);
+    assert!(map_rows(&a, |r, row| row[..(r + 1)].to_vec()).is_err());
+    assert_eq!(map_rows(&Mat64::zero(0, 3), |_, row| row.to_vec()).unwrap(), Mat64::zero(0, 0));
+
+    let mut b = a.clone();
+    for_each_column_mut(&mut b, |c, col| if c == 1 { col.fill(0.0) });
+    assert_eq!(b, Mat64::new(2, 3, &[1.0, 2.0, 0.0, 0.0, 5.0, 6.0]));
+}
+
+#[test]
+fn test_apply() {
+    let mut a = Mat32::new(2, 2, &[1.0, 2.0, 3.0, 4.0]);
+    let b = elemwise(&a, |x| 2.0 * x + 1.0);
+    a.apply(|x| 2.0 * x + 1.0);
+    assert_eq!(a, b);
+    let mut count = 0;
+    a.apply(|x| { count += 1; x });
+    assert_eq!(count, 4);
+}
+
+#[test]
+fn test_ones_full() {
+    assert_eq!(Mat64::ones(2, 1), Mat64::new(2, 1, &[1.0, 1.0]));
+    assert_eq!(Mat32::full(1, 3, -2.0), Mat32::new(1, 3, &[-2.0; 3]));
+    assert_eq!(Mat64::full(0, 3, 1.0), Mat64::zero(0, 3));
+}
+
+#[test]
+fn test_gram() {
+    let a = Mat64::new(4, 3, &[1.0, 3.0, 2.0, 0.0, -2.0, 0.0, 4.0, 1.0, 0.5, 1.0, -1.0, 2.0]);
+    assert_eq!(ata(&a), trmul(&a, &a).unwrap());
+    assert_eq!(aat(&a), mul(&a, &tr(&a)).unwrap());
+    assert_eq!(ata(&Mat64::zero(0, 2)), Mat64::zero(2, 2));
+    assert_eq!(aat(&Mat64::zero(3, 0)), Mat64::zero(3, 3));
+}
+
+#[test]
+fn test_try_new() {
+    assert_eq!(Mat64::try_new(1, 2, &[1.0, 2.0]).unwrap(), Mat64::new(1, 2, &[1.0, 2.0]));
+    assert_eq!(Mat32::try_from_vec(0, 4, vec![]).unwrap(), Mat32::zero(0, 4));
+    let err = Mat64::try_new(2, 2, &[1.0]).unwrap_err();
+    assert_eq!(err.kind(), JolinErrorKind::ShapeMismatching);
+    assert!(Mat64::try_from_vec(usize::MAX, 2, vec![]).is_err());
+}
+
+#[test]
+fn test_error_shapes() {
+    let a = Mat64::zero(2, 3);
+    let err = mul(&a, &a).unwrap_err();
+    assert_eq!(err.kind(), JolinErrorKind::ShapeMismatching);
+    assert_eq!(err.context(), Some(JolinErrorContext::Shape { expected: (3, 3), actual: (2, 3) }));
+    let err = add(&a, &Mat64::zero(3, 2)).unwrap_err();
+    assert_eq!(err.to_string(), "shape mismatching: expected 2x3, got 3x2");
+    let mut c = Mat64::zero(3, 3);
+    let err = gemm(1.0, &a, false, &a, true, 0.0, &mut c).unwrap_err();
+    assert_eq!(err.context(), JolinError::shape_mismatching_with((2, 2), (3, 3)).context());
+    // the context is not compared by equality
+    assert_eq!(err, JolinError::shape_mismatching());
+    assert_ne!(err, JolinError::invalid_value());
+}
+
+#[test]
+fn test_allclose() {
+    let a = Mat64::new(1, 3, &[1e-10, 1.0, 1e10]);
+    let b = Mat64::new(1, 3, &[2e-10, 1.0 + 1e-6, 1e10 + 1e4]);
+    assert!(allclose(&a, &b, 1e-5, 1e-8));
+    assert!(!allclose(&a, &b, 1e-7, 1e-8));
+    assert!(!allclose(&a, &b, 1e-5, 0.0));
+    assert!(!eq_with_error(&a, &b, 1e-5));
+    assert!(!allclose(&a, &Mat64::zero(3, 1), 1.0, 1.0));
+
+    let inf = Mat64::new(1, 2, &[f64::INFINITY, f64::NEG_INFINITY]);
+    assert!(allclose(&inf, &inf, 0.0, 0.0));
+    assert!(!allclose(&Mat64::new(1, 2, &[1.0, 1.0]), &inf, 1.0, 1.0));
+    assert!(!allclose(&inf, &Mat64::new(1, 2, &[1.0, 1.0]), 1.0, 1.0));
+    let nan = Mat64::new(1, 1, &[f64::NAN]);
+    assert!(!allclose(&nan, &nan, 1.0, 1.0));
+}
+
+#[test]
+fn test_clamp() {
+    let a = Mat64::new(1, 4, &[-1.0, 0.25, 2.0, f64::NAN]);
+    let c = clamp(&a, 0.0, 1.0).unwrap();
+    assert_eq!(&c.data()[..3], &[0.0, 0.25, 1.0]);
+    assert!(c.data()[3].is_nan());
+    assert_eq!(&clamp(&a, 0.5, 0.5).unwrap().data()[..3], &[0.5, 0.5, 0.5]);
+    assert_eq!(clamp(&a, 1.0, 0.0).unwrap_err().kind(), JolinErrorKind::InvalidValue);
+    assert!(clamp(&a, f64::NAN, 0.0).is_err());
+
+    let m = maximum_scalar(&a, 0.5);
+    assert_eq!(&m.data()[..3], &[0.5, 0.5, 2.0]);
+    assert!(m.data()[3].is_nan());
+    let m = minimum_scalar(&a, 0.5);
+    assert_eq!(&m.data()[..3], &[-1.0, 0.25, 0.5]);
+    assert!(m.data()[3].is_nan());
+}
@@ -0,0 +1,96 @@
+/*
+ * nn.rs
+ * Activation functions for small neural network experiments.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber, elemwise, for_each_column_mut};
+
+/// Element-wise logistic sigmoid `1 / (1 + e^{-x})`. It is evaluated without
+/// overflow for elements of large magnitude.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::nn::sigmoid;
+/// let s = sigmoid(&mat64![0.0, 1000.0, -1000.0]);
+/// assert_eq!(s, mat64![0.5, 1.0, 0.0]);
+/// ```
+pub fn sigmoid<T: Matrix>(mat: &T) -> T {
+    let one = T::Elem::one();
+    elemwise(mat, |x| {
+        if *x >= T::Elem::zero() {
+            one / (one + (-*x).exp())
+        } else {
+            let e = x.exp();
+            e / (one + e)
+        }
+    })
+}
+
+/// Element-wise rectified linear unit `max(x, 0)`
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::nn::relu;
+/// assert_eq!(relu(&mat64![-1.0, 2.0; 0.5, -0.5]), mat64![0.0, 2.0; 0.5, 0.0]);
+/// ```
+pub fn relu<T: Matrix>(mat: &T) -> T {
+    elemwise(mat, |x| if *x > T::Elem::zero() { *x } else { T::Elem::zero() })
+}
+
+/// Softmax of each column, so every column of the answer is positive and
+/// sums to one. The maximum of the column is subtracted before the
+/// exponential to avoid overflow.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::nn::softmax;
+/// let p = softmax(&mat64![1.0, 1000.0; 1.0, 1000.0]);
+/// assert_eq!(p, mat64![0.5, 0.5; 0.5, 0.5]);
+/// ```
+pub fn softmax<T: Matrix>(mat: &T) -> T {
+    let mut ans = mat.clone();
+    for_each_column_mut(&mut ans, |_, col| {
+        let max = col.iter().fold(col[0], |acc, x| if *x > acc { *x } else { acc });
+        col.iter_mut().for_each(|x| *x = (*x - max).exp());
+        let total = col.iter().cloned().sum::<T::Elem>();
+        col.iter_mut().for_each(|x| *x = *x / total);
+    });
+    ans
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::nn::{*};
+
+    #[test]
+    fn test_sigmoid_relu() {
+        let a = mat64![-2.0, -0.5; 0.5, 2.0];
+        let s = sigmoid(&a);
+        let expected = elemwise(&a, |x| 1.0 / (1.0 + (-x).exp()));
+        assert!(eq_with_error(&s, &expected, 1e-15));
+        // sigmoid(-x) = 1 - sigmoid(x)
+        let flipped = sigmoid(&neg(&a));
+        assert!(eq_with_error(&add(&s, &flipped).unwrap(), &Mat64::ones(2, 2), 1e-15));
+        assert_eq!(relu(&a), mat64![0.0, 0.0; 0.5, 2.0]);
+        assert_eq!(relu(&Mat32::zero(0, 3)), Mat32::zero(0, 3));
+    }
+
+    #[test]
+    fn test_softmax() {
+        let a = mat64![1.0, -800.0; 2.0, -801.0; 3.0, -799.0];
+        let p = softmax(&a);
+        let sums = sum_axis(&p, Axis::Column);
+        assert!(eq_with_error(&sums, &Mat64::ones(1, 2), 1e-15));
+        let e: Vec<f64> = [1.0f64, 2.0, 3.0].iter().map(|x| x.exp()).collect();
+        let total: f64 = e.iter().sum();
+        assert!((p.elem(2, 0) - e[2] / total).abs() < 1e-15);
+        // shifting a column doesn't change its softmax
+        assert!((p.elem(2, 1) - e[2] / total).abs() < 1e-15);
+        assert_eq!(softmax(&Mat64::zero(0, 2)), Mat64::zero(0, 2));
+    }
+}
@@ -0,0 +1,76 @@
+/*
+ * operator.rs
+ * Linear operators defined by their action on vectors.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, mul};
+use crate::error::JolinError;
+
+/// A linear operator known only by its product with column vectors, e.g. a
+/// matrix too large to store densely, or a product of factors that should
+/// not be formed. Iterative methods only need this action.
+///
+/// Every matrix is a linear operator.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::operator::LinearOperator;
+/// // the second difference operator, without storing the matrix
+/// struct Laplacian(usize);
+///
+/// impl LinearOperator<Mat64> for Laplacian {
+///     fn shape(&self) -> (usize, usize) {
+///         (self.0, self.0)
+///     }
+///     fn matvec(&self, x: &Mat64) -> Result<Mat64, jolin::error::JolinError> {
+///         let n = self.0;
+///         Ok(Mat64::from_vec(n, 1, (0..n).map(|i| {
+///             let left = if i > 0 { x.elem(i - 1, 0) } else { 0.0 };
+///             let right = if i + 1 < n { x.elem(i + 1, 0) } else { 0.0 };
+///             2.0 * x.elem(i, 0) - left - right
+///         }).collect()))
+///     }
+/// }
+///
+/// let y = Laplacian(3).matvec(&mat64![1.0; 1.0; 1.0]).unwrap();
+/// assert_eq!(y, mat64![1.0; 0.0; 1.0]);
+/// ```
+pub trait LinearOperator<T: Matrix> {
+    /// Row and column count of the operator
+    fn shape(&self) -> (usize, usize);
+
+    /// Compute `A x` for a column vector `x`.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if `x` is not a column vector of the column
+    ///    count.
+    fn matvec(&self, x: &T) -> Result<T, JolinError>;
+}
+
+impl<T: Matrix> LinearOperator<T> for T {
+    fn shape(&self) -> (usize, usize) {
+        (self.row(), self.column())
+    }
+
+    fn matvec(&self, x: &T) -> Result<T, JolinError> {
+        mul(self, x)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::operator::LinearOperator;
+
+    #[test]
+    fn test_matrix_operator() {
+        let a = mat64![1.0, 2.0; 3.0, 4.0; 5.0, 6.0];
+        assert_eq!(LinearOperator::shape(&a), (3, 2));
+        assert_eq!(a.matvec(&mat64![1.0; -1.0]).unwrap(), mat64![-1.0; -1.0; -1.0]);
+        assert!(a.matvec(&mat64![1.0; 2.0; 3.0]).is_err());
+    }
+}
@@ -0,0 +1,227 @@
+/*
+ * orthogonal.rs
+ * Elementary orthogonal transformations.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber};
+use crate::error::JolinError;
+
+/// Givens rotation `(c, s)` with `[c, s; -s, c] [a; b] = [r; 0]` and
+/// `r >= 0`. It's the identity `(1, 0)` if both are zero.
+/// ```
+/// # use jolin::orthogonal::givens;
+/// assert_eq!(givens(3.0, 4.0), (0.6, 0.8));
+/// assert_eq!(givens(0.0, 0.0), (1.0, 0.0));
+/// ```
+pub fn givens<E: LikeNumber>(a: E, b: E) -> (E, E) {
+    let scale = if a.abs() > b.abs() { a.abs() } else { b.abs() };
+    if scale == E::zero() {
+        return (E::one(), E::zero())
+    }
+    // scaled to avoid overflow and underflow in the squares
+    let (x, y) = (a / scale, b / scale);
+    let r = scale * (x * x + y * y).sqrt();
+    (a / r, b / r)
+}
+
+/// Rotate rows `i` and `j` of the matrix in place, i.e. multiply it from the
+/// left by the rotation that acts as `[c, s; -s, c]` on these rows.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::orthogonal::{givens, apply_givens_left};
+/// let mut a = mat64![3.0, 1.0; 4.0, 2.0];
+/// let (c, s) = givens(3.0, 4.0);
+/// apply_givens_left(&mut a, 0, 1, c, s).unwrap();
+/// assert!(eq_with_error(&a, &mat64![5.0, 2.2; 0.0, 0.4], 1e-12));
+/// ```
+///
+/// Potential errors:
+/// 1. Invalid value - if `i` and `j` are the same or not row indices.
+pub fn apply_givens_left<T: Matrix>(mat: &mut T, i: usize, j: usize, c: T::Elem, s: T::Elem) -> Result<(), JolinError> {
+    if i == j || i >= mat.row() || j >= mat.row() {
+        return Err(JolinError::invalid_value())
+    }
+    rotate_rows(mat, i, j, c, s);
+    Ok(())
+}
+
+/// Rotate columns `i` and `j` of the matrix in place, i.e. multiply it from
+/// the right by the transpose of the rotation in `apply_givens_left`. So
+/// `Q R` is unchanged if the rows of `R` and the columns of `Q` are rotated
+/// alike.
+///
+/// Potential errors:
+/// 1. Invalid value - if `i` and `j` are the same or not column indices.
+pub fn apply_givens_right<T: Matrix>(mat: &mut T, i: usize, j: usize, c: T::Elem, s: T::Elem) -> Result<(), JolinError> {
+    if i == j || i >= mat.column() || j >= mat.column() {
+        return Err(JolinError::invalid_value())
+    }
+    rotate_columns(mat, i, j, c, s);
+    Ok(())
+}
+
+/// Householder reflector `H = I - tau v v^T` with `v[0] = 1` mapping `x` to
+/// `alpha e_0`, where `alpha = -sign(x[0]) |x|` avoids cancellation. It's the
+/// identity, i.e. `tau = 0`, if `x` is zero.
+/// ```
+/// # use jolin::orthogonal::householder_vector;
+/// let (v, tau) = householder_vector(&[3.0, 4.0]);
+/// assert_eq!(v, vec![1.0, 0.5]);
+/// assert_eq!(tau, 1.6);
+/// ```
+pub fn householder_vector<E: LikeNumber>(x: &[E]) -> (Vec<E>, E) {
+    let mut v = vec![E::zero(); x.len()];
+    let norm = x.iter().map(|x| *x * *x).sum::<E>().sqrt();
+    if x.is_empty() || norm == E::zero() {
+        if let Some(v0) = v.first_mut() {
+            *v0 = E::one();
+        }
+        return (v, E::zero())
+    }
+    let alpha = -norm * x[0].sign();
+    let u0 = x[0] - alpha;
+    for (vi, xi) in v.iter_mut().zip(x.iter()) {
+        *vi = *xi / u0;
+    }
+    v[0] = E::one();
+    let tau = E::one().times_real(2.0) / v.iter().map(|x| *x * *x).sum::<E>();
+    (v, tau)
+}
+
+/// Multiply the matrix in place from the left by the reflector
+/// `I - tau v v^T`, e.g. the one from `householder_vector`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::orthogonal::{householder_vector, apply_householder_left};
+/// let mut a = mat64![3.0, 1.0; 4.0, 2.0];
+/// let (v, tau) = householder_vector(a.data_column(0));
+/// apply_householder_left(&mut a, &v, tau).unwrap();
+/// assert!(eq_with_error(&a, &mat64![-5.0, -2.2; 0.0, 0.4], 1e-12));
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the length of `v` is not the row count.
+pub fn apply_householder_left<T: Matrix>(mat: &mut T, v: &[T::Elem], tau: T::Elem) -> Result<(), JolinError> {
+    let m = mat.row();
+    if v.len() != m {
+        return Err(JolinError::shape_mismatching_with((m, 1), (v.len(), 1)))
+    }
+    if m == 0 {
+        return Ok(())
+    }
+    for col in mat.data_mut().chunks_exact_mut(m) {
+        let d = tau * col.iter().zip(v.iter()).map(|(x, y)| *x * *y).sum::<T::Elem>();
+        for (x, y) in col.iter_mut().zip(v.iter()) {
+            *x = *x - d * *y;
+        }
+    }
+    Ok(())
+}
+
+/// Multiply the matrix in place from the right by the reflector
+/// `I - tau v v^T`.
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the length of `v` is not the column count.
+pub fn apply_householder_right<T: Matrix>(mat: &mut T, v: &[T::Elem], tau: T::Elem) -> Result<(), JolinError> {
+    let n = mat.column();
+    if v.len() != n {
+        return Err(JolinError::shape_mismatching_with((n, 1), (v.len(), 1)))
+    }
+    // A v, then the rank-1 update A - tau (A v) v^T
+    let mut av = vec![T::Elem::zero(); mat.row()];
+    for (c, vc) in v.iter().enumerate() {
+        for (y, x) in av.iter_mut().zip(mat.data_column(c).iter()) {
+            *y = *y + *x * *vc;
+        }
+    }
+    for (c, vc) in v.iter().enumerate() {
+        let f = tau * *vc;
+        for (r, y) in av.iter().enumerate() {
+            *mat.elem_mut(r, c) = mat.elem(r, c) - f * *y;
+        }
+    }
+    Ok(())
+}
+
+/// `apply_givens_left` without checking the indices
+pub(crate) fn rotate_rows<T: Matrix>(mat: &mut T, i: usize, j: usize, c: T::Elem, s: T::Elem) {
+    for col in 0..mat.column() {
+        let (x, y) = (mat.elem(i, col), mat.elem(j, col));
+        *mat.elem_mut(i, col) = c * x + s * y;
+        *mat.elem_mut(j, col) = c * y - s * x;
+    }
+}
+
+/// `apply_givens_right` without checking the indices
+pub(crate) fn rotate_columns<T: Matrix>(mat: &mut T, i: usize, j: usize, c: T::Elem, s: T::Elem) {
+    for row in 0..mat.row() {
+        let (x, y) = (mat.elem(row, i), mat.elem(row, j));
+        *mat.elem_mut(row, i) = c * x + s * y;
+        *mat.elem_mut(row, j) = c * y - s * x;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::orthogonal::{*};
+
+    #[test]
+    fn test_givens() {
+        for (a, b) in [(1.0, 2.0), (-3.0, 1e-3), (0.0, -2.0), (1e300, 1e300), (1e-300, -1e-300)] {
+            let (c, s) = givens(a, b);
+            assert!((c * c + s * s - 1.0).abs() < 1e-15);
+            assert!((c * b - s * a).abs() <= 1e-15 * f64::max(a.abs(), b.abs()));
+            assert!(c * a + s * b > 0.0);
+        }
+        assert_eq!(givens(-2.0f32, 0.0), (-1.0, 0.0));
+
+        let a = mat64![1.0, 2.0; 3.0, 4.0; 5.0, 6.0];
+        let (c, s) = givens(a.elem(0, 0), a.elem(2, 0));
+        let mut r = a.clone();
+        apply_givens_left(&mut r, 0, 2, c, s).unwrap();
+        assert!(r.elem(2, 0).abs() < 1e-15);
+        // G^T G A = A
+        let mut q = Mat64::identity(3);
+        apply_givens_right(&mut q, 0, 2, c, s).unwrap();
+        assert!(eq_with_error(&mul(&q, &r).unwrap(), &a, 1e-12));
+
+        assert!(apply_givens_left(&mut r, 1, 1, c, s).is_err());
+        assert!(apply_givens_left(&mut r, 0, 3, c, s).is_err());
+        assert!(apply_givens_right(&mut r, 0, 2, c, s).is_err());
+    }
+
+    #[test]
+    fn test_householder() {
+        let x = [2.0, -1.0, 2.0];
+        let (v, tau) = householder_vector(&x);
+        assert_eq!(v[0], 1.0);
+        let mut h = Mat64::identity(3);
+        apply_householder_left(&mut h, &v, tau).unwrap();
+        // H is symmetric and orthogonal, and maps x to -3 e_0
+        assert!(eq_with_error(&h, &tr(&h), 1e-15));
+        assert!(eq_with_error(&mul(&h, &h).unwrap(), &Mat64::identity(3), 1e-15));
+        let hx = mul(&h, &Mat64::new(3, 1, &x)).unwrap();
+        assert!(eq_with_error(&hx, &Mat64::new(3, 1, &[-3.0, 0.0, 0.0]), 1e-15));
+
+        let mut right = Mat64::identity(3);
+        apply_householder_right(&mut right, &v, tau).unwrap();
+        assert!(eq_with_error(&right, &h, 1e-15));
+        let a = mat64![1.0, 2.0, 3.0; 4.0, 5.0, 6.0];
+        let mut ah = a.clone();
+        apply_householder_right(&mut ah, &v, tau).unwrap();
+        assert!(eq_with_error(&ah, &mul(&a, &h).unwrap(), 1e-12));
+
+        assert_eq!(householder_vector(&[0.0f32, 0.0]), (vec![1.0, 0.0], 0.0));
+        assert_eq!(householder_vector::<f64>(&[]), (vec![], 0.0));
+        assert!(apply_householder_left(&mut ah, &v, tau).is_err());
+        assert!(apply_householder_right(&mut h, &v[..2], tau).is_err());
+    }
+}
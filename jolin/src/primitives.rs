@@ -0,0 +1,155 @@
+/*
+ * primitives.rs
+ * BLAS level-1 and level-2 primitives on slices.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber};
+use crate::matrix::kernel::Kernel;
+use crate::error::JolinError;
+
+/// Compute `y = alpha x + y` in place.
+/// ```
+/// # use jolin::primitives::axpy;
+/// let mut y = vec![1.0, 1.0];
+/// axpy(2.0, &[1.0, 2.0], &mut y).unwrap();
+/// assert_eq!(y, vec![3.0, 5.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `x` and `y` have different lengths.
+pub fn axpy<E: LikeNumber>(alpha: E, x: &[E], y: &mut [E]) -> Result<(), JolinError> {
+    if x.len() != y.len() {
+        return Err(JolinError::shape_mismatching())
+    }
+    E::axpy(alpha, x, y);
+    Ok(())
+}
+
+/// Compute the dot product `x^T y`.
+/// ```
+/// # use jolin::primitives::dot;
+/// assert_eq!(dot(&[1.0, 2.0], &[3.0, 4.0]).unwrap(), 11.0);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `x` and `y` have different lengths.
+pub fn dot<E: LikeNumber>(x: &[E], y: &[E]) -> Result<E, JolinError> {
+    if x.len() != y.len() {
+        return Err(JolinError::shape_mismatching())
+    }
+    Ok(x.iter().zip(y.iter()).map(|(a, b)| *a * *b).sum())
+}
+
+/// Compute the matrix-vector product `y = alpha op(A) x + beta y` in place,
+/// where `op(A)` is `A^T` if `trans` is set and `A` otherwise. When `beta` is
+/// zero, `y` is overwritten, so it may contain NaN.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::primitives::gemv;
+/// let a = mat64![1.0, 2.0; 3.0, 4.0];
+/// let mut y = vec![0.0; 2];
+/// gemv(1.0, &a, false, &[1.0, 1.0], 0.0, &mut y).unwrap();
+/// assert_eq!(y, vec![3.0, 7.0]);
+/// gemv(1.0, &a, true, &[1.0, 1.0], 1.0, &mut y).unwrap();
+/// assert_eq!(y, vec![7.0, 13.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the lengths of `x` and `y` don't match the shape of `op(A)`.
+pub fn gemv<T: Matrix>(alpha: T::Elem, a: &T, trans: bool, x: &[T::Elem], beta: T::Elem, y: &mut [T::Elem]) -> Result<(), JolinError> {
+    let (m, n) = if trans { (a.column(), a.row()) } else { (a.row(), a.column()) };
+    if x.len() != n || y.len() != m {
+        return Err(JolinError::shape_mismatching())
+    }
+    let zero = T::Elem::zero();
+    if beta == zero {
+        y.fill(zero);
+    } else if beta != T::Elem::one() {
+        y.iter_mut().for_each(|v| *v = beta * *v);
+    }
+    if trans {
+        for (i, v) in y.iter_mut().enumerate() {
+            let s: T::Elem = a.data_column(i).iter().zip(x.iter()).map(|(p, q)| *p * *q).sum();
+            *v = *v + alpha * s;
+        }
+    } else {
+        for (j, v) in x.iter().enumerate() {
+            T::Elem::axpy(alpha * *v, a.data_column(j), y);
+        }
+    }
+    Ok(())
+}
+
+/// Apply the rank-1 update `A = alpha x y^T + A` in place.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::primitives::ger;
+/// let mut a = Mat64::identity(2);
+/// ger(2.0, &[1.0, 0.0], &[1.0, 3.0], &mut a).unwrap();
+/// assert_eq!(a, mat64![3.0, 6.0; 0.0, 1.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the length of `x` differs from the row count of
+///    `A`, or the length of `y` differs from its column count.
+pub fn ger<T: Matrix>(alpha: T::Elem, x: &[T::Elem], y: &[T::Elem], a: &mut T) -> Result<(), JolinError> {
+    let m = a.row();
+    if x.len() != m || y.len() != a.column() {
+        return Err(JolinError::shape_mismatching())
+    }
+    if m == 0 {
+        return Ok(())
+    }
+    for (col, v) in a.data_mut().chunks_exact_mut(m).zip(y.iter()) {
+        T::Elem::axpy(alpha * *v, x, col);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::primitives::{*};
+
+    #[test]
+    fn test_level1() {
+        let x = [1.0f32, -2.0, 0.5];
+        let mut y = [0.0f32, 1.0, 2.0];
+        axpy(-2.0, &x, &mut y).unwrap();
+        assert_eq!(y, [-2.0, 5.0, 1.0]);
+        assert!(axpy(1.0, &x, &mut y[..2]).is_err());
+        assert_eq!(dot(&x, &y).unwrap(), -11.5);
+        assert!(dot(&x, &[1.0]).is_err());
+        assert_eq!(dot::<f64>(&[], &[]).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_gemv() {
+        let a = mat64![1.0, 2.0, 3.0; 4.0, 5.0, 6.0];
+        let x = [1.0, 0.0, -1.0];
+        let mut y = [1.0, 1.0];
+        gemv(2.0, &a, false, &x, -1.0, &mut y).unwrap();
+        assert_eq!(y, [-5.0, -5.0]);
+        let mut z = [f64::NAN; 3];
+        gemv(1.0, &a, true, &[1.0, 1.0], 0.0, &mut z).unwrap();
+        assert_eq!(z, [5.0, 7.0, 9.0]);
+        assert!(gemv(1.0, &a, true, &x, 0.0, &mut y).is_err());
+        assert!(gemv(1.0, &a, false, &x, 0.0, &mut z).is_err());
+    }
+
+    #[test]
+    fn test_ger() {
+        let mut a = Mat64::zero(2, 3);
+        ger(1.0, &[1.0, 2.0], &[1.0, 0.0, -1.0], &mut a).unwrap();
+        assert_eq!(a, mat64![1.0, 0.0, -1.0; 2.0, 0.0, -2.0]);
+        assert!(ger(1.0, &[1.0, 2.0], &[1.0], &mut a).is_err());
+        let mut empty = Mat64::zero(0, 2);
+        assert!(ger(1.0, &[], &[1.0, 2.0], &mut empty).is_ok());
+    }
+}
@@ -0,0 +1,118 @@
+/*
+ * psd.rs
+ * Utilities of positive semi-definite matrices.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber};
+use crate::error::JolinError;
+use crate::decomp::eig::eig_symmetric;
+
+/// Find the nearest (in Frobenius norm) positive semi-definite matrix.
+///
+/// The matrix is symmetrized as `(A + A^T) / 2` and then projected onto the
+/// PSD cone by clipping its negative eigenvalues to zero.
+///
+/// If `preserve_diagonal` is set, the answer is further rescaled as `S X S`
+/// with a diagonal `S`, so that its diagonal equals the diagonal of the input,
+/// e.g. a repaired correlation matrix keeps its unit diagonal. The rescaled
+/// matrix is still PSD but no longer the nearest one.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::psd::nearest_psd;
+/// let a = mat64![1.0, 2.0; 2.0, 1.0];
+/// assert!(eq_with_error(&nearest_psd(&a, false).unwrap(), &mat64![1.5, 1.5; 1.5, 1.5], 1e-12));
+/// assert!(eq_with_error(&nearest_psd(&a, true).unwrap(), &mat64![1.0, 1.0; 1.0, 1.0], 1e-12));
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the matrix is not square.
+/// 2. Invalid value - if the diagonal is to be preserved but contains a
+///    negative element.
+#[allow(clippy::needless_range_loop)]
+pub fn nearest_psd<T: Matrix>(mat: &T, preserve_diagonal: bool) -> Result<T, JolinError> {
+    if mat.row() != mat.column() {
+        return Err(JolinError::shape_mismatching())
+    }
+    let n = mat.row();
+    if preserve_diagonal && (0..n).any(|i| mat.elem(i, i) < T::Elem::zero()) {
+        return Err(JolinError::invalid_value())
+    }
+    let mut sym = mat.clone();
+    for c in 0..n {
+        for r in 0..n {
+            *sym.elem_mut(r, c) = (mat.elem(r, c) + mat.elem(c, r)).times_real(0.5);
+        }
+    }
+
+    let eig = eig_symmetric(&sym)?;
+    let clipped: Vec<T::Elem> = eig.values.iter()
+        .map(|v| if *v > T::Elem::zero() { *v } else { T::Elem::zero() })
+        .collect();
+    // X = V * diag(clipped) * V^T
+    let v = &eig.vectors;
+    let mut ans = T::zero(n, n);
+    for c in 0..n {
+        for r in 0..(c + 1) {
+            let mut t = T::Elem::zero();
+            for k in 0..n {
+                t = t + v.elem(r, k) * clipped[k] * v.elem(c, k);
+            }
+            *ans.elem_mut(r, c) = t;
+            *ans.elem_mut(c, r) = t;
+        }
+    }
+
+    if preserve_diagonal {
+        let scale: Vec<T::Elem> = (0..n).map(|i| {
+            let x = ans.elem(i, i);
+            if x > T::Elem::zero() {
+                (mat.elem(i, i) / x).sqrt()
+            } else {
+                T::Elem::one()
+            }
+        }).collect();
+        for c in 0..n {
+            for r in 0..n {
+                let x = ans.elem(r, c);
+                *ans.elem_mut(r, c) = scale[r] * x * scale[c];
+            }
+        }
+    }
+    Ok(ans)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::decomp::eig::eig_symmetric;
+    use crate::psd::nearest_psd;
+
+    #[test]
+    fn test_nearest_psd_indefinite_correlation() {
+        // An inconsistent "correlation" matrix with a negative eigenvalue
+        let a = mat64![1.0, 0.9, -0.9; 0.9, 1.0, 0.9; -0.9, 0.9, 1.0];
+        assert!(eig_symmetric(&a).unwrap().values[0] < 0.0);
+
+        let x = nearest_psd(&a, false).unwrap();
+        assert!(eig_symmetric(&x).unwrap().values[0] > -1e-12);
+        assert!(eq_with_error(&x, &tr(&x), 1e-15));
+
+        let y = nearest_psd(&a, true).unwrap();
+        assert!(eig_symmetric(&y).unwrap().values[0] > -1e-12);
+        for i in 0..3 {
+            assert!((y.elem(i, i) - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_nearest_psd_keeps_psd_input() {
+        let a = mat64![2.0, 1.0; 1.0, 2.0];
+        assert!(eq_with_error(&nearest_psd(&a, false).unwrap(), &a, 1e-12));
+        assert!(nearest_psd(&mat64![-1.0, 0.0; 0.0, 1.0], true).is_err());
+    }
+}
@@ -7,25 +7,29 @@
  */
 
 use std::ops::Neg;
+use std::cmp::Ordering;
 
 use crate::matrix::{Matrix, Mat32, Mat64, LikeNumber};
-use rand::{rngs::ThreadRng, thread_rng, Rng};
+use crate::error::JolinError;
+use crate::decomp::qr::qr_househoulder;
+use rand::{thread_rng, Rng};
+use rand::seq::SliceRandom;
 
 /// Provide the method to generate an element from the standard uniform 
 /// distribution.
 pub trait ElementStandardUniformProvider: Matrix{
     /// Generate a random value
-    fn gen(rng: &mut ThreadRng) -> Self::Elem;
+    fn gen<R: Rng + ?Sized>(rng: &mut R) -> Self::Elem;
 }
 
 impl ElementStandardUniformProvider for Mat64 {
-    fn gen(rng: &mut ThreadRng) -> Self::Elem {
+    fn gen<R: Rng + ?Sized>(rng: &mut R) -> Self::Elem {
         rng.gen()
     }
 }
 
 impl ElementStandardUniformProvider for Mat32 {
-    fn gen(rng: &mut ThreadRng) -> Self::Elem {
+    fn gen<R: Rng + ?Sized>(rng: &mut R) -> Self::Elem {
         rng.gen()
     }
 }
@@ -34,16 +38,66 @@ impl ElementStandardUniformProvider for Mat32 {
 /// 
 /// The generated values are sampled from a uniform distribution of `(0, 1)`.
 pub fn uniform_standard<T: Matrix + ElementStandardUniformProvider>(row: usize, column: usize) -> T {
+    uniform_standard_with(&mut thread_rng(), row, column)
+}
+
+/// Standard uniform distribution random matrix generator with the given
+/// random number generator, see `uniform_standard`.
+///
+/// A seeded generator makes the matrix reproducible:
+/// ```
+/// # use jolin::matrix::*;
+/// # use jolin::rand::uniform_standard_with;
+/// use rand::{rngs::StdRng, SeedableRng};
+/// let a: Mat64 = uniform_standard_with(&mut StdRng::seed_from_u64(42), 3, 3);
+/// let b: Mat64 = uniform_standard_with(&mut StdRng::seed_from_u64(42), 3, 3);
+/// assert_eq!(a, b);
+/// ```
+pub fn uniform_standard_with<T, R>(rng: &mut R, row: usize, column: usize) -> T
+where T: Matrix + ElementStandardUniformProvider, R: Rng + ?Sized {
     let mut data = Vec::new();
     let n = row * column;
     data.reserve_exact(n);
-    let mut rng = thread_rng();
     for _i in 0..n {
-        data.push(T::gen(&mut rng));
+        data.push(T::gen(rng));
     }
     T::from_vec(row, column, data)
 }
 
+/// Uniform distribution random matrix generator
+///
+/// The generated values are sampled from a uniform distribution of
+/// `(low, high)`.
+/// ```
+/// # use jolin::matrix::*;
+/// # use jolin::rand::uniform;
+/// let x: Mat64 = uniform(3, 4, -2.0, 5.0).unwrap();
+/// assert!(x.data().iter().all(|v| *v >= -2.0 && *v < 5.0));
+/// ```
+///
+/// Potential errors:
+/// 1. Invalid value - if `low` is not less than `high`, or either is not finite.
+pub fn uniform<T: Matrix + ElementStandardUniformProvider>(row: usize, column: usize, low: f64, high: f64) -> Result<T, JolinError> {
+    uniform_with(&mut thread_rng(), row, column, low, high)
+}
+
+/// Uniform distribution random matrix generator with the given random
+/// number generator, see `uniform`.
+///
+/// Potential errors:
+/// 1. Invalid value - if `low` is not less than `high`, or either is not finite.
+pub fn uniform_with<T, R>(rng: &mut R, row: usize, column: usize, low: f64, high: f64) -> Result<T, JolinError>
+where T: Matrix + ElementStandardUniformProvider, R: Rng + ?Sized {
+    if low.partial_cmp(&high) != Some(Ordering::Less) || !low.is_finite() || !high.is_finite() {
+        return Err(JolinError::invalid_value())
+    }
+    let mut x: T = uniform_standard_with(rng, row, column);
+    let width = high - low;
+    let low = T::Elem::one().times_real(low);
+    x.apply(|u| low + u.times_real(width));
+    Ok(x)
+}
+
 /// Standard normal (Gaussian) distribution random matrix generator
 /// 
 /// The generated values are sampled from a standard normal distribution where
@@ -60,25 +114,204 @@ pub fn uniform_standard<T: Matrix + ElementStandardUniformProvider>(row: usize,
 /// println!("mean = {} var = {}", mean, var);
 /// ```
 pub fn normal_standard<T: Matrix + ElementStandardUniformProvider>(row: usize, column: usize) -> T {
-    let u: T = uniform_standard(row, column);
-    let v: T = uniform_standard(row, column);
-    let n = row * column;  
+    normal_standard_with(&mut thread_rng(), row, column)
+}
+
+/// Standard normal (Gaussian) distribution random matrix generator with the
+/// given random number generator, see `normal_standard`.
+pub fn normal_standard_with<T, R>(rng: &mut R, row: usize, column: usize) -> T
+where T: Matrix + ElementStandardUniformProvider, R: Rng + ?Sized {
+    let n = row * column;
     let mut data = Vec::new();
-    data.reserve_exact(n);
-    let u_data = u.data();
-    let v_data = v.data();
-    for i in 0..n {
-        let a = u_data[i].ln().neg().times_real(2.0).sqrt();
-        let b = v_data[i].times_real(2.0 * 3.1415926536).cos();
-        data.push(a * b);
+    data.reserve_exact(n + 1);
+    while data.len() < n {
+        // Box-Muller transform gives two independent values from one pair
+        // of uniform values. `1 - u` is in `(0, 1]` so the logarithm is finite.
+        let u = T::Elem::one() - T::gen(rng);
+        let v = T::gen(rng);
+        let a = u.ln().neg().times_real(2.0).sqrt();
+        // the constant of the original generator, kept so seeded sequences
+        // don't change
+        #[allow(clippy::approx_constant)]
+        let theta = v.times_real(2.0 * 3.1415926536);
+        data.push(a * theta.cos());
+        data.push(a * theta.sin());
     }
+    data.truncate(n);
 
     T::from_vec(row, column, data)
 }
 
+/// Normal (Gaussian) distribution random matrix generator
+///
+/// The generated values are sampled from a normal distribution of the mean
+/// and the standard deviation.
+/// ```
+/// # use jolin::matrix::*;
+/// # use jolin::rand::normal;
+/// let x: Mat64 = normal(2, 3, 10.0, 0.5).unwrap();
+/// assert_eq!((x.row(), x.column()), (2, 3));
+/// ```
+///
+/// Potential errors:
+/// 1. Invalid value - if `std` is negative, or either is not finite.
+pub fn normal<T: Matrix + ElementStandardUniformProvider>(row: usize, column: usize, mean: f64, std: f64) -> Result<T, JolinError> {
+    normal_with(&mut thread_rng(), row, column, mean, std)
+}
+
+/// Normal (Gaussian) distribution random matrix generator with the given
+/// random number generator, see `normal`.
+///
+/// Potential errors:
+/// 1. Invalid value - if `std` is negative, or either is not finite.
+pub fn normal_with<T, R>(rng: &mut R, row: usize, column: usize, mean: f64, std: f64) -> Result<T, JolinError>
+where T: Matrix + ElementStandardUniformProvider, R: Rng + ?Sized {
+    if std < 0.0 || !std.is_finite() || !mean.is_finite() {
+        return Err(JolinError::invalid_value())
+    }
+    let mut x: T = normal_standard_with(rng, row, column);
+    let mean = T::Elem::one().times_real(mean);
+    x.apply(|z| mean + z.times_real(std));
+    Ok(x)
+}
+
+/// Random orthogonal matrix generator
+///
+/// The generated matrix is sampled from the Haar distribution, i.e. the
+/// uniform distribution over the `n * n` orthogonal matrices. It's the Q of
+/// the QR decomposition of a standard normal matrix, with the columns
+/// flipped to make the diagonal of R positive.
+/// ```
+/// # use jolin::matrix::*;
+/// # use jolin::rand::orthogonal;
+/// let q: Mat64 = orthogonal(4);
+/// assert!(eq_with_error(&mul(&tr(&q), &q).unwrap(), &Mat64::identity(4), 1e-12));
+/// ```
+pub fn orthogonal<T: Matrix + ElementStandardUniformProvider>(n: usize) -> T {
+    orthogonal_with(&mut thread_rng(), n)
+}
+
+/// Random orthogonal matrix generator with the given random number
+/// generator, see `orthogonal`.
+pub fn orthogonal_with<T, R>(rng: &mut R, n: usize) -> T
+where T: Matrix + ElementStandardUniformProvider, R: Rng + ?Sized {
+    let g: T = normal_standard_with(rng, n, n);
+    // a square matrix is always accepted
+    let qr = qr_househoulder(&g).unwrap();
+    let mut q = qr.q;
+    for c in 0..n {
+        let s = qr.r.elem(c, c).sign();
+        for r in 0..n {
+            *q.elem_mut(r, c) = q.elem(r, c) * s;
+        }
+    }
+    q
+}
+
+/// Random permutation of `0..n`, as an index vector like the `p` of
+/// `LUDecomposition`.
+/// ```
+/// # use jolin::rand::permutation;
+/// let mut p = permutation(5);
+/// p.sort();
+/// assert_eq!(p, vec![0, 1, 2, 3, 4]);
+/// ```
+pub fn permutation(n: usize) -> Vec<usize> {
+    permutation_with(&mut thread_rng(), n)
+}
+
+/// Random permutation of `0..n` with the given random number generator, see
+/// `permutation`.
+pub fn permutation_with<R: Rng + ?Sized>(rng: &mut R, n: usize) -> Vec<usize> {
+    let mut p: Vec<usize> = (0..n).collect();
+    p.shuffle(rng);
+    p
+}
+
+/// Copy of the matrix with the rows in a random order, e.g. to split samples
+/// for cross validation.
+pub fn shuffle_rows<T: Matrix, R: Rng + ?Sized>(mat: &T, rng: &mut R) -> T {
+    let p = permutation_with(rng, mat.row());
+    let mut ans = T::zero(mat.row(), mat.column());
+    for c in 0..mat.column() {
+        for (r, &src) in p.iter().enumerate() {
+            *ans.elem_mut(r, c) = mat.elem(src, c);
+        }
+    }
+    ans
+}
+
+/// Bernoulli random matrix generator
+///
+/// Each entry is one with the probability `p`, and zero otherwise.
+/// ```
+/// # use jolin::matrix::*;
+/// # use jolin::rand::bernoulli;
+/// let x: Mat64 = bernoulli(3, 3, 0.5).unwrap();
+/// assert!(x.data().iter().all(|v| *v == 0.0 || *v == 1.0));
+/// ```
+///
+/// Potential errors:
+/// 1. Invalid value - if `p` is not in `[0, 1]`.
+pub fn bernoulli<T: Matrix + ElementStandardUniformProvider>(row: usize, column: usize, p: f64) -> Result<T, JolinError> {
+    bernoulli_with(&mut thread_rng(), row, column, p)
+}
+
+/// Bernoulli random matrix generator with the given random number
+/// generator, see `bernoulli`.
+///
+/// Potential errors:
+/// 1. Invalid value - if `p` is not in `[0, 1]`.
+pub fn bernoulli_with<T, R>(rng: &mut R, row: usize, column: usize, p: f64) -> Result<T, JolinError>
+where T: Matrix + ElementStandardUniformProvider, R: Rng + ?Sized {
+    sparse_with(rng, row, column, p, |_| T::Elem::one())
+}
+
+/// Sparse uniform random matrix generator
+///
+/// Each entry is nonzero with the probability `density`, and the nonzero
+/// entries are sampled from the standard uniform distribution.
+///
+/// Potential errors:
+/// 1. Invalid value - if `density` is not in `[0, 1]`.
+pub fn sparse_uniform<T: Matrix + ElementStandardUniformProvider>(row: usize, column: usize, density: f64) -> Result<T, JolinError> {
+    sparse_uniform_with(&mut thread_rng(), row, column, density)
+}
+
+/// Sparse uniform random matrix generator with the given random number
+/// generator, see `sparse_uniform`.
+///
+/// Potential errors:
+/// 1. Invalid value - if `density` is not in `[0, 1]`.
+pub fn sparse_uniform_with<T, R>(rng: &mut R, row: usize, column: usize, density: f64) -> Result<T, JolinError>
+where T: Matrix + ElementStandardUniformProvider, R: Rng + ?Sized {
+    sparse_with(rng, row, column, density, |rng| {
+        // `1 - u` is in `(0, 1]`, so the entry is never zero
+        T::Elem::one() - T::gen(rng)
+    })
+}
+
+/// Matrix where each entry is drawn by `f` with the probability `p`, and is
+/// zero otherwise
+fn sparse_with<T, R, F>(rng: &mut R, row: usize, column: usize, p: f64, mut f: F) -> Result<T, JolinError>
+where T: Matrix, R: Rng + ?Sized, F: FnMut(&mut R) -> T::Elem {
+    if !(0.0..=1.0).contains(&p) {
+        return Err(JolinError::invalid_value())
+    }
+    let mut ans = T::zero(row, column);
+    for x in ans.data_mut().iter_mut() {
+        if rng.gen_bool(p) {
+            *x = f(rng);
+        }
+    }
+    Ok(ans)
+}
+
 #[cfg(test)]
 mod test {
-    use super::uniform_standard;
+    use rand::{rngs::StdRng, SeedableRng};
+    use super::{uniform_standard, uniform_with, normal_standard_with, normal_with, orthogonal_with,
+        permutation_with, shuffle_rows, bernoulli_with, sparse_uniform_with};
     use crate::matrix::*;
     #[test]
     fn test_uniform_standard() {
@@ -90,5 +323,95 @@ mod test {
             }
         }
     }
-}
 
+    #[test]
+    fn test_seeded_generators() {
+        let a: Mat32 = normal_standard_with(&mut StdRng::seed_from_u64(7), 4, 3);
+        let b: Mat32 = normal_standard_with(&mut StdRng::seed_from_u64(7), 4, 3);
+        let c: Mat32 = normal_standard_with(&mut StdRng::seed_from_u64(8), 4, 3);
+        assert_eq!(a, b);
+        assert!(a != c);
+    }
+
+    #[test]
+    fn test_uniform() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let x: Mat64 = uniform_with(&mut rng, 20, 20, 10.0, 10.5).unwrap();
+        assert!(x.data().iter().all(|v| *v >= 10.0 && *v < 10.5));
+        let mean = x.data().iter().sum::<f64>() / 400.0;
+        assert!((mean - 10.25).abs() < 0.05);
+        assert!(uniform_with::<Mat32, _>(&mut rng, 2, 2, 1.0, 1.0).is_err());
+        assert!(uniform_with::<Mat32, _>(&mut rng, 2, 2, 0.0, f64::NAN).is_err());
+        assert!(uniform_with::<Mat32, _>(&mut rng, 2, 2, f64::NEG_INFINITY, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_normal() {
+        let mut rng = StdRng::seed_from_u64(3);
+        // an odd count uses half of the last pair
+        let x: Mat64 = normal_with(&mut rng, 101, 99, -3.0, 2.0).unwrap();
+        let n = (x.row() * x.column()) as f64;
+        let mean = x.data().iter().sum::<f64>() / n;
+        let var = x.data().iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / n;
+        assert!((mean + 3.0).abs() < 0.1);
+        assert!((var - 4.0).abs() < 0.3);
+        assert!(x.data().iter().all(|v| v.is_finite()));
+
+        let c: Mat32 = normal_with(&mut rng, 2, 2, 1.0, 0.0).unwrap();
+        assert_eq!(c, Mat32::new(2, 2, &[1.0; 4]));
+        assert!(normal_with::<Mat64, _>(&mut rng, 2, 2, 0.0, -1.0).is_err());
+        assert!(normal_with::<Mat64, _>(&mut rng, 2, 2, f64::NAN, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_orthogonal() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let q: Mat64 = orthogonal_with(&mut rng, 6);
+        assert!(eq_with_error(&mul(&tr(&q), &q).unwrap(), &Mat64::identity(6), 1e-12));
+        let p: Mat64 = orthogonal_with(&mut rng, 6);
+        assert!(q != p);
+        // the first entry is symmetric around zero under the Haar measure
+        let positive = (0..400).filter(|_| orthogonal_with::<Mat64, _>(&mut rng, 2).elem(0, 0) > 0.0).count();
+        assert!((150..250).contains(&positive));
+        assert_eq!(orthogonal_with::<Mat32, _>(&mut rng, 0), Mat32::zero(0, 0));
+    }
+
+    #[test]
+    fn test_permutation() {
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut p = permutation_with(&mut rng, 50);
+        assert!(p != (0..50).collect::<Vec<usize>>());
+        p.sort();
+        assert_eq!(p, (0..50).collect::<Vec<usize>>());
+        assert!(permutation_with(&mut rng, 0).is_empty());
+
+        let a = Mat64::new(4, 2, &[0.0, 1.0, 2.0, 3.0, 10.0, 11.0, 12.0, 13.0]);
+        let b = shuffle_rows(&a, &mut rng);
+        let mut first: Vec<f64> = b.data_column(0).to_vec();
+        first.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        assert_eq!(first, vec![0.0, 1.0, 2.0, 3.0]);
+        // rows are moved as a whole
+        for r in 0..4 {
+            assert_eq!(b.elem(r, 1), b.elem(r, 0) + 10.0);
+        }
+    }
+
+    #[test]
+    fn test_sparse() {
+        let mut rng = StdRng::seed_from_u64(13);
+        let x: Mat64 = bernoulli_with(&mut rng, 50, 40, 0.25).unwrap();
+        let ones = x.data().iter().filter(|v| **v == 1.0).count();
+        assert_eq!(ones + x.data().iter().filter(|v| **v == 0.0).count(), 2000);
+        assert!((400..600).contains(&ones));
+
+        let y: Mat32 = sparse_uniform_with(&mut rng, 50, 40, 0.1).unwrap();
+        let nonzeros = y.data().iter().filter(|v| **v != 0.0).count();
+        assert!((140..260).contains(&nonzeros));
+        assert!(y.data().iter().all(|v| *v >= 0.0 && *v <= 1.0));
+
+        assert_eq!(bernoulli_with::<Mat64, _>(&mut rng, 2, 2, 1.0).unwrap(), Mat64::new(2, 2, &[1.0; 4]));
+        assert_eq!(sparse_uniform_with::<Mat64, _>(&mut rng, 2, 2, 0.0).unwrap(), Mat64::zero(2, 2));
+        assert!(bernoulli_with::<Mat64, _>(&mut rng, 2, 2, 1.5).is_err());
+        assert!(sparse_uniform_with::<Mat64, _>(&mut rng, 2, 2, f64::NAN).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+/*
+ * settings.rs
+ * Global settings of computational kernels.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::sync::{OnceLock, RwLock};
+
+/// Settings of computational kernels.
+///
+/// The global settings are used by default, and functions like `mul_with`
+/// accept a `Settings` to override them for a single call.
+/// ```
+/// # use jolin::settings::{self, Settings};
+/// // restrict jolin to two threads inside an already parallel application
+/// settings::set(Settings { max_threads: 2, ..settings::get() });
+/// assert_eq!(settings::get().max_threads, 2);
+/// # settings::set(Settings::default());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Settings {
+    /// Kernel of matrix multiplication and transpose
+    pub kernel: KernelChoice,
+    /// Maximal count of threads used by one operation with the `parallel`
+    /// feature. Zero means all threads of the rayon thread pool.
+    pub max_threads: usize,
+    /// Minimal count of multiply-adds for a matrix multiplication to run in
+    /// parallel with `KernelChoice::Auto`. Smaller products are faster on a
+    /// single thread.
+    pub parallel_threshold: usize,
+    /// Row count of the blocks of the left operand packed by the matrix
+    /// multiplication kernel
+    pub block_rows: usize,
+    /// Column count of the blocks of the left operand packed by the matrix
+    /// multiplication kernel
+    pub block_depth: usize,
+    /// Absolute value below which a pivot is considered zero by
+    /// `Mat64::lu_decomp`
+    pub pivot_tolerance: f64,
+    /// Column count of the panels of the blocked LU decomposition in
+    /// `lu_inplace`. Matrices not larger than it are factorized unblocked.
+    pub lu_block_size: usize,
+}
+
+impl Settings {
+    /// The default settings
+    pub const fn new() -> Settings {
+        Settings {
+            kernel: KernelChoice::Auto,
+            max_threads: 0,
+            parallel_threshold: 64 * 64 * 64,
+            block_rows: 128,
+            block_depth: 256,
+            pivot_tolerance: 1e-16,
+            lu_block_size: 64,
+        }
+    }
+}
+
+/// Kernels of matrix multiplication and transpose. With the `simd` feature,
+/// all of them use SIMD instructions in the innermost loops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KernelChoice {
+    /// Select the kernel by the problem size and the hardware, see
+    /// `calibration`
+    Auto,
+    /// Simple loops over columns without blocking
+    Naive,
+    /// Cache-blocked loops
+    Blocked,
+    /// Cache-blocked loops split across threads. It is the same as `Blocked`
+    /// without the `parallel` feature.
+    Parallel,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings::new()
+    }
+}
+
+static SETTINGS: RwLock<Settings> = RwLock::new(Settings::new());
+
+/// Hardware information used to select kernels, detected once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calibration {
+    /// Count of CPU cores available to the process
+    pub cores: usize,
+    /// Size in bytes of the L1 data cache of a core
+    pub l1_cache: usize,
+    /// Size in bytes of the L2 cache of a core
+    pub l2_cache: usize,
+}
+
+/// Get the hardware information used by `KernelChoice::Auto`. It is detected
+/// at the first call. Cache sizes are read from sysfs on Linux, otherwise
+/// 32 KiB L1 and 256 KiB L2 caches are assumed.
+pub fn calibration() -> &'static Calibration {
+    static CALIBRATION: OnceLock<Calibration> = OnceLock::new();
+    CALIBRATION.get_or_init(|| {
+        let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        Calibration {
+            cores,
+            l1_cache: cache_size(1).unwrap_or(32 * 1024),
+            l2_cache: cache_size(2).unwrap_or(256 * 1024),
+        }
+    })
+}
+
+/// Size of the data (or unified) cache of the level on CPU 0 from sysfs
+fn cache_size(level: usize) -> Option<usize> {
+    let root = std::path::Path::new("/sys/devices/system/cpu/cpu0/cache");
+    for entry in std::fs::read_dir(root).ok()? {
+        let dir = entry.ok()?.path();
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).map(|s| s.trim().to_string());
+        if read("level").ok()? != level.to_string() || read("type").ok()? == "Instruction" {
+            continue;
+        }
+        return parse_cache_size(&read("size").ok()?)
+    }
+    None
+}
+
+/// Parse sizes like `48K` or `2M`
+fn parse_cache_size(s: &str) -> Option<usize> {
+    let (digits, unit) = match s.find(|ch: char| !ch.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, ""),
+    };
+    let scale = match unit {
+        "" => 1,
+        "K" => 1024,
+        "M" => 1024 * 1024,
+        _ => return None,
+    };
+    digits.parse::<usize>().ok().map(|v| v * scale)
+}
+
+/// Get a copy of the global settings
+pub fn get() -> Settings {
+    match SETTINGS.read() {
+        Ok(s) => *s,
+        Err(poisoned) => *poisoned.into_inner(),
+    }
+}
+
+/// Replace the global settings
+pub fn set(settings: Settings) {
+    match SETTINGS.write() {
+        Ok(mut s) => *s = settings,
+        Err(poisoned) => *poisoned.into_inner() = settings,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::settings::{*};
+
+    #[test]
+    fn test_parse_cache_size() {
+        assert_eq!(parse_cache_size("48K"), Some(48 * 1024));
+        assert_eq!(parse_cache_size("2M"), Some(2 * 1024 * 1024));
+        assert_eq!(parse_cache_size("512"), Some(512));
+        assert_eq!(parse_cache_size("1G"), None);
+        let hw = calibration();
+        assert!(hw.cores >= 1 && hw.l1_cache > 0 && hw.l2_cache > 0);
+    }
+}
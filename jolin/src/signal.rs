@@ -0,0 +1,98 @@
+/*
+ * signal.rs
+ * Convolution of signals stored in matrices.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber};
+use crate::error::JolinError;
+
+/// Which part of the full convolution to keep, the same as NumPy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvolveMode {
+    /// All `n + m - 1` outputs where the signal and the kernel overlap
+    Full,
+    /// `max(n, m)` outputs centered within the full convolution
+    Same,
+    /// `max(n, m) - min(n, m) + 1` outputs where they overlap completely
+    Valid,
+}
+
+/// Convolve each column of `signal` with the column vector `kernel`, i.e.
+/// output `o` of the full convolution is the sum of `kernel[o - i] signal[i]`.
+/// It takes `O(n m)` time for each column of `n` samples and a kernel of `m`,
+/// and only the outputs of the mode are computed.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::signal::{convolve, ConvolveMode};
+/// let x = mat64![1.0; 2.0; 3.0];
+/// let k = mat64![0.0; 1.0; 0.5];
+/// assert_eq!(convolve(&x, &k, ConvolveMode::Full).unwrap(), mat64![0.0; 1.0; 2.5; 4.0; 1.5]);
+/// assert_eq!(convolve(&x, &k, ConvolveMode::Same).unwrap(), mat64![1.0; 2.5; 4.0]);
+/// assert_eq!(convolve(&x, &k, ConvolveMode::Valid).unwrap(), mat64![2.5]);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the kernel is not a column vector.
+/// 2. Not enough input - if the signal or the kernel is empty.
+pub fn convolve<T: Matrix>(signal: &T, kernel: &T, mode: ConvolveMode) -> Result<T, JolinError> {
+    if kernel.column() != 1 {
+        return Err(JolinError::shape_mismatching_with((kernel.row(), 1), (kernel.row(), kernel.column())))
+    }
+    let (n, m) = (signal.row(), kernel.row());
+    if n == 0 || m == 0 {
+        return Err(JolinError::not_enough_input())
+    }
+    let (short, long) = (usize::min(n, m), usize::max(n, m));
+    let (start, len) = match mode {
+        ConvolveMode::Full => (0, n + m - 1),
+        ConvolveMode::Same => ((short - 1) / 2, long),
+        ConvolveMode::Valid => (short - 1, long - short + 1),
+    };
+    // only the kept outputs are computed, each from the overlapping part of
+    // the signal and the kernel
+    let k = kernel.data();
+    let mut ans = T::zero(len, signal.column());
+    for c in 0..signal.column() {
+        let x = signal.data_column(c);
+        let start_idx = ans.idx(0, c);
+        let y = &mut ans.data_mut()[start_idx..(start_idx + len)];
+        for (r, out) in y.iter_mut().enumerate() {
+            let o = start + r;
+            let first = o.saturating_sub(m - 1);
+            let last = usize::min(o, n - 1);
+            *out = (first..=last).fold(T::Elem::zero(), |acc, i| acc + k[o - i] * x[i]);
+        }
+    }
+    Ok(ans)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+    use crate::signal::{*};
+
+    #[test]
+    fn test_convolve() {
+        let x = Mat64::new(5, 2, &[1.0, 2.0, 3.0, 4.0, 5.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+        let k = Mat64::new(2, 1, &[1.0, -1.0]);
+        let full = convolve(&x, &k, ConvolveMode::Full).unwrap();
+        assert_eq!(full, Mat64::new(6, 2, &[1.0, 1.0, 1.0, 1.0, 1.0, -5.0, 1.0, -1.0, 0.0, 0.0, 0.0, 0.0]));
+        assert_eq!(convolve(&x, &k, ConvolveMode::Same).unwrap(), select_rows(&full, &[0, 1, 2, 3, 4]).unwrap());
+        assert_eq!(convolve(&x, &k, ConvolveMode::Valid).unwrap(), select_rows(&full, &[1, 2, 3, 4]).unwrap());
+
+        // the kernel longer than the signal, same as NumPy
+        let s = Mat64::new(2, 1, &[1.0, 2.0]);
+        let long = Mat64::new(4, 1, &[1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(convolve(&s, &long, ConvolveMode::Full).unwrap(), Mat64::new(5, 1, &[1.0, 3.0, 3.0, 3.0, 2.0]));
+        assert_eq!(convolve(&s, &long, ConvolveMode::Same).unwrap(), Mat64::new(4, 1, &[1.0, 3.0, 3.0, 3.0]));
+        assert_eq!(convolve(&s, &long, ConvolveMode::Valid).unwrap(), Mat64::new(3, 1, &[3.0, 3.0, 3.0]));
+
+        assert_eq!(convolve(&x, &Mat64::zero(1, 2), ConvolveMode::Full).unwrap_err().kind(), JolinErrorKind::ShapeMismatching);
+        assert_eq!(convolve(&Mat64::zero(0, 1), &k, ConvolveMode::Full).unwrap_err().kind(), JolinErrorKind::NotEnoughInput);
+    }
+}
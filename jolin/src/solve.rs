@@ -0,0 +1,409 @@
+/*
+ * solve.rs
+ * Solvers of linear systems.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, MatrixView, Mat64, PromoteTo64, LikeNumber, mul, trmul};
+use crate::error::JolinError;
+use crate::decomp::lu::{lu, lu_solve, LUDecomposable};
+
+/// Floating point precision used to factorize the matrix
+#[derive(Debug, PartialEq, Copy, Clone, Eq)]
+pub enum Precision {
+    /// Factorize in 32-bit and refine the answer in 64-bit
+    Single,
+    /// Factorize and solve in 64-bit
+    Double,
+    /// Choose between single and double precision by a condition estimate
+    Auto,
+}
+
+/// Options of the mixed precision solver
+#[derive(Debug, Clone, Copy)]
+pub struct MixedPrecisionOptions {
+    /// Precision used to factorize the matrix
+    pub precision: Precision,
+    /// Maximal count of refinement steps before falling back to 64-bit
+    pub max_refinements: usize,
+}
+
+impl Default for MixedPrecisionOptions {
+    fn default() -> Self {
+        MixedPrecisionOptions { precision: Precision::Auto, max_refinements: 30 }
+    }
+}
+
+/// The answer of the mixed precision solver
+#[derive(Debug, Clone)]
+pub struct MixedPrecisionSolution {
+    /// Solution of the linear system
+    pub x: Mat64,
+    /// Precision actually used to factorize the matrix, never `Auto`
+    pub precision: Precision,
+    /// Count of iterative refinement steps
+    pub refinements: usize,
+}
+
+/// Solve `A X = B` by factorizing `A` in 32-bit and iteratively refining the
+/// answer with residuals computed in 64-bit, like LAPACK's `dsgesv`.
+///
+/// Factorizing in single precision is about twice as fast, and the refinement
+/// recovers double precision accuracy as long as `A` is not too ill
+/// conditioned. With `Precision::Auto`, the condition number is estimated from
+/// the single precision factors and 64-bit is chosen directly for matrices
+/// that are too ill conditioned. If the refinement fails to converge, the
+/// solver falls back to a 64-bit factorization.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::solve::{mixed_precision_solve, MixedPrecisionOptions, Precision};
+/// let a = mat64![4.0, 1.0; 2.0, 3.0];
+/// let b = mat64![1.0; 2.0];
+/// let ans = mixed_precision_solve(&a, &b, &MixedPrecisionOptions::default()).unwrap();
+/// assert_eq!(ans.precision, Precision::Single);
+/// assert!(eq_with_error(&ans.x, &mat64![0.1; 0.6], 1e-15));
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `A` is not square or `B` has a different row count.
+/// 2. Singular matrix - if `A` is singular.
+pub fn mixed_precision_solve(a: &Mat64, b: &Mat64, options: &MixedPrecisionOptions) -> Result<MixedPrecisionSolution, JolinError> {
+    if a.row() != a.column() {
+        return Err(JolinError::shape_mismatching_with((a.row(), a.row()), (a.row(), a.column())))
+    }
+    if a.row() != b.row() {
+        return Err(JolinError::shape_mismatching_with((a.row(), b.column()), (b.row(), b.column())))
+    }
+    if options.precision != Precision::Double {
+        if let Some(ans) = refine_from_single(a, b, options) {
+            return Ok(ans)
+        }
+    }
+    let lud = Mat64::lu_decomp(a)?;
+    Ok(MixedPrecisionSolution { x: lu_solve(&lud, b), precision: Precision::Double, refinements: 0 })
+}
+
+/// Try solving with the single precision factors. Returns `None` if double
+/// precision should be used instead.
+fn refine_from_single(a: &Mat64, b: &Mat64, options: &MixedPrecisionOptions) -> Option<MixedPrecisionSolution> {
+    let n = a.row();
+    let lud = lu(&a.to_f32()).ok()?;
+    if options.precision == Precision::Auto {
+        // The ratio of the largest and the smallest pivots is a cheap lower
+        // estimate of the condition number. Refinement only converges when
+        // cond(A) * eps_32 is well below one.
+        let pivots: Vec<f32> = (0..n).map(|i| lud.u.elem(i, i).abs()).collect();
+        let max_pivot = pivots.iter().cloned().fold(0.0f32, f32::max);
+        let min_pivot = pivots.iter().cloned().fold(f32::INFINITY, f32::min);
+        if (max_pivot / min_pivot) as f64 * (f32::EPSILON as f64) > 1e-2 {
+            return None
+        }
+    }
+
+    let a_norm = inf_norm(a);
+    let threshold = (n as f64).sqrt() * f64::EPSILON * a_norm;
+    let mut x = lu_solve(&lud, &b.to_f32()).promote();
+    for step in 0..(options.max_refinements + 1) {
+        let r = residual(a, &x, b);
+        if inf_norm(&r) <= threshold * inf_norm(&x) {
+            return Some(MixedPrecisionSolution { x, precision: Precision::Single, refinements: step })
+        }
+        if step == options.max_refinements {
+            break;
+        }
+        let d = lu_solve(&lud, &r.to_f32()).promote();
+        for (xi, di) in x.data_mut().iter_mut().zip(d.data().iter()) {
+            *xi += *di;
+        }
+        if x.data().iter().any(|v| !v.is_finite()) {
+            break;
+        }
+    }
+    None
+}
+
+/// Compute `B - A X`
+fn residual(a: &Mat64, x: &Mat64, b: &Mat64) -> Mat64 {
+    let mut r = b.clone();
+    for c in 0..b.column() {
+        for k in 0..a.column() {
+            let xk = x.elem(k, c);
+            for i in 0..a.row() {
+                *r.elem_mut(i, c) -= a.elem(i, k) * xk;
+            }
+        }
+    }
+    r
+}
+
+/// Maximal absolute row sum
+fn inf_norm(a: &Mat64) -> f64 {
+    (0..a.row()).map(|r| (0..a.column()).map(|c| a.elem(r, c).abs()).sum::<f64>())
+        .fold(0.0, f64::max)
+}
+
+/// Inverse of `A + u v^T` from the inverse of `A` with the Sherman-Morrison
+/// formula `A^-1 - A^-1 u v^T A^-1 / (1 + v^T A^-1 u)`, in `O(n^2)` instead
+/// of inverting from scratch. `u` and `v` are column vectors.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::solve::rank1_update_inverse;
+/// let a_inv = Mat64::identity(2);
+/// let ans = rank1_update_inverse(&a_inv, &mat64![1.0; 0.0], &mat64![1.0; 0.0]).unwrap();
+/// assert_eq!(ans, mat64![0.5, 0.0; 0.0, 1.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `A^-1` is not square, or `u` or `v` is not a
+///    column vector of its size.
+/// 2. Singular matrix - if `A + u v^T` is singular up to rounding errors.
+pub fn rank1_update_inverse<T: Matrix>(a_inv: &T, u: &T, v: &T) -> Result<T, JolinError> {
+    let n = a_inv.row();
+    if a_inv.column() != n {
+        return Err(JolinError::shape_mismatching())
+    }
+    for x in [u, v] {
+        if (x.row(), x.column()) != (n, 1) {
+            return Err(JolinError::shape_mismatching_with((n, 1), (x.row(), x.column())))
+        }
+    }
+    let a_inv_u = mul(a_inv, u)?;
+    // row vector v^T A^-1 as a column
+    let a_inv_t_v = trmul(a_inv, v)?;
+    let vt_a_inv_u: T::Elem = v.data().iter().zip(a_inv_u.data().iter()).map(|(x, y)| *x * *y).sum();
+    let denominator = T::Elem::one() + vt_a_inv_u;
+    if denominator.abs() <= T::Elem::epsilon().times_real(n as f64) * (T::Elem::one() + vt_a_inv_u.abs()) {
+        return Err(JolinError::singular_matrix())
+    }
+    let mut ans = a_inv.clone();
+    for c in 0..n {
+        let scale = a_inv_t_v.data()[c] / denominator;
+        for r in 0..n {
+            *ans.elem_mut(r, c) = ans.elem(r, c) - a_inv_u.data()[r] * scale;
+        }
+    }
+    Ok(ans)
+}
+
+/// Row and column scaling factors that equilibrate a matrix, see
+/// `equilibrate`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equilibration<E: LikeNumber> {
+    /// Factor of each row
+    pub row_scale: Vec<E>,
+    /// Factor of each column
+    pub column_scale: Vec<E>,
+}
+
+impl<E: LikeNumber> Equilibration<E> {
+    /// The scaled matrix `diag(row_scale) A diag(column_scale)`
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the shape of `A` doesn't match the factors.
+    pub fn scale<T: Matrix<Elem = E>>(&self, a: &T) -> Result<T, JolinError> {
+        let (m, n) = (self.row_scale.len(), self.column_scale.len());
+        if (a.row(), a.column()) != (m, n) {
+            return Err(JolinError::shape_mismatching_with((m, n), (a.row(), a.column())))
+        }
+        let mut ans = a.clone();
+        for c in 0..n {
+            for r in 0..m {
+                *ans.elem_mut(r, c) = self.row_scale[r] * a.elem(r, c) * self.column_scale[c];
+            }
+        }
+        Ok(ans)
+    }
+}
+
+/// Compute the row and column scaling factors making the largest absolute
+/// value of each row and column of the scaled matrix close to one, like
+/// LAPACK's `geequb`. The rows are scaled first, then the columns of the
+/// row-scaled matrix.
+///
+/// The factors are powers of two, so scaling introduces no rounding error.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::solve::equilibrate;
+/// let a = mat64![1024.0, 2048.0; 0.25, 1.0];
+/// let eq = equilibrate(&a).unwrap();
+/// assert_eq!(eq.row_scale, vec![1.0 / 2048.0, 1.0]);
+/// assert_eq!(eq.column_scale, vec![2.0, 1.0]);
+/// assert_eq!(eq.scale(&a).unwrap(), mat64![1.0, 1.0; 0.5, 1.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Singular matrix - if a row or a column is all zero.
+pub fn equilibrate<T: Matrix>(a: &T) -> Result<Equilibration<T::Elem>, JolinError> {
+    let (m, n) = (a.row(), a.column());
+    let mut row_scale = Vec::with_capacity(m);
+    for r in 0..m {
+        let max = max_abs((0..n).map(|c| a.elem(r, c)));
+        row_scale.push(reciprocal_power_of_two(max)?);
+    }
+    let mut column_scale = Vec::with_capacity(n);
+    for c in 0..n {
+        let max = max_abs((0..m).map(|r| row_scale[r] * a.elem(r, c)));
+        column_scale.push(reciprocal_power_of_two(max)?);
+    }
+    Ok(Equilibration { row_scale, column_scale })
+}
+
+/// Solve `A X = B` on the equilibrated system: the scaled matrix
+/// `A_s = R A C` is factorized, `A_s Y = R B` is solved, and `X = C Y`.
+/// It's much more accurate than `lu` when the rows or columns of `A` have
+/// very different magnitudes.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::solve::equilibrated_solve;
+/// let a = mat64![1e10, 2e10; 1e-10, -1e-10];
+/// let x = equilibrated_solve(&a, &mat64![3e10; 0.0]).unwrap();
+/// assert!(eq_with_error(&x, &mat64![1.0; 1.0], 1e-12));
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if `A` is not square or `B` has a different row count.
+/// 2. Singular matrix - if `A` is singular.
+pub fn equilibrated_solve<T: Matrix>(a: &T, b: &T) -> Result<T, JolinError> {
+    if a.row() != a.column() {
+        return Err(JolinError::shape_mismatching_with((a.row(), a.row()), (a.row(), a.column())))
+    }
+    if b.row() != a.row() {
+        return Err(JolinError::shape_mismatching_with((a.row(), b.column()), (b.row(), b.column())))
+    }
+    let eq = equilibrate(a)?;
+    let lud = lu(&eq.scale(a)?)?;
+    let mut rb = b.clone();
+    for c in 0..b.column() {
+        for r in 0..b.row() {
+            *rb.elem_mut(r, c) = eq.row_scale[r] * b.elem(r, c);
+        }
+    }
+    let mut x = lud.solve(&rb)?;
+    for c in 0..x.column() {
+        for r in 0..x.row() {
+            *x.elem_mut(r, c) = eq.column_scale[r] * x.elem(r, c);
+        }
+    }
+    Ok(x)
+}
+
+fn max_abs<E: LikeNumber, I: Iterator<Item = E>>(v: I) -> E {
+    v.map(|x| x.abs()).fold(E::zero(), |acc, x| if x > acc { x } else { acc })
+}
+
+/// The power of two nearest to `1 / x` in the log scale
+fn reciprocal_power_of_two<E: LikeNumber>(x: E) -> Result<E, JolinError> {
+    let v = x.to_f64();
+    if v == 0.0 || !v.is_finite() {
+        return Err(JolinError::singular_matrix())
+    }
+    Ok(E::one().times_real((-v.log2().round()).exp2()))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::solve::{*};
+
+    #[test]
+    fn test_mixed_precision_refinement() {
+        let a = mat64![
+            10.0, 1.0, 2.0, 0.5;
+            1.0, 8.0, -1.0, 0.25;
+            2.0, -1.0, 9.0, 1.0;
+            0.5, 0.25, 1.0, 7.0];
+        let x = mat64![1.0, 0.5; -2.0, 0.25; 3.0, -1.0; 0.1, 2.0];
+        let b = mul(&a, &x).unwrap();
+        let ans = mixed_precision_solve(&a, &b, &MixedPrecisionOptions::default()).unwrap();
+        assert_eq!(ans.precision, Precision::Single);
+        assert!(ans.refinements > 0);
+        assert!(eq_with_error(&ans.x, &x, 1e-13));
+    }
+
+    #[test]
+    fn test_mixed_precision_ill_conditioned() {
+        // Hilbert matrix of order 8 has a condition number around 1e10
+        let n = 8;
+        let mut a = Mat64::zero(n, n);
+        for r in 0..n {
+            for c in 0..n {
+                *a.elem_mut(r, c) = 1.0 / ((r + c + 1) as f64);
+            }
+        }
+        let b = mul(&a, &Mat64::ones(n, 1)).unwrap();
+        let ans = mixed_precision_solve(&a, &b, &MixedPrecisionOptions::default()).unwrap();
+        assert_eq!(ans.precision, Precision::Double);
+
+        // Forcing single precision still falls back when refinement fails
+        let options = MixedPrecisionOptions { precision: Precision::Single, max_refinements: 5 };
+        let ans = mixed_precision_solve(&a, &b, &options).unwrap();
+        assert_eq!(ans.precision, Precision::Double);
+    }
+
+    #[test]
+    fn test_mixed_precision_errors() {
+        let a = mat64![1.0, 2.0; 2.0, 4.0];
+        let options = MixedPrecisionOptions::default();
+        assert!(mixed_precision_solve(&a, &mat64![1.0; 1.0], &options).is_err());
+        let err = mixed_precision_solve(&a, &mat64![1.0; 1.0; 1.0], &options).unwrap_err();
+        assert_eq!(err.context(), JolinError::shape_mismatching_with((2, 1), (3, 1)).context());
+        let err = mixed_precision_solve(&Mat64::zero(2, 3), &mat64![1.0; 1.0], &options).unwrap_err();
+        assert_eq!(err.context(), JolinError::shape_mismatching_with((2, 2), (2, 3)).context());
+    }
+
+    #[test]
+    fn test_rank1_update_inverse() {
+        let a = mat64![4.0, 1.0, 0.0; 2.0, 5.0, 1.0; 0.0, 1.0, 3.0];
+        let a_inv = lu(&a).unwrap().inverse();
+        let u = mat64![1.0; -1.0; 2.0];
+        let v = mat64![0.5; 1.0; 0.0];
+        let updated = add(&a, &mul(&u, &tr(&v)).unwrap()).unwrap();
+        let ans = rank1_update_inverse(&a_inv, &u, &v).unwrap();
+        assert!(eq_with_error(&mul(&ans, &updated).unwrap(), &Mat64::identity(3), 1e-12));
+
+        // A - e_0 e_0^T of diag(1, 2) is singular
+        let d_inv = mat64![1.0, 0.0; 0.0, 0.5];
+        let e0 = mat64![1.0; 0.0];
+        let minus_e0 = mat64![-1.0; 0.0];
+        assert!(rank1_update_inverse(&d_inv, &e0, &minus_e0).is_err());
+        assert!(rank1_update_inverse(&a_inv, &e0, &v).is_err());
+    }
+
+    #[test]
+    fn test_equilibrate() {
+        let a = mat64![3e8, 1e8, 0.0; 1e-6, 4e-6, 2e-6; 0.5, 0.0, 1.0];
+        let eq = equilibrate(&a).unwrap();
+        let s = eq.scale(&a).unwrap();
+        // every row and column has its largest element within a factor of
+        // two around one
+        for r in 0..3 {
+            let max = (0..3).map(|c| s.elem(r, c).abs()).fold(0.0, f64::max);
+            assert!(max > 0.35 && max < 2.9, "row {} max {}", r, max);
+        }
+        for c in 0..3 {
+            let max = (0..3).map(|r| s.elem(r, c).abs()).fold(0.0, f64::max);
+            assert!(max > 0.35 && max < 2.9, "column {} max {}", c, max);
+        }
+        assert!(eq.row_scale.iter().chain(eq.column_scale.iter()).all(|x| x.log2().fract() == 0.0));
+
+        let x = mat64![1.0, -1.0; 2.0, 0.5; -3.0, 4.0];
+        let b = mul(&a, &x).unwrap();
+        let ans = equilibrated_solve(&a, &b).unwrap();
+        assert!(eq_with_error(&ans, &x, 1e-9));
+
+        assert!(equilibrate(&mat64![1.0, 0.0; 0.0, 0.0]).is_err());
+        assert!(equilibrate(&mat64![1.0, 0.0; 1.0, 0.0]).is_err());
+        assert!(eq.scale(&Mat64::zero(2, 3)).is_err());
+        let err = equilibrated_solve(&a, &Mat64::zero(2, 1)).unwrap_err();
+        assert_eq!(err.context(), JolinError::shape_mismatching_with((3, 1), (2, 1)).context());
+        let err = equilibrated_solve(&Mat64::zero(2, 3), &Mat64::zero(2, 1)).unwrap_err();
+        assert_eq!(err.context(), JolinError::shape_mismatching_with((2, 2), (2, 3)).context());
+    }
+}
@@ -0,0 +1,311 @@
+/*
+ * special.rs
+ * Constructors of special matrices.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::f64::consts::PI;
+use crate::matrix::{Matrix, LikeNumber};
+use crate::error::JolinError;
+use crate::fft::{Complex, fft, ifft};
+
+/// Hilbert matrix of shape `n * n`, where the element at `(i, j)` is
+/// `1 / (i + j + 1)`. It's a classic ill-conditioned matrix for testing.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::special::hilbert;
+/// let h: Mat64 = hilbert(2);
+/// assert_eq!(h, mat64![1.0, 0.5; 0.5, 1.0 / 3.0]);
+/// ```
+pub fn hilbert<T: Matrix>(n: usize) -> T {
+    let mut ans = T::zero(n, n);
+    for c in 0..n {
+        for r in 0..n {
+            *ans.elem_mut(r, c) = T::Elem::one().times_real(1.0 / (r + c + 1) as f64);
+        }
+    }
+    ans
+}
+
+/// The exact inverse of the Hilbert matrix of shape `n * n`, which has
+/// integer elements. They are exactly representable in `f64` for `n` up to
+/// 13 or so, and grow too quickly to be useful beyond.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::special::hilbert_inverse;
+/// let h: Mat64 = hilbert_inverse(2);
+/// assert_eq!(h, mat64![4.0, -6.0; -6.0, 12.0]);
+/// ```
+pub fn hilbert_inverse<T: Matrix>(n: usize) -> T {
+    let mut ans = T::zero(n, n);
+    for c in 0..n {
+        for r in 0..n {
+            let sign = if (r + c) % 2 == 0 { 1.0 } else { -1.0 };
+            let b = binomial(r + c, r);
+            let v = sign * (r + c + 1) as f64
+                * binomial(n + r, n - c - 1) * binomial(n + c, n - r - 1) * b * b;
+            *ans.elem_mut(r, c) = T::Elem::one().times_real(v);
+        }
+    }
+    ans
+}
+
+/// Toeplitz matrix with constant diagonals, given its first column and first
+/// row. The element at `(i, j)` is `first_col[i - j]` for `i >= j`, and
+/// `first_row[j - i]` otherwise.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::special::toeplitz;
+/// let t: Mat64 = toeplitz(&[1.0, 2.0, 3.0], &[1.0, 4.0]).unwrap();
+/// assert_eq!(t, mat64![1.0, 4.0; 2.0, 1.0; 3.0, 2.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Invalid value - if the first elements of the column and the row differ.
+pub fn toeplitz<T: Matrix>(first_col: &[T::Elem], first_row: &[T::Elem]) -> Result<T, JolinError> {
+    if let (Some(a), Some(b)) = (first_col.first(), first_row.first()) {
+        if a != b {
+            return Err(JolinError::invalid_value())
+        }
+    }
+    let mut ans = T::zero(first_col.len(), first_row.len());
+    for c in 0..first_row.len() {
+        for r in 0..first_col.len() {
+            *ans.elem_mut(r, c) = if r >= c { first_col[r - c] } else { first_row[c - r] };
+        }
+    }
+    Ok(ans)
+}
+
+/// Hankel matrix with constant anti-diagonals, given its first column and
+/// last row. The element at `(i, j)` is `first_col[i + j]` if it's in the
+/// column, and `last_row[i + j - m + 1]` otherwise, where `m` is the row
+/// count.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::special::hankel;
+/// let h: Mat64 = hankel(&[1.0, 2.0, 3.0], &[3.0, 4.0, 5.0]).unwrap();
+/// assert_eq!(h, mat64![1.0, 2.0, 3.0; 2.0, 3.0, 4.0; 3.0, 4.0, 5.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Invalid value - if the last element of the column and the first
+///    element of the row differ.
+pub fn hankel<T: Matrix>(first_col: &[T::Elem], last_row: &[T::Elem]) -> Result<T, JolinError> {
+    if let (Some(a), Some(b)) = (first_col.last(), last_row.first()) {
+        if a != b {
+            return Err(JolinError::invalid_value())
+        }
+    }
+    let m = first_col.len();
+    let mut ans = T::zero(m, last_row.len());
+    for c in 0..last_row.len() {
+        for r in 0..m {
+            *ans.elem_mut(r, c) = if r + c < m { first_col[r + c] } else { last_row[r + c + 1 - m] };
+        }
+    }
+    Ok(ans)
+}
+
+/// Companion matrix of the polynomial `c[0] x^n + c[1] x^(n-1) + ... + c[n]`,
+/// whose eigenvalues are the roots of the polynomial. The first row is
+/// `-c[1..] / c[0]`, and the subdiagonal is one.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::special::companion;
+/// // x^2 - 3x + 2 = (x - 1)(x - 2)
+/// let c: Mat64 = companion(&[1.0, -3.0, 2.0]).unwrap();
+/// assert_eq!(c, mat64![3.0, -2.0; 1.0, 0.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Not enough input - if there are less than two coefficients.
+/// 2. Invalid value - if the leading coefficient is zero.
+pub fn companion<T: Matrix>(coeffs: &[T::Elem]) -> Result<T, JolinError> {
+    if coeffs.len() < 2 {
+        return Err(JolinError::not_enough_input())
+    }
+    if coeffs[0] == T::Elem::zero() {
+        return Err(JolinError::invalid_value())
+    }
+    let n = coeffs.len() - 1;
+    let mut ans = T::zero(n, n);
+    for c in 0..n {
+        *ans.elem_mut(0, c) = T::Elem::zero() - coeffs[c + 1] / coeffs[0];
+        if c + 1 < n {
+            *ans.elem_mut(c + 1, c) = T::Elem::one();
+        }
+    }
+    Ok(ans)
+}
+
+/// Discrete Fourier transform matrix of shape `n * n` as its real and
+/// imaginary parts, where the element at `(j, k)` is `e^{-2 pi i jk / n}`.
+/// It's not normalized, so `F^H F = n I`.
+///
+/// Multiplying with the matrix costs `O(n^2)` per column, and
+/// `fft_columns` computes the same product in `O(n log n)`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::special::dft;
+/// let (re, im): (Mat64, Mat64) = dft(2);
+/// assert_eq!(re, mat64![1.0, 1.0; 1.0, -1.0]);
+/// assert!(eq_with_error(&im, &Mat64::zero(2, 2), 1e-15));
+/// ```
+pub fn dft<T: Matrix>(n: usize) -> (T, T) {
+    let mut re = T::zero(n, n);
+    let mut im = T::zero(n, n);
+    for c in 0..n {
+        for r in 0..n {
+            // jk mod n keeps the angle small and accurate
+            let jk = (r as u128 * c as u128 % n as u128) as f64;
+            let w = Complex::from_angle(-2.0 * PI * jk / n as f64);
+            *re.elem_mut(r, c) = T::Elem::one().times_real(w.re);
+            *im.elem_mut(r, c) = T::Elem::one().times_real(w.im);
+        }
+    }
+    (re, im)
+}
+
+/// Discrete Fourier transform of each column of a real matrix in
+/// `O(n log n)` per column, i.e. `F A` with `F` from `dft`. The answer is
+/// the real and imaginary parts.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::special::fft_columns;
+/// let (re, im) = fft_columns(&mat64![1.0; 2.0; 3.0; 4.0]);
+/// assert!(eq_with_error(&re, &mat64![10.0; -2.0; -2.0; -2.0], 1e-12));
+/// assert!(eq_with_error(&im, &mat64![0.0; 2.0; 0.0; -2.0], 1e-12));
+/// ```
+pub fn fft_columns<T: Matrix>(mat: &T) -> (T, T) {
+    let zero = T::zero(mat.row(), mat.column());
+    transform_columns(mat, &zero, fft)
+}
+
+/// Inverse discrete Fourier transform of each column of a complex matrix
+/// given as its real and imaginary parts, including the `1 / n` factor, so
+/// it undoes `fft_columns`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::special::{fft_columns, ifft_columns};
+/// let a = mat64![1.0, 0.5; 2.0, -1.0; 3.0, 2.0];
+/// let (re, im) = fft_columns(&a);
+/// let (back, zero) = ifft_columns(&re, &im).unwrap();
+/// assert!(eq_with_error(&back, &a, 1e-12));
+/// assert!(eq_with_error(&zero, &Mat64::zero(3, 2), 1e-12));
+/// ```
+///
+/// Potential errors:
+/// 1. Shape mismatching - if the parts have different shapes.
+pub fn ifft_columns<T: Matrix>(re: &T, im: &T) -> Result<(T, T), JolinError> {
+    if (re.row(), re.column()) != (im.row(), im.column()) {
+        return Err(JolinError::shape_mismatching_with((re.row(), re.column()), (im.row(), im.column())))
+    }
+    Ok(transform_columns(re, im, ifft))
+}
+
+fn transform_columns<T: Matrix>(re: &T, im: &T, f: fn(&[Complex]) -> Vec<Complex>) -> (T, T) {
+    let mut ans_re = T::zero(re.row(), re.column());
+    let mut ans_im = T::zero(re.row(), re.column());
+    for c in 0..re.column() {
+        let x: Vec<Complex> = re.data_column(c).iter().zip(im.data_column(c).iter())
+            .map(|(u, v)| Complex::new(u.to_f64(), v.to_f64()))
+            .collect();
+        for (r, v) in f(&x).iter().enumerate() {
+            *ans_re.elem_mut(r, c) = T::Elem::one().times_real(v.re);
+            *ans_im.elem_mut(r, c) = T::Elem::one().times_real(v.im);
+        }
+    }
+    (ans_re, ans_im)
+}
+
+/// Binomial coefficient `C(n, k)`
+fn binomial(n: usize, k: usize) -> f64 {
+    let k = usize::min(k, n - k);
+    // each partial product is an integer, so there is no rounding for
+    // small inputs
+    (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::special::{*};
+
+    #[test]
+    fn test_hilbert() {
+        let h: Mat64 = hilbert(3);
+        assert_eq!(h.elem(2, 1), 0.25);
+        assert_eq!(hilbert_inverse::<Mat64>(3), mat64![
+            9.0, -36.0, 30.0;
+            -36.0, 192.0, -180.0;
+            30.0, -180.0, 180.0
+        ]);
+        for n in [1, 5, 8] {
+            let prod = mul(&hilbert::<Mat64>(n), &hilbert_inverse(n)).unwrap();
+            assert!(eq_with_error(&prod, &Mat64::identity(n), 1e-6));
+        }
+        assert_eq!(hilbert::<Mat32>(0), Mat32::zero(0, 0));
+        assert_eq!(binomial(10, 3), 120.0);
+    }
+
+    #[test]
+    fn test_toeplitz_hankel() {
+        let t: Mat64 = toeplitz(&[1.0, 2.0], &[1.0, 5.0, 6.0]).unwrap();
+        assert_eq!(t, mat64![1.0, 5.0, 6.0; 2.0, 1.0, 5.0]);
+        let h: Mat64 = hankel(&[1.0, 2.0], &[2.0, 7.0, 8.0]).unwrap();
+        assert_eq!(h, mat64![1.0, 2.0, 7.0; 2.0, 7.0, 8.0]);
+        let err = toeplitz::<Mat64>(&[1.0], &[2.0]).unwrap_err();
+        assert_eq!(err.kind(), crate::error::JolinErrorKind::InvalidValue);
+        assert!(hankel::<Mat64>(&[1.0, 2.0], &[1.0]).is_err());
+        assert_eq!(toeplitz::<Mat32>(&[], &[1.0]).unwrap(), Mat32::zero(0, 1));
+    }
+
+    #[test]
+    fn test_companion() {
+        // (x - 1)(x - 2)(x - 3)
+        let c: Mat64 = companion(&[2.0, -12.0, 22.0, -12.0]).unwrap();
+        assert_eq!(c, mat64![6.0, -11.0, 6.0; 1.0, 0.0, 0.0; 0.0, 1.0, 0.0]);
+        for root in [1.0, 2.0, 3.0] {
+            let shifted = elemwise_indexed(&c, |r, k, v| if r == k { v - root } else { *v });
+            assert!(crate::det::det(&shifted).unwrap().abs() < 1e-12);
+        }
+        assert_eq!(companion::<Mat64>(&[2.0, 1.0]).unwrap(), mat64![-0.5]);
+        let err = companion::<Mat64>(&[1.0]).unwrap_err();
+        assert_eq!(err.kind(), crate::error::JolinErrorKind::NotEnoughInput);
+        assert!(companion::<Mat64>(&[0.0, 1.0]).is_err());
+    }
+
+    #[test]
+    fn test_dft() {
+        for n in [1, 4, 6] {
+            let (re, im): (Mat64, Mat64) = dft(n);
+            // F^H F = (re^T re + im^T im) + i (re^T im - im^T re) = n I
+            let gram = add(&trmul(&re, &re).unwrap(), &trmul(&im, &im).unwrap()).unwrap();
+            let cross = sub(&trmul(&re, &im).unwrap(), &trmul(&im, &re).unwrap()).unwrap();
+            assert!(eq_with_error(&gram, &elemwise(&Mat64::identity(n), |x| x * n as f64), 1e-12));
+            assert!(eq_with_error(&cross, &Mat64::zero(n, n), 1e-12));
+
+            let a = elemwise_indexed(&Mat64::zero(n, 3), |r, c, _| (r * 3 + c * c) as f64 - 2.5);
+            let (fre, fim) = fft_columns(&a);
+            assert!(eq_with_error(&fre, &mul(&re, &a).unwrap(), 1e-12));
+            assert!(eq_with_error(&fim, &mul(&im, &a).unwrap(), 1e-12));
+            let (back, zero) = ifft_columns(&fre, &fim).unwrap();
+            assert!(eq_with_error(&back, &a, 1e-12));
+            assert!(eq_with_error(&zero, &Mat64::zero(n, 3), 1e-12));
+        }
+        assert_eq!(dft::<Mat32>(0), (Mat32::zero(0, 0), Mat32::zero(0, 0)));
+        assert!(ifft_columns(&Mat64::zero(2, 2), &Mat64::zero(2, 1)).is_err());
+    }
+}
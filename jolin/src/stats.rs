@@ -0,0 +1,642 @@
+/*
+ * stats.rs
+ * Statistics of matrices.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::fmt;
+use crate::matrix::{Matrix, LikeNumber, Axis, mean_axis, mul, tr, ata};
+use crate::error::JolinError;
+use crate::batch::Batch;
+use crate::decomp::eig::eig_symmetric;
+use crate::checks::is_symmetric;
+
+/// Summary of a matrix, see `describe`
+#[derive(Debug, Clone)]
+pub struct MatrixSummary<T: Matrix> {
+    /// Row count
+    pub row: usize,
+    /// Column count
+    pub column: usize,
+    /// Minimal element
+    pub min: T::Elem,
+    /// Maximal element
+    pub max: T::Elem,
+    /// Mean of the elements
+    pub mean: T::Elem,
+    /// Population standard deviation of the elements
+    pub std: T::Elem,
+    /// Frobenius norm
+    pub norm: T::Elem,
+    /// Whether the matrix is square and symmetric up to rounding errors
+    pub symmetric: bool,
+    /// Numerical rank, or `None` if the matrix contains NaN
+    pub rank: Option<usize>,
+    /// 2-norm condition number, or `None` if the matrix contains NaN. It is
+    /// infinite for rank deficient matrices.
+    pub condition: Option<T::Elem>,
+    /// Count of NaN elements
+    pub nan_count: usize,
+}
+
+/// Summarize a matrix: its shape, the range, mean and standard deviation of
+/// its elements, norm, symmetry, numerical rank, condition number and count
+/// of NaN. NaN elements are skipped when computing the element statistics.
+///
+/// The rank and the condition number come from the singular values, which
+/// are computed from the eigenvalues of `A^T A` (or `A A^T`), so the cost is
+/// cubic in the smaller dimension of the matrix.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::stats::describe;
+/// let summary = describe(&mat64![2.0, 0.0; 0.0, 1.0]).unwrap();
+/// assert_eq!(summary.max, 2.0);
+/// assert_eq!(summary.rank, Some(2));
+/// assert!(summary.symmetric);
+/// println!("{}", summary);
+/// ```
+///
+/// Potential errors:
+/// 1. Not enough input - if the matrix is empty or all its elements are NaN.
+pub fn describe<T: Matrix>(mat: &T) -> Result<MatrixSummary<T>, JolinError> {
+    let values: Vec<T::Elem> = mat.data().iter().cloned().filter(|x| !is_nan(x)).collect();
+    if values.is_empty() {
+        return Err(JolinError::not_enough_input())
+    }
+    let nan_count = mat.data().len() - values.len();
+    let mut min = values[0];
+    let mut max = values[0];
+    for x in values.iter() {
+        if *x < min {
+            min = *x;
+        }
+        if *x > max {
+            max = *x;
+        }
+    }
+    let count = T::Elem::one().times_real(values.len() as f64);
+    let mean = values.iter().cloned().sum::<T::Elem>() / count;
+    let std = (values.iter().map(|x| (*x - mean) * (*x - mean)).sum::<T::Elem>() / count).sqrt();
+    let norm = values.iter().map(|x| (*x) * (*x)).sum::<T::Elem>().sqrt();
+    let max_abs = if max.abs() > min.abs() { max.abs() } else { min.abs() };
+
+    let tol = T::Elem::epsilon().times_real(100.0) * max_abs;
+    let symmetric = nan_count == 0 && is_symmetric(mat, tol);
+
+    let (rank, condition) = if nan_count == 0 {
+        let (rank, condition) = rank_and_condition(mat)?;
+        (Some(rank), Some(condition))
+    } else {
+        (None, None)
+    };
+
+    Ok(MatrixSummary {
+        row: mat.row(),
+        column: mat.column(),
+        min, max, mean, std, norm, symmetric, rank, condition, nan_count,
+    })
+}
+
+impl<T: Matrix> fmt::Display for MatrixSummary<T> where T::Elem: fmt::Display {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "shape:     {} x {}", self.row, self.column)?;
+        writeln!(f, "min:       {}", self.min)?;
+        writeln!(f, "max:       {}", self.max)?;
+        writeln!(f, "mean:      {}", self.mean)?;
+        writeln!(f, "std:       {}", self.std)?;
+        writeln!(f, "norm:      {}", self.norm)?;
+        writeln!(f, "symmetric: {}", self.symmetric)?;
+        match self.rank {
+            Some(rank) => writeln!(f, "rank:      {}", rank)?,
+            None => writeln!(f, "rank:      -")?,
+        }
+        match self.condition {
+            Some(cond) => writeln!(f, "condition: {}", cond)?,
+            None => writeln!(f, "condition: -")?,
+        }
+        write!(f, "NaN count: {}", self.nan_count)
+    }
+}
+
+/// Normalization of the sums of squares in covariance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Normalization {
+    /// Divide by `n - 1`, the unbiased sample covariance
+    Unbiased,
+    /// Divide by `n`, the maximum likelihood estimate
+    Biased,
+}
+
+/// Sample covariance matrix of the columns of a matrix, whose rows are the
+/// observations. The sum of squares is divided by `n - 1`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::stats::cov;
+/// let x = mat64![1.0, 2.0; 2.0, 4.0; 3.0, 6.0];
+/// assert_eq!(cov(&x).unwrap(), mat64![1.0, 2.0; 2.0, 4.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Not enough input - if there are less than two observations.
+pub fn cov<T: Matrix>(mat: &T) -> Result<T, JolinError> {
+    cov_with(mat, Normalization::Unbiased)
+}
+
+/// Covariance matrix of the columns of a matrix, whose rows are the
+/// observations, with the given normalization.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::stats::{cov_with, Normalization};
+/// let x = mat64![1.0; 3.0];
+/// assert_eq!(cov_with(&x, Normalization::Biased).unwrap(), mat64![1.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Not enough input - if there are less than two observations for the
+///    unbiased normalization, or none for the biased one.
+pub fn cov_with<T: Matrix>(mat: &T, normalization: Normalization) -> Result<T, JolinError> {
+    let n = match normalization {
+        Normalization::Unbiased => mat.row().saturating_sub(1),
+        Normalization::Biased => mat.row(),
+    };
+    if n == 0 {
+        return Err(JolinError::not_enough_input())
+    }
+    let mean = mean_axis(mat, Axis::Column);
+    let mut centered = mat.clone();
+    for c in 0..mat.column() {
+        for r in 0..mat.row() {
+            *centered.elem_mut(r, c) = mat.elem(r, c) - mean.data()[c];
+        }
+    }
+    let mut ans = ata(&centered);
+    let scale = T::Elem::one() / T::Elem::one().times_real(n as f64);
+    ans.apply(|x| x * scale);
+    Ok(ans)
+}
+
+/// Pearson correlation matrix of the columns of a matrix, whose rows are the
+/// observations.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::stats::corr;
+/// let x = mat64![1.0, 3.0; 2.0, 2.0; 3.0, 1.0];
+/// assert_eq!(corr(&x).unwrap(), mat64![1.0, -1.0; -1.0, 1.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Not enough input - if there are less than two observations.
+/// 2. Invalid value - if a column is constant.
+pub fn corr<T: Matrix>(mat: &T) -> Result<T, JolinError> {
+    let mut ans = cov(mat)?;
+    let sd: Vec<T::Elem> = (0..ans.row()).map(|i| ans.elem(i, i).sqrt()).collect();
+    if sd.iter().any(|s| *s == T::Elem::zero()) {
+        return Err(JolinError::invalid_value())
+    }
+    for c in 0..ans.column() {
+        for r in 0..ans.row() {
+            let v = if r == c { T::Elem::one() } else { ans.elem(r, c) / (sd[r] * sd[c]) };
+            *ans.elem_mut(r, c) = v;
+        }
+    }
+    Ok(ans)
+}
+
+/// The answer of principal component analysis, see `pca`
+#[derive(Debug, Clone)]
+pub struct Pca<T: Matrix> {
+    /// Column means of the data as a row vector
+    pub mean: T,
+    /// Principal axes stored as columns, in the descending order of the
+    /// explained variance. The largest component of each axis is positive.
+    pub components: T,
+    /// Variance of the data along each principal axis
+    pub explained_variance: Vec<T::Elem>,
+    /// Ratio of each explained variance to the total variance of the data
+    pub explained_variance_ratio: Vec<T::Elem>,
+}
+
+/// Principal component analysis of the data, whose rows are the observations
+/// and columns are the variables. The data are centered, and the principal
+/// axes are the eigenvectors of the sample covariance matrix with the
+/// `n_components` largest eigenvalues.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::stats::pca;
+/// let x = mat64![1.0, 1.0; 2.0, 2.0; 3.0, 3.0];
+/// let ans = pca(&x, 1).unwrap();
+/// assert!((ans.explained_variance[0] - 2.0).abs() < 1e-12);
+/// let projected = ans.transform(&x).unwrap();
+/// assert!((projected.elem(2, 0) - 2.0f64.sqrt()).abs() < 1e-12);
+/// ```
+///
+/// Potential errors:
+/// 1. Not enough input - if there are less than two observations.
+/// 2. Invalid value - if `n_components` is zero or more than the variables.
+#[allow(clippy::needless_range_loop)]
+pub fn pca<T: Matrix>(data: &T, n_components: usize) -> Result<Pca<T>, JolinError> {
+    let p = data.column();
+    if n_components == 0 || n_components > p {
+        return Err(JolinError::invalid_value())
+    }
+    let c = cov(data)?;
+    let eig = eig_symmetric(&c)?;
+    let total = eig.values.iter().fold(T::Elem::zero(), |acc, v| acc + *v);
+    let mut components = T::zero(p, n_components);
+    let mut explained_variance = Vec::with_capacity(n_components);
+    for k in 0..n_components {
+        // the eigenvalues are in ascending order
+        let i = p - 1 - k;
+        let axis = eig.vectors.data_column(i);
+        let mut largest = axis[0];
+        for v in axis.iter() {
+            if v.abs() > largest.abs() {
+                largest = *v;
+            }
+        }
+        let sign = largest.sign();
+        for r in 0..p {
+            *components.elem_mut(r, k) = axis[r] * sign;
+        }
+        // rounding errors could make zero variance slightly negative
+        let v = eig.values[i];
+        explained_variance.push(if v > T::Elem::zero() { v } else { T::Elem::zero() });
+    }
+    let explained_variance_ratio = explained_variance.iter()
+        .map(|v| if total > T::Elem::zero() { *v / total } else { T::Elem::zero() })
+        .collect();
+    Ok(Pca {
+        mean: mean_axis(data, Axis::Column),
+        components,
+        explained_variance,
+        explained_variance_ratio,
+    })
+}
+
+impl<T: Matrix> Pca<T> {
+    /// Project the observations in the rows of `data` onto the principal
+    /// axes, after centering by the mean of the fitted data.
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the column count of `data` differs from the
+    ///    fitted data.
+    pub fn transform(&self, data: &T) -> Result<T, JolinError> {
+        if data.column() != self.mean.column() {
+            return Err(JolinError::shape_mismatching_with((data.row(), self.mean.column()), (data.row(), data.column())))
+        }
+        let mut centered = data.clone();
+        for c in 0..data.column() {
+            for r in 0..data.row() {
+                *centered.elem_mut(r, c) = data.elem(r, c) - self.mean.data()[c];
+            }
+        }
+        mul(&centered, &self.components)
+    }
+}
+
+/// Pearson correlation matrix of the columns, the same as `corr` under the
+/// name of NumPy. It doesn't depend on the normalization of covariance.
+///
+/// Potential errors:
+/// 1. Not enough input - if there are less than two observations.
+/// 2. Invalid value - if a column is constant.
+pub fn corrcoef<T: Matrix>(mat: &T) -> Result<T, JolinError> {
+    corr(mat)
+}
+
+/// An affine transform `(x - shift) / scale` of each column, which keeps a
+/// fitted standardization or normalization for new data
+#[derive(Debug, Clone)]
+pub struct ColumnTransform<T: Matrix> {
+    /// Value subtracted from each column
+    pub shift: Vec<T::Elem>,
+    /// Divisor of each column
+    pub scale: Vec<T::Elem>,
+}
+
+impl<T: Matrix> ColumnTransform<T> {
+    /// Apply the transform to the columns of `mat`
+    ///
+    /// Potential errors:
+    /// 1. Shape mismatching - if the column count differs from the fitted
+    ///    data.
+    pub fn apply(&self, mat: &T) -> Result<T, JolinError> {
+        if mat.column() != self.scale.len() {
+            return Err(JolinError::shape_mismatching_with((mat.row(), self.scale.len()), (mat.row(), mat.column())))
+        }
+        let mut ans = mat.clone();
+        for c in 0..mat.column() {
+            for r in 0..mat.row() {
+                *ans.elem_mut(r, c) = (mat.elem(r, c) - self.shift[c]) / self.scale[c];
+            }
+        }
+        Ok(ans)
+    }
+}
+
+/// Standardize each column to zero mean and unit population standard
+/// deviation. The answer includes the transform for new data. Constant
+/// columns are only centered, i.e. their scale is one.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::stats::zscore_columns;
+/// let (z, transform) = zscore_columns(&mat64![1.0, 5.0; 3.0, 5.0]).unwrap();
+/// assert_eq!(z, mat64![-1.0, 0.0; 1.0, 0.0]);
+/// assert_eq!(transform.apply(&mat64![5.0, 6.0]).unwrap(), mat64![3.0, 1.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Not enough input - if the matrix has no row.
+pub fn zscore_columns<T: Matrix>(mat: &T) -> Result<(T, ColumnTransform<T>), JolinError> {
+    if mat.row() == 0 {
+        return Err(JolinError::not_enough_input())
+    }
+    let shift = mean_axis(mat, Axis::Column).data().to_vec();
+    let count = T::Elem::one().times_real(mat.row() as f64);
+    let scale = (0..mat.column()).map(|c| {
+        let var = mat.data_column(c).iter().map(|x| (*x - shift[c]) * (*x - shift[c])).sum::<T::Elem>() / count;
+        nonzero_or_one(var.sqrt())
+    }).collect();
+    let transform = ColumnTransform { shift, scale };
+    Ok((transform.apply(mat)?, transform))
+}
+
+/// Scale each column to unit `p`-norm, where `p` is at least one or
+/// infinity for the maximal absolute value. The answer includes the
+/// transform for new data. Zero columns are kept, i.e. their scale is one.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::stats::normalize_columns;
+/// let (n, transform) = normalize_columns(&mat64![3.0, 1.0; 4.0, -2.0], 2.0).unwrap();
+/// assert_eq!(n.data_column(0), &[0.6, 0.8]);
+/// assert_eq!(transform.scale[1], 5.0f64.sqrt());
+/// ```
+///
+/// Potential errors:
+/// 1. Invalid value - if `p` is less than one or NaN.
+pub fn normalize_columns<T: Matrix>(mat: &T, p: f64) -> Result<(T, ColumnTransform<T>), JolinError> {
+    if p.partial_cmp(&1.0).is_none_or(|o| o == std::cmp::Ordering::Less) {
+        return Err(JolinError::invalid_value())
+    }
+    let scale = (0..mat.column()).map(|c| {
+        let col = mat.data_column(c);
+        let norm = if p == f64::INFINITY {
+            col.iter().fold(T::Elem::zero(), |acc, x| if x.abs() > acc { x.abs() } else { acc })
+        } else {
+            let sum: f64 = col.iter().map(|x| x.abs().to_f64().powf(p)).sum();
+            T::Elem::one().times_real(sum.powf(1.0 / p))
+        };
+        nonzero_or_one(norm)
+    }).collect();
+    let transform = ColumnTransform { shift: vec![T::Elem::zero(); mat.column()], scale };
+    Ok((transform.apply(mat)?, transform))
+}
+
+fn nonzero_or_one<E: LikeNumber>(x: E) -> E {
+    if x == E::zero() { E::one() } else { x }
+}
+
+/// Column means of each matrix of a batch, e.g. of the windows of a signal.
+/// The answer has one row vector per matrix. With the `parallel` feature, the
+/// matrices are processed in parallel.
+pub fn batch_mean<T: Matrix + Send + Sync>(stack: &Batch<T>) -> Batch<T> {
+    let means: Vec<T> = batch_map(stack, |m| mean_axis(m, Axis::Column));
+    pack(&means, 1, stack.column())
+}
+
+/// Sample covariance matrix of each matrix of a batch, see `cov`. With the
+/// `parallel` feature, the matrices are processed in parallel.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::batch::Batch;
+/// # use jolin::stats::batch_cov;
+/// let windows = Batch::from_matrices(&[mat64![1.0; 3.0], mat64![0.0; 4.0]]).unwrap();
+/// let covs = batch_cov(&windows).unwrap();
+/// assert_eq!(covs.to_matrices(), vec![mat64![2.0], mat64![8.0]]);
+/// ```
+///
+/// Potential errors:
+/// 1. Not enough input - if the matrices have less than two observations.
+pub fn batch_cov<T: Matrix + Send + Sync>(stack: &Batch<T>) -> Result<Batch<T>, JolinError> {
+    let covs: Result<Vec<T>, JolinError> = batch_map(stack, cov);
+    Ok(pack(&covs?, stack.column(), stack.column()))
+}
+
+/// Pearson correlation matrix of each matrix of a batch, see `corr`. With the
+/// `parallel` feature, the matrices are processed in parallel.
+///
+/// Potential errors:
+/// 1. Not enough input - if the matrices have less than two observations.
+/// 2. Invalid value - if a column of a matrix is constant.
+pub fn batch_corr<T: Matrix + Send + Sync>(stack: &Batch<T>) -> Result<Batch<T>, JolinError> {
+    let corrs: Result<Vec<T>, JolinError> = batch_map(stack, corr);
+    Ok(pack(&corrs?, stack.column(), stack.column()))
+}
+
+/// Map each matrix of the batch and collect the answers, e.g. into `Vec<T>`,
+/// or into `Result<Vec<T>, JolinError>` for a fallible `f`.
+#[cfg(feature = "parallel")]
+fn batch_map<T, R, C, F>(stack: &Batch<T>, f: F) -> C
+    where T: Matrix + Send + Sync, R: Send, C: rayon::iter::FromParallelIterator<R>, F: Fn(&T) -> R + Send + Sync {
+    use rayon::prelude::*;
+    (0..stack.len()).into_par_iter().map(|i| f(&stack.get(i))).collect()
+}
+
+/// Map each matrix of the batch and collect the answers, e.g. into `Vec<T>`,
+/// or into `Result<Vec<T>, JolinError>` for a fallible `f`.
+#[cfg(not(feature = "parallel"))]
+fn batch_map<T, R, C, F>(stack: &Batch<T>, f: F) -> C
+    where T: Matrix + Send + Sync, R: Send, C: FromIterator<R>, F: Fn(&T) -> R + Send + Sync {
+    (0..stack.len()).map(|i| f(&stack.get(i))).collect()
+}
+
+/// Batch of the matrices, which all have the shape `row * column`
+fn pack<T: Matrix>(mats: &[T], row: usize, column: usize) -> Batch<T> {
+    let mut ans = Batch::zero(mats.len(), row, column);
+    for (i, m) in mats.iter().enumerate() {
+        ans.matrix_data_mut(i).copy_from_slice(m.data());
+    }
+    ans
+}
+
+/// Numerical rank and 2-norm condition number from the singular values
+fn rank_and_condition<T: Matrix>(mat: &T) -> Result<(usize, T::Elem), JolinError> {
+    let gram = if mat.row() >= mat.column() {
+        mul(&tr(mat), mat)?
+    } else {
+        mul(mat, &tr(mat))?
+    };
+    let eig = eig_symmetric(&gram)?;
+    let singular: Vec<T::Elem> = eig.values.iter()
+        .map(|v| if *v > T::Elem::zero() { v.sqrt() } else { T::Elem::zero() })
+        .collect();
+    let largest = singular[singular.len() - 1];
+    // Squaring the matrix loses half of the significant digits, so the
+    // threshold uses the square root of the machine epsilon.
+    let dim = usize::max(mat.row(), mat.column()) as f64;
+    let threshold = largest * T::Elem::epsilon().sqrt().times_real(dim);
+    let rank = singular.iter().filter(|s| **s > threshold).count();
+    let smallest = singular[0];
+    let condition = if rank < singular.len() {
+        T::Elem::one() / T::Elem::zero()
+    } else {
+        largest / smallest
+    };
+    Ok((rank, condition))
+}
+
+fn is_nan<E: LikeNumber>(x: &E) -> bool {
+    x.partial_cmp(x).is_none()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::stats::{*};
+
+    #[test]
+    fn test_describe() {
+        let a = mat64![1.0, 2.0, 3.0; 2.0, 4.0, 6.0];
+        let s = describe(&a).unwrap();
+        assert_eq!((s.row, s.column), (2, 3));
+        assert_eq!((s.min, s.max), (1.0, 6.0));
+        assert!((s.mean - 3.0).abs() < 1e-15);
+        assert!((s.std - (8.0f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert!((s.norm - 70.0f64.sqrt()).abs() < 1e-12);
+        assert!(!s.symmetric);
+        assert_eq!(s.rank, Some(1));
+        assert!(s.condition.unwrap().is_infinite());
+        assert_eq!(s.nan_count, 0);
+
+        let b = mat64![3.0, 1.0; 1.0, 3.0];
+        let s = describe(&b).unwrap();
+        assert!(s.symmetric);
+        assert_eq!(s.rank, Some(2));
+        assert!((s.condition.unwrap() - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_describe_nan() {
+        let a = mat64![1.0, f64::NAN; 3.0, 5.0];
+        let s = describe(&a).unwrap();
+        assert_eq!(s.nan_count, 1);
+        assert_eq!(s.max, 5.0);
+        assert_eq!(s.rank, None);
+        let report = format!("{}", s);
+        assert!(report.contains("shape:     2 x 2"));
+        assert!(report.contains("rank:      -"));
+        assert!(report.ends_with("NaN count: 1"));
+
+        assert!(describe(&Mat64::new(1, 1, &[f64::NAN])).is_err());
+        assert!(describe(&Mat64::zero(0, 0)).is_err());
+    }
+
+    #[test]
+    fn test_cov_corr() {
+        let x = mat64![1.0, 2.0, 0.0; 2.0, 1.0, 1.0; 3.0, 3.0, 2.0; 4.0, 2.0, 5.0];
+        let c = cov(&x).unwrap();
+        assert!((c.elem(0, 0) - 5.0 / 3.0).abs() < 1e-12);
+        assert!((c.elem(0, 1) - 1.0 / 3.0).abs() < 1e-12);
+        assert_eq!(c, tr(&c));
+        let r = corr(&x).unwrap();
+        assert!((r.elem(0, 1) - (1.0 / 3.0) / (5.0f64 / 3.0 * 2.0 / 3.0).sqrt()).abs() < 1e-12);
+        assert_eq!(r.elem(2, 2), 1.0);
+        assert!(cov(&mat64![1.0, 2.0]).is_err());
+        let biased = cov_with(&x, Normalization::Biased).unwrap();
+        assert!(eq_with_error(&biased, &elemwise(&c, |v| v * 0.75), 1e-12));
+        assert_eq!(cov_with(&mat64![1.0, 2.0], Normalization::Biased).unwrap(), Mat64::zero(2, 2));
+        assert!(cov_with(&Mat64::zero(0, 2), Normalization::Biased).is_err());
+        assert_eq!(corrcoef(&x).unwrap(), r);
+        assert!(corr(&mat64![1.0, 2.0; 1.0, 3.0]).is_err());
+    }
+
+    #[test]
+    fn test_batch_stats() {
+        let mats = vec![
+            mat64![1.0, 2.0; 3.0, 5.0; 5.0, 2.0],
+            mat64![0.0, 1.0; 1.0, 0.0; 2.0, 2.0],
+            mat64![2.0, 2.0; 4.0, 0.0; 0.0, 7.0],
+        ];
+        let stack = Batch::from_matrices(&mats).unwrap();
+        let means = batch_mean(&stack);
+        assert_eq!((means.len(), means.row(), means.column()), (3, 1, 2));
+        assert_eq!(means.get(1), mat64![1.0, 1.0]);
+        let covs = batch_cov(&stack).unwrap();
+        let corrs = batch_corr(&stack).unwrap();
+        for (i, m) in mats.iter().enumerate() {
+            assert_eq!(means.get(i), mean_axis(m, Axis::Column));
+            assert_eq!(covs.get(i), cov(m).unwrap());
+            assert_eq!(corrs.get(i), corr(m).unwrap());
+        }
+        let short = Batch::from_matrices(&[mat64![1.0, 2.0]]).unwrap();
+        assert!(batch_cov(&short).is_err());
+        let empty = batch_cov(&Batch::<Mat64>::zero(0, 3, 2)).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!((empty.row(), empty.column()), (2, 2));
+    }
+
+    #[test]
+    fn test_pca() {
+        // points along (2, 1) with a small spread along (-1, 2)
+        let x = mat64![
+            -4.0, -2.0;
+            -2.0, -1.0;
+            0.0, 0.0;
+            2.0, 1.0;
+            4.0, 2.0;
+            -0.2, 0.4;
+            0.2, -0.4];
+        let ans = pca(&x, 2).unwrap();
+        let axis = 1.0 / 5.0f64.sqrt();
+        assert!((ans.components.elem(0, 0) - 2.0 * axis).abs() < 1e-12);
+        assert!((ans.components.elem(1, 0) - axis).abs() < 1e-12);
+        assert!(ans.explained_variance[0] > ans.explained_variance[1]);
+        let ratio_sum: f64 = ans.explained_variance_ratio.iter().sum();
+        assert!((ratio_sum - 1.0).abs() < 1e-12);
+
+        // the variance of the projections is the explained variance
+        let projected = ans.transform(&x).unwrap();
+        let c = cov(&projected).unwrap();
+        assert!((c.elem(0, 0) - ans.explained_variance[0]).abs() < 1e-12);
+        assert!(c.elem(0, 1).abs() < 1e-12);
+        assert!(ans.transform(&Mat64::zero(1, 3)).is_err());
+        assert!(pca(&x, 0).is_err());
+        assert!(pca(&x, 3).is_err());
+        assert!(pca(&mat64![1.0, 2.0], 1).is_err());
+    }
+
+    #[test]
+    fn test_zscore_normalize() {
+        let x = mat64![1.0, 2.0, 0.0; 2.0, 4.0, 0.0; 3.0, 9.0, 0.0; 6.0, 1.0, 0.0];
+        let (z, t) = zscore_columns(&x).unwrap();
+        let mean = mean_axis(&z, Axis::Column);
+        assert!(eq_with_error(&mean, &Mat64::zero(1, 3), 1e-12));
+        let var = cov_with(&z, Normalization::Biased).unwrap();
+        assert!((var.elem(0, 0) - 1.0).abs() < 1e-12 && (var.elem(1, 1) - 1.0).abs() < 1e-12);
+        assert_eq!(t.scale[2], 1.0);
+        assert_eq!(t.apply(&x).unwrap(), z);
+        assert!(t.apply(&Mat64::zero(1, 2)).is_err());
+        assert!(zscore_columns(&Mat64::zero(0, 2)).is_err());
+
+        let (n, t) = normalize_columns(&x, 1.0).unwrap();
+        assert_eq!(t.scale, vec![12.0, 16.0, 1.0]);
+        assert!((n.data_column(1).iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        let (n, _) = normalize_columns(&x, f64::INFINITY).unwrap();
+        assert_eq!(n.elem(2, 1), 1.0);
+        let (n, _) = normalize_columns(&mat64![1.0; 2.0; 2.0], 3.0).unwrap();
+        assert!((n.data().iter().map(|v| v.powi(3)).sum::<f64>() - 1.0).abs() < 1e-12);
+        assert!(normalize_columns(&x, 0.5).is_err());
+        assert!(normalize_columns(&x, f64::NAN).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+/*
+ * stochastic.rs
+ * Row- and column-stochastic matrices.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use crate::matrix::{Matrix, LikeNumber, Axis, sum_axis};
+use crate::error::JolinError;
+
+/// Normalize each row of a nonnegative matrix to sum to one, e.g. to turn
+/// transition counts into the transition matrix of a Markov chain.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::stochastic::to_row_stochastic;
+/// let counts = mat64![3.0, 1.0; 0.0, 2.0];
+/// assert_eq!(to_row_stochastic(&counts).unwrap(), mat64![0.75, 0.25; 0.0, 1.0]);
+/// ```
+///
+/// Potential errors:
+/// 1. Invalid value - if the matrix has a negative element or a row whose sum is zero.
+pub fn to_row_stochastic<T: Matrix>(mat: &T) -> Result<T, JolinError> {
+    to_stochastic(mat, Axis::Row)
+}
+
+/// Normalize each column of a nonnegative matrix to sum to one.
+///
+/// Potential errors:
+/// 1. Invalid value - if the matrix has a negative element or a column whose sum is zero.
+pub fn to_column_stochastic<T: Matrix>(mat: &T) -> Result<T, JolinError> {
+    to_stochastic(mat, Axis::Column)
+}
+
+/// Check whether the matrix is row-stochastic (`Axis::Row`) or
+/// column-stochastic (`Axis::Column`): all elements are nonnegative and each
+/// row or column sums to one, both up to `tol`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::stochastic::validate_stochastic;
+/// let p = mat64![0.5, 0.5; 0.1, 0.9];
+/// assert!(validate_stochastic(&p, Axis::Row, 1e-12).is_ok());
+/// assert!(validate_stochastic(&p, Axis::Column, 1e-12).is_err());
+/// ```
+///
+/// Potential errors:
+/// 1. Invalid value - if an element is below `-tol` or a row or column sum
+///    differs from one by more than `tol`.
+pub fn validate_stochastic<T: Matrix>(mat: &T, axis: Axis, tol: T::Elem) -> Result<(), JolinError> {
+    if !mat.data().iter().all(|x| *x >= -tol) {
+        return Err(JolinError::invalid_value())
+    }
+    let sums = sum_axis(mat, axis);
+    if !sums.data().iter().all(|s| (*s - T::Elem::one()).abs() <= tol) {
+        return Err(JolinError::invalid_value())
+    }
+    Ok(())
+}
+
+fn to_stochastic<T: Matrix>(mat: &T, axis: Axis) -> Result<T, JolinError> {
+    // NaN is rejected as well
+    if !mat.data().iter().all(|x| *x >= T::Elem::zero()) {
+        return Err(JolinError::invalid_value())
+    }
+    let sums = sum_axis(mat, axis);
+    if sums.data().iter().any(|s| *s == T::Elem::zero()) {
+        return Err(JolinError::invalid_value())
+    }
+    let mut ans = mat.clone();
+    for c in 0..mat.column() {
+        for r in 0..mat.row() {
+            let s = match axis {
+                Axis::Row => sums.data()[r],
+                Axis::Column => sums.data()[c],
+            };
+            *ans.elem_mut(r, c) = mat.elem(r, c) / s;
+        }
+    }
+    Ok(ans)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+    use crate::stochastic::{*};
+
+    #[test]
+    fn test_to_stochastic() {
+        let a = mat64![1.0, 3.0; 2.0, 2.0; 0.0, 5.0];
+        let p = to_row_stochastic(&a).unwrap();
+        assert_eq!(p, mat64![0.25, 0.75; 0.5, 0.5; 0.0, 1.0]);
+        assert!(validate_stochastic(&p, Axis::Row, 1e-15).is_ok());
+        let q = to_column_stochastic(&a).unwrap();
+        assert_eq!(q, mat64![1.0 / 3.0, 0.3; 2.0 / 3.0, 0.2; 0.0, 0.5]);
+        assert!(validate_stochastic(&q, Axis::Column, 1e-15).is_ok());
+
+        let err = to_row_stochastic(&mat64![1.0, -1.0; 1.0, 1.0]).unwrap_err();
+        assert_eq!(err.kind(), JolinErrorKind::InvalidValue);
+        assert!(to_row_stochastic(&mat64![1.0, 1.0; 0.0, 0.0]).is_err());
+        assert!(to_column_stochastic(&mat64![1.0, 0.0; 1.0, 0.0]).is_err());
+        assert!(to_row_stochastic(&mat64![1.0, f64::NAN]).is_err());
+    }
+
+    #[test]
+    fn test_validate_stochastic() {
+        let p = mat64![0.5, 0.5 + 1e-10; -1e-10, 1.0];
+        assert!(validate_stochastic(&p, Axis::Row, 1e-9).is_ok());
+        assert!(validate_stochastic(&p, Axis::Row, 1e-11).is_err());
+        assert!(validate_stochastic(&mat64![0.5, 0.6], Axis::Row, 1e-3).is_err());
+        assert!(validate_stochastic(&mat64![1.5, -0.5], Axis::Row, 1e-3).is_err());
+    }
+}
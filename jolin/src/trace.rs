@@ -0,0 +1,141 @@
+/*
+ * trace.rs
+ * Debug-time tracing of the shapes flowing through matrix operations.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::fmt;
+use crate::matrix::Matrix;
+use crate::error::JolinError;
+
+/// Maximal count of entries kept in the trace of each thread
+pub const TRACE_CAPACITY: usize = 256;
+
+/// A traced matrix operation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    /// Name of the operation
+    pub op: &'static str,
+    /// Shapes `(row, column)` of the input matrices
+    pub inputs: Vec<(usize, usize)>,
+    /// Shape of the answer, or `None` if the operation failed
+    pub output: Option<(usize, usize)>,
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}(", self.op)?;
+        for (i, (r, c)) in self.inputs.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}x{}", r, c)?;
+        }
+        match self.output {
+            Some((r, c)) => write!(f, ") -> {}x{}", r, c),
+            None => write!(f, ") -> error"),
+        }
+    }
+}
+
+#[cfg(feature = "shape-trace")]
+thread_local! {
+    static TRACE: std::cell::RefCell<std::collections::VecDeque<TraceEntry>> =
+        const { std::cell::RefCell::new(std::collections::VecDeque::new()) };
+}
+
+/// Get the recently traced operations of the current thread, oldest first.
+///
+/// Operations are only recorded when the `shape-trace` feature is enabled,
+/// otherwise the trace is always empty. When an operation deep inside a long
+/// formula fails, the last entry with no output tells which operation failed
+/// and with which input shapes.
+pub fn trace() -> Vec<TraceEntry> {
+    #[cfg(feature = "shape-trace")]
+    {
+        TRACE.with(|t| t.borrow().iter().cloned().collect())
+    }
+    #[cfg(not(feature = "shape-trace"))]
+    {
+        Vec::new()
+    }
+}
+
+/// Clear the trace of the current thread.
+pub fn clear() {
+    #[cfg(feature = "shape-trace")]
+    TRACE.with(|t| t.borrow_mut().clear());
+}
+
+/// Get the most recent failed operation of the current thread.
+pub fn last_failure() -> Option<TraceEntry> {
+    trace().into_iter().rev().find(|e| e.output.is_none())
+}
+
+/// Run the operation `f` and record it in the trace.
+#[cfg(feature = "shape-trace")]
+pub(crate) fn traced<T: Matrix, F: FnOnce() -> Result<T, JolinError>>(op: &'static str, inputs: &[&T], f: F) -> Result<T, JolinError> {
+    let ans = f();
+    let entry = TraceEntry {
+        op,
+        inputs: inputs.iter().map(|m| (m.row(), m.column())).collect(),
+        output: ans.as_ref().ok().map(|m| (m.row(), m.column())),
+    };
+    TRACE.with(|t| {
+        let mut t = t.borrow_mut();
+        if t.len() == TRACE_CAPACITY {
+            t.pop_front();
+        }
+        t.push_back(entry);
+    });
+    ans
+}
+
+/// Run the operation `f`. Nothing is recorded without the `shape-trace` feature.
+#[cfg(not(feature = "shape-trace"))]
+#[inline(always)]
+pub(crate) fn traced<T: Matrix, F: FnOnce() -> Result<T, JolinError>>(_op: &'static str, _inputs: &[&T], f: F) -> Result<T, JolinError> {
+    f()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::matrix::{*};
+    use crate::trace::{*};
+
+    #[test]
+    fn test_trace_entry_display() {
+        let e = TraceEntry { op: "mul", inputs: vec![(2, 3), (4, 1)], output: None };
+        assert_eq!(format!("{}", e), "mul(2x3, 4x1) -> error");
+        let e = TraceEntry { op: "add", inputs: vec![(2, 2), (2, 2)], output: Some((2, 2)) };
+        assert_eq!(format!("{}", e), "add(2x2, 2x2) -> 2x2");
+    }
+
+    #[cfg(feature = "shape-trace")]
+    #[test]
+    fn test_trace_failure() {
+        clear();
+        let a = Mat64::zero(2, 3);
+        let b = Mat64::zero(3, 1);
+        let ab = mul(&a, &b).unwrap();
+        let c = add(&ab, &a);
+        assert!(c.is_err());
+        let t = trace();
+        assert_eq!(t.len(), 2);
+        assert_eq!(t[0], TraceEntry { op: "mul", inputs: vec![(2, 3), (3, 1)], output: Some((2, 1)) });
+        assert_eq!(last_failure().unwrap(), TraceEntry { op: "add", inputs: vec![(2, 1), (2, 3)], output: None });
+        clear();
+        assert!(trace().is_empty());
+    }
+
+    #[cfg(not(feature = "shape-trace"))]
+    #[test]
+    fn test_trace_disabled() {
+        let a = Mat64::zero(2, 3);
+        assert!(add(&a, &Mat64::zero(3, 2)).is_err());
+        assert!(trace().is_empty());
+        assert!(last_failure().is_none());
+    }
+}
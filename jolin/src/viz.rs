@@ -0,0 +1,299 @@
+/*
+ * viz.rs
+ * Visualization of matrices.
+ *
+ * Copyright 2023-present Mengxiao Lin, all rights reserved.
+ * See LICENSE file in the root of the repo.
+ */
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use crate::matrix::{Matrix, LikeNumber};
+use crate::error::JolinError;
+
+/// Colormaps of heatmaps
+#[derive(Debug, PartialEq, Copy, Clone, Eq)]
+pub enum Colormap {
+    /// From black (minimum) to white (maximum)
+    Gray,
+    /// The perceptually uniform colormap from dark purple to yellow
+    Viridis,
+    /// Diverging colormap from blue through light gray to red, best used with
+    /// symmetric scaling so that zero is light gray
+    CoolWarm,
+}
+
+/// How matrix values are mapped onto the colormap
+#[derive(Debug, PartialEq, Copy, Clone, Eq)]
+pub enum HeatmapScaling {
+    /// Map `[min, max]` of the matrix onto the colormap
+    MinMax,
+    /// Map `[-m, m]` onto the colormap, where `m` is the maximal absolute value
+    Symmetric,
+}
+
+/// Options of heatmap rendering
+#[derive(Debug, Clone, Copy)]
+pub struct HeatmapOptions {
+    /// Colormap of the heatmap
+    pub colormap: Colormap,
+    /// Scaling of the values
+    pub scaling: HeatmapScaling,
+    /// Width and height in pixels of the square drawn for each element
+    pub pixel_size: usize,
+}
+
+impl Default for HeatmapOptions {
+    fn default() -> Self {
+        HeatmapOptions { colormap: Colormap::Viridis, scaling: HeatmapScaling::MinMax, pixel_size: 1 }
+    }
+}
+
+const VIRIDIS: [[f64; 3]; 5] = [
+    [68.0, 1.0, 84.0],
+    [59.0, 82.0, 139.0],
+    [33.0, 145.0, 140.0],
+    [94.0, 201.0, 98.0],
+    [253.0, 231.0, 37.0],
+];
+
+const COOLWARM: [[f64; 3]; 3] = [
+    [59.0, 76.0, 192.0],
+    [221.0, 221.0, 221.0],
+    [180.0, 4.0, 38.0],
+];
+
+const GRAY: [[f64; 3]; 2] = [
+    [0.0, 0.0, 0.0],
+    [255.0, 255.0, 255.0],
+];
+
+impl Colormap {
+    /// Get the RGB color of `t` in `[0, 1]`. Values out of the range are
+    /// clamped, and NaN is rendered black.
+    pub fn color(&self, t: f64) -> [u8; 3] {
+        if t.is_nan() {
+            return [0, 0, 0]
+        }
+        let stops: &[[f64; 3]] = match self {
+            Colormap::Gray => &GRAY,
+            Colormap::Viridis => &VIRIDIS,
+            Colormap::CoolWarm => &COOLWARM,
+        };
+        // linear interpolation between the two nearest stops
+        let pos = t.clamp(0.0, 1.0) * (stops.len() - 1) as f64;
+        let i = (pos.floor() as usize).min(stops.len() - 2);
+        let w = pos - i as f64;
+        let mut rgb = [0u8; 3];
+        for k in 0..3 {
+            rgb[k] = (stops[i][k] * (1.0 - w) + stops[i + 1][k] * w).round() as u8;
+        }
+        rgb
+    }
+}
+
+/// Render the matrix as a heatmap. The answer is the RGB bytes of the image,
+/// row by row from the top, with `row * pixel_size` rows and
+/// `column * pixel_size` columns of pixels.
+///
+/// If all values are mapped to the same point, e.g. a constant matrix with
+/// min/max scaling, the middle color of the colormap is used.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::viz::{heatmap_rgb, HeatmapOptions, Colormap};
+/// let a = mat64![0.0, 1.0];
+/// let options = HeatmapOptions { colormap: Colormap::Gray, ..Default::default() };
+/// assert_eq!(heatmap_rgb(&a, &options), vec![0, 0, 0, 255, 255, 255]);
+/// ```
+pub fn heatmap_rgb<T: Matrix>(mat: &T, options: &HeatmapOptions) -> Vec<u8> {
+    let values: Vec<f64> = mat.data().iter().map(|x| x.to_f64()).collect();
+    let finite = values.iter().filter(|x| x.is_finite());
+    let (low, high) = match options.scaling {
+        HeatmapScaling::MinMax => (
+            finite.clone().cloned().fold(f64::INFINITY, f64::min),
+            finite.cloned().fold(f64::NEG_INFINITY, f64::max),
+        ),
+        HeatmapScaling::Symmetric => {
+            let m = finite.map(|x| x.abs()).fold(0.0, f64::max);
+            (-m, m)
+        }
+    };
+    let normalize = |x: f64| {
+        if high > low {
+            (x - low) / (high - low)
+        } else if x.is_nan() {
+            x
+        } else {
+            0.5
+        }
+    };
+
+    let size = options.pixel_size;
+    let width = mat.column() * size;
+    let mut rgb = Vec::with_capacity(mat.row() * size * width * 3);
+    for r in 0..mat.row() {
+        let line: Vec<u8> = (0..mat.column())
+            .flat_map(|c| {
+                let color = options.colormap.color(normalize(values[mat.idx(r, c)]));
+                std::iter::repeat_n(color, size).flatten()
+            })
+            .collect();
+        for _ in 0..size {
+            rgb.extend_from_slice(&line);
+        }
+    }
+    rgb
+}
+
+/// Render the matrix as a heatmap and save it as a binary PPM image, which
+/// can be opened by most image viewers.
+///
+/// Potential errors:
+/// 1. Not enough input - if the matrix is empty or the pixel size is zero.
+/// 2. IO error - if the file cannot be written.
+pub fn to_ppm_heatmap<T: Matrix, P: AsRef<Path>>(mat: &T, options: &HeatmapOptions, path: P) -> Result<(), JolinError> {
+    let width = mat.column() * options.pixel_size;
+    let height = mat.row() * options.pixel_size;
+    if width == 0 || height == 0 {
+        return Err(JolinError::not_enough_input())
+    }
+    write_ppm(path, width, height, &heatmap_rgb(mat, options))
+}
+
+/// Render the sparsity pattern of the matrix as text. Each element is a
+/// character, `*` for the elements whose absolute values are greater than
+/// `tol` and `.` for the others. Rows are separated by `\n`.
+/// ```
+/// # use jolin::matrix::{*};
+/// # use jolin::mat64;
+/// # use jolin::viz::spy_string;
+/// let a = mat64![4.0, 1e-12, 0.0; -1.0, 4.0, 2.0];
+/// assert_eq!(spy_string(&a, 1e-10), "*..\n***");
+/// ```
+pub fn spy_string<T: Matrix>(mat: &T, tol: T::Elem) -> String {
+    let mut ans = String::with_capacity(mat.row() * (mat.column() + 1));
+    for r in 0..mat.row() {
+        if r > 0 {
+            ans.push('\n');
+        }
+        for c in 0..mat.column() {
+            ans.push(if mat.elem(r, c).abs() > tol { '*' } else { '.' });
+        }
+    }
+    ans
+}
+
+/// Render the sparsity pattern of the matrix and save it as a binary PPM
+/// image. Elements whose absolute values are greater than `tol` are black
+/// squares of `pixel_size` pixels, and the others are white.
+///
+/// Potential errors:
+/// 1. Not enough input - if the matrix is empty or the pixel size is zero.
+/// 2. IO error - if the file cannot be written.
+pub fn spy_image<T: Matrix, P: AsRef<Path>>(mat: &T, tol: T::Elem, pixel_size: usize, path: P) -> Result<(), JolinError> {
+    let width = mat.column() * pixel_size;
+    let height = mat.row() * pixel_size;
+    if width == 0 || height == 0 {
+        return Err(JolinError::not_enough_input())
+    }
+    let mut rgb = Vec::with_capacity(width * height * 3);
+    for r in 0..mat.row() {
+        let line: Vec<u8> = (0..mat.column())
+            .flat_map(|c| {
+                let v = if mat.elem(r, c).abs() > tol { 0u8 } else { 255u8 };
+                std::iter::repeat_n(v, pixel_size * 3)
+            })
+            .collect();
+        for _ in 0..pixel_size {
+            rgb.extend_from_slice(&line);
+        }
+    }
+    write_ppm(path, width, height, &rgb)
+}
+
+/// Write the RGB bytes as a binary PPM (P6) image
+fn write_ppm<P: AsRef<Path>>(path: P, width: usize, height: usize, rgb: &[u8]) -> Result<(), JolinError> {
+    let mut w = BufWriter::new(File::create(path)?);
+    write!(w, "P6\n{} {}\n255\n", width, height)?;
+    w.write_all(rgb)?;
+    w.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::mat64;
+    use crate::matrix::{*};
+    use crate::error::JolinErrorKind;
+    use crate::viz::{*};
+
+    #[test]
+    fn test_colormap() {
+        assert_eq!(Colormap::Gray.color(0.5), [128, 128, 128]);
+        assert_eq!(Colormap::Viridis.color(-1.0), [68, 1, 84]);
+        assert_eq!(Colormap::Viridis.color(2.0), [253, 231, 37]);
+        assert_eq!(Colormap::CoolWarm.color(0.5), [221, 221, 221]);
+        assert_eq!(Colormap::CoolWarm.color(f64::NAN), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_heatmap_rgb() {
+        let a = mat64![-1.0, 0.0; 0.5, 1.0];
+        let options = HeatmapOptions {
+            colormap: Colormap::CoolWarm,
+            scaling: HeatmapScaling::Symmetric,
+            pixel_size: 2,
+        };
+        let rgb = heatmap_rgb(&a, &options);
+        assert_eq!(rgb.len(), 4 * 4 * 3);
+        // top-left 2x2 block is -1, the top-right one is 0
+        assert_eq!(&rgb[0..6], &[59, 76, 192, 59, 76, 192]);
+        assert_eq!(&rgb[6..12], &[221, 221, 221, 221, 221, 221]);
+        assert_eq!(&rgb[12..24], &rgb[0..12]);
+        assert_eq!(&rgb[42..48], &[180, 4, 38, 180, 4, 38]);
+
+        let constant = Mat64::new(1, 2, &[3.0, 3.0]);
+        let gray = HeatmapOptions { colormap: Colormap::Gray, ..Default::default() };
+        assert_eq!(heatmap_rgb(&constant, &gray), vec![128; 6]);
+    }
+
+    #[test]
+    fn test_to_ppm_heatmap() {
+        let path = std::env::temp_dir().join("jolin_test_heatmap.ppm");
+        let a = Mat32::new(2, 3, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        to_ppm_heatmap(&a, &HeatmapOptions::default(), &path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let header = b"P6\n3 2\n255\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(bytes.len(), header.len() + 2 * 3 * 3);
+
+        let err = to_ppm_heatmap(&Mat64::zero(0, 2), &HeatmapOptions::default(), &path);
+        assert_eq!(err.unwrap_err().kind(), JolinErrorKind::NotEnoughInput);
+        let err = to_ppm_heatmap(&a, &HeatmapOptions::default(), "/nonexistent/dir/a.ppm");
+        assert_eq!(err.unwrap_err().kind(), JolinErrorKind::IOError);
+    }
+
+    #[test]
+    fn test_spy() {
+        let a = mat64![1.0, 0.0, 0.0; 0.5, 2.0, 0.0; 0.0, -1e-9, 3.0];
+        assert_eq!(spy_string(&a, 1e-6), "*..\n**.\n..*");
+        assert_eq!(spy_string(&a, 0.0), "*..\n**.\n.**");
+        assert_eq!(spy_string(&Mat64::zero(0, 3), 0.0), "");
+
+        let path = std::env::temp_dir().join("jolin_test_spy.ppm");
+        spy_image(&a, 1e-6, 2, &path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let header = b"P6\n6 6\n255\n";
+        assert_eq!(&bytes[..header.len()], header);
+        let pixels = &bytes[header.len()..];
+        assert_eq!(pixels.len(), 6 * 6 * 3);
+        // pixel (1, 1) is in the block of a[0, 0], pixel (0, 2) in the block of a[0, 1]
+        assert_eq!(&pixels[(6 + 1) * 3..(6 + 2) * 3], &[0, 0, 0]);
+        assert_eq!(&pixels[2 * 3..3 * 3], &[255, 255, 255]);
+        assert!(spy_image(&a, 1e-6, 0, &path).is_err());
+    }
+}